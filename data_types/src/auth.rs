@@ -0,0 +1,274 @@
+//! API tokens, which authenticate requests, and the permissions they grant
+use std::convert::TryFrom;
+
+use snafu::Snafu;
+
+use generated_types::{
+    google::{FieldViolation, FieldViolationExt},
+    influxdata::iox::management::v1 as management,
+};
+
+use crate::field_validation::{FromField, FromFieldString, FromFieldVec};
+use crate::DatabaseName;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(context(false))]
+    ProstDecodeError { source: prost::DecodeError },
+
+    #[snafu(context(false))]
+    ProstEncodeError { source: prost::EncodeError },
+
+    #[snafu(context(false))]
+    FieldViolation { source: FieldViolation },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The operations a `Permission` grants. Each scope includes the operations
+/// of the scopes it is greater than
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    /// Query data
+    Read,
+    /// Query and write data
+    Write,
+    /// Query and write data, and manage the database
+    Admin,
+}
+
+/// Grants the operations of `scope` on one or all databases
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permission {
+    /// The database the permission applies to, `None` for all databases.
+    ///
+    /// Permissions for all databases also apply to operations that are not
+    /// specific to a database, such as setting the writer id, creating
+    /// databases and managing tokens
+    pub db_name: Option<DatabaseName<'static>>,
+
+    /// The operations granted
+    pub scope: Scope,
+}
+
+/// An API token and the permissions it grants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiToken {
+    /// Identifies the token without revealing it
+    pub id: String,
+
+    /// The secret sent by clients to authenticate, or its hash for the
+    /// tokens kept by the server
+    pub token: String,
+
+    /// A human readable description of the token
+    pub description: String,
+
+    /// The permissions granted by the token
+    pub permissions: Vec<Permission>,
+}
+
+impl ApiToken {
+    /// Returns true if this token permits the operations of `scope` on the
+    /// database `db_name`, or on the server if `db_name` is `None`
+    pub fn allows(&self, db_name: Option<&str>, scope: Scope) -> bool {
+        self.permissions.iter().any(|permission| {
+            let db_matches = match (&permission.db_name, db_name) {
+                (None, _) => true,
+                (Some(permitted), Some(db_name)) => permitted.as_str() == db_name,
+                (Some(_), None) => false,
+            };
+            db_matches && permission.scope >= scope
+        })
+    }
+}
+
+/// Decodes a list of tokens from the format they are stored in
+pub fn decode_tokens(bytes: prost::bytes::Bytes) -> Result<Vec<ApiToken>> {
+    let message: management::Tokens = prost::Message::decode(bytes)?;
+    Ok(message.tokens.vec_field("tokens")?)
+}
+
+/// Encodes a list of tokens into the format they are stored in
+pub fn encode_tokens(tokens: Vec<ApiToken>, bytes: &mut prost::bytes::BytesMut) -> Result<()> {
+    let encoded = management::Tokens {
+        tokens: tokens.into_iter().map(Into::into).collect(),
+    };
+    Ok(prost::Message::encode(&encoded, bytes)?)
+}
+
+impl From<ApiToken> for management::Token {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            id: token.id,
+            token: token.token,
+            description: token.description,
+            permissions: token.permissions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<management::Token> for ApiToken {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Token) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: proto.id.required("id")?,
+            token: proto.token.required("token")?,
+            description: proto.description,
+            permissions: proto.permissions.vec_field("permissions")?,
+        })
+    }
+}
+
+impl From<Permission> for management::Permission {
+    fn from(permission: Permission) -> Self {
+        Self {
+            db_name: permission
+                .db_name
+                .map(|db_name| db_name.to_string())
+                .unwrap_or_default(),
+            scope: management::Scope::from(permission.scope).into(),
+        }
+    }
+}
+
+impl TryFrom<management::Permission> for Permission {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Permission) -> Result<Self, Self::Error> {
+        let scope = proto.scope().scope("scope")?;
+        let db_name = match proto.db_name.optional() {
+            Some(db_name) => Some(DatabaseName::new(db_name).field("db_name")?),
+            None => None,
+        };
+
+        Ok(Self { db_name, scope })
+    }
+}
+
+impl From<Scope> for management::Scope {
+    fn from(scope: Scope) -> Self {
+        match scope {
+            Scope::Read => Self::Read,
+            Scope::Write => Self::Write,
+            Scope::Admin => Self::Admin,
+        }
+    }
+}
+
+impl TryFrom<management::Scope> for Scope {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Scope) -> Result<Self, Self::Error> {
+        match proto {
+            management::Scope::Read => Ok(Self::Read),
+            management::Scope::Write => Ok(Self::Write),
+            management::Scope::Admin => Ok(Self::Admin),
+            management::Scope::Unspecified => Err(FieldViolation::required("")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn token(permissions: Vec<Permission>) -> ApiToken {
+        ApiToken {
+            id: "1".to_string(),
+            token: "secret".to_string(),
+            description: "test".to_string(),
+            permissions,
+        }
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let token = token(vec![
+            Permission {
+                db_name: Some(DatabaseName::new("db1").unwrap()),
+                scope: Scope::Write,
+            },
+            Permission {
+                db_name: None,
+                scope: Scope::Read,
+            },
+        ]);
+
+        let protobuf: management::Token = token.clone().into();
+        assert_eq!(protobuf.permissions[1].db_name, "");
+
+        let back: ApiToken = protobuf.try_into().unwrap();
+        assert_eq!(token, back);
+    }
+
+    #[test]
+    fn test_tokens_encoding() {
+        let tokens = vec![
+            token(vec![Permission {
+                db_name: None,
+                scope: Scope::Admin,
+            }]),
+            token(vec![]),
+        ];
+
+        let mut bytes = prost::bytes::BytesMut::new();
+        encode_tokens(tokens.clone(), &mut bytes).unwrap();
+        let decoded = decode_tokens(bytes.freeze()).unwrap();
+
+        assert_eq!(tokens, decoded);
+    }
+
+    #[test]
+    fn test_token_invalid() {
+        let protobuf = management::Token {
+            id: "1".to_string(),
+            token: "secret".to_string(),
+            description: "".to_string(),
+            permissions: vec![management::Permission {
+                db_name: "db1".to_string(),
+                scope: management::Scope::Unspecified.into(),
+            }],
+        };
+        let err = ApiToken::try_from(protobuf).unwrap_err();
+        assert_eq!(err.field, "permissions.0.scope");
+
+        let protobuf = management::Token {
+            id: "1".to_string(),
+            token: "secret".to_string(),
+            description: "".to_string(),
+            permissions: vec![management::Permission {
+                db_name: "my\tdb".to_string(),
+                scope: management::Scope::Read.into(),
+            }],
+        };
+        let err = ApiToken::try_from(protobuf).unwrap_err();
+        assert_eq!(err.field, "permissions.0.db_name");
+    }
+
+    #[test]
+    fn test_token_allows() {
+        let token = token(vec![
+            Permission {
+                db_name: Some(DatabaseName::new("db1").unwrap()),
+                scope: Scope::Write,
+            },
+            Permission {
+                db_name: None,
+                scope: Scope::Read,
+            },
+        ]);
+
+        assert!(token.allows(Some("db1"), Scope::Read));
+        assert!(token.allows(Some("db1"), Scope::Write));
+        assert!(!token.allows(Some("db1"), Scope::Admin));
+
+        assert!(token.allows(Some("db2"), Scope::Read));
+        assert!(!token.allows(Some("db2"), Scope::Write));
+
+        assert!(token.allows(None, Scope::Read));
+        assert!(!token.allows(None, Scope::Write));
+    }
+}
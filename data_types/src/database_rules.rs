@@ -71,6 +71,9 @@ pub struct DatabaseRules {
     /// shard you would have partitions, which would likely be based off time.
     /// This makes it possible to horizontally scale out writes.
     pub shard_config: Option<ShardConfig>,
+
+    /// Limits on the writes and queries of this database
+    pub limits: Limits,
 }
 
 impl DatabaseRules {
@@ -89,6 +92,7 @@ impl DatabaseRules {
             wal_buffer_config: None,
             lifecycle_rules: Default::default(),
             shard_config: None,
+            limits: Default::default(),
         }
     }
 
@@ -132,6 +136,7 @@ impl From<DatabaseRules> for management::DatabaseRules {
             wal_buffer_config: rules.wal_buffer_config.map(Into::into),
             lifecycle_rules: Some(rules.lifecycle_rules.into()),
             shard_config: rules.shard_config.map(Into::into),
+            limits: Some(rules.limits.into()),
         }
     }
 }
@@ -159,12 +164,15 @@ impl TryFrom<management::DatabaseRules> for DatabaseRules {
             .optional("shard_config")
             .unwrap_or_default();
 
+        let limits = proto.limits.optional("limits")?.unwrap_or_default();
+
         Ok(Self {
             name,
             partition_template,
             wal_buffer_config,
             lifecycle_rules,
             shard_config,
+            limits,
         })
     }
 }
@@ -215,6 +223,30 @@ pub struct LifecycleRules {
 
     /// Do not allow writing new data to this database
     pub immutable: bool,
+
+    /// Once a partition has at least this many persisted chunks that are no
+    /// larger than
+    /// [`persisted_compaction_max_chunk_size`](Self::
+    /// persisted_compaction_max_chunk_size), they are compacted into a
+    /// single chunk
+    pub persisted_compaction_chunk_count: Option<NonZeroU32>,
+
+    /// Only persisted chunks up to this number of bytes are compacted
+    pub persisted_compaction_max_chunk_size: Option<NonZeroUsize>,
+
+    /// The tag columns to sort persisted data on, ahead of any other tag
+    /// columns and time
+    ///
+    /// Tag columns not listed here are sorted on in order of increasing
+    /// cardinality, followed by time
+    pub persisted_sort_key: Vec<String>,
+
+    /// Data with timestamps older than this number of seconds is dropped
+    ///
+    /// Chunks, including their files in object storage, are dropped once all
+    /// of their data is older than the retention period, and writes of data
+    /// older than the retention period are rejected
+    pub retention_period_seconds: Option<NonZeroU32>,
 }
 
 impl From<LifecycleRules> for management::LifecycleRules {
@@ -244,6 +276,19 @@ impl From<LifecycleRules> for management::LifecycleRules {
             drop_non_persisted: config.drop_non_persisted,
             persist: config.persist,
             immutable: config.immutable,
+            persisted_compaction_chunk_count: config
+                .persisted_compaction_chunk_count
+                .map(Into::into)
+                .unwrap_or_default(),
+            persisted_compaction_max_chunk_size: config
+                .persisted_compaction_max_chunk_size
+                .map(|x| x.get() as u64)
+                .unwrap_or_default(),
+            persisted_sort_key: config.persisted_sort_key,
+            retention_period_seconds: config
+                .retention_period_seconds
+                .map(Into::into)
+                .unwrap_or_default(),
         }
     }
 }
@@ -252,6 +297,13 @@ impl TryFrom<management::LifecycleRules> for LifecycleRules {
     type Error = FieldViolation;
 
     fn try_from(proto: management::LifecycleRules) -> Result<Self, Self::Error> {
+        if proto.persisted_compaction_chunk_count == 1 {
+            return Err(FieldViolation {
+                field: "persisted_compaction_chunk_count".to_string(),
+                description: "Must be 0 or at least 2".to_string(),
+            });
+        }
+
         Ok(Self {
             mutable_linger_seconds: proto.mutable_linger_seconds.try_into().ok(),
             mutable_minimum_age_seconds: proto.mutable_minimum_age_seconds.try_into().ok(),
@@ -262,6 +314,80 @@ impl TryFrom<management::LifecycleRules> for LifecycleRules {
             drop_non_persisted: proto.drop_non_persisted,
             persist: proto.persist,
             immutable: proto.immutable,
+            persisted_compaction_chunk_count: proto
+                .persisted_compaction_chunk_count
+                .try_into()
+                .ok(),
+            persisted_compaction_max_chunk_size: (proto.persisted_compaction_max_chunk_size
+                as usize)
+                .try_into()
+                .ok(),
+            persisted_sort_key: proto.persisted_sort_key,
+            retention_period_seconds: proto.retention_period_seconds.try_into().ok(),
+        })
+    }
+}
+
+/// Limits on the writes and queries of a database, so that a single
+/// database can not starve the others of resources. Requests exceeding a
+/// limit are rejected
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Limits {
+    /// The maximum number of queries that run at the same time
+    pub max_concurrent_queries: Option<NonZeroU32>,
+
+    /// The maximum number of rows in a single write
+    pub max_write_rows: Option<NonZeroUsize>,
+
+    /// The maximum number of bytes in a single write, measured as the size
+    /// of the write once encoded as entries
+    pub max_write_bytes: Option<NonZeroUsize>,
+
+    /// The maximum number of bytes written per second, averaged over one
+    /// second and measured like `max_write_bytes`
+    pub write_bytes_per_second: Option<NonZeroUsize>,
+
+    /// Queries running for longer than this are cancelled
+    pub query_timeout_seconds: Option<NonZeroU32>,
+}
+
+impl From<Limits> for management::Limits {
+    fn from(limits: Limits) -> Self {
+        Self {
+            max_concurrent_queries: limits
+                .max_concurrent_queries
+                .map(Into::into)
+                .unwrap_or_default(),
+            max_write_rows: limits
+                .max_write_rows
+                .map(|x| x.get() as u64)
+                .unwrap_or_default(),
+            max_write_bytes: limits
+                .max_write_bytes
+                .map(|x| x.get() as u64)
+                .unwrap_or_default(),
+            write_bytes_per_second: limits
+                .write_bytes_per_second
+                .map(|x| x.get() as u64)
+                .unwrap_or_default(),
+            query_timeout_seconds: limits
+                .query_timeout_seconds
+                .map(Into::into)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<management::Limits> for Limits {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Limits) -> Result<Self, Self::Error> {
+        Ok(Self {
+            max_concurrent_queries: proto.max_concurrent_queries.try_into().ok(),
+            max_write_rows: (proto.max_write_rows as usize).try_into().ok(),
+            max_write_bytes: (proto.max_write_bytes as usize).try_into().ok(),
+            write_bytes_per_second: (proto.write_bytes_per_second as usize).try_into().ok(),
+            query_timeout_seconds: proto.query_timeout_seconds.try_into().ok(),
         })
     }
 }
@@ -1211,6 +1337,7 @@ mod tests {
         // DatabaseRules
         assert_eq!(back.partition_template, Some(Default::default()));
         assert_eq!(back.lifecycle_rules, Some(LifecycleRules::default().into()));
+        assert_eq!(back.limits, Some(Limits::default().into()));
 
         // These should be none as preserved on non-protobuf DatabaseRules
         assert!(back.wal_buffer_config.is_none());
@@ -1363,6 +1490,10 @@ mod tests {
             drop_non_persisted: true,
             persist: true,
             immutable: true,
+            persisted_compaction_chunk_count: 10,
+            persisted_compaction_max_chunk_size: 1024,
+            persisted_sort_key: vec!["region".to_string(), "host".to_string()],
+            retention_period_seconds: 86400,
         };
 
         let config: LifecycleRules = protobuf.clone().try_into().unwrap();
@@ -1391,6 +1522,19 @@ mod tests {
         );
         assert_eq!(config.drop_non_persisted, protobuf.drop_non_persisted);
         assert_eq!(config.immutable, protobuf.immutable);
+        assert_eq!(
+            config.persisted_compaction_chunk_count.unwrap().get(),
+            protobuf.persisted_compaction_chunk_count
+        );
+        assert_eq!(
+            config.persisted_compaction_max_chunk_size.unwrap().get(),
+            protobuf.persisted_compaction_max_chunk_size as usize
+        );
+        assert_eq!(config.persisted_sort_key, protobuf.persisted_sort_key);
+        assert_eq!(
+            config.retention_period_seconds.unwrap().get(),
+            protobuf.retention_period_seconds
+        );
 
         assert_eq!(back.mutable_linger_seconds, protobuf.mutable_linger_seconds);
         assert_eq!(
@@ -1402,6 +1546,54 @@ mod tests {
         assert_eq!(back.buffer_size_hard, protobuf.buffer_size_hard);
         assert_eq!(back.drop_non_persisted, protobuf.drop_non_persisted);
         assert_eq!(back.immutable, protobuf.immutable);
+        assert_eq!(
+            back.persisted_compaction_chunk_count,
+            protobuf.persisted_compaction_chunk_count
+        );
+        assert_eq!(
+            back.persisted_compaction_max_chunk_size,
+            protobuf.persisted_compaction_max_chunk_size
+        );
+        assert_eq!(back.persisted_sort_key, protobuf.persisted_sort_key);
+        assert_eq!(
+            back.retention_period_seconds,
+            protobuf.retention_period_seconds
+        );
+    }
+
+    #[test]
+    fn lifecycle_rules_compact_single_chunk() {
+        let protobuf = management::LifecycleRules {
+            persisted_compaction_chunk_count: 1,
+            ..Default::default()
+        };
+
+        let res: Result<LifecycleRules, _> = protobuf.try_into();
+        let err = res.expect_err("compacting a single chunk should be rejected");
+        assert_eq!(&err.field, "persisted_compaction_chunk_count");
+        assert_eq!(&err.description, "Must be 0 or at least 2");
+    }
+
+    #[test]
+    fn limits() {
+        let protobuf = management::Limits {
+            max_concurrent_queries: 4,
+            max_write_rows: 1000,
+            max_write_bytes: 0,
+            write_bytes_per_second: 1024,
+            query_timeout_seconds: 30,
+        };
+
+        let limits: Limits = protobuf.clone().try_into().unwrap();
+        let back: management::Limits = limits.clone().into();
+
+        assert_eq!(limits.max_concurrent_queries.unwrap().get(), 4);
+        assert_eq!(limits.max_write_rows.unwrap().get(), 1000);
+        assert_eq!(limits.max_write_bytes, None);
+        assert_eq!(limits.write_bytes_per_second.unwrap().get(), 1024);
+        assert_eq!(limits.query_timeout_seconds.unwrap().get(), 30);
+
+        assert_eq!(back, protobuf);
     }
 
     #[test]
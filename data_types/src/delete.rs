@@ -0,0 +1,217 @@
+//! Delete predicates, which describe rows of a table that have been deleted
+use std::convert::TryFrom;
+
+use generated_types::{google::FieldViolation, influxdata::iox::management::v1 as management};
+
+use crate::field_validation::{FromField, FromFieldString, FromFieldVec};
+use crate::timestamp::TimestampRange;
+
+/// Describes the rows of a table that are deleted: all rows with a
+/// timestamp within `range` that match every expression in `exprs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletePredicate {
+    /// The table the rows are deleted from
+    pub table_name: String,
+
+    /// Only rows with a timestamp within this range are deleted
+    pub range: TimestampRange,
+
+    /// Only rows matching all of these expressions are deleted
+    pub exprs: Vec<DeleteExpr>,
+}
+
+/// Compares the value of a column with a constant string. A row without a
+/// value for the column compares as if its value were the empty string, as
+/// is the case for tags in InfluxDB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteExpr {
+    /// The name of the column
+    pub column: String,
+
+    /// The comparison operator
+    pub op: DeleteOp,
+
+    /// The value the column is compared with
+    pub value: String,
+}
+
+/// The comparison operator of a `DeleteExpr`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOp {
+    /// The column value is equal to the expression value
+    Eq,
+    /// The column value is not equal to the expression value
+    Ne,
+}
+
+impl DeleteExpr {
+    /// Returns true if a row with the column value `value` (`None` if the
+    /// row has no value for the column) matches this expression
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        let value = value.unwrap_or("");
+        match self.op {
+            DeleteOp::Eq => value == self.value,
+            DeleteOp::Ne => value != self.value,
+        }
+    }
+}
+
+impl From<DeletePredicate> for management::DeletePredicate {
+    fn from(predicate: DeletePredicate) -> Self {
+        Self {
+            table_name: predicate.table_name,
+            start: predicate.range.start,
+            stop: predicate.range.end,
+            exprs: predicate.exprs.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<management::DeletePredicate> for DeletePredicate {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::DeletePredicate) -> Result<Self, Self::Error> {
+        if proto.stop < proto.start {
+            return Err(FieldViolation {
+                field: "stop".to_string(),
+                description: "Stop must not be before start".to_string(),
+            });
+        }
+
+        Ok(Self {
+            table_name: proto.table_name.required("table_name")?,
+            range: TimestampRange::new(proto.start, proto.stop),
+            exprs: proto.exprs.vec_field("exprs")?,
+        })
+    }
+}
+
+impl From<DeleteExpr> for management::DeleteExpr {
+    fn from(expr: DeleteExpr) -> Self {
+        Self {
+            column: expr.column,
+            op: management::DeleteOp::from(expr.op).into(),
+            value: expr.value,
+        }
+    }
+}
+
+impl TryFrom<management::DeleteExpr> for DeleteExpr {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::DeleteExpr) -> Result<Self, Self::Error> {
+        let op = proto.op().scope("op")?;
+
+        Ok(Self {
+            column: proto.column.required("column")?,
+            op,
+            value: proto.value,
+        })
+    }
+}
+
+impl From<DeleteOp> for management::DeleteOp {
+    fn from(op: DeleteOp) -> Self {
+        match op {
+            DeleteOp::Eq => Self::Eq,
+            DeleteOp::Ne => Self::Ne,
+        }
+    }
+}
+
+impl TryFrom<management::DeleteOp> for DeleteOp {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::DeleteOp) -> Result<Self, Self::Error> {
+        match proto {
+            management::DeleteOp::Eq => Ok(Self::Eq),
+            management::DeleteOp::Ne => Ok(Self::Ne),
+            management::DeleteOp::Unspecified => Err(FieldViolation::required("")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_delete_predicate_roundtrip() {
+        let predicate = DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(10, 20),
+            exprs: vec![
+                DeleteExpr {
+                    column: "host".to_string(),
+                    op: DeleteOp::Eq,
+                    value: "a".to_string(),
+                },
+                DeleteExpr {
+                    column: "region".to_string(),
+                    op: DeleteOp::Ne,
+                    value: "".to_string(),
+                },
+            ],
+        };
+
+        let protobuf: management::DeletePredicate = predicate.clone().into();
+        let back: DeletePredicate = protobuf.try_into().unwrap();
+        assert_eq!(predicate, back);
+    }
+
+    #[test]
+    fn test_delete_predicate_invalid() {
+        let protobuf = management::DeletePredicate {
+            table_name: "".to_string(),
+            start: 10,
+            stop: 20,
+            exprs: vec![],
+        };
+        let err = DeletePredicate::try_from(protobuf).unwrap_err();
+        assert_eq!(err.field, "table_name");
+
+        let protobuf = management::DeletePredicate {
+            table_name: "cpu".to_string(),
+            start: 20,
+            stop: 10,
+            exprs: vec![],
+        };
+        let err = DeletePredicate::try_from(protobuf).unwrap_err();
+        assert_eq!(err.field, "stop");
+
+        let protobuf = management::DeletePredicate {
+            table_name: "cpu".to_string(),
+            start: 10,
+            stop: 20,
+            exprs: vec![management::DeleteExpr {
+                column: "host".to_string(),
+                op: management::DeleteOp::Unspecified.into(),
+                value: "a".to_string(),
+            }],
+        };
+        let err = DeletePredicate::try_from(protobuf).unwrap_err();
+        assert_eq!(err.field, "exprs.0.op");
+    }
+
+    #[test]
+    fn test_delete_expr_matches() {
+        let expr = DeleteExpr {
+            column: "host".to_string(),
+            op: DeleteOp::Eq,
+            value: "a".to_string(),
+        };
+        assert!(expr.matches(Some("a")));
+        assert!(!expr.matches(Some("b")));
+        assert!(!expr.matches(None));
+
+        let expr = DeleteExpr {
+            column: "host".to_string(),
+            op: DeleteOp::Ne,
+            value: "".to_string(),
+        };
+        assert!(expr.matches(Some("a")));
+        assert!(!expr.matches(Some("")));
+        assert!(!expr.matches(None));
+    }
+}
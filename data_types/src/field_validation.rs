@@ -89,7 +89,7 @@ impl FromFieldString for String {
 
 /// An extension trait that adds the method `vec_field` to any Vec of a type
 /// implementing `TryInto<U, Error = FieldViolation>`
-pub(crate) trait FromFieldVec<T> {
+pub trait FromFieldVec<T> {
     /// Converts to a `Vec<U>`, short-circuiting on the first error and
     /// returning a correctly scoped `FieldViolation` for where the error
     /// was encountered
@@ -2,6 +2,11 @@
 use crate::wal::SegmentSummary;
 use serde::{Deserialize, Serialize};
 
+/// The precision of the timestamps of the write APIs, given by their
+/// `precision` query parameter. It is defined alongside the line
+/// protocol parser, so that `LineBuilder` can write timestamps with it.
+pub use influxdb_line_protocol::Precision;
+
 /// Query string for WAL metadata endpoint
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Eq, PartialEq)]
 pub struct WalMetadataQuery {
@@ -34,6 +34,62 @@ pub enum Job {
         partition_key: String,
         chunk_id: u32,
     },
+
+    /// Delete files in object store that are no longer referenced by the
+    /// catalog
+    CollectGarbage {
+        db_name: String,
+    },
+
+    /// Compact persisted chunks of a partition into a single chunk
+    CompactChunks {
+        db_name: String,
+        partition_key: String,
+        chunk_ids: Vec<u32>,
+    },
+
+    /// Drop a chunk from the catalog and all storage systems
+    DropChunk {
+        db_name: String,
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    /// Write a backup of the persisted chunks of a database
+    BackupDatabase {
+        db_name: String,
+        path: String,
+    },
+
+    /// Restore a database from a backup
+    RestoreDatabase {
+        db_name: String,
+        path: String,
+    },
+
+    /// A query of a database
+    Query {
+        db_name: String,
+        query: String,
+    },
+}
+
+impl Job {
+    /// Returns the name of the database this job operates on, if any
+    pub fn db_name(&self) -> Option<&str> {
+        match self {
+            Self::Dummy { .. } => None,
+            Self::PersistSegment { .. } => None,
+            Self::CloseChunk { db_name, .. } => Some(db_name),
+            Self::WriteChunk { db_name, .. } => Some(db_name),
+            Self::CollectGarbage { db_name } => Some(db_name),
+            Self::CompactChunks { db_name, .. } => Some(db_name),
+            Self::DropChunk { db_name, .. } => Some(db_name),
+            Self::BackupDatabase { db_name, .. } => Some(db_name),
+            Self::RestoreDatabase { db_name, .. } => Some(db_name),
+            Self::Query { db_name, .. } => Some(db_name),
+        }
+    }
 }
 
 impl From<Job> for management::operation_metadata::Job {
@@ -65,6 +121,34 @@ impl From<Job> for management::operation_metadata::Job {
                 partition_key,
                 chunk_id,
             }),
+            Job::CollectGarbage { db_name } => {
+                Self::CollectGarbage(management::CollectGarbage { db_name })
+            }
+            Job::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            } => Self::CompactChunks(management::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            }),
+            Job::DropChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            } => Self::DropChunk(management::DropChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            }),
+            Job::BackupDatabase { db_name, path } => {
+                Self::BackupDatabase(management::BackupDatabase { db_name, path })
+            }
+            Job::RestoreDatabase { db_name, path } => {
+                Self::RestoreDatabase(management::RestoreDatabase { db_name, path })
+            }
+            Job::Query { db_name, query } => Self::Query(management::Query { db_name, query }),
         }
     }
 }
@@ -99,6 +183,34 @@ impl From<management::operation_metadata::Job> for Job {
                 partition_key,
                 chunk_id,
             },
+            Job::CollectGarbage(management::CollectGarbage { db_name }) => {
+                Self::CollectGarbage { db_name }
+            }
+            Job::CompactChunks(management::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            }) => Self::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            },
+            Job::DropChunk(management::DropChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            }) => Self::DropChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            },
+            Job::BackupDatabase(management::BackupDatabase { db_name, path }) => {
+                Self::BackupDatabase { db_name, path }
+            }
+            Job::RestoreDatabase(management::RestoreDatabase { db_name, path }) => {
+                Self::RestoreDatabase { db_name, path }
+            }
+            Job::Query(management::Query { db_name, query }) => Self::Query { db_name, query },
         }
     }
 }
@@ -12,13 +12,16 @@
 
 pub use database_name::*;
 
+pub mod auth;
 pub mod chunk;
 pub mod database_rules;
+pub mod delete;
 pub mod error;
 pub mod http;
 pub mod job;
 pub mod names;
 pub mod partition_metadata;
+pub mod server_config;
 pub mod timestamp;
 pub mod wal;
 
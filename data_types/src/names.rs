@@ -29,6 +29,30 @@ pub fn org_and_bucket_to_database<'a, O: AsRef<str>, B: AsRef<str>>(
     DatabaseName::new(db_name).context(InvalidDatabaseName)
 }
 
+/// The name of the default retention policy in InfluxDB 1.x.
+const DEFAULT_RETENTION_POLICY: &str = "autogen";
+
+/// Map an InfluxDB 1.X database & retention policy into an IOx DatabaseName.
+///
+/// A retention policy other than the default (`autogen`) is mapped as
+/// though it was a bucket of an org named after the database, as per
+/// [`org_and_bucket_to_database`]. Without a retention policy (or with
+/// the default one), only the database name is used.
+pub fn db_and_rp_to_database<'a, D: AsRef<str>>(
+    db: D,
+    rp: Option<&str>,
+) -> Result<DatabaseName<'a>, OrgBucketMappingError> {
+    match rp {
+        Some(rp) if !rp.is_empty() && rp != DEFAULT_RETENTION_POLICY => {
+            org_and_bucket_to_database(db, rp)
+        }
+        _ => {
+            let db: Cow<'_, str> = utf8_percent_encode(db.as_ref(), NON_ALPHANUMERIC).into();
+            DatabaseName::new(db.into_owned()).context(InvalidDatabaseName)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,6 +88,30 @@ mod tests {
         assert_eq!(got.as_str(), "my%255Forg%5F_bucket");
     }
 
+    #[test]
+    fn test_db_rp_map_db_ok() {
+        let got = db_and_rp_to_database("telegraf", None).unwrap();
+        assert_eq!(got.as_str(), "telegraf");
+
+        let got = db_and_rp_to_database("telegraf", Some("autogen")).unwrap();
+        assert_eq!(got.as_str(), "telegraf");
+
+        let got = db_and_rp_to_database("telegraf", Some("")).unwrap();
+        assert_eq!(got.as_str(), "telegraf");
+
+        let got = db_and_rp_to_database("telegraf", Some("one_week")).unwrap();
+        assert_eq!(got.as_str(), "telegraf_one%5Fweek");
+    }
+
+    #[test]
+    fn test_db_rp_map_db_contains_underscore() {
+        let got = db_and_rp_to_database("my_db", None).unwrap();
+        assert_eq!(got.as_str(), "my%5Fdb");
+
+        let got = db_and_rp_to_database("my_db", Some("rp")).unwrap();
+        assert_eq!(got.as_str(), "my%5Fdb_rp");
+    }
+
     #[test]
     fn test_bad_database_name_is_encoded() {
         let got = org_and_bucket_to_database("org", "bucket?").unwrap();
@@ -3,6 +3,7 @@
 
 use std::fmt::{Debug, Display};
 use std::mem;
+use std::num::NonZeroU64;
 
 use serde::{Deserialize, Serialize};
 
@@ -125,11 +126,19 @@ impl ColumnSummary {
         mem::size_of::<Self>() + self.name.len() + mem::size_of_val(&self.stats)
     }
 
-    // Updates statistics from other if the same type, otherwise a noop
+    /// Returns the number of distinct non-nil values in this column, if known
+    pub fn distinct_count(&self) -> Option<NonZeroU64> {
+        self.stats.distinct_count()
+    }
+
+    // Updates statistics from other if the same type, otherwise a noop. The
+    // distinct counts of two summaries cannot be combined, so the distinct
+    // count is reset.
     pub fn update_from(&mut self, other: &Self) {
         match (&mut self.stats, &other.stats) {
             (Statistics::F64(s), Statistics::F64(o)) => {
                 s.count += o.count;
+                s.distinct_count = None;
                 if o.min < s.min {
                     s.min = o.min;
                 }
@@ -139,6 +148,7 @@ impl ColumnSummary {
             }
             (Statistics::I64(s), Statistics::I64(o)) => {
                 s.count += o.count;
+                s.distinct_count = None;
                 if o.min < s.min {
                     s.min = o.min;
                 }
@@ -148,6 +158,7 @@ impl ColumnSummary {
             }
             (Statistics::Bool(s), Statistics::Bool(o)) => {
                 s.count += o.count;
+                s.distinct_count = None;
                 if s.min {
                     s.min = o.min
                 }
@@ -157,6 +168,7 @@ impl ColumnSummary {
             }
             (Statistics::String(s), Statistics::String(o)) => {
                 s.count += o.count;
+                s.distinct_count = None;
                 if o.min < s.min {
                     s.min = o.min.clone();
                 }
@@ -166,6 +178,7 @@ impl ColumnSummary {
             }
             (Statistics::U64(s), Statistics::U64(o)) => {
                 s.count += o.count;
+                s.distinct_count = None;
                 if o.min < s.min {
                     s.min = o.min;
                 }
@@ -219,6 +232,28 @@ impl Statistics {
             Self::String(s) => s.count,
         }
     }
+
+    /// Returns the number of distinct non-nil values in this column, if known
+    pub fn distinct_count(&self) -> Option<NonZeroU64> {
+        match self {
+            Self::I64(s) => s.distinct_count,
+            Self::U64(s) => s.distinct_count,
+            Self::F64(s) => s.distinct_count,
+            Self::Bool(s) => s.distinct_count,
+            Self::String(s) => s.distinct_count,
+        }
+    }
+
+    /// Sets the number of distinct non-nil values in this column
+    pub fn set_distinct_count(&mut self, distinct_count: Option<NonZeroU64>) {
+        match self {
+            Self::I64(s) => s.distinct_count = distinct_count,
+            Self::U64(s) => s.distinct_count = distinct_count,
+            Self::F64(s) => s.distinct_count = distinct_count,
+            Self::Bool(s) => s.distinct_count = distinct_count,
+            Self::String(s) => s.distinct_count = distinct_count,
+        }
+    }
 }
 
 /// Summary statistics for a column.
@@ -228,6 +263,9 @@ pub struct StatValues<T: PartialEq + PartialOrd + Debug + Display + Clone> {
     pub max: T,
     /// number of non-nil values in this column
     pub count: u64,
+    /// number of distinct non-nil values in this column, if known
+    #[serde(default)]
+    pub distinct_count: Option<NonZeroU64>,
 }
 
 impl<T> StatValues<T>
@@ -239,6 +277,7 @@ where
             min: starting_value.clone(),
             max: starting_value,
             count: 1,
+            distinct_count: None,
         }
     }
 
@@ -388,7 +427,8 @@ mod tests {
             Statistics::String(StatValues {
                 min: "aaa".to_string(),
                 max: "zzz".to_string(),
-                count: 4
+                count: 4,
+                distinct_count: None,
             })
         );
 
@@ -398,7 +438,8 @@ mod tests {
             Statistics::I64(StatValues {
                 min: 1,
                 max: 9,
-                count: 4
+                count: 4,
+                distinct_count: None,
             })
         );
 
@@ -408,7 +449,8 @@ mod tests {
             Statistics::F64(StatValues {
                 min: 1.3,
                 max: 9.1,
-                count: 2
+                count: 2,
+                distinct_count: None,
             })
         );
 
@@ -419,7 +461,8 @@ mod tests {
             Statistics::String(StatValues {
                 min: "aaa".to_string(),
                 max: "zzz".to_string(),
-                count: 4
+                count: 4,
+                distinct_count: None,
             })
         );
 
@@ -429,7 +472,8 @@ mod tests {
             Statistics::I64(StatValues {
                 min: 1,
                 max: 9,
-                count: 4
+                count: 4,
+                distinct_count: None,
             })
         );
 
@@ -439,7 +483,8 @@ mod tests {
             Statistics::F64(StatValues {
                 min: 1.3,
                 max: 9.1,
-                count: 2
+                count: 2,
+                distinct_count: None,
             })
         );
     }
@@ -499,7 +544,8 @@ mod tests {
             Statistics::String(StatValues {
                 min: "bar".to_string(),
                 max: "foo".to_string(),
-                count: 2
+                count: 2,
+                distinct_count: None,
             })
         );
         let col = t.column("int").unwrap();
@@ -508,7 +554,8 @@ mod tests {
             Statistics::I64(StatValues {
                 min: 1,
                 max: 10,
-                count: 3
+                count: 3,
+                distinct_count: None,
             })
         );
         let t = partition.table("b").unwrap();
@@ -518,7 +565,8 @@ mod tests {
             Statistics::I64(StatValues {
                 min: 10,
                 max: 203,
-                count: 2
+                count: 2,
+                distinct_count: None,
             })
         );
     }
@@ -531,6 +579,7 @@ mod tests {
                 min: false,
                 max: false,
                 count: 1,
+                distinct_count: None,
             }),
         };
         let bool_true = ColumnSummary {
@@ -539,6 +588,7 @@ mod tests {
                 min: true,
                 max: true,
                 count: 1,
+                distinct_count: None,
             }),
         };
 
@@ -546,6 +596,7 @@ mod tests {
             min: false,
             max: true,
             count: 2,
+            distinct_count: None,
         });
 
         let mut b = bool_false.clone();
@@ -565,6 +616,7 @@ mod tests {
                 min: 5,
                 max: 23,
                 count: 1,
+                distinct_count: None,
             }),
         };
 
@@ -574,6 +626,7 @@ mod tests {
                 min: 6,
                 max: 506,
                 count: 43,
+                distinct_count: None,
             }),
         };
 
@@ -583,7 +636,35 @@ mod tests {
             min: 5,
             max: 506,
             count: 44,
+            distinct_count: None,
         });
         assert_eq!(min.stats, expected);
     }
+    #[test]
+    fn column_update_from_resets_distinct_count() {
+        let mut a = ColumnSummary {
+            name: "host".to_string(),
+            stats: Statistics::String(StatValues {
+                min: "a".to_string(),
+                max: "b".to_string(),
+                count: 2,
+                distinct_count: NonZeroU64::new(2),
+            }),
+        };
+        assert_eq!(a.distinct_count(), NonZeroU64::new(2));
+
+        let b = ColumnSummary {
+            name: "host".to_string(),
+            stats: Statistics::String(StatValues {
+                min: "b".to_string(),
+                max: "c".to_string(),
+                count: 2,
+                distinct_count: NonZeroU64::new(2),
+            }),
+        };
+
+        a.update_from(&b);
+        assert_eq!(a.count(), 4);
+        assert_eq!(a.distinct_count(), None);
+    }
 }
@@ -0,0 +1,99 @@
+//! The configuration of a server that is persisted in object storage, so that
+//! it survives restarts
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use snafu::Snafu;
+
+use generated_types::influxdata::iox::management::v1 as management;
+
+use crate::database_rules::WriterId;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(context(false))]
+    ProstDecodeError { source: prost::DecodeError },
+
+    #[snafu(context(false))]
+    ProstEncodeError { source: prost::EncodeError },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The writer id and remotes of a server
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Incremented on every change, so that a server can detect that another
+    /// server changed the stored configuration since it last read it
+    pub version: u64,
+
+    /// The writer id of the server, if set
+    pub writer_id: Option<NonZeroU32>,
+
+    /// The gRPC connection strings of remote servers
+    pub remotes: BTreeMap<WriterId, String>,
+}
+
+impl ServerConfig {
+    pub fn decode(bytes: prost::bytes::Bytes) -> Result<Self> {
+        let message: management::ServerConfig = prost::Message::decode(bytes)?;
+        Ok(message.into())
+    }
+
+    pub fn encode(self, bytes: &mut prost::bytes::BytesMut) -> Result<()> {
+        let encoded: management::ServerConfig = self.into();
+        Ok(prost::Message::encode(&encoded, bytes)?)
+    }
+}
+
+impl From<ServerConfig> for management::ServerConfig {
+    fn from(config: ServerConfig) -> Self {
+        Self {
+            version: config.version,
+            writer_id: config.writer_id.map(NonZeroU32::get).unwrap_or_default(),
+            remotes: config.remotes.into_iter().collect(),
+        }
+    }
+}
+
+impl From<management::ServerConfig> for ServerConfig {
+    fn from(proto: management::ServerConfig) -> Self {
+        Self {
+            version: proto.version,
+            writer_id: NonZeroU32::new(proto.writer_id),
+            remotes: proto.remotes.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_config_roundtrip() {
+        let mut remotes = BTreeMap::new();
+        remotes.insert(2, "http://127.0.0.1:8082".to_string());
+        remotes.insert(3, "http://127.0.0.1:8092".to_string());
+
+        let config = ServerConfig {
+            version: 4,
+            writer_id: NonZeroU32::new(1),
+            remotes,
+        };
+
+        let mut bytes = prost::bytes::BytesMut::new();
+        config.clone().encode(&mut bytes).unwrap();
+        let back = ServerConfig::decode(bytes.freeze()).unwrap();
+
+        assert_eq!(config, back);
+    }
+
+    #[test]
+    fn test_server_config_without_writer_id() {
+        let protobuf: management::ServerConfig = ServerConfig::default().into();
+        assert_eq!(protobuf.writer_id, 0);
+
+        let back: ServerConfig = protobuf.into();
+        assert_eq!(back.writer_id, None);
+    }
+}
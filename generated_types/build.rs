@@ -19,11 +19,13 @@ fn main() -> Result<()> {
 /// - `influxdata.platform.storage.rs`
 /// - `com.github.influxdata.idpe.storage.read.rs`
 /// - `influxdata.iox.management.v1.rs`
+/// - `influxdata.iox.catalog.v1.rs`
 fn generate_grpc_types(root: &Path) -> Result<()> {
     let storage_path = root.join("influxdata/platform/storage");
     let idpe_path = root.join("com/github/influxdata/idpe/storage/read");
     let management_path = root.join("influxdata/iox/management/v1");
     let write_path = root.join("influxdata/iox/write/v1");
+    let catalog_path = root.join("influxdata/iox/catalog/v1");
 
     let proto_files = vec![
         storage_path.join("test.proto"),
@@ -39,7 +41,10 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         management_path.join("service.proto"),
         management_path.join("shard.proto"),
         management_path.join("jobs.proto"),
+        management_path.join("token.proto"),
+        management_path.join("server_config.proto"),
         write_path.join("service.proto"),
+        catalog_path.join("catalog.proto"),
         root.join("grpc/health/v1/service.proto"),
         root.join("google/longrunning/operations.proto"),
         root.join("google/rpc/error_details.proto"),
@@ -38,12 +38,16 @@ impl From<prost::EncodeError> for EncodeError {
 }
 
 fn encode_status(code: tonic::Code, message: String, details: Any) -> tonic::Status {
+    encode_status_details(code, message, vec![details])
+}
+
+fn encode_status_details(code: tonic::Code, message: String, details: Vec<Any>) -> tonic::Status {
     let mut buffer = BytesMut::new();
 
     let status = rpc::Status {
         code: code as i32,
         message: message.clone(),
-        details: vec![details],
+        details,
     };
 
     match status.encode(&mut buffer) {
@@ -279,13 +283,53 @@ where
 pub struct QuotaFailure {
     pub subject: String,
     pub description: String,
+    /// How long the client should wait before retrying the request, if
+    /// retrying it unchanged can succeed
+    pub retry_delay: Option<std::time::Duration>,
+}
+
+fn encode_quota_failure(quota_failure: QuotaFailure) -> Result<Vec<Any>, EncodeError> {
+    use rpc::quota_failure::Violation;
+
+    let mut buffer = BytesMut::new();
+
+    rpc::QuotaFailure {
+        violations: vec![Violation {
+            subject: quota_failure.subject,
+            description: quota_failure.description,
+        }],
+    }
+    .encode(&mut buffer)?;
+
+    let mut details = vec![Any {
+        type_url: "type.googleapis.com/google.rpc.QuotaFailure".to_string(),
+        value: buffer.freeze(),
+    }];
+
+    if let Some(retry_delay) = quota_failure.retry_delay {
+        let mut buffer = BytesMut::new();
+
+        rpc::RetryInfo {
+            retry_delay: Some(retry_delay.into()),
+        }
+        .encode(&mut buffer)?;
+
+        details.push(Any {
+            type_url: "type.googleapis.com/google.rpc.RetryInfo".to_string(),
+            value: buffer.freeze(),
+        });
+    }
+
+    Ok(details)
 }
 
 impl From<QuotaFailure> for tonic::Status {
     fn from(quota_failure: QuotaFailure) -> Self {
-        tonic::Status::new(
-            tonic::Code::ResourceExhausted,
-            format!("{}: {}", quota_failure.subject, quota_failure.description),
-        )
+        let message = format!("{}: {}", quota_failure.subject, quota_failure.description);
+
+        match encode_quota_failure(quota_failure) {
+            Ok(details) => encode_status_details(tonic::Code::ResourceExhausted, message, details),
+            Err(e) => e.into(),
+        }
     }
 }
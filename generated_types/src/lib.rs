@@ -45,6 +45,12 @@ pub mod influxdata {
                 include!(concat!(env!("OUT_DIR"), "/influxdata.iox.write.v1.rs"));
             }
         }
+
+        pub mod catalog {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.catalog.v1.rs"));
+            }
+        }
     }
 }
 
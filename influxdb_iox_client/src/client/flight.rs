@@ -19,6 +19,7 @@ use arrow_deps::{
 };
 
 use crate::connection::Connection;
+use tonic::transport::Channel;
 
 /// Error responses when querying an IOx database using the Arrow Flight gRPC
 /// API.
@@ -81,14 +82,15 @@ pub enum Error {
 /// ```
 #[derive(Debug)]
 pub struct Client {
-    inner: FlightServiceClient<Connection>,
+    inner: FlightServiceClient<Channel>,
 }
 
 impl Client {
     /// Creates a new client with the provided connection
-    pub fn new(channel: Connection) -> Self {
+    pub fn new(connection: impl Into<Connection>) -> Self {
+        let (channel, interceptor) = connection.into().into_parts();
         Self {
-            inner: FlightServiceClient::new(channel),
+            inner: FlightServiceClient::with_interceptor(channel, interceptor),
         }
     }
 
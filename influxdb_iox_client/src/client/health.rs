@@ -3,6 +3,7 @@ use thiserror::Error;
 use generated_types::grpc::health::v1::*;
 
 use crate::connection::Connection;
+use tonic::transport::Channel;
 
 /// Error type for the health check client
 #[derive(Debug, Error)]
@@ -32,14 +33,15 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// Allows checking the status of a given service
 #[derive(Debug)]
 pub struct Client {
-    inner: health_client::HealthClient<Connection>,
+    inner: health_client::HealthClient<Channel>,
 }
 
 impl Client {
     /// Creates a new client with the provided connection
-    pub fn new(channel: Connection) -> Self {
+    pub fn new(connection: impl Into<Connection>) -> Self {
+        let (channel, interceptor) = connection.into().into_parts();
         Self {
-            inner: health_client::HealthClient::new(channel),
+            inner: health_client::HealthClient::with_interceptor(channel, interceptor),
         }
     }
 
@@ -4,6 +4,7 @@ use self::generated_types::{management_service_client::ManagementServiceClient,
 
 use crate::connection::Connection;
 use ::generated_types::google::longrunning::Operation;
+use tonic::transport::Channel;
 
 use std::convert::TryInto;
 use std::num::NonZeroU32;
@@ -73,6 +74,42 @@ pub enum UpdateDatabaseError {
     ServerError(tonic::Status),
 }
 
+/// Errors returned by Client::delete_database
+#[derive(Debug, Error)]
+pub enum DeleteDatabaseError {
+    /// Writer ID is not set
+    #[error("Writer ID not set")]
+    NoWriterId,
+
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::delete
+#[derive(Debug, Error)]
+pub enum DeleteError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Server returned an invalid argument error
+    #[error("Invalid delete predicate: {}", .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
 /// Errors returned by Client::list_databases
 #[derive(Debug, Error)]
 pub enum ListDatabaseError {
@@ -201,6 +238,126 @@ pub enum ClosePartitionChunkError {
     ServerError(tonic::Status),
 }
 
+/// Errors returned by Client::drop_partition_chunk
+#[derive(Debug, Error)]
+pub enum DropPartitionChunkError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::persist_partition_chunk
+#[derive(Debug, Error)]
+pub enum PersistPartitionChunkError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::find_orphaned_files and Client::collect_garbage
+#[derive(Debug, Error)]
+pub enum CollectGarbageError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::backup_database
+#[derive(Debug, Error)]
+pub enum BackupDatabaseError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::restore_database
+#[derive(Debug, Error)]
+pub enum RestoreDatabaseError {
+    /// Database already exists
+    #[error("Database already exists")]
+    DatabaseAlreadyExists,
+
+    /// The backup could not be restored, e.g. because it could not be read
+    #[error("Can not restore backup: {}", .0.message())]
+    FailedPrecondition(tonic::Status),
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::create_token
+#[derive(Debug, Error)]
+pub enum CreateTokenError {
+    /// Server returned an invalid argument error
+    #[error("Invalid token permissions: {}", .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::list_tokens
+#[derive(Debug, Error)]
+pub enum ListTokensError {
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::revoke_token
+#[derive(Debug, Error)]
+pub enum RevokeTokenError {
+    /// Token not found
+    #[error("Token not found")]
+    TokenNotFound,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
 /// An IOx Management API client.
 ///
 /// This client wraps the underlying `tonic` generated client with a
@@ -233,14 +390,15 @@ pub enum ClosePartitionChunkError {
 /// ```
 #[derive(Debug, Clone)]
 pub struct Client {
-    inner: ManagementServiceClient<Connection>,
+    inner: ManagementServiceClient<Channel>,
 }
 
 impl Client {
     /// Creates a new client with the provided connection
-    pub fn new(channel: tonic::transport::Channel) -> Self {
+    pub fn new(connection: impl Into<Connection>) -> Self {
+        let (channel, interceptor) = connection.into().into_parts();
         Self {
-            inner: ManagementServiceClient::new(channel),
+            inner: ManagementServiceClient::with_interceptor(channel, interceptor),
         }
     }
 
@@ -310,6 +468,26 @@ impl Client {
         Ok(response.into_inner().rules.unwrap())
     }
 
+    /// Deletes a database, including its rules and data in object storage
+    pub async fn delete_database(
+        &mut self,
+        db_name: impl Into<String>,
+    ) -> Result<(), DeleteDatabaseError> {
+        self.inner
+            .delete_database(DeleteDatabaseRequest {
+                db_name: db_name.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => DeleteDatabaseError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => DeleteDatabaseError::NoWriterId,
+                tonic::Code::InvalidArgument => DeleteDatabaseError::InvalidArgument(status),
+                _ => DeleteDatabaseError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
     /// List databases.
     pub async fn list_databases(&mut self) -> Result<Vec<String>, ListDatabaseError> {
         let response = self
@@ -535,4 +713,256 @@ impl Client {
             .operation
             .ok_or(ClosePartitionChunkError::EmptyResponse)?)
     }
+
+    /// Drops the specified chunk in the specified partition from the
+    /// catalog and all storage systems.
+    ///
+    /// Returns the job tracking the chunk's removal
+    pub async fn drop_partition_chunk(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+    ) -> Result<Operation, DropPartitionChunkError> {
+        let db_name = db_name.into();
+        let partition_key = partition_key.into();
+
+        let response = self
+            .inner
+            .drop_partition_chunk(DropPartitionChunkRequest {
+                db_name,
+                partition_key,
+                chunk_id,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => DropPartitionChunkError::DatabaseNotFound,
+                _ => DropPartitionChunkError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .ok_or(DropPartitionChunkError::EmptyResponse)?)
+    }
+
+    /// Writes the specified chunk in the specified partition from the
+    /// read buffer to object storage.
+    ///
+    /// Returns the job tracking the data's persistence
+    pub async fn persist_partition_chunk(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+    ) -> Result<Operation, PersistPartitionChunkError> {
+        let db_name = db_name.into();
+        let partition_key = partition_key.into();
+
+        let response = self
+            .inner
+            .persist_partition_chunk(PersistPartitionChunkRequest {
+                db_name,
+                partition_key,
+                chunk_id,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => PersistPartitionChunkError::DatabaseNotFound,
+                _ => PersistPartitionChunkError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .ok_or(PersistPartitionChunkError::EmptyResponse)?)
+    }
+
+    /// Deletes the rows described by `predicate` from the specified
+    /// database.
+    ///
+    /// Only rows written before the request are deleted.
+    pub async fn delete(
+        &mut self,
+        db_name: impl Into<String>,
+        predicate: DeletePredicate,
+    ) -> Result<(), DeleteError> {
+        self.inner
+            .delete(DeleteRequest {
+                db_name: db_name.into(),
+                predicate: Some(predicate),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => DeleteError::DatabaseNotFound,
+                tonic::Code::InvalidArgument => DeleteError::InvalidArgument(status),
+                _ => DeleteError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
+    /// Returns the paths of files in object storage that are no longer
+    /// referenced by the specified database and were last modified at least
+    /// `grace_period_seconds` ago, without deleting them
+    pub async fn find_orphaned_files(
+        &mut self,
+        db_name: impl Into<String>,
+        grace_period_seconds: u64,
+    ) -> Result<Vec<String>, CollectGarbageError> {
+        let db_name = db_name.into();
+
+        let response = self
+            .inner
+            .collect_garbage(CollectGarbageRequest {
+                db_name,
+                grace_period_seconds,
+                dry_run: true,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => CollectGarbageError::DatabaseNotFound,
+                _ => CollectGarbageError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner().orphaned_files)
+    }
+
+    /// Deletes files in object storage that are no longer referenced by
+    /// the specified database and were last modified at least
+    /// `grace_period_seconds` ago.
+    ///
+    /// Returns the job tracking the deletion
+    pub async fn collect_garbage(
+        &mut self,
+        db_name: impl Into<String>,
+        grace_period_seconds: u64,
+    ) -> Result<Operation, CollectGarbageError> {
+        let db_name = db_name.into();
+
+        let response = self
+            .inner
+            .collect_garbage(CollectGarbageRequest {
+                db_name,
+                grace_period_seconds,
+                dry_run: false,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => CollectGarbageError::DatabaseNotFound,
+                _ => CollectGarbageError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .ok_or(CollectGarbageError::EmptyResponse)?)
+    }
+
+    /// Writes a backup of the rules, catalog and persisted chunks of the
+    /// specified database to the directory `path` on the server's file
+    /// system, which must be empty. The job fails if the database holds
+    /// data that has not been persisted yet.
+    ///
+    /// Returns the job tracking the backup
+    pub async fn backup_database(
+        &mut self,
+        db_name: impl Into<String>,
+        path: impl Into<String>,
+    ) -> Result<Operation, BackupDatabaseError> {
+        let response = self
+            .inner
+            .backup_database(BackupDatabaseRequest {
+                db_name: db_name.into(),
+                path: path.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => BackupDatabaseError::DatabaseNotFound,
+                _ => BackupDatabaseError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .ok_or(BackupDatabaseError::EmptyResponse)?)
+    }
+
+    /// Creates a database from the backup in the directory `path` on the
+    /// server's file system. The database is named `db_name` if given, and
+    /// like the backed up database otherwise.
+    ///
+    /// Returns the job tracking the restore of the chunks
+    pub async fn restore_database(
+        &mut self,
+        path: impl Into<String>,
+        db_name: Option<String>,
+    ) -> Result<Operation, RestoreDatabaseError> {
+        let response = self
+            .inner
+            .restore_database(RestoreDatabaseRequest {
+                path: path.into(),
+                db_name: db_name.unwrap_or_default(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::AlreadyExists => RestoreDatabaseError::DatabaseAlreadyExists,
+                tonic::Code::FailedPrecondition => RestoreDatabaseError::FailedPrecondition(status),
+                _ => RestoreDatabaseError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .ok_or(RestoreDatabaseError::EmptyResponse)?)
+    }
+
+    /// Creates an API token granting `permissions`, returning it including
+    /// its secret. The secret cannot be retrieved later
+    pub async fn create_token(
+        &mut self,
+        description: impl Into<String>,
+        permissions: Vec<Permission>,
+    ) -> Result<Token, CreateTokenError> {
+        let response = self
+            .inner
+            .create_token(CreateTokenRequest {
+                description: description.into(),
+                permissions,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::InvalidArgument => CreateTokenError::InvalidArgument(status),
+                _ => CreateTokenError::ServerError(status),
+            })?;
+
+        Ok(response
+            .into_inner()
+            .token
+            .ok_or(CreateTokenError::EmptyResponse)?)
+    }
+
+    /// List the API tokens, without their secrets
+    pub async fn list_tokens(&mut self) -> Result<Vec<Token>, ListTokensError> {
+        let response = self
+            .inner
+            .list_tokens(ListTokensRequest {})
+            .await
+            .map_err(ListTokensError::ServerError)?;
+
+        Ok(response.into_inner().tokens)
+    }
+
+    /// Revoke the API token with the given `id`
+    pub async fn revoke_token(&mut self, id: impl Into<String>) -> Result<(), RevokeTokenError> {
+        self.inner
+            .revoke_token(RevokeTokenRequest { id: id.into() })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => RevokeTokenError::TokenNotFound,
+                _ => RevokeTokenError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
 }
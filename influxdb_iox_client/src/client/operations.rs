@@ -6,6 +6,7 @@ use ::generated_types::{
 
 use self::generated_types::{operations_client::OperationsClient, *};
 use crate::connection::Connection;
+use tonic::transport::Channel;
 /// Re-export generated_types
 pub mod generated_types {
     pub use generated_types::google::longrunning::*;
@@ -54,14 +55,15 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// ```
 #[derive(Debug, Clone)]
 pub struct Client {
-    inner: OperationsClient<Connection>,
+    inner: OperationsClient<Channel>,
 }
 
 impl Client {
     /// Creates a new client with the provided connection
-    pub fn new(channel: tonic::transport::Channel) -> Self {
+    pub fn new(connection: impl Into<Connection>) -> Self {
+        let (channel, interceptor) = connection.into().into_parts();
         Self {
-            inner: OperationsClient::new(channel),
+            inner: OperationsClient::with_interceptor(channel, interceptor),
         }
     }
 
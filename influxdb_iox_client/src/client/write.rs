@@ -3,6 +3,7 @@ use thiserror::Error;
 use self::generated_types::{write_service_client::WriteServiceClient, *};
 
 use crate::connection::Connection;
+use tonic::transport::Channel;
 
 /// Re-export generated_types
 pub mod generated_types {
@@ -43,14 +44,15 @@ pub enum WriteError {
 /// ```
 #[derive(Debug, Clone)]
 pub struct Client {
-    inner: WriteServiceClient<Connection>,
+    inner: WriteServiceClient<Channel>,
 }
 
 impl Client {
     /// Creates a new client with the provided connection
-    pub fn new(channel: tonic::transport::Channel) -> Self {
+    pub fn new(connection: impl Into<Connection>) -> Self {
+        let (channel, interceptor) = connection.into().into_parts();
         Self {
-            inner: WriteServiceClient::new(channel),
+            inner: WriteServiceClient::with_interceptor(channel, interceptor),
         }
     }
 
@@ -2,10 +2,53 @@ use http::{uri::InvalidUri, Uri};
 use std::convert::TryInto;
 use std::time::Duration;
 use thiserror::Error;
-use tonic::transport::Endpoint;
+use tonic::{
+    metadata::{errors::InvalidMetadataValue, AsciiMetadataValue},
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity},
+    Interceptor,
+};
 
 /// The connection type used for clients
-pub type Connection = tonic::transport::Channel;
+///
+/// Sends the API token configured with [`Builder::token`] with every request
+#[derive(Debug, Clone)]
+pub struct Connection {
+    channel: Channel,
+    authorization: Option<AsciiMetadataValue>,
+}
+
+impl Connection {
+    /// Returns the underlying channel, for use with gRPC clients that are
+    /// not part of this crate. The channel does not send the API token
+    pub fn into_channel(self) -> Channel {
+        self.channel
+    }
+
+    /// Returns the channel and an interceptor adding the API token to
+    /// requests, for constructing the generated gRPC clients
+    pub(crate) fn into_parts(self) -> (Channel, Interceptor) {
+        let authorization = self.authorization;
+        let interceptor = Interceptor::new(move |mut request: tonic::Request<()>| {
+            if let Some(authorization) = &authorization {
+                request
+                    .metadata_mut()
+                    .insert("authorization", authorization.clone());
+            }
+            Ok(request)
+        });
+
+        (self.channel, interceptor)
+    }
+}
+
+impl From<Channel> for Connection {
+    fn from(channel: Channel) -> Self {
+        Self {
+            channel,
+            authorization: None,
+        }
+    }
+}
 
 /// The default User-Agent header sent by the HTTP client.
 pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
@@ -24,6 +67,10 @@ pub enum Error {
     /// Client received an unexpected error from the server
     #[error("Invalid URI: {}", .0)]
     InvalidUri(#[from] InvalidUri),
+
+    /// The API token cannot be sent as gRPC metadata
+    #[error("Invalid API token: {}", .0)]
+    InvalidToken(#[from] InvalidMetadataValue),
 }
 
 /// Result type for the ConnectionBuilder
@@ -32,6 +79,8 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// A builder that produces a connection that can be used with any of the gRPC
 /// clients
 ///
+/// The connection uses TLS if any of the `tls_*` options are set.
+///
 /// ```no_run
 /// #[tokio::main]
 /// # async fn main() {
@@ -41,6 +90,7 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// let connection = Builder::default()
 ///     .timeout(Duration::from_secs(42))
 ///     .user_agent("my_awesome_client")
+///     .token("my_api_token")
 ///     .build("http://127.0.0.1:8082/")
 ///     .await
 ///     .expect("connection must succeed");
@@ -53,6 +103,8 @@ pub struct Builder {
     user_agent: String,
     connect_timeout: Duration,
     timeout: Duration,
+    token: Option<String>,
+    tls: Option<ClientTlsConfig>,
 }
 
 impl std::default::Default for Builder {
@@ -61,6 +113,8 @@ impl std::default::Default for Builder {
             user_agent: USER_AGENT.into(),
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             timeout: DEFAULT_TIMEOUT,
+            token: None,
+            tls: None,
         }
     }
 }
@@ -71,9 +125,17 @@ impl Builder {
     where
         D: TryInto<Uri, Error = InvalidUri>,
     {
-        let endpoint = Endpoint::from(dst.try_into()?)
+        let authorization = self
+            .token
+            .map(|token| format!("Token {}", token).parse())
+            .transpose()?;
+
+        let mut endpoint = Endpoint::from(dst.try_into()?)
             .user_agent(self.user_agent)?
             .timeout(self.timeout);
+        if let Some(tls) = self.tls {
+            endpoint = endpoint.tls_config(tls)?;
+        }
 
         // Manually construct connector to workaround https://github.com/hyperium/tonic/issues/498
         let mut connector = hyper::client::HttpConnector::new();
@@ -84,7 +146,12 @@ impl Builder {
         connector.set_nodelay(true);
         connector.set_keepalive(None);
 
-        Ok(endpoint.connect_with_connector(connector).await?)
+        let channel = endpoint.connect_with_connector(connector).await?;
+
+        Ok(Connection {
+            channel,
+            authorization,
+        })
     }
 
     /// Set the `User-Agent` header sent by this client.
@@ -95,6 +162,49 @@ impl Builder {
         }
     }
 
+    /// Authenticate requests with the API `token`.
+    pub fn token(self, token: impl Into<String>) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Connect over TLS, verifying the server certificate with the PEM
+    /// encoded CA certificate `pem`.
+    pub fn tls_ca_certificate(self, pem: impl AsRef<[u8]>) -> Self {
+        let tls = self.tls_config().ca_certificate(Certificate::from_pem(pem));
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    /// Connect over TLS, presenting the PEM encoded certificate and private
+    /// key to servers that require client certificates.
+    pub fn tls_identity(self, cert: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Self {
+        let tls = self.tls_config().identity(Identity::from_pem(cert, key));
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    /// Connect over TLS, verifying the server certificate against
+    /// `domain_name` rather than the host of the URL passed to
+    /// [`build`][Self::build].
+    pub fn tls_domain_name(self, domain_name: impl Into<String>) -> Self {
+        let tls = self.tls_config().domain_name(domain_name);
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    fn tls_config(&self) -> ClientTlsConfig {
+        self.tls.clone().unwrap_or_else(ClientTlsConfig::new)
+    }
+
     /// Sets the maximum duration of time the client will wait for the IOx
     /// server to accept the TCP connection before aborting the request.
     ///
@@ -0,0 +1,414 @@
+//! Building owned line protocol
+//!
+//! Unlike `ParsedLine`, which borrows from its input, a [`LineBuilder`]
+//! owns all of its data and escapes it so that the output can always
+//! be parsed back by [`parse_lines`](crate::parse_lines) into the same
+//! values.
+use std::{collections::BTreeMap, fmt, io};
+
+use snafu::{ensure, ResultExt};
+
+use crate::{
+    EmptyName, EscapedStr, FieldSetMissing, FieldValue, NonFiniteFloat, Precision, Result,
+    UnrepresentableName, WritingLine,
+};
+
+/// Characters that are escaped in measurement names
+const MEASUREMENT_ESCAPES: &[char] = &[',', ' ', '\\'];
+
+/// Characters that are escaped in tag keys and values and field keys
+const KEY_ESCAPES: &[char] = &[',', '=', ' ', '\\'];
+
+/// Characters that are escaped in string field values
+const STRING_FIELD_ESCAPES: &[char] = &['"', '\\'];
+
+/// Incrementally constructs a single line of line protocol
+///
+/// ```
+/// use influxdb_line_protocol::{LineBuilder, Precision};
+///
+/// let line = LineBuilder::new("cpu")
+///     .tag("host", "A B")
+///     .field("usage", 0.5)
+///     .field("count", 3u64)
+///     .field("status", r#"ok "now""#)
+///     .timestamp(1_590_488_773_254_420_000)
+///     .precision(Precision::Seconds)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     line,
+///     r#"cpu,host=A\ B count=3u,status="ok \"now\"",usage=0.5 1590488773"#
+/// );
+/// ```
+///
+/// Tags and fields are written sorted by key, and setting a tag or
+/// field that already exists replaces it. Tags with empty values are
+/// omitted, as line protocol can not represent them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineBuilder {
+    measurement: String,
+    tags: BTreeMap<String, String>,
+    fields: BTreeMap<String, FieldValue<'static>>,
+    timestamp: Option<i64>,
+    precision: Precision,
+}
+
+impl LineBuilder {
+    pub fn new(measurement: impl Into<String>) -> Self {
+        Self {
+            measurement: measurement.into(),
+            tags: Default::default(),
+            fields: Default::default(),
+            timestamp: None,
+            precision: Precision::default(),
+        }
+    }
+
+    /// Sets a tag, replacing any existing tag of the same name
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets a field, replacing any existing field of the same name
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<FieldValue<'static>>) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the timestamp, in nanoseconds since the epoch
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the precision the timestamp is written with. Timestamps
+    /// given to [`LineBuilder::timestamp`] are always in nanoseconds and
+    /// are truncated to this precision when written.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Returns the line protocol for this line, without a trailing
+    /// newline
+    pub fn build(&self) -> Result<String> {
+        self.validate()?;
+
+        let mut line = String::new();
+        self.write_unchecked(&mut line)
+            .expect("writing to a String can not fail");
+        Ok(line)
+    }
+
+    /// Writes the line protocol for this line to `w`, followed by a
+    /// newline
+    pub fn write_to<W: io::Write>(&self, mut w: W) -> Result<()> {
+        let line = self.build()?;
+        w.write_all(line.as_bytes()).context(WritingLine)?;
+        w.write_all(b"\n").context(WritingLine)
+    }
+
+    /// Checks this line can be represented as line protocol
+    fn validate(&self) -> Result<()> {
+        validate_name("measurement", &self.measurement)?;
+        ensure!(
+            !self.measurement.starts_with('#'),
+            UnrepresentableName {
+                kind: "measurement",
+                value: &self.measurement,
+            }
+        );
+
+        for (key, value) in &self.tags {
+            validate_name("tag key", key)?;
+            if !value.is_empty() {
+                validate_name("tag value", value)?;
+            }
+        }
+
+        ensure!(!self.fields.is_empty(), FieldSetMissing);
+        for (key, value) in &self.fields {
+            validate_name("field key", key)?;
+            if let FieldValue::F64(v) = value {
+                ensure!(v.is_finite(), NonFiniteFloat { field_key: key });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_unchecked(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        write_escaped(w, &self.measurement, MEASUREMENT_ESCAPES)?;
+
+        for (key, value) in &self.tags {
+            if value.is_empty() {
+                continue;
+            }
+            w.write_char(',')?;
+            write_escaped(w, key, KEY_ESCAPES)?;
+            w.write_char('=')?;
+            write_escaped(w, value, KEY_ESCAPES)?;
+        }
+
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            w.write_char(if i == 0 { ' ' } else { ',' })?;
+            write_escaped(w, key, KEY_ESCAPES)?;
+            w.write_char('=')?;
+            match value {
+                FieldValue::String(s) => {
+                    w.write_char('"')?;
+                    write_escaped(w, s, STRING_FIELD_ESCAPES)?;
+                    w.write_char('"')?;
+                }
+                // the `Display` implementation for the other types is
+                // already line protocol
+                _ => write!(w, "{}", value)?,
+            }
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            write!(w, " {}", timestamp / self.precision.multiplier())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a measurement, key or tag value is not empty and does not
+/// contain characters that line protocol can't escape
+fn validate_name(kind: &'static str, value: &str) -> Result<()> {
+    ensure!(!value.is_empty(), EmptyName { kind });
+    ensure!(
+        !value.contains(|c| c == '\n' || c == '\t'),
+        UnrepresentableName { kind, value }
+    );
+    Ok(())
+}
+
+/// Writes `value` to `w`, preceding any of the `escapes` characters
+/// with a backslash
+fn write_escaped(w: &mut impl fmt::Write, value: &str, escapes: &[char]) -> fmt::Result {
+    for c in value.chars() {
+        if escapes.contains(&c) {
+            w.write_char('\\')?;
+        }
+        w.write_char(c)?;
+    }
+    Ok(())
+}
+
+impl<'a> From<i64> for FieldValue<'a> {
+    fn from(other: i64) -> Self {
+        Self::I64(other)
+    }
+}
+
+impl<'a> From<u64> for FieldValue<'a> {
+    fn from(other: u64) -> Self {
+        Self::U64(other)
+    }
+}
+
+impl<'a> From<f64> for FieldValue<'a> {
+    fn from(other: f64) -> Self {
+        Self::F64(other)
+    }
+}
+
+impl<'a> From<bool> for FieldValue<'a> {
+    fn from(other: bool) -> Self {
+        Self::Boolean(other)
+    }
+}
+
+impl<'a> From<String> for FieldValue<'a> {
+    fn from(other: String) -> Self {
+        Self::String(EscapedStr::CopiedValue(other))
+    }
+}
+
+/// Copies `other`, so that it can be used in a [`LineBuilder`]
+impl From<&str> for FieldValue<'static> {
+    fn from(other: &str) -> Self {
+        Self::String(EscapedStr::CopiedValue(other.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lines;
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    #[test]
+    fn build_escapes() {
+        let line = LineBuilder::new(r#"m,e a\s=u"r"#)
+            .tag(r#"t,k=e y\"#, r#"t,v=a l\"#)
+            .tag("empty", "")
+            .field(r#"f,k=e y\"#, r#"s t"r\i,n=g"#)
+            .field("i", -1i64)
+            .field("u", u64::MAX)
+            .field("b", true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            line,
+            r#"m\,e\ a\\s=u"r,t\,k\=e\ y\\=t\,v\=a\ l\\ b=true,f\,k\=e\ y\\="s t\"r\\i,n=g",i=-1i,u=18446744073709551615u"#
+        );
+    }
+
+    #[test]
+    fn build_precision() {
+        let builder = LineBuilder::new("m")
+            .field("f", 1.5)
+            .timestamp(1_234_567_891);
+
+        assert_eq!(builder.build().unwrap(), "m f=1.5 1234567891");
+        let builder = builder.precision(Precision::Microseconds);
+        assert_eq!(builder.build().unwrap(), "m f=1.5 1234567");
+        let builder = builder.precision(Precision::Milliseconds);
+        assert_eq!(builder.build().unwrap(), "m f=1.5 1234");
+        let builder = builder.precision(Precision::Seconds);
+        assert_eq!(builder.build().unwrap(), "m f=1.5 1");
+    }
+
+    #[test]
+    fn build_errors() {
+        let err = LineBuilder::new("m").build().unwrap_err();
+        assert_eq!(err.to_string(), "No fields were provided");
+
+        let err = LineBuilder::new("").field("f", 1.0).build().unwrap_err();
+        assert_eq!(err.to_string(), "measurement may not be empty");
+
+        let err = LineBuilder::new("m")
+            .tag("a\nb", "c")
+            .field("f", 1.0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "tag key \"a\nb\" can not be represented in line protocol"
+        );
+
+        let err = LineBuilder::new("#m").field("f", 1.0).build().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "measurement \"#m\" can not be represented in line protocol"
+        );
+
+        let err = LineBuilder::new("m")
+            .field("f", f64::NAN)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Float field \"f\" must be finite to be written as line protocol"
+        );
+    }
+
+    #[test]
+    fn write_to() {
+        let mut out = vec![];
+        LineBuilder::new("a")
+            .field("f", 1i64)
+            .write_to(&mut out)
+            .unwrap();
+        LineBuilder::new("b")
+            .field("f", 2i64)
+            .write_to(&mut out)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "a f=1i\nb f=2i\n");
+    }
+
+    /// Characters used to generate random names and values, which
+    /// includes all the characters that need escaping
+    const ALPHABET: &[char] = &[
+        'a', 'b', 'Z', '0', '9', '_', '-', '.', ' ', ',', '=', '\\', '"', '\'', '\n', 'é', '日',
+    ];
+
+    fn random_string(rng: &mut StdRng, allow_newline: bool) -> String {
+        let len = rng.gen_range(1..8);
+        (0..len)
+            .map(|_| loop {
+                let c = *ALPHABET.choose(rng).unwrap();
+                if allow_newline || c != '\n' {
+                    break c;
+                }
+            })
+            .collect()
+    }
+
+    fn random_field_value(rng: &mut StdRng) -> FieldValue<'static> {
+        match rng.gen_range(0..5) {
+            0 => FieldValue::I64(rng.gen()),
+            1 => FieldValue::U64(rng.gen()),
+            2 => loop {
+                let v = f64::from_bits(rng.gen());
+                if v.is_finite() {
+                    break FieldValue::F64(v);
+                }
+            },
+            3 => FieldValue::Boolean(rng.gen()),
+            _ => random_string(rng, true).into(),
+        }
+    }
+
+    /// Checks that randomly generated lines can be parsed back into
+    /// the values they were built from
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..1000 {
+            let mut builder = LineBuilder::new(random_string(&mut rng, false));
+            for _ in 0..rng.gen_range(0..4) {
+                builder = builder.tag(
+                    random_string(&mut rng, false),
+                    random_string(&mut rng, false),
+                );
+            }
+            for _ in 0..rng.gen_range(1..4) {
+                builder =
+                    builder.field(random_string(&mut rng, false), random_field_value(&mut rng));
+            }
+            if rng.gen() {
+                builder = builder.timestamp(rng.gen());
+            }
+
+            let line = builder
+                .build()
+                .unwrap_or_else(|e| panic!("could not build {:?}: {}", builder, e));
+
+            let parsed: Vec<_> = parse_lines(&line)
+                .collect::<Result<_>>()
+                .unwrap_or_else(|e| panic!("could not parse {:?}: {}", line, e));
+            assert_eq!(parsed.len(), 1, "{:?}", line);
+            let parsed = &parsed[0];
+
+            assert_eq!(parsed.series.measurement, builder.measurement, "{:?}", line);
+
+            let tags: BTreeMap<String, String> = parsed
+                .series
+                .tag_set
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            assert_eq!(tags, builder.tags, "{:?}", line);
+
+            let fields: BTreeMap<String, FieldValue<'_>> = parsed
+                .field_set
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect();
+            assert_eq!(fields, builder.fields, "{:?}", line);
+
+            assert_eq!(parsed.timestamp, builder.timestamp, "{:?}", line);
+        }
+    }
+}
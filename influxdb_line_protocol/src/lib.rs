@@ -37,6 +37,14 @@ use std::{
     ops::Deref,
 };
 
+mod builder;
+mod precision;
+mod stream;
+
+pub use builder::LineBuilder;
+pub use precision::Precision;
+pub use stream::{RawLine, StreamingParser};
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display(r#"Must not contain duplicate tags, but "{}" was repeated"#, tag_key))]
@@ -81,6 +89,24 @@ pub enum Error {
     ))]
     CannotParseEntireLine { trailing_content: String },
 
+    #[snafu(display(r#"{} may not be empty"#, kind))]
+    EmptyName { kind: &'static str },
+
+    #[snafu(display(r#"{} "{}" can not be represented in line protocol"#, kind, value))]
+    UnrepresentableName { kind: &'static str, value: String },
+
+    #[snafu(display(
+        r#"Float field "{}" must be finite to be written as line protocol"#,
+        field_key
+    ))]
+    NonFiniteFloat { field_key: String },
+
+    #[snafu(display(r#"Unable to write line protocol: {}"#, source))]
+    WritingLine { source: std::io::Error },
+
+    #[snafu(display(r#"Line is not valid UTF-8: {}"#, source))]
+    InvalidUtf8 { source: std::str::Utf8Error },
+
     // TODO: Replace this with specific failures.
     #[snafu(display(r#"A generic parsing error occurred: {:?}"#, kind))]
     GenericParsingError {
@@ -472,36 +498,70 @@ impl PartialEq<String> for EscapedStr<'_> {
 }
 
 pub fn parse_lines(input: &str) -> impl Iterator<Item = Result<ParsedLine<'_>>> {
-    split_lines(input).filter_map(|line| {
-        let i = trim_leading(line);
+    split_lines(input).filter_map(parse_single_line)
+}
 
-        if i.is_empty() {
-            return None;
-        }
+/// Parses `input` like [`parse_lines`], but also returns the 1-based
+/// line number within `input` that each result was parsed from.
+///
+/// Line numbers account for blank and commented-out lines (which do
+/// not produce a result) as well as newlines embedded in quoted string
+/// field values, so they can be used to report errors back to users.
+///
+/// ```
+/// let input = "cpu f=1\n\n# a comment\ncpu f=\nmem f=2";
+/// let results: Vec<_> = influxdb_line_protocol::parse_lines_with_line_numbers(input)
+///     .map(|(line_number, result)| (line_number, result.is_ok()))
+///     .collect();
+///
+/// assert_eq!(results, vec![(1, true), (4, false), (5, true)]);
+/// ```
+pub fn parse_lines_with_line_numbers(
+    input: &str,
+) -> impl Iterator<Item = (usize, Result<ParsedLine<'_>>)> {
+    let mut next_line_number = 1;
+    split_lines(input).filter_map(move |line| {
+        let line_number = next_line_number;
+        // a single logical line may span several physical lines if it
+        // has newlines inside quoted string fields
+        next_line_number += 1 + line.matches('\n').count();
+
+        parse_single_line(line).map(|res| (line_number, res))
+    })
+}
 
-        let res = match parse_line(i) {
-            Ok((remaining, line)) => {
-                // should have parsed the whole input line, if any
-                // data remains it is a parse error for this line
-                // corresponding Go logic:
-                // https://github.com/influxdata/influxdb/blob/217eddc87e14a79b01d0c22994fc139f530094a2/models/points_parser.go#L259-L266
-                if !remaining.is_empty() {
-                    Some(Err(Error::CannotParseEntireLine {
-                        trailing_content: String::from(remaining),
-                    }))
-                } else {
-                    Some(Ok(line))
-                }
+/// Parses a single line (as returned by `split_lines`), returning
+/// `None` if the line is empty or entirely commented out.
+fn parse_single_line(line: &str) -> Option<Result<ParsedLine<'_>>> {
+    let i = trim_leading(line);
+
+    if i.is_empty() {
+        return None;
+    }
+
+    let res = match parse_line(i) {
+        Ok((remaining, line)) => {
+            // should have parsed the whole input line, if any
+            // data remains it is a parse error for this line
+            // corresponding Go logic:
+            // https://github.com/influxdata/influxdb/blob/217eddc87e14a79b01d0c22994fc139f530094a2/models/points_parser.go#L259-L266
+            if !remaining.is_empty() {
+                Some(Err(Error::CannotParseEntireLine {
+                    trailing_content: String::from(remaining),
+                }))
+            } else {
+                Some(Ok(line))
             }
-            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Some(Err(e)),
-            Err(nom::Err::Incomplete(_)) => unreachable!("Cannot have incomplete data"), // Only streaming parsers have this
-        };
-
-        if let Some(Err(r)) = &res {
-            debug!("Error parsing line: '{}'. Error was {:?}", line, r);
         }
-        res
-    })
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Some(Err(e)),
+        // Only streaming parsers have this
+        Err(nom::Err::Incomplete(_)) => unreachable!("Cannot have incomplete data"),
+    };
+
+    if let Some(Err(r)) = &res {
+        debug!("Error parsing line: '{}'. Error was {:?}", line, r);
+    }
+    res
 }
 
 /// Split `input` into invidividual lines to be parsed, based on the
@@ -515,60 +575,71 @@ pub fn parse_lines(input: &str) -> impl Iterator<Item = Result<ParsedLine<'_>>>
 /// we can be more sure of the compatibility of the rust parser and
 /// the canonical Go parser.
 fn split_lines(input: &str) -> impl Iterator<Item = &str> {
-    // NB: This is ported as closely as possibly from the original Go code:
-    let mut quoted = false;
-    let mut fields = false;
+    let mut splitter = LineSplitter::default();
+    input.split(move |c| splitter.is_line_end(c))
+}
+
+/// The state needed to find the ends of lines in line protocol, used
+/// by [`split_lines`] and by the [`StreamingParser`], which may see a
+/// line in several pieces.
+#[derive(Debug, Default, Clone)]
+struct LineSplitter {
+    quoted: bool,
+    fields: bool,
 
     // tracks how many '=' and commas we've seen
     // this duplicates some of the functionality in scanFields
-    let mut equals = 0;
-    let mut commas = 0;
+    equals: usize,
+    commas: usize,
+
+    in_escape: bool,
+}
+
+impl LineSplitter {
+    /// Feeds the next character of the input to the splitter,
+    /// returning true if it ends the current line.
+    fn is_line_end(&mut self, c: char) -> bool {
+        // NB: This is ported as closely as possibly from the original Go code:
 
-    let mut in_escape = false;
-    input.split(move |c| {
         // skip past escaped characters
-        if in_escape {
-            in_escape = false;
+        if self.in_escape {
+            self.in_escape = false;
             return false;
         }
 
         if c == '\\' {
-            in_escape = true;
+            self.in_escape = true;
             return false;
         }
 
         if c == ' ' {
-            fields = true;
+            self.fields = true;
             return false;
         }
 
         // If we see a double quote, makes sure it is not escaped
-        if fields {
-            if !quoted && c == '=' {
-                equals += 1;
+        if self.fields {
+            if !self.quoted && c == '=' {
+                self.equals += 1;
                 return false;
-            } else if !quoted && c == ',' {
-                commas += 1;
+            } else if !self.quoted && c == ',' {
+                self.commas += 1;
                 return false;
-            } else if c == '"' && equals > commas {
-                quoted = !quoted;
+            } else if c == '"' && self.equals > self.commas {
+                self.quoted = !self.quoted;
                 return false;
             }
         }
 
-        if c == '\n' && !quoted {
+        if c == '\n' && !self.quoted {
             // reset all the state -- we found a line
-            quoted = false;
-            fields = false;
-            equals = 0;
-            commas = 0;
-            assert!(!in_escape);
-            in_escape = false;
+            assert!(!self.in_escape);
+            *self = Self::default();
             return true;
         }
 
         false
-    })
+    }
 }
 
 fn parse_line(i: &str) -> IResult<&str, ParsedLine<'_>> {
@@ -1217,6 +1288,31 @@ mod test {
         assert!(matches!(vals, Err(super::Error::FieldSetMissing)));
     }
 
+    #[test]
+    fn parse_with_line_numbers() {
+        let input = r#"
+foo value=1i 123
+# comment
+
+foo 1234
+bar str="multi
+line" 456
+bar value=
+baz value=2i 789"#;
+
+        let vals: Vec<_> = super::parse_lines_with_line_numbers(input).collect();
+        assert_eq!(vals.len(), 5);
+
+        let line_numbers: Vec<_> = vals.iter().map(|(n, _)| *n).collect();
+        assert_eq!(line_numbers, vec![2, 5, 6, 8, 9]);
+
+        assert!(vals[0].1.is_ok());
+        assert!(matches!(vals[1].1, Err(super::Error::FieldSetMissing)));
+        assert_eq!(vals[2].1.as_ref().unwrap().series.measurement, "bar");
+        assert!(vals[3].1.is_err());
+        assert_eq!(vals[4].1.as_ref().unwrap().timestamp, Some(789));
+    }
+
     #[test]
     fn parse_single_field_integer() {
         let input = "foo asdf=23i 1234";
@@ -0,0 +1,96 @@
+//! The precision of line protocol timestamps
+//!
+//! Line protocol itself does not say which unit its timestamps are in;
+//! writers and readers agree on it out of band, e.g. with the
+//! `precision` query parameter of the InfluxDB write APIs.
+
+/// The unit of the timestamps of line protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl Precision {
+    /// Parses the `precision` query parameter of the InfluxDB v2 write
+    /// API, returning `None` if it is not one of `ns`, `us`, `ms` or `s`
+    pub fn from_v2(precision: &str) -> Option<Self> {
+        match precision {
+            "ns" => Some(Self::Nanoseconds),
+            "us" => Some(Self::Microseconds),
+            "ms" => Some(Self::Milliseconds),
+            "s" => Some(Self::Seconds),
+            _ => None,
+        }
+    }
+
+    /// Parses the `precision` (or `epoch`) query parameter of the
+    /// InfluxDB 1.x API, which has its own set of abbreviations and also
+    /// accepts minutes and hours
+    pub fn from_v1(precision: &str) -> Option<Self> {
+        match precision {
+            "n" | "ns" => Some(Self::Nanoseconds),
+            "u" | "us" | "µ" => Some(Self::Microseconds),
+            "ms" => Some(Self::Milliseconds),
+            "s" => Some(Self::Seconds),
+            "m" => Some(Self::Minutes),
+            "h" => Some(Self::Hours),
+            _ => None,
+        }
+    }
+
+    /// The number of nanoseconds in one unit of this precision
+    pub fn multiplier(&self) -> i64 {
+        match self {
+            Self::Nanoseconds => 1,
+            Self::Microseconds => 1_000,
+            Self::Milliseconds => 1_000_000,
+            Self::Seconds => 1_000_000_000,
+            Self::Minutes => 60_000_000_000,
+            Self::Hours => 3_600_000_000_000,
+        }
+    }
+
+    /// Converts `timestamp` from this precision into nanoseconds,
+    /// returning `None` if the result would overflow
+    pub fn to_nanoseconds(&self, timestamp: i64) -> Option<i64> {
+        timestamp.checked_mul(self.multiplier())
+    }
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self::Nanoseconds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_v2() {
+        assert_eq!(Precision::from_v2("ns"), Some(Precision::Nanoseconds));
+        assert_eq!(Precision::from_v2("s"), Some(Precision::Seconds));
+        assert_eq!(Precision::from_v2("n"), None);
+        assert_eq!(Precision::from_v2("h"), None);
+    }
+
+    #[test]
+    fn parse_v1() {
+        assert_eq!(Precision::from_v1("n"), Some(Precision::Nanoseconds));
+        assert_eq!(Precision::from_v1("µ"), Some(Precision::Microseconds));
+        assert_eq!(Precision::from_v1("h"), Some(Precision::Hours));
+        assert_eq!(Precision::from_v1("d"), None);
+    }
+
+    #[test]
+    fn to_nanoseconds() {
+        assert_eq!(Precision::Minutes.to_nanoseconds(2), Some(120_000_000_000));
+        assert_eq!(Precision::Hours.to_nanoseconds(i64::MAX), None);
+    }
+}
@@ -0,0 +1,287 @@
+//! Incremental splitting of line protocol that arrives in chunks, such
+//! as the body of an HTTP request.
+use crate::{parse_single_line, Error, LineSplitter, ParsedLine, Result};
+use bytes::{Bytes, BytesMut};
+
+/// Splits a stream of line protocol chunks into [`RawLine`]s as soon
+/// as each line is complete, so lines can be parsed (and written)
+/// before the entire input has been received.
+///
+/// Lines that lie entirely within one chunk refer to that chunk
+/// without copying; only lines that span chunk boundaries are copied.
+///
+/// ```
+/// use bytes::Bytes;
+/// use influxdb_line_protocol::StreamingParser;
+///
+/// let mut parser = StreamingParser::new();
+/// let mut lines = parser.push(Bytes::from("cpu f=1\ncpu f"));
+/// lines.extend(parser.push(Bytes::from("=2\nmem f=3")));
+/// lines.extend(parser.finish());
+///
+/// let parsed: Vec<_> = lines
+///     .iter()
+///     .filter_map(|line| line.parse())
+///     .map(|res| res.unwrap().series.measurement.to_string())
+///     .collect();
+/// assert_eq!(parsed, vec!["cpu", "cpu", "mem"]);
+/// ```
+#[derive(Debug)]
+pub struct StreamingParser {
+    /// The start of a line that was not terminated in a previous chunk
+    partial: BytesMut,
+
+    /// Byte offset within the stream of the start of the next line
+    offset: usize,
+
+    /// 1-based line number of the start of the next line
+    line_number: usize,
+
+    splitter: LineSplitter,
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self {
+            partial: BytesMut::new(),
+            offset: 0,
+            line_number: 1,
+            splitter: LineSplitter::default(),
+        }
+    }
+
+    /// Adds the next chunk of input, returning the lines that it
+    /// completes. Any trailing incomplete line is buffered until a
+    /// subsequent chunk (or [`finish`](Self::finish)) completes it.
+    pub fn push(&mut self, chunk: Bytes) -> Vec<RawLine> {
+        let mut lines = vec![];
+        let mut start = 0;
+
+        for (i, &b) in chunk.iter().enumerate() {
+            // All the characters significant for finding line ends
+            // are ASCII, so other bytes of multi-byte characters
+            // can never be mistaken for them.
+            if self.splitter.is_line_end(b as char) {
+                let bytes = if self.partial.is_empty() {
+                    chunk.slice(start..i)
+                } else {
+                    self.partial.extend_from_slice(&chunk[start..i]);
+                    self.partial.split().freeze()
+                };
+                lines.push(self.next_line(bytes));
+                start = i + 1;
+            }
+        }
+
+        self.partial.extend_from_slice(&chunk[start..]);
+        lines
+    }
+
+    /// Returns the number of bytes of an incomplete line that are
+    /// currently buffered
+    pub fn buffered_len(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// Signals the end of the input, returning the final line if it
+    /// was not terminated by a newline.
+    pub fn finish(mut self) -> Option<RawLine> {
+        if self.partial.is_empty() {
+            None
+        } else {
+            let bytes = self.partial.split().freeze();
+            Some(self.next_line(bytes))
+        }
+    }
+
+    fn next_line(&mut self, bytes: Bytes) -> RawLine {
+        let line = RawLine {
+            offset: self.offset,
+            line_number: self.line_number,
+            bytes,
+        };
+
+        // account for the newline that terminated the line as well as
+        // any embedded in quoted string fields
+        self.offset += line.bytes.len() + 1;
+        self.line_number += 1 + line.bytes.iter().filter(|&&b| b == b'\n').count();
+
+        line
+    }
+}
+
+/// A single unparsed line of line protocol, as split by a
+/// [`StreamingParser`], along with its position in the input.
+#[derive(Debug, Clone)]
+pub struct RawLine {
+    bytes: Bytes,
+    offset: usize,
+    line_number: usize,
+}
+
+impl RawLine {
+    /// The contents of the line, without its terminating newline
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// The byte offset of the start of this line within the input
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The 1-based line number of the start of this line within the
+    /// input
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Parses this line, returning `None` if it is empty or entirely
+    /// commented out.
+    pub fn parse(&self) -> Option<Result<ParsedLine<'_>>> {
+        match std::str::from_utf8(&self.bytes) {
+            Ok(line) => parse_single_line(line),
+            Err(source) => Some(Err(Error::InvalidUtf8 { source })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lines_with_line_numbers;
+
+    /// Splits `input` into chunks at `boundaries` and returns the
+    /// lines produced by a `StreamingParser`
+    fn stream(input: &str, boundaries: &[usize]) -> Vec<RawLine> {
+        let input = Bytes::from(input.to_string());
+        let mut parser = StreamingParser::new();
+        let mut lines = vec![];
+
+        let mut start = 0;
+        for &end in boundaries {
+            lines.extend(parser.push(input.slice(start..end)));
+            start = end;
+        }
+        lines.extend(parser.push(input.slice(start..)));
+        lines.extend(parser.finish());
+        lines
+    }
+
+    /// Returns (line number, result as a string) for each parsed line
+    fn summarize(lines: &[RawLine]) -> Vec<(usize, String)> {
+        lines
+            .iter()
+            .filter_map(|line| {
+                line.parse()
+                    .map(|res| (line.line_number(), summarize_result(res)))
+            })
+            .collect()
+    }
+
+    fn summarize_result(res: Result<ParsedLine<'_>>) -> String {
+        match res {
+            Ok(line) => line.to_string(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[test]
+    fn all_chunk_boundaries() {
+        let input = "cpu,host=a f=1 10\n\n# comment\nm s=\"line\none\",f=2\ncpu f=\nmem\\ ory f=3i";
+        let expected: Vec<_> = parse_lines_with_line_numbers(input)
+            .map(|(line_number, res)| (line_number, summarize_result(res)))
+            .collect();
+        assert_eq!(expected.len(), 4);
+
+        for i in 0..=input.len() {
+            let lines = stream(input, &[i]);
+            assert_eq!(summarize(&lines), expected, "split at {}", i);
+
+            for j in i..=input.len() {
+                let lines = stream(input, &[i, j]);
+                assert_eq!(summarize(&lines), expected, "split at {} and {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn offsets_and_line_numbers() {
+        let input = "cpu f=1\nm s=\"a\nb\"\n\nmem f=2";
+        let lines = stream(input, &[3, 12]);
+
+        let positions: Vec<_> = lines
+            .iter()
+            .map(|line| (line.offset(), line.line_number()))
+            .collect();
+        assert_eq!(positions, vec![(0, 1), (8, 2), (18, 4), (19, 5)]);
+
+        for line in &lines {
+            let end = line.offset() + line.bytes().len();
+            assert_eq!(&input.as_bytes()[line.offset()..end], &line.bytes()[..]);
+        }
+    }
+
+    #[test]
+    fn lines_within_a_chunk_are_not_copied() {
+        let chunk = Bytes::from("cpu f=1\nmem f=2\ndisk f");
+        let mut parser = StreamingParser::new();
+
+        let lines = parser.push(chunk.clone());
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].bytes().as_ptr(), chunk.as_ptr());
+        assert_eq!(lines[1].bytes().as_ptr(), chunk[8..].as_ptr());
+        assert_eq!(parser.buffered_len(), 6);
+
+        assert!(parser.push(Bytes::from("=3")).is_empty());
+        let last = parser.finish().unwrap();
+        assert_eq!(&last.bytes()[..], b"disk f=3");
+        assert_eq!(last.line_number(), 3);
+    }
+
+    #[test]
+    fn multi_byte_characters_across_chunks() {
+        let input = "température,lieu=intérieur f=1\n🦀 f=2";
+        for i in 0..=input.len() {
+            let lines = stream(input, &[i]);
+            let measurements: Vec<_> = lines
+                .iter()
+                .map(|line| {
+                    line.parse()
+                        .unwrap()
+                        .unwrap()
+                        .series
+                        .measurement
+                        .to_string()
+                })
+                .collect();
+            assert_eq!(measurements, vec!["température", "🦀"], "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_only_fails_its_line() {
+        let mut parser = StreamingParser::new();
+        let mut lines = parser.push(Bytes::from_static(b"cpu f=1\nm\xff f=2\nmem f=3\n"));
+        lines.extend(parser.finish());
+
+        let results: Vec<_> = lines.iter().filter_map(|line| line.parse()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidUtf8 { .. })));
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn finish_without_partial_line() {
+        let mut parser = StreamingParser::new();
+        assert_eq!(parser.push(Bytes::from("cpu f=1\n")).len(), 1);
+        assert!(parser.finish().is_none());
+    }
+}
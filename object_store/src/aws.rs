@@ -2,6 +2,7 @@
 //! store.
 use crate::{
     buffer::slurp_stream_tempfile,
+    list_all_pages,
     path::{cloud::CloudPath, DELIMITER},
     ListResult, ObjectMeta, ObjectStoreApi,
 };
@@ -17,7 +18,7 @@ use rusoto_credential::{InstanceMetadataProvider, StaticProvider};
 use rusoto_s3::S3;
 use snafu::{futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
 use std::convert::TryFrom;
-use std::{fmt, io};
+use std::{fmt, io, ops::Range};
 
 /// A specialized `Result` for object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -56,6 +57,18 @@ pub enum Error {
         location: String,
     },
 
+    #[snafu(display(
+        "Unable to HEAD data. Bucket: {}, Location: {}, Error: {}",
+        bucket,
+        location,
+        source,
+    ))]
+    UnableToHeadData {
+        source: rusoto_core::RusotoError<rusoto_s3::HeadObjectError>,
+        bucket: String,
+        location: String,
+    },
+
     #[snafu(display(
         "Unable to GET part of the data. Bucket: {}, Location: {}, Error: {}",
         bucket,
@@ -200,6 +213,80 @@ impl ObjectStoreApi for AmazonS3 {
             .boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        // S3 can't express an empty range
+        if range.start >= range.end {
+            return Ok(Bytes::new());
+        }
+
+        let key = location.to_raw();
+        let get_request = rusoto_s3::GetObjectRequest {
+            bucket: self.bucket_name.clone(),
+            key: key.clone(),
+            // HTTP ranges include the last byte
+            range: Some(format!("bytes={}-{}", range.start, range.end - 1)),
+            ..Default::default()
+        };
+        let data = self
+            .client
+            .get_object(get_request)
+            .await
+            .context(UnableToGetData {
+                bucket: self.bucket_name.to_owned(),
+                location: key.clone(),
+            })?
+            .body
+            .context(NoData {
+                bucket: self.bucket_name.to_owned(),
+                location: key.clone(),
+            })?
+            .map_ok(|b| bytes::BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .context(UnableToGetPieceOfData {
+                bucket: self.bucket_name.to_owned(),
+                location: key,
+            })?;
+
+        Ok(data.freeze())
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let key = location.to_raw();
+        let head_request = rusoto_s3::HeadObjectRequest {
+            bucket: self.bucket_name.clone(),
+            key: key.clone(),
+            ..Default::default()
+        };
+        let resp = self
+            .client
+            .head_object(head_request)
+            .await
+            .context(UnableToHeadData {
+                bucket: self.bucket_name.to_owned(),
+                location: key,
+            })?;
+
+        // Unlike when listing, the last modified time is in the format of
+        // the HTTP Last-Modified header
+        let last_modified = match resp.last_modified {
+            Some(lm) => DateTime::parse_from_rfc2822(&lm)
+                .context(UnableToParseLastModified {
+                    bucket: &self.bucket_name,
+                })?
+                .with_timezone(&Utc),
+            None => Utc::now(),
+        };
+        let size = usize::try_from(resp.content_length.unwrap_or(0))
+            .expect("unsupported size on this platform");
+
+        Ok(ObjectMeta {
+            location: location.clone(),
+            last_modified,
+            size,
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let key = location.to_raw();
         let delete_request = rusoto_s3::DeleteObjectRequest {
@@ -283,7 +370,11 @@ impl ObjectStoreApi for AmazonS3 {
     }
 
     async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
-        self.list_with_delimiter_and_token(prefix, &None).await
+        list_all_pages(|next_token| async move {
+            self.list_with_delimiter_and_token(prefix, &next_token)
+                .await
+        })
+        .await
     }
 }
 
@@ -322,9 +413,10 @@ impl AmazonS3 {
         })
     }
 
-    /// List objects with the given prefix and a set delimiter of `/`. Returns
-    /// common prefixes (directories) in addition to object metadata. Optionally
-    /// takes a continuation token for paging.
+    /// List one page of objects with the given prefix and a set delimiter of
+    /// `/`. Returns common prefixes (directories) in addition to object
+    /// metadata. Optionally takes the continuation token returned with the
+    /// previous page.
     pub async fn list_with_delimiter_and_token<'a>(
         &'a self,
         prefix: &'a CloudPath,
@@ -395,6 +487,28 @@ impl AmazonS3 {
 }
 
 impl Error {
+    /// Returns true if this error may be transient, such as a network
+    /// problem or a server side error, so the operation could be retried.
+    pub(crate) fn is_retryable(&self) -> bool {
+        use Error::*;
+
+        match self {
+            UnableToDeleteData { source, .. } => rusoto_error_is_retryable(source),
+            UnableToGetData { source, .. } => rusoto_error_is_retryable(source),
+            UnableToHeadData { source, .. } => rusoto_error_is_retryable(source),
+            UnableToPutData { source, .. } => rusoto_error_is_retryable(source),
+            UnableToListData { source, .. } => rusoto_error_is_retryable(source),
+            UnableToGetPieceOfData { .. } => true,
+            DataDoesNotMatchLength { .. }
+            | NoData { .. }
+            | UnableToParseLastModified { .. }
+            | UnableToBufferStream { .. }
+            | InvalidRegion { .. }
+            | MissingAccessKey
+            | MissingSecretAccessKey => false,
+        }
+    }
+
     #[cfg(test)]
     fn s3_error_due_to_credentials(&self) -> bool {
         use rusoto_core::RusotoError;
@@ -410,6 +524,10 @@ impl Error {
                 source: RusotoError::Credentials(_),
                 bucket: _,
                 location: _,
+            } | UnableToHeadData {
+                source: RusotoError::Credentials(_),
+                bucket: _,
+                location: _,
             } | UnableToDeleteData {
                 source: RusotoError::Credentials(_),
                 bucket: _,
@@ -422,11 +540,25 @@ impl Error {
     }
 }
 
+fn rusoto_error_is_retryable<E>(error: &rusoto_core::RusotoError<E>) -> bool {
+    use rusoto_core::RusotoError;
+
+    match error {
+        RusotoError::HttpDispatch(_) => true,
+        RusotoError::Unknown(response) => {
+            response.status.is_server_error() || response.status.as_u16() == 429
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        tests::{get_nonexistent_object, list_with_delimiter, put_get_delete_list},
+        tests::{
+            get_nonexistent_object, get_range_and_head, list_with_delimiter, put_get_delete_list,
+        },
         AmazonS3, Error as ObjectStoreError, ObjectStore, ObjectStoreApi, ObjectStorePath,
     };
     use bytes::Bytes;
@@ -527,6 +659,7 @@ mod tests {
 
         check_credentials(put_get_delete_list(&integration).await).unwrap();
         check_credentials(list_with_delimiter(&integration).await).unwrap();
+        check_credentials(get_range_and_head(&integration).await).unwrap();
     }
 
     #[tokio::test]
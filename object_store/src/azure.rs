@@ -1,6 +1,7 @@
 //! This module contains the IOx implementation for using Azure Blob storage as
 //! the object store.
 use crate::{
+    list_all_pages,
     path::{cloud::CloudPath, DELIMITER},
     ListResult, ObjectMeta, ObjectStoreApi,
 };
@@ -21,6 +22,9 @@ use snafu::{ensure, ResultExt, Snafu};
 use std::sync::Arc;
 use std::{convert::TryInto, io};
 
+// `Range` is also exported by the azure prelude
+type ByteRange = std::ops::Range<usize>;
+
 /// A specialized `Result` for Azure object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -122,6 +126,47 @@ impl ObjectStoreApi for MicrosoftAzure {
         .boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: ByteRange) -> Result<Bytes> {
+        // Azure can't express an empty range
+        if range.start >= range.end {
+            return Ok(Bytes::new());
+        }
+
+        let location = location.to_raw();
+        self.container_client
+            .as_blob_client(&location)
+            .get()
+            .range(Range::new(range.start as u64, range.end as u64))
+            .execute()
+            .await
+            .map(|blob| blob.data.into())
+            .context(UnableToGetData { location })
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let raw_location = location.to_raw();
+        let properties = self
+            .container_client
+            .as_blob_client(&raw_location)
+            .get_properties()
+            .execute()
+            .await
+            .context(UnableToGetData {
+                location: raw_location,
+            })?
+            .blob
+            .properties;
+
+        Ok(ObjectMeta {
+            location: location.clone(),
+            last_modified: properties.last_modified,
+            size: properties
+                .content_length
+                .try_into()
+                .expect("unsupported size on this platform"),
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let location = location.to_raw();
         self.container_client
@@ -190,6 +235,94 @@ impl ObjectStoreApi for MicrosoftAzure {
     }
 
     async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
+        list_all_pages(|next_token| async move {
+            self.list_with_delimiter_and_token(prefix, &next_token)
+                .await
+        })
+        .await
+    }
+}
+
+impl Error {
+    /// Returns true if this error may be transient, such as a network
+    /// problem or a server side error, so the operation could be retried.
+    pub(crate) fn is_retryable(&self) -> bool {
+        use Error::*;
+
+        match self {
+            UnableToDeleteData { source, .. }
+            | UnableToGetData { source, .. }
+            | UnableToPutData { source, .. }
+            | UnableToListData { source } => azure_error_is_retryable(source.as_ref()),
+            DataDoesNotMatchLength { .. } => false,
+        }
+    }
+}
+
+/// The Azure SDK reports errors as opaque boxed errors, so classify them
+/// by the HTTP response or client error somewhere in their chain of sources
+fn azure_error_is_retryable(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut error = Some(error);
+    while let Some(e) = error {
+        if let Some(result) = e.downcast_ref::<azure_core::errors::UnexpectedHTTPResult>() {
+            let status = result.status_code();
+            return status.is_server_error() || status.as_u16() == 429;
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout()
+                || e.is_connect()
+                || e.status().map_or(false, |status| {
+                    status.is_server_error() || status.as_u16() == 429
+                });
+        }
+        if e.is::<io::Error>() {
+            return true;
+        }
+        error = e.source();
+    }
+    false
+}
+
+impl MicrosoftAzure {
+    /// Configure a connection to container with given name on Microsoft Azure
+    /// Blob store.
+    ///
+    /// The credentials `account` and `access_key` must provide access to the
+    /// store.
+    pub fn new(
+        account: impl Into<String>,
+        access_key: impl Into<String>,
+        container_name: impl Into<String>,
+    ) -> Self {
+        let account = account.into();
+        let access_key = access_key.into();
+        // From https://github.com/Azure/azure-sdk-for-rust/blob/master/sdk/storage/examples/blob_00.rs#L29
+        let http_client: Arc<Box<dyn HttpClient>> = Arc::new(Box::new(reqwest::Client::new()));
+
+        let storage_account_client =
+            StorageAccountClient::new_access_key(Arc::clone(&http_client), &account, &access_key);
+
+        let storage_client = storage_account_client.as_storage_client();
+
+        let container_name = container_name.into();
+
+        let container_client = storage_client.as_container_client(&container_name);
+
+        Self {
+            container_client,
+            container_name,
+        }
+    }
+
+    /// List one page of objects with the given prefix and a set delimiter of
+    /// `/`. Returns common prefixes (directories) in addition to object
+    /// metadata. Optionally takes the continuation token returned with the
+    /// previous page.
+    pub async fn list_with_delimiter_and_token(
+        &self,
+        prefix: &CloudPath,
+        next_token: &Option<String>,
+    ) -> Result<ListResult<CloudPath>> {
         let mut request = self.container_client.list_blobs();
 
         let prefix = prefix.to_raw();
@@ -197,6 +330,10 @@ impl ObjectStoreApi for MicrosoftAzure {
         request = request.delimiter(Delimiter::new(DELIMITER));
         request = request.prefix(&*prefix);
 
+        if let Some(marker) = next_token {
+            request = request.next_marker(marker as &str);
+        }
+
         let resp = request.execute().await.context(UnableToListData)?;
 
         let next_token = resp.next_marker.as_ref().map(|m| m.as_str().to_string());
@@ -241,42 +378,10 @@ impl ObjectStoreApi for MicrosoftAzure {
     }
 }
 
-impl MicrosoftAzure {
-    /// Configure a connection to container with given name on Microsoft Azure
-    /// Blob store.
-    ///
-    /// The credentials `account` and `access_key` must provide access to the
-    /// store.
-    pub fn new(
-        account: impl Into<String>,
-        access_key: impl Into<String>,
-        container_name: impl Into<String>,
-    ) -> Self {
-        let account = account.into();
-        let access_key = access_key.into();
-        // From https://github.com/Azure/azure-sdk-for-rust/blob/master/sdk/storage/examples/blob_00.rs#L29
-        let http_client: Arc<Box<dyn HttpClient>> = Arc::new(Box::new(reqwest::Client::new()));
-
-        let storage_account_client =
-            StorageAccountClient::new_access_key(Arc::clone(&http_client), &account, &access_key);
-
-        let storage_client = storage_account_client.as_storage_client();
-
-        let container_name = container_name.into();
-
-        let container_client = storage_client.as_container_client(&container_name);
-
-        Self {
-            container_client,
-            container_name,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{list_with_delimiter, put_get_delete_list};
+    use crate::tests::{get_range_and_head, list_with_delimiter, put_get_delete_list};
     use crate::ObjectStore;
     use std::env;
 
@@ -346,5 +451,27 @@ mod tests {
 
         put_get_delete_list(&integration).await.unwrap();
         list_with_delimiter(&integration).await.unwrap();
+        get_range_and_head(&integration).await.unwrap();
+    }
+
+    #[test]
+    fn classify_retryable_errors() {
+        let err = Error::UnableToGetData {
+            source: Box::new(io::Error::new(io::ErrorKind::ConnectionReset, "reset")),
+            location: "file".to_string(),
+        };
+        assert!(err.is_retryable());
+
+        let err = Error::UnableToGetData {
+            source: "The specified blob does not exist".into(),
+            location: "file".to_string(),
+        };
+        assert!(!err.is_retryable());
+
+        let err = Error::DataDoesNotMatchLength {
+            expected: 1,
+            actual: 2,
+        };
+        assert!(!err.is_retryable());
     }
 }
@@ -0,0 +1,604 @@
+//! This module contains a read-through cache that keeps objects (and ranges
+//! of objects) fetched from another object store in a bounded directory on
+//! local disk, so repeated reads of the same data don't go over the network.
+use crate::{path, ListResult, ObjectMeta, ObjectStore, ObjectStoreApi, ObjectStorePath, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
+use observability_deps::opentelemetry::{self, metrics::Counter};
+use std::{collections::HashMap, ffi::OsStr, fmt, io, ops::Range, path::PathBuf, sync::Mutex};
+use tokio::fs;
+
+/// Extension of the files the cache stores data in
+const CACHE_FILE_EXTENSION: &str = "cache";
+
+/// Counts of how effective a [`LocalDiskCache`] has been
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of reads served from local disk
+    pub hits: u64,
+    /// Number of reads that had to be fetched from the inner store
+    pub misses: u64,
+    /// Number of bytes served from local disk
+    pub hit_bytes: u64,
+    /// Number of bytes fetched from the inner store
+    pub miss_bytes: u64,
+    /// Number of cached objects or ranges removed to make space
+    pub evictions: u64,
+}
+
+/// A read-through cache in front of another object store.
+///
+/// The results of `get` and `get_range` are stored in files in a local
+/// directory, up to a total of `max_bytes`, evicting the least recently
+/// used entries to make room. Before cached data is returned it is
+/// validated against the size and last modified time reported by the
+/// inner store's `head`, so objects changed by other writers are
+/// fetched again. Writes and deletes go straight to the inner store.
+pub struct LocalDiskCache {
+    inner: ObjectStore,
+    dir: PathBuf,
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+    metrics: CacheMetrics,
+}
+
+impl fmt::Debug for LocalDiskCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalDiskCache")
+            .field("inner", &self.inner)
+            .field("dir", &self.dir)
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+/// Identifies what is cached: a whole object, or a range of one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    location: String,
+    range: Option<Range<usize>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    /// File containing the cached data
+    file: PathBuf,
+    /// Number of bytes of cached data
+    len: usize,
+    /// Size of the object when the data was cached
+    object_size: usize,
+    /// Last modified time of the object when the data was cached
+    last_modified: DateTime<Utc>,
+    /// Value of the cache's clock when this entry was last used
+    last_used: u64,
+}
+
+impl CacheEntry {
+    fn is_valid_for<P: ObjectStorePath>(&self, meta: &ObjectMeta<P>) -> bool {
+        self.object_size == meta.size && self.last_modified == meta.last_modified
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Total bytes of all cached entries
+    used_bytes: usize,
+    /// Logical clock, incremented on every use, for finding the least
+    /// recently used entry
+    clock: u64,
+    next_file_id: u64,
+    stats: CacheStats,
+}
+
+impl CacheState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> Option<PathBuf> {
+        let entry = self.entries.remove(key)?;
+        self.used_bytes -= entry.len;
+        Some(entry.file)
+    }
+
+    /// Removes least recently used entries until at most `max_bytes`
+    /// are used, returning the files of the removed entries
+    fn evict(&mut self, max_bytes: usize) -> Vec<PathBuf> {
+        let mut files = vec![];
+        while self.used_bytes > max_bytes {
+            let key = match self.entries.iter().min_by_key(|(_, e)| e.last_used) {
+                Some((key, _)) => key.clone(),
+                None => break,
+            };
+            files.extend(self.remove(&key));
+            self.stats.evictions += 1;
+        }
+        files
+    }
+}
+
+struct CacheMetrics {
+    hits: Counter<u64>,
+    misses: Counter<u64>,
+    hit_bytes: Counter<u64>,
+    miss_bytes: Counter<u64>,
+    evictions: Counter<u64>,
+}
+
+impl CacheMetrics {
+    fn new() -> Self {
+        let meter = opentelemetry::global::meter("iox");
+        Self {
+            hits: meter
+                .u64_counter("object_store.cache.hits")
+                .with_description("object store reads served from the local disk cache")
+                .init(),
+            misses: meter
+                .u64_counter("object_store.cache.misses")
+                .with_description("object store reads not found in the local disk cache")
+                .init(),
+            hit_bytes: meter
+                .u64_counter("object_store.cache.hit.bytes")
+                .with_description("bytes served from the local disk cache")
+                .init(),
+            miss_bytes: meter
+                .u64_counter("object_store.cache.miss.bytes")
+                .with_description("bytes fetched from the object store on cache misses")
+                .init(),
+            evictions: meter
+                .u64_counter("object_store.cache.evictions")
+                .with_description("entries evicted from the local disk cache")
+                .init(),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStoreApi for LocalDiskCache {
+    type Path = path::Path;
+    type Error = crate::Error;
+
+    fn new_path(&self) -> Self::Path {
+        self.inner.new_path()
+    }
+
+    async fn put<S>(&self, location: &Self::Path, bytes: S, length: Option<usize>) -> Result<()>
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+    {
+        let res = self.inner.put(location, bytes, length).await;
+        self.invalidate(location).await;
+        res
+    }
+
+    async fn get(&self, location: &Self::Path) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let data = self.read_through(location, None).await?;
+        Ok(futures::stream::once(async move { Ok(data) }).boxed())
+    }
+
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        self.read_through(location, Some(range)).await
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Self::Path) -> Result<()> {
+        let res = self.inner.delete(location).await;
+        self.invalidate(location).await;
+        res
+    }
+
+    async fn list<'a>(
+        &'a self,
+        prefix: Option<&'a Self::Path>,
+    ) -> Result<BoxStream<'a, Result<Vec<Self::Path>>>> {
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+}
+
+impl LocalDiskCache {
+    /// Create a cache in front of `inner` that stores up to `max_bytes`
+    /// of data in files in `dir`, creating it if needed.
+    ///
+    /// Cache files left in `dir` by a previous process are removed, as
+    /// there is no record of what they contain.
+    pub fn new(inner: ObjectStore, dir: impl Into<PathBuf>, max_bytes: usize) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension() == Some(OsStr::new(CACHE_FILE_EXTENSION)) {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(Self {
+            inner,
+            dir,
+            max_bytes,
+            state: Default::default(),
+            metrics: CacheMetrics::new(),
+        })
+    }
+
+    /// The store this cache reads through to
+    pub fn inner(&self) -> &ObjectStore {
+        &self.inner
+    }
+
+    /// Returns counts of how effective the cache has been so far
+    pub fn stats(&self) -> CacheStats {
+        self.state.lock().expect("mutex poisoned").stats
+    }
+
+    /// Returns the total number of bytes currently cached
+    pub fn used_bytes(&self) -> usize {
+        self.state.lock().expect("mutex poisoned").used_bytes
+    }
+
+    /// Returns the object (or `range` of it) at `location`, from local
+    /// disk if a valid copy is cached and from the inner store otherwise.
+    ///
+    /// Problems with the local disk are never reported as errors; the
+    /// data is fetched from the inner store instead.
+    async fn read_through(
+        &self,
+        location: &path::Path,
+        range: Option<Range<usize>>,
+    ) -> Result<Bytes> {
+        let meta = self.inner.head(location).await?;
+        let key = CacheKey {
+            location: location.display(),
+            range,
+        };
+
+        if let Some(file) = self.lookup(&key, &meta).await {
+            match fs::read(&file).await {
+                Ok(data) => {
+                    self.record_hit(data.len());
+                    return Ok(data.into());
+                }
+                // evicted since the lookup, or removed by someone else
+                Err(_) => self.remove(&key).await,
+            }
+        }
+
+        let data = match &key.range {
+            Some(range) => self.inner.get_range(location, range.clone()).await?,
+            None => self
+                .inner
+                .get(location)
+                .await?
+                .map_ok(|b| bytes::BytesMut::from(&b[..]))
+                .try_concat()
+                .await?
+                .freeze(),
+        };
+        self.record_miss(data.len());
+
+        self.insert(key, &meta, &data).await;
+
+        Ok(data)
+    }
+
+    /// Returns the file caching `key`, if it is valid for an object
+    /// described by `meta`, removing it if it is not.
+    async fn lookup(&self, key: &CacheKey, meta: &ObjectMeta<path::Path>) -> Option<PathBuf> {
+        let stale = {
+            let mut state = self.state.lock().expect("mutex poisoned");
+            let now = state.tick();
+            let entry = state.entries.get_mut(key)?;
+            if entry.is_valid_for(meta) {
+                entry.last_used = now;
+                return Some(entry.file.clone());
+            }
+            state.remove(key)
+        };
+
+        remove_files(stale).await;
+        None
+    }
+
+    /// Stores `data` for `key` on disk, evicting other entries to make
+    /// space if needed.
+    async fn insert(&self, key: CacheKey, meta: &ObjectMeta<path::Path>, data: &Bytes) {
+        if data.len() > self.max_bytes {
+            return;
+        }
+
+        let file = {
+            let mut state = self.state.lock().expect("mutex poisoned");
+            state.next_file_id += 1;
+            self.dir
+                .join(format!("{}.{}", state.next_file_id, CACHE_FILE_EXTENSION))
+        };
+
+        if fs::write(&file, data).await.is_err() {
+            // The cache directory may be full or unavailable; the data is
+            // still returned, just not cached.
+            remove_files(Some(file)).await;
+            return;
+        }
+
+        let removed = {
+            let mut state = self.state.lock().expect("mutex poisoned");
+            let last_used = state.tick();
+            let mut removed: Vec<_> = state.remove(&key).into_iter().collect();
+
+            state.used_bytes += data.len();
+            state.entries.insert(
+                key,
+                CacheEntry {
+                    file,
+                    len: data.len(),
+                    object_size: meta.size,
+                    last_modified: meta.last_modified,
+                    last_used,
+                },
+            );
+
+            let evicted = state.evict(self.max_bytes);
+            self.metrics.evictions.add(evicted.len() as u64, &[]);
+            removed.extend(evicted);
+            removed
+        };
+
+        remove_files(removed).await;
+    }
+
+    async fn remove(&self, key: &CacheKey) {
+        let file = self.state.lock().expect("mutex poisoned").remove(key);
+        remove_files(file).await;
+    }
+
+    /// Removes everything cached for the object at `location`
+    async fn invalidate(&self, location: &path::Path) {
+        let location = location.display();
+        let files: Vec<_> = {
+            let mut state = self.state.lock().expect("mutex poisoned");
+            let keys: Vec<_> = state
+                .entries
+                .keys()
+                .filter(|key| key.location == location)
+                .cloned()
+                .collect();
+            keys.iter().filter_map(|key| state.remove(key)).collect()
+        };
+
+        remove_files(files).await;
+    }
+
+    fn record_hit(&self, bytes: usize) {
+        let mut state = self.state.lock().expect("mutex poisoned");
+        state.stats.hits += 1;
+        state.stats.hit_bytes += bytes as u64;
+        self.metrics.hits.add(1, &[]);
+        self.metrics.hit_bytes.add(bytes as u64, &[]);
+    }
+
+    fn record_miss(&self, bytes: usize) {
+        let mut state = self.state.lock().expect("mutex poisoned");
+        state.stats.misses += 1;
+        state.stats.miss_bytes += bytes as u64;
+        self.metrics.misses.add(1, &[]);
+        self.metrics.miss_bytes.add(bytes as u64, &[]);
+    }
+}
+
+async fn remove_files(files: impl IntoIterator<Item = PathBuf>) {
+    for file in files {
+        // don't care if it errors, the file may already be gone
+        let _ = fs::remove_file(&file).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        disk::File,
+        tests::{get_range_and_head, list_with_delimiter, put_get_delete_list},
+    };
+    use tempfile::TempDir;
+
+    struct TestCache {
+        store: ObjectStore,
+        _remote_dir: TempDir,
+        cache_dir: TempDir,
+    }
+
+    impl TestCache {
+        /// Returns a cache of `max_bytes` in front of a `File` store
+        /// standing in for a remote store
+        fn new(max_bytes: usize) -> Self {
+            let remote_dir = TempDir::new().unwrap();
+            let cache_dir = TempDir::new().unwrap();
+            let remote = ObjectStore::new_file(File::new(remote_dir.path()));
+            let cache = LocalDiskCache::new(remote, cache_dir.path(), max_bytes).unwrap();
+
+            Self {
+                store: ObjectStore::new_cached(cache),
+                _remote_dir: remote_dir,
+                cache_dir,
+            }
+        }
+
+        fn cache(&self) -> &LocalDiskCache {
+            match &self.store.0 {
+                crate::ObjectStoreIntegration::Cached(cache) => cache,
+                _ => unreachable!(),
+            }
+        }
+
+        fn location(&self, name: &str) -> path::Path {
+            let mut location = self.store.new_path();
+            location.set_file_name(name);
+            location
+        }
+
+        async fn put(&self, store: &ObjectStore, name: &str, data: &'static str) {
+            let data = Bytes::from(data);
+            let len = data.len();
+            store
+                .put(
+                    &self.location(name),
+                    futures::stream::once(async move { io::Result::Ok(data) }),
+                    Some(len),
+                )
+                .await
+                .unwrap();
+        }
+
+        async fn get(&self, name: &str) -> Bytes {
+            self.store
+                .get(&self.location(name))
+                .await
+                .unwrap()
+                .map_ok(|b| bytes::BytesMut::from(&b[..]))
+                .try_concat()
+                .await
+                .unwrap()
+                .freeze()
+        }
+
+        fn num_cache_files(&self) -> usize {
+            std::fs::read_dir(self.cache_dir.path()).unwrap().count()
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_file_test() {
+        let test = TestCache::new(1024);
+
+        put_get_delete_list(&test.store).await.unwrap();
+        list_with_delimiter(&test.store).await.unwrap();
+        get_range_and_head(&test.store).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_are_cached() {
+        let test = TestCache::new(1024);
+        test.put(&test.store, "a", "arbitrary data").await;
+
+        assert_eq!(test.get("a").await, "arbitrary data");
+        assert_eq!(test.get("a").await, "arbitrary data");
+
+        let location = test.location("a");
+        assert_eq!(
+            test.store.get_range(&location, 2..7).await.unwrap(),
+            "bitra"
+        );
+        assert_eq!(
+            test.store.get_range(&location, 2..7).await.unwrap(),
+            "bitra"
+        );
+
+        assert_eq!(
+            test.cache().stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                hit_bytes: 19,
+                miss_bytes: 19,
+                evictions: 0,
+            }
+        );
+        assert_eq!(test.cache().used_bytes(), 19);
+        assert_eq!(test.num_cache_files(), 2);
+    }
+
+    #[tokio::test]
+    async fn writes_invalidate_cache() {
+        let test = TestCache::new(1024);
+        test.put(&test.store, "a", "old data").await;
+        assert_eq!(test.get("a").await, "old data");
+
+        test.put(&test.store, "a", "new data").await;
+        assert_eq!(test.get("a").await, "new data");
+
+        test.store.delete(&test.location("a")).await.unwrap();
+        assert_eq!(test.num_cache_files(), 0);
+        assert_eq!(test.cache().stats().misses, 2);
+    }
+
+    #[tokio::test]
+    async fn changes_to_remote_are_detected() {
+        let test = TestCache::new(1024);
+        test.put(&test.store, "a", "old data").await;
+        assert_eq!(test.get("a").await, "old data");
+
+        // write directly to the remote store, bypassing the cache
+        test.put(test.cache().inner(), "a", "newer data").await;
+        assert_eq!(test.get("a").await, "newer data");
+
+        let stats = test.cache().stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(test.num_cache_files(), 1);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_is_evicted() {
+        let test = TestCache::new(20);
+        test.put(&test.store, "a", "aaaaaaaaaa").await;
+        test.put(&test.store, "b", "bbbbbbbbbb").await;
+        test.put(&test.store, "c", "cccccccccc").await;
+
+        test.get("a").await;
+        test.get("b").await;
+        test.get("a").await;
+        // evicts b, which is the least recently used
+        test.get("c").await;
+
+        assert_eq!(test.cache().stats().evictions, 1);
+        assert_eq!(test.cache().used_bytes(), 20);
+        assert_eq!(test.num_cache_files(), 2);
+
+        let hits = test.cache().stats().hits;
+        test.get("a").await;
+        test.get("c").await;
+        assert_eq!(test.cache().stats().hits, hits + 2);
+
+        test.get("b").await;
+        assert_eq!(test.cache().stats().hits, hits + 2);
+    }
+
+    #[tokio::test]
+    async fn objects_larger_than_cache_are_not_cached() {
+        let test = TestCache::new(4);
+        test.put(&test.store, "a", "arbitrary data").await;
+
+        assert_eq!(test.get("a").await, "arbitrary data");
+        assert_eq!(test.get("a").await, "arbitrary data");
+
+        assert_eq!(test.cache().stats().misses, 2);
+        assert_eq!(test.num_cache_files(), 0);
+    }
+
+    #[tokio::test]
+    async fn old_cache_files_are_removed() {
+        let cache_dir = TempDir::new().unwrap();
+        let old_file = cache_dir.path().join("1.cache");
+        let other_file = cache_dir.path().join("keep.txt");
+        std::fs::write(&old_file, "old").unwrap();
+        std::fs::write(&other_file, "other").unwrap();
+
+        let remote_dir = TempDir::new().unwrap();
+        let remote = ObjectStore::new_file(File::new(remote_dir.path()));
+        LocalDiskCache::new(remote, cache_dir.path(), 1024).unwrap();
+
+        assert!(!old_file.exists());
+        assert!(other_file.exists());
+    }
+}
@@ -8,8 +8,17 @@ use futures::{
     Stream, StreamExt, TryStreamExt,
 };
 use snafu::{ensure, futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
-use std::{collections::BTreeSet, convert::TryFrom, io, path::PathBuf};
-use tokio::fs;
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    io::{self, SeekFrom},
+    ops::Range,
+    path::PathBuf,
+};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
 use tokio_util::codec::{BytesCodec, FramedRead};
 use walkdir::WalkDir;
 
@@ -50,6 +59,9 @@ pub enum Error {
     #[snafu(display("Unable to open file {}: {}", path.display(), source))]
     UnableToOpenFile { source: io::Error, path: PathBuf },
 
+    #[snafu(display("Unable to read metadata for {}: {}", path.display(), source))]
+    UnableToReadMetadata { source: io::Error, path: PathBuf },
+
     #[snafu(display("Unable to process directory entry: {}", source))]
     UnableToProcessEntry { source: walkdir::Error },
 
@@ -136,6 +148,46 @@ impl ObjectStoreApi for File {
         Ok(s.boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let path = self.path(location);
+
+        let mut file = fs::File::open(&path)
+            .await
+            .context(UnableToOpenFile { path: &path })?;
+
+        file.seek(SeekFrom::Start(range.start as u64))
+            .await
+            .context(UnableToReadBytes { path: &path })?;
+
+        let mut data = Vec::with_capacity(range.len());
+        file.take(range.len() as u64)
+            .read_to_end(&mut data)
+            .await
+            .context(UnableToReadBytes { path })?;
+
+        Ok(data.into())
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let path = self.path(location);
+
+        let metadata = fs::metadata(&path)
+            .await
+            .context(UnableToReadMetadata { path: &path })?;
+
+        let last_modified = metadata
+            .modified()
+            .expect("Modified file time should be supported on this platform")
+            .into();
+        let size = usize::try_from(metadata.len()).context(FileSizeOverflowedUsize { path })?;
+
+        Ok(ObjectMeta {
+            location: location.to_owned(),
+            last_modified,
+            size,
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let path = self.path(location);
         fs::remove_file(&path)
@@ -244,6 +296,36 @@ impl ObjectStoreApi for File {
     }
 }
 
+impl Error {
+    /// Returns true if this error may be transient, so the operation could
+    /// be retried.
+    pub(crate) fn is_retryable(&self) -> bool {
+        use Error::*;
+
+        match self {
+            UnableToCopyDataToFile { source }
+            | UnableToCreateDir { source, .. }
+            | UnableToDeleteFile { source, .. }
+            | UnableToOpenFile { source, .. }
+            | UnableToReadMetadata { source, .. }
+            | UnableToReadBytes { source, .. }
+            | UnableToStreamDataIntoMemory { source } => io_error_is_retryable(source),
+            UnableToCreateFile { err, .. } => io_error_is_retryable(err),
+            UnableToAccessMetadata { source, .. } | UnableToProcessEntry { source } => {
+                source.io_error().map_or(false, io_error_is_retryable)
+            }
+            DataDoesNotMatchLength { .. } | FileSizeOverflowedUsize { .. } => false,
+        }
+    }
+}
+
+fn io_error_is_retryable(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
 impl File {
     /// Create new filesystem storage.
     pub fn new(root: impl Into<PathBuf>) -> Self {
@@ -264,7 +346,7 @@ mod tests {
     use super::*;
 
     use crate::{
-        tests::{list_with_delimiter, put_get_delete_list},
+        tests::{get_range_and_head, list_with_delimiter, put_get_delete_list},
         Error as ObjectStoreError, ObjectStore, ObjectStoreApi, ObjectStorePath,
     };
     use futures::stream;
@@ -277,6 +359,7 @@ mod tests {
 
         put_get_delete_list(&integration).await.unwrap();
         list_with_delimiter(&integration).await.unwrap();
+        get_range_and_head(&integration).await.unwrap();
     }
 
     #[tokio::test]
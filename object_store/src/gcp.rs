@@ -9,7 +9,10 @@ use bytes::Bytes;
 use cloud_storage::Client;
 use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use snafu::{ensure, futures::TryStreamExt as _, ResultExt, Snafu};
-use std::{convert::TryFrom, env, io};
+use std::{convert::TryFrom, env, io, ops::Range};
+
+/// How long the signed URLs used for ranged reads are valid for, in seconds
+const SIGNED_URL_DURATION_SECS: u32 = 60;
 
 /// A specialized `Result` for Google Cloud Storage object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -68,6 +71,18 @@ pub enum Error {
         bucket: String,
         location: String,
     },
+
+    #[snafu(display(
+        "Unable to GET range of data. Bucket: {}, Location: {}, Error: {}",
+        bucket,
+        location,
+        source,
+    ))]
+    UnableToGetRange {
+        source: reqwest::Error,
+        bucket: String,
+        location: String,
+    },
 }
 
 /// Configuration for connecting to [Google Cloud Storage](https://cloud.google.com/storage/).
@@ -146,6 +161,75 @@ impl ObjectStoreApi for GoogleCloudStorage {
         Ok(futures::stream::once(async move { Ok(bytes.into()) }).boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        // GCS can't express an empty range
+        if range.start >= range.end {
+            return Ok(Bytes::new());
+        }
+
+        let location = location.to_raw();
+
+        let object = self
+            .client
+            .object()
+            .read(&self.bucket_name, &location)
+            .await
+            .context(UnableToGetData {
+                bucket: &self.bucket_name,
+                location: &location,
+            })?;
+
+        // The cloud storage crate can't make ranged requests, so make one
+        // directly using a signed URL for the object
+        let url = object
+            .download_url(SIGNED_URL_DURATION_SECS)
+            .context(UnableToGetData {
+                bucket: &self.bucket_name,
+                location: &location,
+            })?;
+
+        reqwest::Client::new()
+            .get(&url)
+            // HTTP ranges include the last byte
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
+            )
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(UnableToGetRange {
+                bucket: &self.bucket_name,
+                location: &location,
+            })?
+            .bytes()
+            .await
+            .context(UnableToGetRange {
+                bucket: &self.bucket_name,
+                location,
+            })
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let location = location.to_raw();
+
+        let object = self
+            .client
+            .object()
+            .read(&self.bucket_name, &location)
+            .await
+            .context(UnableToGetData {
+                bucket: &self.bucket_name,
+                location,
+            })?;
+
+        Ok(ObjectMeta {
+            location: CloudPath::raw(&object.name),
+            last_modified: object.updated,
+            size: usize::try_from(object.size).expect("unsupported size on this platform"),
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let location = location.to_raw();
         let location_copy = location.clone();
@@ -203,6 +287,7 @@ impl ObjectStoreApi for GoogleCloudStorage {
             ..Default::default()
         };
 
+        // The client follows the page tokens, yielding each page in turn
         let mut object_lists = Box::pin(
             self.client
                 .object()
@@ -213,44 +298,74 @@ impl ObjectStoreApi for GoogleCloudStorage {
                 })?,
         );
 
-        let result = match object_lists.next().await {
-            None => ListResult {
-                objects: vec![],
-                common_prefixes: vec![],
-                next_token: None,
-            },
-            Some(list_response) => {
-                let list_response = list_response.context(UnableToStreamListData {
-                    bucket: &self.bucket_name,
-                })?;
-
-                ListResult {
-                    objects: list_response
-                        .items
-                        .iter()
-                        .map(|object| {
-                            let location = CloudPath::raw(&object.name);
-                            let last_modified = object.updated;
-                            let size = usize::try_from(object.size)
-                                .expect("unsupported size on this platform");
-
-                            ObjectMeta {
-                                location,
-                                last_modified,
-                                size,
-                            }
-                        })
-                        .collect(),
-                    common_prefixes: list_response.prefixes.iter().map(CloudPath::raw).collect(),
-                    next_token: list_response.next_page_token,
-                }
-            }
+        let mut result = ListResult {
+            objects: vec![],
+            common_prefixes: vec![],
+            next_token: None,
         };
 
+        while let Some(list_response) = object_lists.next().await {
+            let list_response = list_response.context(UnableToStreamListData {
+                bucket: &self.bucket_name,
+            })?;
+
+            result
+                .objects
+                .extend(list_response.items.iter().map(|object| {
+                    let location = CloudPath::raw(&object.name);
+                    let last_modified = object.updated;
+                    let size =
+                        usize::try_from(object.size).expect("unsupported size on this platform");
+
+                    ObjectMeta {
+                        location,
+                        last_modified,
+                        size,
+                    }
+                }));
+            result
+                .common_prefixes
+                .extend(list_response.prefixes.iter().map(CloudPath::raw));
+        }
+
         Ok(result)
     }
 }
 
+impl Error {
+    /// Returns true if this error may be transient, such as a network
+    /// problem or a server side error, so the operation could be retried.
+    pub(crate) fn is_retryable(&self) -> bool {
+        use Error::*;
+
+        match self {
+            UnableToPutData { source, .. }
+            | UnableToListData { source, .. }
+            | UnableToStreamListData { source, .. }
+            | UnableToDeleteData { source, .. }
+            | UnableToGetData { source, .. } => cloud_storage_error_is_retryable(source),
+            UnableToGetRange { source, .. } => reqwest_error_is_retryable(source),
+            DataDoesNotMatchLength { .. } => false,
+        }
+    }
+}
+
+fn cloud_storage_error_is_retryable(error: &cloud_storage::Error) -> bool {
+    match error {
+        cloud_storage::Error::Google(response) => matches!(response.error.code, 429 | 500..=599),
+        cloud_storage::Error::Reqwest(error) => reqwest_error_is_retryable(error),
+        _ => false,
+    }
+}
+
+fn reqwest_error_is_retryable(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+        || error.is_connect()
+        || error.status().map_or(false, |status| {
+            status.is_server_error() || status.as_u16() == 429
+        })
+}
+
 impl GoogleCloudStorage {
     /// Configure a connection to Google Cloud Storage.
     pub fn new(
@@ -272,7 +387,9 @@ impl GoogleCloudStorage {
 mod test {
     use super::*;
     use crate::{
-        tests::{get_nonexistent_object, list_with_delimiter, put_get_delete_list},
+        tests::{
+            get_nonexistent_object, get_range_and_head, list_with_delimiter, put_get_delete_list,
+        },
         Error as ObjectStoreError, GoogleCloudStorage, ObjectStore, ObjectStoreApi,
         ObjectStorePath,
     };
@@ -339,6 +456,7 @@ mod test {
 
         put_get_delete_list(&integration).await.unwrap();
         list_with_delimiter(&integration).await.unwrap();
+        get_range_and_head(&integration).await.unwrap();
     }
 
     #[tokio::test]
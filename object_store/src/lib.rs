@@ -11,7 +11,8 @@
 //! # object_store
 //!
 //! This crate provides APIs for interacting with object storage services. It
-//! currently supports PUT, GET, DELETE, and list for Google Cloud Storage,
+//! currently supports PUT, GET (of whole objects or byte ranges), HEAD,
+//! DELETE, and list for Google Cloud Storage,
 //! Amazon S3, in-memory and local file storage.
 //!
 //! Future compatibility will include Azure Blob Storage, Minio, and Ceph.
@@ -19,24 +20,28 @@
 pub mod aws;
 pub mod azure;
 mod buffer;
+pub mod cache;
 pub mod disk;
 pub mod gcp;
 pub mod memory;
 pub mod path;
+pub mod retry;
 
 use aws::AmazonS3;
 use azure::MicrosoftAzure;
+use cache::LocalDiskCache;
 use disk::File;
 use gcp::GoogleCloudStorage;
 use memory::InMemory;
 use path::ObjectStorePath;
+use retry::RetryingStore;
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use snafu::{ResultExt, Snafu};
-use std::io;
+use std::{future::Future, io, ops::Range, time::Duration};
 
 /// Universal API to multiple object store services.
 #[async_trait]
@@ -66,6 +71,19 @@ pub trait ObjectStoreApi: Send + Sync + 'static {
         location: &Self::Path,
     ) -> Result<BoxStream<'static, Result<Bytes, Self::Error>>, Self::Error>;
 
+    /// Return the bytes in the given byte `range` of the object at the
+    /// specified location. If the range extends past the end of the
+    /// object, only the bytes up to the end of the object are returned.
+    async fn get_range(
+        &self,
+        location: &Self::Path,
+        range: Range<usize>,
+    ) -> Result<Bytes, Self::Error>;
+
+    /// Return the metadata of the object at the specified location,
+    /// without fetching its contents.
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>, Self::Error>;
+
     /// Delete the object at the specified location.
     async fn delete(&self, location: &Self::Path) -> Result<(), Self::Error>;
 
@@ -78,6 +96,10 @@ pub trait ObjectStoreApi: Send + Sync + 'static {
     /// List objects with the given prefix and an implementation specific
     /// delimiter. Returns common prefixes (directories) in addition to object
     /// metadata.
+    ///
+    /// Implementations follow any continuation tokens of the underlying
+    /// service, so the result contains everything under `prefix` and its
+    /// `next_token` is `None`.
     async fn list_with_delimiter(
         &self,
         prefix: &Self::Path,
@@ -113,6 +135,16 @@ impl ObjectStore {
     pub fn new_microsoft_azure(azure: MicrosoftAzure) -> Self {
         Self(ObjectStoreIntegration::MicrosoftAzure(Box::new(azure)))
     }
+
+    /// Configure a local disk cache in front of another store.
+    pub fn new_cached(cache: LocalDiskCache) -> Self {
+        Self(ObjectStoreIntegration::Cached(Box::new(cache)))
+    }
+
+    /// Configure retries of the failed operations of another store.
+    pub fn new_retrying(retrying: RetryingStore) -> Self {
+        Self(ObjectStoreIntegration::Retrying(Box::new(retrying)))
+    }
 }
 
 #[async_trait]
@@ -128,6 +160,8 @@ impl ObjectStoreApi for ObjectStore {
             InMemory(in_mem) => path::Path::InMemory(in_mem.new_path()),
             File(file) => path::Path::File(file.new_path()),
             MicrosoftAzure(azure) => path::Path::MicrosoftAzure(azure.new_path()),
+            Cached(cache) => cache.new_path(),
+            Retrying(retrying) => retrying.new_path(),
         }
     }
 
@@ -154,6 +188,8 @@ impl ObjectStoreApi for ObjectStore {
             (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => {
                 azure.put(location, bytes, length).await?
             }
+            (Cached(cache), location) => cache.put(location, bytes, length).await?,
+            (Retrying(retrying), location) => retrying.put(location, bytes, length).await?,
             _ => unreachable!(),
         }
 
@@ -181,10 +217,75 @@ impl ObjectStoreApi for ObjectStore {
             (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => {
                 azure.get(location).await?.err_into().boxed()
             }
+            (Cached(cache), location) => cache.get(location).await?,
+            (Retrying(retrying), location) => retrying.get(location).await?,
             _ => unreachable!(),
         })
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        use ObjectStoreIntegration::*;
+        match (&self.0, location) {
+            (AmazonS3(s3), path::Path::AmazonS3(location)) => s3
+                .get_range(location, range)
+                .await
+                .context(AwsObjectStoreError),
+            (GoogleCloudStorage(gcs), path::Path::GoogleCloudStorage(location)) => gcs
+                .get_range(location, range)
+                .await
+                .context(GcsObjectStoreError),
+            (InMemory(in_mem), path::Path::InMemory(location)) => in_mem
+                .get_range(location, range)
+                .await
+                .context(InMemoryObjectStoreError),
+            (File(file), path::Path::File(location)) => file
+                .get_range(location, range)
+                .await
+                .context(FileObjectStoreError),
+            (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => azure
+                .get_range(location, range)
+                .await
+                .context(AzureObjectStoreError),
+            (Cached(cache), location) => cache.get_range(location, range).await,
+            (Retrying(retrying), location) => retrying.get_range(location, range).await,
+            _ => unreachable!(),
+        }
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        use ObjectStoreIntegration::*;
+        match (&self.0, location) {
+            (AmazonS3(s3), path::Path::AmazonS3(location)) => s3
+                .head(location)
+                .map_ok(|meta| meta.map_paths(path::Path::AmazonS3))
+                .await
+                .context(AwsObjectStoreError),
+            (GoogleCloudStorage(gcs), path::Path::GoogleCloudStorage(location)) => gcs
+                .head(location)
+                .map_ok(|meta| meta.map_paths(path::Path::GoogleCloudStorage))
+                .await
+                .context(GcsObjectStoreError),
+            (InMemory(in_mem), path::Path::InMemory(location)) => in_mem
+                .head(location)
+                .map_ok(|meta| meta.map_paths(path::Path::InMemory))
+                .await
+                .context(InMemoryObjectStoreError),
+            (File(file), path::Path::File(location)) => file
+                .head(location)
+                .map_ok(|meta| meta.map_paths(path::Path::File))
+                .await
+                .context(FileObjectStoreError),
+            (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => azure
+                .head(location)
+                .map_ok(|meta| meta.map_paths(path::Path::MicrosoftAzure))
+                .await
+                .context(AzureObjectStoreError),
+            (Cached(cache), location) => cache.head(location).await,
+            (Retrying(retrying), location) => retrying.head(location).await,
+            _ => unreachable!(),
+        }
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         use ObjectStoreIntegration::*;
         match (&self.0, location) {
@@ -197,6 +298,8 @@ impl ObjectStoreApi for ObjectStore {
             (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => {
                 azure.delete(location).await?
             }
+            (Cached(cache), location) => cache.delete(location).await?,
+            (Retrying(retrying), location) => retrying.delete(location).await?,
             _ => unreachable!(),
         }
 
@@ -273,6 +376,9 @@ impl ObjectStoreApi for ObjectStore {
                 .map_ok(|s| s.into_iter().map(path::Path::MicrosoftAzure).collect())
                 .err_into()
                 .boxed(),
+
+            (Cached(cache), prefix) => cache.list(prefix).await?,
+            (Retrying(retrying), prefix) => retrying.list(prefix).await?,
             _ => unreachable!(),
         })
     }
@@ -305,6 +411,8 @@ impl ObjectStoreApi for ObjectStore {
                 .map_ok(|list_result| list_result.map_paths(path::Path::MicrosoftAzure))
                 .await
                 .context(AzureObjectStoreError),
+            (Cached(cache), prefix) => cache.list_with_delimiter(prefix).await,
+            (Retrying(retrying), prefix) => retrying.list_with_delimiter(prefix).await,
             _ => unreachable!(),
         }
     }
@@ -323,11 +431,16 @@ pub enum ObjectStoreIntegration {
     File(File),
     /// Microsoft Azure Blob storage
     MicrosoftAzure(Box<MicrosoftAzure>),
+    /// Local disk read-through cache in front of another store
+    Cached(Box<LocalDiskCache>),
+    /// Retries of the failed operations of another store
+    Retrying(Box<RetryingStore>),
 }
 
 /// Result of a list call that includes objects, prefixes (directories) and a
-/// token for the next set of results. Individual result sets may be limited to
-/// 1,000 objects based on the underlying object storage's limitations.
+/// token for the next set of results. Individual pages of results may be
+/// limited to 1,000 objects based on the underlying object storage's
+/// limitations.
 #[derive(Debug)]
 pub struct ListResult<P: ObjectStorePath> {
     /// Token passed to the API for the next page of list results.
@@ -361,6 +474,28 @@ impl<P: ObjectStorePath> ListResult<P> {
     }
 }
 
+/// Lists all results by calling `list_page` with the `next_token` of each
+/// page of results until there are no more pages, combining the pages
+/// into a single `ListResult` with no `next_token`.
+async fn list_all_pages<P, E, F, Fut>(mut list_page: F) -> Result<ListResult<P>, E>
+where
+    P: ObjectStorePath,
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<ListResult<P>, E>>,
+{
+    let mut result = list_page(None).await?;
+
+    while let Some(next_token) = result.next_token.take() {
+        let page = list_page(Some(next_token)).await?;
+
+        result.common_prefixes.extend(page.common_prefixes);
+        result.objects.extend(page.objects);
+        result.next_token = page.next_token;
+    }
+
+    Ok(result)
+}
+
 /// The metadata that describes an object.
 #[derive(Debug)]
 pub struct ObjectMeta<P: ObjectStorePath> {
@@ -416,6 +551,32 @@ pub enum Error {
 
     #[snafu(display("In-memory-based Object Store error: {}", source))]
     InMemoryObjectStoreError { source: memory::Error },
+
+    #[snafu(display("Object Store {} timed out after {:?}", operation, timeout))]
+    TimedOut {
+        operation: &'static str,
+        timeout: Duration,
+    },
+
+    #[snafu(display("Unable to buffer data to write to the Object Store: {}", source))]
+    UnableToBufferData { source: io::Error },
+}
+
+impl Error {
+    /// Returns true if the operation that failed with this error might
+    /// succeed if it is tried again, such as after a network problem or a
+    /// timeout.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::FileObjectStoreError { source } => source.is_retryable(),
+            Self::GcsObjectStoreError { source } => source.is_retryable(),
+            Self::AwsObjectStoreError { source } => source.is_retryable(),
+            Self::AzureObjectStoreError { source } => source.is_retryable(),
+            Self::InMemoryObjectStoreError { .. } => false,
+            Self::TimedOut { .. } => true,
+            Self::UnableToBufferData { .. } => false,
+        }
+    }
 }
 
 impl From<disk::Error> for Error {
@@ -607,6 +768,43 @@ mod tests {
         Ok(())
     }
 
+    pub(crate) async fn get_range_and_head(storage: &ObjectStore) -> Result<()> {
+        delete_fixtures(storage).await;
+
+        let data = Bytes::from("arbitrary data");
+        let location = str_to_path(storage, "test_file");
+
+        let stream_data = std::io::Result::Ok(data.clone());
+        storage
+            .put(
+                &location,
+                futures::stream::once(async move { stream_data }),
+                Some(data.len()),
+            )
+            .await?;
+
+        let range = storage.get_range(&location, 2..7).await?;
+        assert_eq!(range, Bytes::from("bitra"));
+
+        let range = storage.get_range(&location, 0..data.len()).await?;
+        assert_eq!(range, data);
+
+        // ranges past the end of the object are truncated
+        let range = storage.get_range(&location, 10..100).await?;
+        assert_eq!(range, Bytes::from("data"));
+
+        let meta = storage.head(&location).await?;
+        assert_eq!(meta.location, location);
+        assert_eq!(meta.size, data.len());
+
+        storage.delete(&location).await?;
+
+        let err = storage.head(&location).await;
+        assert!(err.is_err(), "Expected HEAD of deleted object to fail");
+
+        Ok(())
+    }
+
     pub(crate) async fn get_nonexistent_object(
         storage: &ObjectStore,
         location: Option<<ObjectStore as ObjectStoreApi>::Path>,
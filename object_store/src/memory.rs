@@ -1,15 +1,16 @@
 //! This module contains the IOx implementation for using memory as the object
 //! store.
 use crate::{
-    path::parsed::DirsAndFileName, ListResult, ObjectMeta, ObjectStoreApi, ObjectStorePath,
+    list_all_pages, path::parsed::DirsAndFileName, ListResult, ObjectMeta, ObjectStoreApi,
+    ObjectStorePath,
 };
 use async_trait::async_trait;
 use bytes::Bytes;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::collections::BTreeSet;
-use std::{collections::BTreeMap, io};
+use std::{collections::BTreeMap, io, ops::Range};
 use tokio::sync::RwLock;
 
 /// A specialized `Result` for in-memory object store-related errors
@@ -27,13 +28,27 @@ pub enum Error {
 
     #[snafu(display("No data in memory found. Location: {}", location))]
     NoDataInMemory { location: String },
+
+    #[snafu(display("Invalid list continuation token: {}", token))]
+    InvalidNextToken { token: String },
 }
 
 /// In-memory storage suitable for testing or for opting out of using a cloud
 /// storage provider.
 #[derive(Debug, Default)]
 pub struct InMemory {
-    storage: RwLock<BTreeMap<DirsAndFileName, Bytes>>,
+    storage: RwLock<BTreeMap<DirsAndFileName, Entry>>,
+    /// If set, the maximum number of common prefixes and objects returned
+    /// by each request for a page of `list_with_delimiter` results
+    page_size: Option<usize>,
+}
+
+/// An object stored in an [`InMemory`] store
+#[derive(Debug, Clone)]
+struct Entry {
+    data: Bytes,
+    /// When the object was written, as reported by `head` and listings
+    last_modified: DateTime<Utc>,
 }
 
 #[async_trait]
@@ -65,29 +80,42 @@ impl ObjectStoreApi for InMemory {
             );
         }
 
-        let content = content.freeze();
+        let entry = Entry {
+            data: content.freeze(),
+            last_modified: Utc::now(),
+        };
 
         self.storage
             .write()
             .await
-            .insert(location.to_owned(), content);
+            .insert(location.to_owned(), entry);
         Ok(())
     }
 
     async fn get(&self, location: &Self::Path) -> Result<BoxStream<'static, Result<Bytes>>> {
-        let data = self
-            .storage
-            .read()
-            .await
-            .get(location)
-            .cloned()
-            .context(NoDataInMemory {
-                location: location.display(),
-            })?;
+        let data = self.get_entry(location).await?.data;
 
         Ok(futures::stream::once(async move { Ok(data) }).boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let data = self.get_entry(location).await?.data;
+
+        let end = range.end.min(data.len());
+        let start = range.start.min(end);
+        Ok(data.slice(start..end))
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let entry = self.get_entry(location).await?;
+
+        Ok(ObjectMeta {
+            location: location.to_owned(),
+            last_modified: entry.last_modified,
+            size: entry.data.len(),
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         self.storage.write().await.remove(&location);
         Ok(())
@@ -112,12 +140,67 @@ impl ObjectStoreApi for InMemory {
         Ok(futures::stream::once(async move { Ok(list) }).boxed())
     }
 
-    /// The memory implementation returns all results, as opposed to the cloud
-    /// versions which limit their results to 1k or more because of API
-    /// limitations.
     async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
+        list_all_pages(|next_token| async move {
+            self.list_with_delimiter_and_token(prefix, &next_token)
+                .await
+        })
+        .await
+    }
+}
+
+impl InMemory {
+    /// Create new in-memory storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn get_entry(&self, location: &DirsAndFileName) -> Result<Entry> {
+        self.storage
+            .read()
+            .await
+            .get(location)
+            .cloned()
+            .context(NoDataInMemory {
+                location: location.display(),
+            })
+    }
+
+    /// Create new in-memory storage that, like the cloud stores, splits
+    /// `list_with_delimiter` results into pages of at most `page_size`
+    /// common prefixes and objects, to test following continuation tokens.
+    pub fn new_with_page_size(page_size: usize) -> Self {
+        assert!(page_size > 0, "page size must be positive");
+
+        Self {
+            page_size: Some(page_size),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a clone of the store
+    pub async fn clone(&self) -> Self {
+        let storage = self.storage.read().await;
+        let storage = storage.clone();
+
+        Self {
+            storage: RwLock::new(storage),
+            page_size: self.page_size,
+        }
+    }
+
+    /// List one page of objects with the given prefix, returning common
+    /// prefixes (directories) in addition to object metadata. Optionally
+    /// takes the continuation token returned with the previous page.
+    ///
+    /// Unless created with [`new_with_page_size`](Self::new_with_page_size)
+    /// all results are returned in a single page.
+    pub async fn list_with_delimiter_and_token(
+        &self,
+        prefix: &DirsAndFileName,
+        next_token: &Option<String>,
+    ) -> Result<ListResult<DirsAndFileName>> {
         let mut common_prefixes = BTreeSet::new();
-        let last_modified = Utc::now();
 
         // Only objects in this base level should be returned in the
         // response. Otherwise, we just collect the common prefixes.
@@ -140,35 +223,56 @@ impl ObjectStoreApi for InMemory {
             } else {
                 let object = ObjectMeta {
                     location: k.to_owned(),
-                    last_modified,
-                    size: v.len(),
+                    last_modified: v.last_modified,
+                    size: v.data.len(),
                 };
                 objects.push(object);
             }
         }
 
-        Ok(ListResult {
-            objects,
-            common_prefixes: common_prefixes.into_iter().collect(),
-            next_token: None,
-        })
-    }
-}
+        let common_prefixes: Vec<_> = common_prefixes.into_iter().collect();
 
-impl InMemory {
-    /// Create new in-memory storage.
-    pub fn new() -> Self {
-        Self::default()
-    }
+        let page_size = match self.page_size {
+            Some(page_size) => page_size,
+            None => {
+                return Ok(ListResult {
+                    objects,
+                    common_prefixes,
+                    next_token: None,
+                })
+            }
+        };
 
-    /// Creates a clone of the store
-    pub async fn clone(&self) -> Self {
-        let storage = self.storage.read().await;
-        let storage = storage.clone();
+        // The token is the number of results returned by previous pages,
+        // counting common prefixes first and then objects
+        let start = match next_token {
+            Some(token) => token
+                .parse::<usize>()
+                .ok()
+                .context(InvalidNextToken { token })?,
+            None => 0,
+        };
+        let end = start + page_size;
+        let total = common_prefixes.len() + objects.len();
 
-        Self {
-            storage: RwLock::new(storage),
-        }
+        let num_prefixes = common_prefixes.len();
+        Ok(ListResult {
+            common_prefixes: common_prefixes
+                .into_iter()
+                .skip(start)
+                .take(page_size)
+                .collect(),
+            objects: objects
+                .into_iter()
+                .skip(start.saturating_sub(num_prefixes))
+                .take(end.saturating_sub(num_prefixes.max(start)))
+                .collect(),
+            next_token: if end < total {
+                Some(end.to_string())
+            } else {
+                None
+            },
+        })
     }
 }
 
@@ -177,7 +281,7 @@ mod tests {
     use super::*;
 
     use crate::{
-        tests::{list_with_delimiter, put_get_delete_list},
+        tests::{get_range_and_head, list_with_delimiter, put_get_delete_list},
         Error as ObjectStoreError, ObjectStore, ObjectStoreApi, ObjectStorePath,
     };
     use futures::stream;
@@ -188,6 +292,118 @@ mod tests {
 
         put_get_delete_list(&integration).await.unwrap();
         list_with_delimiter(&integration).await.unwrap();
+        get_range_and_head(&integration).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn in_memory_with_small_pages_test() {
+        for page_size in 1..=3 {
+            let integration = ObjectStore::new_in_memory(InMemory::new_with_page_size(page_size));
+
+            put_get_delete_list(&integration).await.unwrap();
+            list_with_delimiter(&integration).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn list_with_delimiter_follows_pages() {
+        let in_memory = InMemory::new_with_page_size(2);
+
+        let names = &["a/1", "b/1", "b/2", "c/d/1", "e", "f"];
+        for name in names {
+            let mut location = in_memory.new_path();
+            let mut parts: Vec<_> = name.split('/').collect();
+            location.set_file_name(parts.pop().unwrap());
+            location.push_all_dirs(parts);
+
+            let data = Bytes::from("arbitrary data");
+            in_memory
+                .put(
+                    &location,
+                    futures::stream::once(async move { io::Result::Ok(data) }),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        // 3 common prefixes and 2 objects make 3 pages
+        let prefix = in_memory.new_path();
+        let mut next_token = None;
+        let mut pages = vec![];
+        loop {
+            let page = in_memory
+                .list_with_delimiter_and_token(&prefix, &next_token)
+                .await
+                .unwrap();
+            pages.push((page.common_prefixes.len(), page.objects.len()));
+
+            next_token = page.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![(2, 0), (1, 1), (0, 1)]);
+
+        let result = in_memory.list_with_delimiter(&prefix).await.unwrap();
+        assert!(result.next_token.is_none());
+
+        let common_prefixes: Vec<_> = result.common_prefixes.iter().map(|p| p.display()).collect();
+        assert_eq!(common_prefixes, vec!["a/", "b/", "c/"]);
+
+        let objects: Vec<_> = result
+            .objects
+            .iter()
+            .map(|o| o.location.display())
+            .collect();
+        assert_eq!(objects, vec!["e", "f"]);
+    }
+
+    #[tokio::test]
+    async fn last_modified_is_the_write_time() {
+        let in_memory = InMemory::new();
+
+        let mut location = in_memory.new_path();
+        location.set_file_name("file");
+
+        let before = Utc::now();
+        let data = Bytes::from("arbitrary data");
+        in_memory
+            .put(
+                &location,
+                futures::stream::once(async move { io::Result::Ok(data) }),
+                None,
+            )
+            .await
+            .unwrap();
+        let after = Utc::now();
+
+        let meta = in_memory.head(&location).await.unwrap();
+        assert!(before <= meta.last_modified && meta.last_modified <= after);
+
+        // later requests report the same time
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            in_memory.head(&location).await.unwrap().last_modified,
+            meta.last_modified
+        );
+
+        let listed = in_memory
+            .list_with_delimiter(&in_memory.new_path())
+            .await
+            .unwrap();
+        assert_eq!(listed.objects[0].last_modified, meta.last_modified);
+    }
+
+    #[tokio::test]
+    async fn invalid_next_token_is_an_error() {
+        let in_memory = InMemory::new_with_page_size(2);
+
+        let res = in_memory
+            .list_with_delimiter_and_token(&in_memory.new_path(), &Some("junk".into()))
+            .await;
+
+        assert!(matches!(res, Err(Error::InvalidNextToken { .. })));
     }
 
     #[tokio::test]
@@ -0,0 +1,552 @@
+//! This module contains a wrapper around another object store that retries
+//! operations failing with transient errors, backing off exponentially
+//! between attempts, and times out attempts that take too long.
+use crate::{
+    path, ListResult, ObjectMeta, ObjectStore, ObjectStoreApi, Result, TimedOut, UnableToBufferData,
+};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{stream::BoxStream, Future, Stream, StreamExt, TryStreamExt};
+use observability_deps::{
+    opentelemetry::{self, metrics::Counter, KeyValue},
+    tracing::warn,
+};
+use rand::Rng;
+use snafu::ResultExt;
+use std::{fmt, io, ops::Range, time::Duration};
+
+/// How a [`RetryingStore`] retries failed operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of times to retry an operation after its first
+    /// attempt fails
+    pub max_retries: usize,
+
+    /// The delay before the first retry, which is doubled for each further
+    /// retry
+    pub initial_backoff: Duration,
+
+    /// The maximum delay between retries
+    pub max_backoff: Duration,
+
+    /// If set, attempts that take longer than this fail with a retryable
+    /// [`Error::TimedOut`](crate::Error::TimedOut)
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            timeout: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Returns how long to wait before retry number `retry` (from 0).
+    ///
+    /// The delay is picked at random between half and all of the
+    /// exponential backoff, so that many clients failing at the same time
+    /// don't retry in lockstep.
+    fn backoff(&self, retry: usize) -> Duration {
+        let factor = 1_u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        rand::thread_rng().gen_range(backoff / 2..=backoff)
+    }
+}
+
+/// An object store that retries the operations of another store that fail
+/// with errors for which [`Error::is_retryable`](crate::Error::is_retryable)
+/// is true, as configured by a [`RetryConfig`].
+///
+/// To be able to retry them, `put` buffers the data to write in memory,
+/// and `get` reads the entire object before returning it. Errors from the
+/// stream returned by `list` are not retried, only starting the listing.
+pub struct RetryingStore<O = ObjectStore> {
+    inner: O,
+    config: RetryConfig,
+    metrics: RetryMetrics,
+}
+
+impl<O: fmt::Debug> fmt::Debug for RetryingStore<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingStore")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+struct RetryMetrics {
+    retries: Counter<u64>,
+    timeouts: Counter<u64>,
+    failures: Counter<u64>,
+}
+
+impl RetryMetrics {
+    fn new() -> Self {
+        let meter = opentelemetry::global::meter("iox");
+        Self {
+            retries: meter
+                .u64_counter("object_store.retries")
+                .with_description("object store operations retried after a transient error")
+                .init(),
+            timeouts: meter
+                .u64_counter("object_store.timeouts")
+                .with_description("object store operation attempts that timed out")
+                .init(),
+            failures: meter
+                .u64_counter("object_store.failures")
+                .with_description("object store operations that failed after any retries")
+                .init(),
+        }
+    }
+}
+
+#[async_trait]
+impl<O> ObjectStoreApi for RetryingStore<O>
+where
+    O: ObjectStoreApi<Path = path::Path, Error = crate::Error>,
+{
+    type Path = path::Path;
+    type Error = crate::Error;
+
+    fn new_path(&self) -> Self::Path {
+        self.inner.new_path()
+    }
+
+    async fn put<S>(&self, location: &Self::Path, bytes: S, length: Option<usize>) -> Result<()>
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+    {
+        let data = bytes
+            .map_ok(|b| BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .context(UnableToBufferData)?
+            .freeze();
+
+        let inner = &self.inner;
+        self.retry("put", move || {
+            let data = data.clone();
+            inner.put(
+                location,
+                futures::stream::once(async move { io::Result::Ok(data) }),
+                length,
+            )
+        })
+        .await
+    }
+
+    async fn get(&self, location: &Self::Path) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let inner = &self.inner;
+        let data = self
+            .retry("get", move || get_bytes(inner, location))
+            .await?;
+
+        Ok(futures::stream::once(async move { Ok(data) }).boxed())
+    }
+
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let inner = &self.inner;
+        self.retry("get_range", move || {
+            inner.get_range(location, range.clone())
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+        let inner = &self.inner;
+        self.retry("head", move || inner.head(location)).await
+    }
+
+    async fn delete(&self, location: &Self::Path) -> Result<()> {
+        let inner = &self.inner;
+        self.retry("delete", move || inner.delete(location)).await
+    }
+
+    async fn list<'a>(
+        &'a self,
+        prefix: Option<&'a Self::Path>,
+    ) -> Result<BoxStream<'a, Result<Vec<Self::Path>>>> {
+        let inner = &self.inner;
+        self.retry("list", move || inner.list(prefix)).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
+        let inner = &self.inner;
+        self.retry("list_with_delimiter", move || {
+            inner.list_with_delimiter(prefix)
+        })
+        .await
+    }
+}
+
+impl<O> RetryingStore<O>
+where
+    O: ObjectStoreApi<Path = path::Path, Error = crate::Error>,
+{
+    /// Create a store that retries failed operations of `inner` as
+    /// configured by `config`
+    pub fn new(inner: O, config: RetryConfig) -> Self {
+        Self {
+            inner,
+            config,
+            metrics: RetryMetrics::new(),
+        }
+    }
+
+    /// The store whose operations are retried
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    /// Runs the future returned by `attempt` until it succeeds, fails with
+    /// an error that isn't retryable, or has been retried
+    /// `max_retries` times.
+    async fn retry<T, F, Fut>(&self, operation: &'static str, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let labels = [KeyValue::new("operation", operation)];
+        let mut retries = 0;
+
+        loop {
+            let res = match self.config.timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, attempt()).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        self.metrics.timeouts.add(1, &labels);
+                        TimedOut { operation, timeout }.fail()
+                    }
+                },
+                None => attempt().await,
+            };
+
+            match res {
+                Err(e) if e.is_retryable() && retries < self.config.max_retries => {
+                    let backoff = self.config.backoff(retries);
+                    warn!(
+                        %operation, %e, retries, ?backoff,
+                        "retrying object store operation after error"
+                    );
+                    self.metrics.retries.add(1, &labels);
+
+                    tokio::time::sleep(backoff).await;
+                    retries += 1;
+                }
+                Err(e) => {
+                    self.metrics.failures.add(1, &labels);
+                    return Err(e);
+                }
+                Ok(value) => return Ok(value),
+            }
+        }
+    }
+}
+
+/// Reads the entire object at `location`
+async fn get_bytes<O>(store: &O, location: &path::Path) -> Result<Bytes>
+where
+    O: ObjectStoreApi<Path = path::Path, Error = crate::Error>,
+{
+    let data = store
+        .get(location)
+        .await?
+        .map_ok(|b| BytesMut::from(&b[..]))
+        .try_concat()
+        .await?;
+
+    Ok(data.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::InMemory,
+        tests::{get_range_and_head, list_with_delimiter, put_get_delete_list},
+        Error, ObjectStorePath,
+    };
+    use std::{collections::VecDeque, sync::Mutex};
+
+    /// A fault injected into an operation of a [`FaultyStore`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Fault {
+        /// Fail the operation with a retryable error
+        Error,
+        /// Wait this long before performing the operation
+        Delay(Duration),
+    }
+
+    /// An in-memory store that injects faults into its next operations, one
+    /// fault per operation, in order, to test how an unreliable object
+    /// store is retried
+    #[derive(Debug)]
+    struct FaultyStore {
+        inner: ObjectStore,
+        faults: Mutex<VecDeque<Fault>>,
+    }
+
+    impl FaultyStore {
+        fn new() -> Self {
+            Self {
+                inner: ObjectStore::new_in_memory(InMemory::new()),
+                faults: Default::default(),
+            }
+        }
+
+        fn inject_faults(&self, faults: impl IntoIterator<Item = Fault>) {
+            self.faults.lock().expect("mutex poisoned").extend(faults);
+        }
+
+        /// Applies the next injected fault, if any
+        async fn next_fault(&self) -> Result<()> {
+            let fault = self.faults.lock().expect("mutex poisoned").pop_front();
+            match fault {
+                None => Ok(()),
+                // Fail like an attempt that took too long, which is retried
+                Some(Fault::Error) => TimedOut {
+                    operation: "injected fault",
+                    timeout: Duration::from_secs(0),
+                }
+                .fail(),
+                Some(Fault::Delay(delay)) => {
+                    tokio::time::sleep(delay).await;
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStoreApi for FaultyStore {
+        type Path = path::Path;
+        type Error = Error;
+
+        fn new_path(&self) -> Self::Path {
+            self.inner.new_path()
+        }
+
+        async fn put<S>(&self, location: &Self::Path, bytes: S, length: Option<usize>) -> Result<()>
+        where
+            S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+        {
+            self.next_fault().await?;
+            self.inner.put(location, bytes, length).await
+        }
+
+        async fn get(&self, location: &Self::Path) -> Result<BoxStream<'static, Result<Bytes>>> {
+            self.next_fault().await?;
+            self.inner.get(location).await
+        }
+
+        async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+            self.next_fault().await?;
+            self.inner.get_range(location, range).await
+        }
+
+        async fn head(&self, location: &Self::Path) -> Result<ObjectMeta<Self::Path>> {
+            self.next_fault().await?;
+            self.inner.head(location).await
+        }
+
+        async fn delete(&self, location: &Self::Path) -> Result<()> {
+            self.next_fault().await?;
+            self.inner.delete(location).await
+        }
+
+        async fn list<'a>(
+            &'a self,
+            prefix: Option<&'a Self::Path>,
+        ) -> Result<BoxStream<'a, Result<Vec<Self::Path>>>> {
+            self.next_fault().await?;
+            self.inner.list(prefix).await
+        }
+
+        async fn list_with_delimiter(&self, prefix: &Self::Path) -> Result<ListResult<Self::Path>> {
+            self.next_fault().await?;
+            self.inner.list_with_delimiter(prefix).await
+        }
+    }
+
+    /// Retry quickly, so tests don't take long
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            timeout: Some(Duration::from_millis(500)),
+        }
+    }
+
+    fn retrying_faulty(config: RetryConfig) -> RetryingStore<FaultyStore> {
+        RetryingStore::new(FaultyStore::new(), config)
+    }
+
+    fn location<O: ObjectStoreApi<Path = path::Path>>(store: &O) -> path::Path {
+        let mut location = store.new_path();
+        location.set_file_name("some_file");
+        location
+    }
+
+    async fn put<O>(store: &O, data: &'static str) -> Result<()>
+    where
+        O: ObjectStoreApi<Path = path::Path, Error = Error>,
+    {
+        let data = Bytes::from(data);
+        let len = data.len();
+        store
+            .put(
+                &location(store),
+                futures::stream::once(async move { io::Result::Ok(data) }),
+                Some(len),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn retrying_test() {
+        let store = ObjectStore::new_retrying(RetryingStore::new(
+            ObjectStore::new_in_memory(InMemory::new_with_page_size(1)),
+            config(),
+        ));
+
+        put_get_delete_list(&store).await.unwrap();
+        list_with_delimiter(&store).await.unwrap();
+        get_range_and_head(&store).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let store = retrying_faulty(config());
+        let faults = vec![Fault::Error; 3];
+
+        store.inner().inject_faults(faults.clone());
+        put(&store, "arbitrary data").await.unwrap();
+
+        store.inner().inject_faults(faults.clone());
+        let data = store.get_range(&location(&store), 0..9).await.unwrap();
+        assert_eq!(data, "arbitrary");
+
+        store.inner().inject_faults(faults.clone());
+        let data = get_bytes(&store, &location(&store)).await.unwrap();
+        assert_eq!(data, "arbitrary data");
+
+        store.inner().inject_faults(faults.clone());
+        let meta = store.head(&location(&store)).await.unwrap();
+        assert_eq!(meta.size, 14);
+
+        store.inner().inject_faults(faults.clone());
+        let list = store.list_with_delimiter(&store.new_path()).await.unwrap();
+        assert_eq!(list.objects.len(), 1);
+
+        store.inner().inject_faults(faults);
+        store.delete(&location(&store)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let store = retrying_faulty(config());
+        store.inner().inject_faults(vec![Fault::Error; 5]);
+
+        let err = put(&store, "arbitrary data").await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TimedOut {
+                operation: "injected fault",
+                ..
+            }
+        ));
+
+        // 4 attempts used 4 faults, so the next operation fails once and
+        // then succeeds
+        put(&store, "arbitrary data").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fatal_errors_are_not_retried() {
+        let store = retrying_faulty(config());
+        store
+            .inner()
+            .inject_faults(vec![Fault::Delay(Duration::from_millis(0)), Fault::Error]);
+
+        let err = store.head(&location(&store)).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InMemoryObjectStoreError {
+                source: crate::memory::Error::NoDataInMemory { .. }
+            }
+        ));
+
+        // Not finding the object wasn't retried, so the second fault is
+        // still waiting for the next operation
+        let faulty = store.inner();
+        let err = faulty.head(&faulty.new_path()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TimedOut {
+                operation: "injected fault",
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn slow_attempts_time_out_and_are_retried() {
+        let store = retrying_faulty(RetryConfig {
+            timeout: Some(Duration::from_millis(50)),
+            ..config()
+        });
+
+        store
+            .inner()
+            .inject_faults(vec![Fault::Delay(Duration::from_secs(10)); 2]);
+        put(&store, "arbitrary data").await.unwrap();
+
+        store
+            .inner()
+            .inject_faults(vec![Fault::Delay(Duration::from_secs(10)); 4]);
+        let err = store.head(&location(&store)).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TimedOut {
+                operation: "head",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max() {
+        let config = RetryConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+            ..Default::default()
+        };
+
+        for (retry, expected) in [100, 200, 400, 800, 1000, 1000].iter().enumerate() {
+            let expected = Duration::from_millis(*expected);
+            let backoff = config.backoff(retry);
+            assert!(
+                backoff >= expected / 2 && backoff <= expected,
+                "retry {}: {:?} not in range of {:?}",
+                retry,
+                backoff,
+                expected
+            );
+        }
+
+        assert!(config.backoff(usize::MAX) <= config.max_backoff);
+    }
+}
@@ -0,0 +1,1332 @@
+//! This module contains the catalog of a database that is preserved in object
+//! storage alongside the parquet files of its chunks.
+//!
+//! The catalog is an append-only log of transactions, each stored as its own
+//! file:
+//!
+//! ```text
+//! <writer id>/<database>/transactions/<revision>.txn
+//! ```
+//!
+//! Every `checkpoint_interval` revisions the complete state of the catalog is
+//! additionally written to a checkpoint file
+//! (`<writer id>/<database>/transactions/<revision>.ckpt`), so that loading
+//! the catalog only needs to read the latest checkpoint plus the transactions
+//! committed after it.
+//!
+//! Only a single writer may commit to the catalog of a database, which is
+//! guaranteed by the writer id being part of the path.
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+};
+
+use bytes::BytesMut;
+use chrono::Utc;
+use futures::TryStreamExt;
+use generated_types::influxdata::iox::catalog::v1 as proto;
+use observability_deps::tracing::{debug, warn};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::Mutex;
+
+use data_types::{
+    delete::{DeleteExpr, DeleteOp, DeletePredicate},
+    partition_metadata::{ColumnSummary, StatValues, Statistics, TableSummary},
+    timestamp::TimestampRange,
+};
+use internal_types::schema::{
+    builder::SchemaBuilder, InfluxColumnType, InfluxFieldType, Schema, TIME_COLUMN_NAME,
+};
+use object_store::{
+    path::{ObjectStorePath, Path, DELIMITER},
+    ObjectStore, ObjectStoreApi,
+};
+use tracker::MemRegistry;
+
+use crate::{chunk::Chunk, storage::Storage};
+
+/// Version of the catalog format written by this module
+pub const CATALOG_VERSION: u32 = 1;
+
+/// Default number of transactions after which a checkpoint is written
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100;
+
+const TRANSACTIONS_DIRECTORY: &str = "transactions";
+const TRANSACTION_FILE_SUFFIX: &str = "txn";
+const CHECKPOINT_FILE_SUFFIX: &str = "ckpt";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error listing catalog files: {}", source))]
+    ListingFiles { source: object_store::Error },
+
+    #[snafu(display("Error reading catalog file {}: {}", path, source))]
+    ReadingFile {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error writing catalog file {}: {}", path, source))]
+    WritingFile {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error decoding catalog file {}: {}", path, source))]
+    DecodingFile {
+        path: String,
+        source: prost::DecodeError,
+    },
+
+    #[snafu(display("Error encoding catalog file: {}", source))]
+    EncodingFile { source: prost::EncodeError },
+
+    #[snafu(display("Unsupported catalog version {} in {}", version, path))]
+    UnsupportedVersion { path: String, version: u32 },
+
+    #[snafu(display(
+        "Catalog file {} contains revision {}, expected {}",
+        path,
+        actual,
+        expected
+    ))]
+    RevisionMismatch {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+
+    #[snafu(display("Catalog transaction {} is missing", revision))]
+    MissingTransaction { revision: u64 },
+
+    #[snafu(display("Chunk {}:{} not found in the catalog", partition_key, chunk_id))]
+    ChunkNotFound {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display("Chunk {}:{} already exists in the catalog", partition_key, chunk_id))]
+    ChunkAlreadyExists {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display("Column {} has no InfluxDB data model type", column))]
+    UnsupportedColumnType { column: String },
+
+    #[snafu(display("Invalid catalog data: {}", description))]
+    InvalidData { description: String },
+
+    #[snafu(display("Invalid schema in catalog: {}", source))]
+    InvalidSchema {
+        source: internal_types::schema::builder::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An inclusive range of the sequence numbers of the writes contained in a
+/// chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl SequenceRange {
+    /// Return a range containing only the given sequence number
+    pub fn new(sequence: u64) -> Self {
+        Self {
+            min: sequence,
+            max: sequence,
+        }
+    }
+
+    /// Extend this range to include the given sequence number
+    pub fn update(&mut self, sequence: u64) {
+        self.min = self.min.min(sequence);
+        self.max = self.max.max(sequence);
+    }
+}
+
+/// Metadata of a table of a chunk, stored as a single parquet file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableMetadata {
+    /// Name and column statistics of the table
+    pub summary: TableSummary,
+
+    /// The IOx schema of the parquet file
+    pub schema: Schema,
+
+    /// Range of the timestamps in the parquet file
+    pub time_range: Option<TimestampRange>,
+
+    /// Columns the rows of the parquet file are sorted on, empty if the rows
+    /// are not sorted
+    pub sort_key: Vec<String>,
+}
+
+/// Metadata of a chunk that has been written to object storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkMetadata {
+    pub partition_key: String,
+
+    pub chunk_id: u32,
+
+    /// Sequence numbers of the writes contained in this chunk, if known
+    pub sequence_range: Option<SequenceRange>,
+
+    pub tables: Vec<TableMetadata>,
+
+    /// Predicates of the rows deleted from this chunk after its parquet
+    /// files were written, which the files still contain
+    pub delete_predicates: Vec<DeletePredicate>,
+}
+
+impl ChunkMetadata {
+    /// Return the object store paths of the parquet files of this chunk
+    pub fn paths(&self, storage: &Storage) -> Vec<Path> {
+        self.tables
+            .iter()
+            .map(|table| {
+                storage.location(
+                    self.partition_key.clone(),
+                    self.chunk_id,
+                    table.summary.name.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Create a parquet chunk that reads the files described by this
+    /// metadata
+    pub fn to_parquet_chunk(
+        &self,
+        storage: &Storage,
+        store: Arc<ObjectStore>,
+        memory_registry: &MemRegistry,
+    ) -> Chunk {
+        let mut chunk = Chunk::new(
+            self.partition_key.clone(),
+            self.chunk_id,
+            store,
+            memory_registry,
+        );
+
+        for (table, path) in self.tables.iter().zip(self.paths(storage)) {
+            chunk.add_table(
+                table.summary.clone(),
+                path,
+                table.schema.clone(),
+                table.time_range,
+            );
+        }
+
+        chunk
+    }
+}
+
+/// A change to the catalog
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// A chunk has been written to object storage. There must not be a chunk
+    /// with the same partition key and id in the catalog yet
+    AddChunk(ChunkMetadata),
+
+    /// A chunk has been removed and its files may be deleted
+    RemoveChunk {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    /// The rows of a chunk matching `predicate` have been deleted
+    AddDeletePredicate {
+        partition_key: String,
+        chunk_id: u32,
+        predicate: DeletePredicate,
+    },
+}
+
+/// The in-memory state of the catalog at a given revision
+#[derive(Debug, Default, Clone)]
+struct CatalogState {
+    revision: u64,
+    chunks: BTreeMap<(String, u32), ChunkMetadata>,
+}
+
+impl CatalogState {
+    fn apply(&mut self, action: Action) -> Result<()> {
+        match action {
+            Action::AddChunk(chunk) => {
+                // The files of a chunk are never replaced, so a chunk that
+                // is already in the catalog must not be added again
+                let key = (chunk.partition_key.clone(), chunk.chunk_id);
+                ensure!(
+                    !self.chunks.contains_key(&key),
+                    ChunkAlreadyExists {
+                        partition_key: key.0,
+                        chunk_id: key.1,
+                    }
+                );
+                self.chunks.insert(key, chunk);
+            }
+            Action::RemoveChunk {
+                partition_key,
+                chunk_id,
+            } => {
+                self.chunks
+                    .remove(&(partition_key.clone(), chunk_id))
+                    .context(ChunkNotFound {
+                        partition_key,
+                        chunk_id,
+                    })?;
+            }
+            Action::AddDeletePredicate {
+                partition_key,
+                chunk_id,
+                predicate,
+            } => {
+                // Rows may be deleted while the chunk is being replaced, e.g.
+                // by compaction, in which case the predicate is recorded for
+                // the replacing chunk too
+                match self.chunks.get_mut(&(partition_key, chunk_id)) {
+                    Some(chunk) => chunk.delete_predicates.push(predicate),
+                    None => debug!(%chunk_id, "ignoring delete predicate of removed chunk"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The catalog of a database, preserved in object storage as a log of
+/// transactions.
+///
+/// The state is loaded from object storage on first access and kept in
+/// memory afterwards.
+#[derive(Debug)]
+pub struct PreservedCatalog {
+    object_store: Arc<ObjectStore>,
+    server_id: NonZeroU32,
+    db_name: String,
+
+    /// Number of revisions after which a checkpoint is written, 0 disables
+    /// checkpoints
+    checkpoint_interval: u64,
+
+    state: Mutex<Option<CatalogState>>,
+}
+
+impl PreservedCatalog {
+    pub fn new(
+        object_store: Arc<ObjectStore>,
+        server_id: NonZeroU32,
+        db_name: impl Into<String>,
+        checkpoint_interval: u64,
+    ) -> Self {
+        Self {
+            object_store,
+            server_id,
+            db_name: db_name.into(),
+            checkpoint_interval,
+            state: Default::default(),
+        }
+    }
+
+    /// Return the directory in the object store that holds the transaction
+    /// and checkpoint files
+    pub fn transactions_location(&self) -> Path {
+        let mut path = self.object_store.new_path();
+        path.push_dir(self.server_id.to_string());
+        path.push_dir(self.db_name.clone());
+        path.push_dir(TRANSACTIONS_DIRECTORY);
+        path
+    }
+
+    /// Return the latest committed revision, 0 if nothing has been committed
+    pub async fn revision(&self) -> Result<u64> {
+        let mut state = self.state.lock().await;
+        Ok(self.loaded(&mut state).await?.revision)
+    }
+
+    /// Return the metadata of all chunks in the catalog, ordered by partition
+    /// key and chunk id
+    pub async fn chunks(&self) -> Result<Vec<ChunkMetadata>> {
+        let mut state = self.state.lock().await;
+        Ok(self
+            .loaded(&mut state)
+            .await?
+            .chunks
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    /// Return true if the chunk `chunk_id` of the partition `partition_key`
+    /// is in the catalog
+    pub async fn contains_chunk(&self, partition_key: &str, chunk_id: u32) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        Ok(self
+            .loaded(&mut state)
+            .await?
+            .chunks
+            .contains_key(&(partition_key.to_string(), chunk_id)))
+    }
+
+    /// Discard the in-memory state and load it again from object storage
+    pub async fn reload(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        *state = Some(self.load().await?);
+        Ok(())
+    }
+
+    /// Record that a chunk has been written to object storage, returning
+    /// the new revision
+    pub async fn add_chunk(&self, chunk: ChunkMetadata) -> Result<u64> {
+        self.commit(vec![Action::AddChunk(chunk)]).await
+    }
+
+    /// Record that a chunk has been removed, returning the new revision
+    pub async fn remove_chunk(
+        &self,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+    ) -> Result<u64> {
+        self.commit(vec![Action::RemoveChunk {
+            partition_key: partition_key.into(),
+            chunk_id,
+        }])
+        .await
+    }
+
+    /// Record that the rows of a chunk matching `predicate` have been
+    /// deleted, returning the new revision
+    pub async fn add_delete_predicate(
+        &self,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+        predicate: DeletePredicate,
+    ) -> Result<u64> {
+        self.commit(vec![Action::AddDeletePredicate {
+            partition_key: partition_key.into(),
+            chunk_id,
+            predicate,
+        }])
+        .await
+    }
+
+    /// Atomically apply the given actions and persist them as a new
+    /// transaction, returning the new revision.
+    ///
+    /// Nothing is changed if any of the actions is invalid or the transaction
+    /// can not be written.
+    pub async fn commit(&self, actions: Vec<Action>) -> Result<u64> {
+        let mut guard = self.state.lock().await;
+        let state = self.loaded(&mut guard).await?;
+
+        let mut new_state = state.clone();
+        for action in &actions {
+            new_state.apply(action.clone())?;
+        }
+        new_state.revision += 1;
+        let revision = new_state.revision;
+
+        let transaction = proto::Transaction {
+            version: CATALOG_VERSION,
+            revision,
+            timestamp: Some(Utc::now().into()),
+            actions: actions
+                .iter()
+                .map(encode_action)
+                .collect::<Result<Vec<_>>>()?,
+        };
+        let path = self.file_path(revision, TRANSACTION_FILE_SUFFIX);
+        self.write_file(&path, &transaction).await?;
+
+        debug!(db_name=%self.db_name, %revision, "committed catalog transaction");
+
+        *state = new_state;
+
+        if self.checkpoint_interval > 0 && revision % self.checkpoint_interval == 0 {
+            // The transaction is already durable, a missing checkpoint only
+            // makes loading the catalog slower
+            if let Err(e) = self.write_checkpoint(state).await {
+                warn!(?e, db_name=%self.db_name, %revision, "error writing catalog checkpoint");
+            }
+        }
+
+        Ok(revision)
+    }
+
+    /// Write a checkpoint of the current revision
+    pub async fn checkpoint(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        let state = self.loaded(&mut state).await?;
+        self.write_checkpoint(state).await
+    }
+
+    async fn write_checkpoint(&self, state: &CatalogState) -> Result<()> {
+        let checkpoint = proto::Checkpoint {
+            version: CATALOG_VERSION,
+            revision: state.revision,
+            timestamp: Some(Utc::now().into()),
+            chunks: state
+                .chunks
+                .values()
+                .map(encode_chunk)
+                .collect::<Result<Vec<_>>>()?,
+        };
+        let path = self.file_path(state.revision, CHECKPOINT_FILE_SUFFIX);
+        self.write_file(&path, &checkpoint).await
+    }
+
+    async fn loaded<'a>(
+        &self,
+        state: &'a mut Option<CatalogState>,
+    ) -> Result<&'a mut CatalogState> {
+        if state.is_none() {
+            *state = Some(self.load().await?);
+        }
+        Ok(state.as_mut().expect("catalog state just loaded"))
+    }
+
+    /// Rebuild the catalog state from the latest checkpoint and the
+    /// transactions committed after it
+    async fn load(&self) -> Result<CatalogState> {
+        let files: Vec<Path> = self
+            .object_store
+            .list(Some(&self.transactions_location()))
+            .await
+            .context(ListingFiles)?
+            .try_concat()
+            .await
+            .context(ListingFiles)?;
+
+        let mut transactions = BTreeMap::new();
+        let mut checkpoints = BTreeMap::new();
+        for path in files {
+            let display = path.display();
+            let file_name = display.rsplit(DELIMITER).next().unwrap_or_default();
+            match parse_file_name(file_name) {
+                Some((revision, TRANSACTION_FILE_SUFFIX)) => {
+                    transactions.insert(revision, path);
+                }
+                Some((revision, CHECKPOINT_FILE_SUFFIX)) => {
+                    checkpoints.insert(revision, path);
+                }
+                _ => debug!(path=%display, "ignoring unknown file in catalog directory"),
+            }
+        }
+
+        let mut state = CatalogState::default();
+
+        if let Some((&revision, path)) = checkpoints.iter().next_back() {
+            let checkpoint: proto::Checkpoint = self.read_file(path).await?;
+            check_header(path, checkpoint.version, revision, checkpoint.revision)?;
+
+            for chunk in checkpoint.chunks {
+                state.apply(Action::AddChunk(chunk.try_into()?))?;
+            }
+            state.revision = revision;
+        }
+
+        for (&revision, path) in transactions.range(state.revision + 1..) {
+            ensure!(
+                revision == state.revision + 1,
+                MissingTransaction {
+                    revision: state.revision + 1
+                }
+            );
+
+            let transaction: proto::Transaction = self.read_file(path).await?;
+            check_header(path, transaction.version, revision, transaction.revision)?;
+
+            for action in transaction.actions {
+                state.apply(action.try_into()?)?;
+            }
+            state.revision = revision;
+        }
+
+        debug!(db_name=%self.db_name, revision=%state.revision, "loaded catalog");
+
+        Ok(state)
+    }
+
+    fn file_path(&self, revision: u64, suffix: &str) -> Path {
+        let mut path = self.transactions_location();
+        path.set_file_name(format!("{:020}.{}", revision, suffix));
+        path
+    }
+
+    async fn read_file<M: prost::Message + Default>(&self, path: &Path) -> Result<M> {
+        let data = self
+            .object_store
+            .get(path)
+            .await
+            .context(ReadingFile {
+                path: path.display(),
+            })?
+            .map_ok(|b| BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .context(ReadingFile {
+                path: path.display(),
+            })?;
+
+        M::decode(data.freeze()).context(DecodingFile {
+            path: path.display(),
+        })
+    }
+
+    async fn write_file<M: prost::Message>(&self, path: &Path, message: &M) -> Result<()> {
+        let mut data = BytesMut::new();
+        message.encode(&mut data).context(EncodingFile)?;
+
+        let len = data.len();
+        let stream_data = std::io::Result::Ok(data.freeze());
+        self.object_store
+            .put(
+                path,
+                futures::stream::once(async move { stream_data }),
+                Some(len),
+            )
+            .await
+            .context(WritingFile {
+                path: path.display(),
+            })
+    }
+}
+
+/// Parse a file name of the form `<revision>.<suffix>`
+fn parse_file_name(file_name: &str) -> Option<(u64, &str)> {
+    let mut parts = file_name.splitn(2, '.');
+    let revision = parts.next()?.parse().ok()?;
+    let suffix = parts.next()?;
+    Some((revision, suffix))
+}
+
+fn check_header(path: &Path, version: u32, expected: u64, actual: u64) -> Result<()> {
+    ensure!(
+        version == CATALOG_VERSION,
+        UnsupportedVersion {
+            path: path.display(),
+            version
+        }
+    );
+    ensure!(
+        expected == actual,
+        RevisionMismatch {
+            path: path.display(),
+            expected,
+            actual
+        }
+    );
+    Ok(())
+}
+
+fn invalid_data(description: impl Into<String>) -> Error {
+    Error::InvalidData {
+        description: description.into(),
+    }
+}
+
+fn encode_action(action: &Action) -> Result<proto::Action> {
+    let action = match action {
+        Action::AddChunk(chunk) => proto::action::Action::AddChunk(encode_chunk(chunk)?),
+        Action::RemoveChunk {
+            partition_key,
+            chunk_id,
+        } => proto::action::Action::RemoveChunk(proto::RemoveChunk {
+            partition_key: partition_key.clone(),
+            chunk_id: *chunk_id,
+        }),
+        Action::AddDeletePredicate {
+            partition_key,
+            chunk_id,
+            predicate,
+        } => proto::action::Action::AddDeletePredicate(proto::AddDeletePredicate {
+            partition_key: partition_key.clone(),
+            chunk_id: *chunk_id,
+            predicate: Some(encode_delete_predicate(predicate)),
+        }),
+    };
+
+    Ok(proto::Action {
+        action: Some(action),
+    })
+}
+
+impl TryFrom<proto::Action> for Action {
+    type Error = Error;
+
+    fn try_from(value: proto::Action) -> Result<Self> {
+        match value.action {
+            Some(proto::action::Action::AddChunk(chunk)) => Ok(Self::AddChunk(chunk.try_into()?)),
+            Some(proto::action::Action::RemoveChunk(proto::RemoveChunk {
+                partition_key,
+                chunk_id,
+            })) => Ok(Self::RemoveChunk {
+                partition_key,
+                chunk_id,
+            }),
+            Some(proto::action::Action::AddDeletePredicate(proto::AddDeletePredicate {
+                partition_key,
+                chunk_id,
+                predicate,
+            })) => Ok(Self::AddDeletePredicate {
+                partition_key,
+                chunk_id,
+                predicate: decode_delete_predicate(predicate.context(InvalidData {
+                    description: "delete predicate not set",
+                })?)?,
+            }),
+            None => Err(invalid_data("action not set")),
+        }
+    }
+}
+
+fn encode_chunk(chunk: &ChunkMetadata) -> Result<proto::ChunkMetadata> {
+    Ok(proto::ChunkMetadata {
+        partition_key: chunk.partition_key.clone(),
+        chunk_id: chunk.chunk_id,
+        sequence_range: chunk.sequence_range.map(|range| proto::SequenceRange {
+            min: range.min,
+            max: range.max,
+        }),
+        tables: chunk
+            .tables
+            .iter()
+            .map(encode_table)
+            .collect::<Result<Vec<_>>>()?,
+        delete_predicates: chunk
+            .delete_predicates
+            .iter()
+            .map(encode_delete_predicate)
+            .collect(),
+    })
+}
+
+impl TryFrom<proto::ChunkMetadata> for ChunkMetadata {
+    type Error = Error;
+
+    fn try_from(value: proto::ChunkMetadata) -> Result<Self> {
+        Ok(Self {
+            partition_key: value.partition_key,
+            chunk_id: value.chunk_id,
+            sequence_range: value.sequence_range.map(|range| SequenceRange {
+                min: range.min,
+                max: range.max,
+            }),
+            tables: value
+                .tables
+                .into_iter()
+                .map(TableMetadata::try_from)
+                .collect::<Result<Vec<_>>>()?,
+            delete_predicates: value
+                .delete_predicates
+                .into_iter()
+                .map(decode_delete_predicate)
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+}
+
+fn encode_delete_predicate(predicate: &DeletePredicate) -> proto::DeletePredicate {
+    proto::DeletePredicate {
+        table_name: predicate.table_name.clone(),
+        start: predicate.range.start,
+        stop: predicate.range.end,
+        exprs: predicate
+            .exprs
+            .iter()
+            .map(|expr| proto::DeleteExpr {
+                column: expr.column.clone(),
+                op: match expr.op {
+                    DeleteOp::Eq => proto::DeleteOp::Eq,
+                    DeleteOp::Ne => proto::DeleteOp::Ne,
+                }
+                .into(),
+                value: expr.value.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn decode_delete_predicate(value: proto::DeletePredicate) -> Result<DeletePredicate> {
+    let exprs = value
+        .exprs
+        .into_iter()
+        .map(|expr| {
+            let op = match proto::DeleteOp::from_i32(expr.op) {
+                Some(proto::DeleteOp::Eq) => DeleteOp::Eq,
+                Some(proto::DeleteOp::Ne) => DeleteOp::Ne,
+                _ => {
+                    return Err(invalid_data(format!(
+                        "invalid operator of delete expression on column {}",
+                        expr.column
+                    )))
+                }
+            };
+            Ok(DeleteExpr {
+                column: expr.column,
+                op,
+                value: expr.value,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(DeletePredicate {
+        table_name: value.table_name,
+        range: TimestampRange::new(value.start, value.stop),
+        exprs,
+    })
+}
+
+pub(crate) fn encode_table(table: &TableMetadata) -> Result<proto::TableMetadata> {
+    Ok(proto::TableMetadata {
+        name: table.summary.name.clone(),
+        columns: table.summary.columns.iter().map(encode_column).collect(),
+        schema: Some(encode_schema(&table.schema)?),
+        time_range: table.time_range.map(|range| proto::TimestampRange {
+            start: range.start,
+            end: range.end,
+        }),
+        sort_key: table.sort_key.clone(),
+    })
+}
+
+impl TryFrom<proto::TableMetadata> for TableMetadata {
+    type Error = Error;
+
+    fn try_from(value: proto::TableMetadata) -> Result<Self> {
+        let columns = value
+            .columns
+            .into_iter()
+            .map(decode_column)
+            .collect::<Result<Vec<_>>>()?;
+        let schema = decode_schema(value.schema.context(InvalidData {
+            description: "table schema not set",
+        })?)?;
+
+        Ok(Self {
+            summary: TableSummary {
+                name: value.name,
+                columns,
+            },
+            schema,
+            time_range: value
+                .time_range
+                .map(|range| TimestampRange::new(range.start, range.end)),
+            sort_key: value.sort_key,
+        })
+    }
+}
+
+fn encode_column(column: &ColumnSummary) -> proto::ColumnSummary {
+    use proto::column_summary::Stats;
+
+    let stats = match &column.stats {
+        Statistics::I64(s) => Stats::I64Stats(proto::I64Stats {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: s.distinct_count.map_or(0, NonZeroU64::get),
+        }),
+        Statistics::U64(s) => Stats::U64Stats(proto::U64Stats {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: s.distinct_count.map_or(0, NonZeroU64::get),
+        }),
+        Statistics::F64(s) => Stats::F64Stats(proto::F64Stats {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: s.distinct_count.map_or(0, NonZeroU64::get),
+        }),
+        Statistics::Bool(s) => Stats::BoolStats(proto::BoolStats {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: s.distinct_count.map_or(0, NonZeroU64::get),
+        }),
+        Statistics::String(s) => Stats::StringStats(proto::StringStats {
+            min: s.min.clone(),
+            max: s.max.clone(),
+            count: s.count,
+            distinct_count: s.distinct_count.map_or(0, NonZeroU64::get),
+        }),
+    };
+
+    proto::ColumnSummary {
+        name: column.name.clone(),
+        stats: Some(stats),
+    }
+}
+
+fn decode_column(value: proto::ColumnSummary) -> Result<ColumnSummary> {
+    use proto::column_summary::Stats;
+
+    let stats = match value.stats {
+        Some(Stats::I64Stats(s)) => Statistics::I64(StatValues {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: NonZeroU64::new(s.distinct_count),
+        }),
+        Some(Stats::U64Stats(s)) => Statistics::U64(StatValues {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: NonZeroU64::new(s.distinct_count),
+        }),
+        Some(Stats::F64Stats(s)) => Statistics::F64(StatValues {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: NonZeroU64::new(s.distinct_count),
+        }),
+        Some(Stats::BoolStats(s)) => Statistics::Bool(StatValues {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: NonZeroU64::new(s.distinct_count),
+        }),
+        Some(Stats::StringStats(s)) => Statistics::String(StatValues {
+            min: s.min,
+            max: s.max,
+            count: s.count,
+            distinct_count: NonZeroU64::new(s.distinct_count),
+        }),
+        None => {
+            return Err(invalid_data(format!(
+                "stats of column {} not set",
+                value.name
+            )))
+        }
+    };
+
+    Ok(ColumnSummary {
+        name: value.name,
+        stats,
+    })
+}
+
+fn encode_schema(schema: &Schema) -> Result<proto::Schema> {
+    let columns = schema
+        .iter()
+        .map(|(influxdb_column_type, field)| {
+            let column_type = match influxdb_column_type {
+                Some(InfluxColumnType::Tag) => proto::ColumnType::Tag,
+                Some(InfluxColumnType::Timestamp) => proto::ColumnType::Timestamp,
+                Some(InfluxColumnType::Field(InfluxFieldType::Float)) => {
+                    proto::ColumnType::FieldFloat
+                }
+                Some(InfluxColumnType::Field(InfluxFieldType::Integer)) => {
+                    proto::ColumnType::FieldInteger
+                }
+                Some(InfluxColumnType::Field(InfluxFieldType::UInteger)) => {
+                    proto::ColumnType::FieldUinteger
+                }
+                Some(InfluxColumnType::Field(InfluxFieldType::String)) => {
+                    proto::ColumnType::FieldString
+                }
+                Some(InfluxColumnType::Field(InfluxFieldType::Boolean)) => {
+                    proto::ColumnType::FieldBoolean
+                }
+                None => {
+                    return UnsupportedColumnType {
+                        column: field.name(),
+                    }
+                    .fail()
+                }
+            };
+
+            Ok(proto::Column {
+                name: field.name().clone(),
+                column_type: column_type.into(),
+                nullable: field.is_nullable(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(proto::Schema {
+        measurement: schema.measurement().cloned().unwrap_or_default(),
+        columns,
+    })
+}
+
+fn decode_schema(value: proto::Schema) -> Result<Schema> {
+    let mut builder = SchemaBuilder::new();
+    if !value.measurement.is_empty() {
+        builder = builder.measurement(value.measurement);
+    }
+
+    for column in value.columns {
+        let column_type = proto::ColumnType::from_i32(column.column_type).context(InvalidData {
+            description: format!("unknown type of column {}", column.name),
+        })?;
+
+        let field_type = match column_type {
+            proto::ColumnType::Tag => {
+                builder = if column.nullable {
+                    builder.tag(&column.name)
+                } else {
+                    builder.non_null_tag(&column.name)
+                };
+                continue;
+            }
+            proto::ColumnType::Timestamp => {
+                ensure!(
+                    column.name == TIME_COLUMN_NAME,
+                    InvalidData {
+                        description: format!("unexpected timestamp column {}", column.name),
+                    }
+                );
+                builder = builder.timestamp();
+                continue;
+            }
+            proto::ColumnType::FieldFloat => InfluxFieldType::Float,
+            proto::ColumnType::FieldInteger => InfluxFieldType::Integer,
+            proto::ColumnType::FieldUinteger => InfluxFieldType::UInteger,
+            proto::ColumnType::FieldString => InfluxFieldType::String,
+            proto::ColumnType::FieldBoolean => InfluxFieldType::Boolean,
+            proto::ColumnType::Unspecified => {
+                return Err(invalid_data(format!(
+                    "type of column {} not set",
+                    column.name
+                )))
+            }
+        };
+
+        builder = if column.nullable {
+            builder.influx_field(&column.name, field_type)
+        } else {
+            builder.non_null_field(&column.name, field_type.into())
+        };
+    }
+
+    builder.build().context(InvalidSchema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::arrow::datatypes::DataType as ArrowDataType;
+    use object_store::memory::InMemory;
+
+    fn make_catalog(store: &Arc<ObjectStore>, checkpoint_interval: u64) -> PreservedCatalog {
+        PreservedCatalog::new(
+            Arc::clone(store),
+            NonZeroU32::new(1).unwrap(),
+            "db1",
+            checkpoint_interval,
+        )
+    }
+
+    fn make_chunk(partition_key: &str, chunk_id: u32) -> ChunkMetadata {
+        let schema = SchemaBuilder::new()
+            .measurement("cpu")
+            .tag("host")
+            .non_null_tag("region")
+            .influx_field("usage", InfluxFieldType::Float)
+            .non_null_field("count", ArrowDataType::UInt64)
+            .influx_field("status", InfluxFieldType::String)
+            .influx_field("active", InfluxFieldType::Boolean)
+            .influx_field("errors", InfluxFieldType::Integer)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let summary = TableSummary {
+            name: "cpu".to_string(),
+            columns: vec![
+                ColumnSummary {
+                    name: "host".to_string(),
+                    stats: Statistics::String(StatValues {
+                        min: "a".to_string(),
+                        max: "z".to_string(),
+                        count: 3,
+                        distinct_count: NonZeroU64::new(2),
+                    }),
+                },
+                ColumnSummary {
+                    name: "usage".to_string(),
+                    stats: Statistics::F64(StatValues {
+                        min: 0.5,
+                        max: 99.5,
+                        count: 3,
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: "count".to_string(),
+                    stats: Statistics::U64(StatValues {
+                        min: 1,
+                        max: 10,
+                        count: 3,
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: "active".to_string(),
+                    stats: Statistics::Bool(StatValues {
+                        min: false,
+                        max: true,
+                        count: 3,
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: "time".to_string(),
+                    stats: Statistics::I64(StatValues {
+                        min: 10,
+                        max: 30,
+                        count: 3,
+                        distinct_count: None,
+                    }),
+                },
+            ],
+        };
+
+        ChunkMetadata {
+            partition_key: partition_key.to_string(),
+            chunk_id,
+            sequence_range: Some(SequenceRange { min: 1, max: 3 }),
+            tables: vec![TableMetadata {
+                summary,
+                schema,
+                time_range: Some(TimestampRange::new(10, 31)),
+                sort_key: vec!["host".to_string(), "time".to_string()],
+            }],
+            delete_predicates: vec![],
+        }
+    }
+
+    async fn list_files(store: &ObjectStore) -> Vec<String> {
+        let mut files: Vec<_> = store
+            .list(None)
+            .await
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.display())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn empty_catalog() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 0);
+
+        assert_eq!(catalog.revision().await.unwrap(), 0);
+        assert!(catalog.chunks().await.unwrap().is_empty());
+        assert!(list_files(&store).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn transactions_are_persisted() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 0);
+
+        assert_eq!(catalog.add_chunk(make_chunk("p1", 0)).await.unwrap(), 1);
+        assert_eq!(catalog.add_chunk(make_chunk("p1", 1)).await.unwrap(), 2);
+        assert_eq!(catalog.add_chunk(make_chunk("p2", 0)).await.unwrap(), 3);
+        assert_eq!(catalog.remove_chunk("p1", 0).await.unwrap(), 4);
+
+        assert_eq!(
+            list_files(&store).await,
+            vec![
+                "1/db1/transactions/00000000000000000001.txn",
+                "1/db1/transactions/00000000000000000002.txn",
+                "1/db1/transactions/00000000000000000003.txn",
+                "1/db1/transactions/00000000000000000004.txn",
+            ]
+        );
+
+        let expected = vec![make_chunk("p1", 1), make_chunk("p2", 0)];
+        assert_eq!(catalog.chunks().await.unwrap(), expected);
+
+        // A new catalog rebuilds the same state from object storage
+        let rebuilt = make_catalog(&store, 0);
+        assert_eq!(rebuilt.revision().await.unwrap(), 4);
+        assert_eq!(rebuilt.chunks().await.unwrap(), expected);
+
+        // And can continue committing where the other one stopped
+        assert_eq!(rebuilt.remove_chunk("p2", 0).await.unwrap(), 5);
+        catalog.reload().await.unwrap();
+        assert_eq!(catalog.chunks().await.unwrap(), vec![make_chunk("p1", 1)]);
+    }
+
+    #[tokio::test]
+    async fn invalid_transactions_are_rejected() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 0);
+
+        catalog.add_chunk(make_chunk("p1", 0)).await.unwrap();
+
+        let err = catalog.remove_chunk("p1", 1).await.unwrap_err();
+        assert!(matches!(err, Error::ChunkNotFound { .. }), "{}", err);
+
+        // A transaction is applied completely or not at all
+        let err = catalog
+            .commit(vec![
+                Action::AddChunk(make_chunk("p1", 1)),
+                Action::RemoveChunk {
+                    partition_key: "p2".to_string(),
+                    chunk_id: 0,
+                },
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ChunkNotFound { .. }), "{}", err);
+
+        assert_eq!(catalog.revision().await.unwrap(), 1);
+        assert_eq!(catalog.chunks().await.unwrap(), vec![make_chunk("p1", 0)]);
+        assert_eq!(list_files(&store).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn adding_a_chunk_again_is_rejected() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 0);
+
+        catalog.add_chunk(make_chunk("p1", 0)).await.unwrap();
+        assert!(catalog.contains_chunk("p1", 0).await.unwrap());
+        assert!(!catalog.contains_chunk("p1", 1).await.unwrap());
+
+        let mut replacement = make_chunk("p1", 0);
+        replacement.sequence_range = Some(SequenceRange { min: 7, max: 9 });
+        let err = catalog.add_chunk(replacement).await.unwrap_err();
+        assert!(matches!(err, Error::ChunkAlreadyExists { .. }), "{}", err);
+
+        assert_eq!(catalog.chunks().await.unwrap(), vec![make_chunk("p1", 0)]);
+        assert_eq!(
+            make_catalog(&store, 0).chunks().await.unwrap(),
+            vec![make_chunk("p1", 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn checkpoints_are_written_and_used() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 2);
+
+        for chunk_id in 0..5 {
+            catalog.add_chunk(make_chunk("p1", chunk_id)).await.unwrap();
+        }
+
+        let files = list_files(&store).await;
+        assert!(files.contains(&"1/db1/transactions/00000000000000000002.ckpt".to_string()));
+        assert!(files.contains(&"1/db1/transactions/00000000000000000004.ckpt".to_string()));
+        assert_eq!(files.len(), 7);
+
+        // transactions covered by the latest checkpoint are not needed anymore
+        for revision in 1..=4 {
+            let path = catalog.file_path(revision, TRANSACTION_FILE_SUFFIX);
+            store.delete(&path).await.unwrap();
+        }
+
+        let rebuilt = make_catalog(&store, 2);
+        assert_eq!(rebuilt.revision().await.unwrap(), 5);
+        assert_eq!(
+            rebuilt.chunks().await.unwrap(),
+            (0..5).map(|id| make_chunk("p1", id)).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_predicates_are_persisted() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 3);
+
+        let predicate = DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(10, 20),
+            exprs: vec![DeleteExpr {
+                column: "host".to_string(),
+                op: DeleteOp::Ne,
+                value: "a".to_string(),
+            }],
+        };
+
+        catalog.add_chunk(make_chunk("p1", 0)).await.unwrap();
+        catalog.add_chunk(make_chunk("p1", 1)).await.unwrap();
+        catalog
+            .add_delete_predicate("p1", 0, predicate.clone())
+            .await
+            .unwrap();
+        // Rows of a chunk that has been removed in the meantime
+        catalog.remove_chunk("p1", 1).await.unwrap();
+        catalog
+            .add_delete_predicate("p1", 1, predicate.clone())
+            .await
+            .unwrap();
+
+        let mut expected = make_chunk("p1", 0);
+        expected.delete_predicates = vec![predicate];
+        assert_eq!(catalog.chunks().await.unwrap(), vec![expected.clone()]);
+
+        // The predicates are restored from the checkpoint at revision 3 as
+        // well as from transactions
+        let rebuilt = make_catalog(&store, 3);
+        assert_eq!(rebuilt.chunks().await.unwrap(), vec![expected.clone()]);
+
+        for revision in 1..=3 {
+            let path = catalog.file_path(revision, TRANSACTION_FILE_SUFFIX);
+            store.delete(&path).await.unwrap();
+        }
+        let rebuilt = make_catalog(&store, 3);
+        assert_eq!(rebuilt.chunks().await.unwrap(), vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn missing_transactions_are_an_error() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let catalog = make_catalog(&store, 0);
+
+        for chunk_id in 0..3 {
+            catalog.add_chunk(make_chunk("p1", chunk_id)).await.unwrap();
+        }
+
+        let path = catalog.file_path(2, TRANSACTION_FILE_SUFFIX);
+        store.delete(&path).await.unwrap();
+
+        let err = make_catalog(&store, 0).revision().await.unwrap_err();
+        assert!(
+            matches!(err, Error::MissingTransaction { revision: 2 }),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn parquet_chunk_from_metadata() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let storage = Storage::new(
+            Arc::clone(&store),
+            NonZeroU32::new(1).unwrap(),
+            "db1".to_string(),
+        );
+        let metadata = make_chunk("p1", 3);
+
+        let chunk = metadata.to_parquet_chunk(&storage, store, &MemRegistry::new());
+
+        assert_eq!(chunk.id(), 3);
+        assert_eq!(chunk.partition_key(), "p1");
+        assert_eq!(
+            chunk.table_summaries(),
+            vec![metadata.tables[0].summary.clone()]
+        );
+        assert_eq!(
+            chunk.all_paths()[0].display(),
+            "1/db1/data/p1/3/cpu.parquet"
+        );
+    }
+}
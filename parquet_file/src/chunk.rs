@@ -5,11 +5,11 @@ use crate::table::Table;
 use arrow_deps::datafusion::physical_plan::SendableRecordBatchStream;
 use data_types::{partition_metadata::TableSummary, timestamp::TimestampRange};
 use internal_types::{schema::Schema, selection::Selection};
-use object_store::path::Path;
+use object_store::{path::Path, ObjectStore};
 use query::predicate::Predicate;
 use tracker::{MemRegistry, MemTracker};
 
-use std::mem;
+use std::{mem, sync::Arc};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -53,16 +53,25 @@ pub struct Chunk {
     /// Tables of this chunk
     tables: Vec<Table>,
 
+    /// Object store where the parquet files of this chunk's tables are
+    object_store: Arc<ObjectStore>,
+
     /// Track memory used by this chunk
     memory_tracker: MemTracker,
 }
 
 impl Chunk {
-    pub fn new(part_key: String, chunk_id: u32, memory_registry: &MemRegistry) -> Self {
+    pub fn new(
+        part_key: String,
+        chunk_id: u32,
+        store: Arc<ObjectStore>,
+        memory_registry: &MemRegistry,
+    ) -> Self {
         let mut chunk = Self {
             partition_key: part_key,
             id: chunk_id,
             tables: Default::default(),
+            object_store: store,
             memory_tracker: memory_registry.register(),
         };
         chunk.memory_tracker.set_bytes(chunk.size());
@@ -97,8 +106,13 @@ impl Chunk {
         schema: Schema,
         range: Option<TimestampRange>,
     ) {
-        self.tables
-            .push(Table::new(table_summary, file_location, schema, range));
+        self.tables.push(Table::new(
+            table_summary,
+            file_location,
+            Arc::clone(&self.object_store),
+            schema,
+            range,
+        ));
     }
 
     /// Return true if this chunk includes the given table
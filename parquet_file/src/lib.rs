@@ -7,6 +7,8 @@
     clippy::clone_on_ref_ptr
 )]
 
+pub mod catalog;
 pub mod chunk;
+pub mod metadata;
 pub mod storage;
 pub mod table;
@@ -0,0 +1,233 @@
+//! This module contains the IOx metadata that is embedded in the key-value
+//! metadata of every parquet file written by
+//! [`Storage`](crate::storage::Storage).
+//!
+//! The metadata contains the IOx schema (which distinguishes tags, fields and
+//! timestamps, something the Arrow schema stored by the parquet writer cannot
+//! express), the column statistics of the table, as well as the partition
+//! key, chunk id and sequence numbers of the chunk the file belongs to. It is
+//! stored as a base64 encoded `ParquetMetadata` protobuf message under the
+//! [`METADATA_KEY`] key, so that a file can be interpreted from its footer
+//! alone.
+use std::convert::TryFrom;
+
+use arrow_deps::parquet::file::metadata::{KeyValue, ParquetMetaData};
+use generated_types::influxdata::iox::catalog::v1 as proto;
+use prost::Message;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::catalog::{self, encode_table, SequenceRange, TableMetadata};
+
+/// Key under which the IOx metadata is stored in the key-value metadata of a
+/// parquet file
+pub const METADATA_KEY: &str = "IOX:metadata";
+
+/// Version of the metadata format written by this module
+pub const METADATA_VERSION: u32 = 1;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Parquet file has no IOx metadata"))]
+    MetadataNotFound {},
+
+    #[snafu(display("Error decoding base64 IOx metadata: {}", source))]
+    DecodingBase64 { source: base64::DecodeError },
+
+    #[snafu(display("Error decoding IOx metadata: {}", source))]
+    DecodingProtobuf { source: prost::DecodeError },
+
+    #[snafu(display("Error encoding IOx metadata: {}", source))]
+    EncodingProtobuf { source: prost::EncodeError },
+
+    #[snafu(display("Unsupported IOx metadata version {}", version))]
+    UnsupportedVersion { version: u32 },
+
+    #[snafu(display("IOx metadata does not contain a table"))]
+    TableNotFound {},
+
+    #[snafu(display("Invalid IOx metadata: {}", source))]
+    InvalidMetadata { source: catalog::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The IOx metadata of a single parquet file
+#[derive(Debug, Clone, PartialEq)]
+pub struct IoxMetadata {
+    pub partition_key: String,
+
+    pub chunk_id: u32,
+
+    /// Sequence numbers of the writes contained in the chunk, if known
+    pub sequence_range: Option<SequenceRange>,
+
+    /// Schema, column statistics and time range of the table stored in the
+    /// file
+    pub table: TableMetadata,
+}
+
+impl IoxMetadata {
+    /// Encode this metadata as a parquet key-value pair
+    pub fn to_key_value(&self) -> Result<KeyValue> {
+        let message = proto::ParquetMetadata {
+            version: METADATA_VERSION,
+            partition_key: self.partition_key.clone(),
+            chunk_id: self.chunk_id,
+            sequence_range: self.sequence_range.map(|range| proto::SequenceRange {
+                min: range.min,
+                max: range.max,
+            }),
+            table: Some(encode_table(&self.table).context(InvalidMetadata)?),
+        };
+
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message.encode(&mut buf).context(EncodingProtobuf)?;
+
+        Ok(KeyValue {
+            key: METADATA_KEY.to_string(),
+            value: Some(base64::encode(buf)),
+        })
+    }
+
+    /// Decode the metadata stored under [`METADATA_KEY`] in the footer of a
+    /// parquet file
+    pub fn from_parquet_metadata(metadata: &ParquetMetaData) -> Result<Self> {
+        let value = metadata
+            .file_metadata()
+            .key_value_metadata()
+            .iter()
+            .flatten()
+            .find(|kv| kv.key == METADATA_KEY)
+            .and_then(|kv| kv.value.as_ref())
+            .context(MetadataNotFound)?;
+
+        Self::decode(value)
+    }
+
+    /// Decode metadata encoded by [`to_key_value`](Self::to_key_value)
+    fn decode(value: &str) -> Result<Self> {
+        let buf = base64::decode(value).context(DecodingBase64)?;
+        let message = proto::ParquetMetadata::decode(buf.as_slice()).context(DecodingProtobuf)?;
+
+        ensure!(
+            message.version == METADATA_VERSION,
+            UnsupportedVersion {
+                version: message.version
+            }
+        );
+
+        let table = TableMetadata::try_from(message.table.context(TableNotFound)?)
+            .context(InvalidMetadata)?;
+
+        Ok(Self {
+            partition_key: message.partition_key,
+            chunk_id: message.chunk_id,
+            sequence_range: message.sequence_range.map(|range| SequenceRange {
+                min: range.min,
+                max: range.max,
+            }),
+            table,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::{
+        partition_metadata::{ColumnSummary, StatValues, Statistics, TableSummary},
+        timestamp::TimestampRange,
+    };
+    use internal_types::schema::{builder::SchemaBuilder, InfluxFieldType};
+    use std::num::NonZeroU64;
+
+    fn make_metadata() -> IoxMetadata {
+        let schema = SchemaBuilder::new()
+            .measurement("cpu")
+            .tag("host")
+            .influx_field("usage", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let summary = TableSummary {
+            name: "cpu".to_string(),
+            columns: vec![
+                ColumnSummary {
+                    name: "host".to_string(),
+                    stats: Statistics::String(StatValues {
+                        min: "a".to_string(),
+                        max: "b".to_string(),
+                        count: 2,
+                        distinct_count: NonZeroU64::new(2),
+                    }),
+                },
+                ColumnSummary {
+                    name: "usage".to_string(),
+                    stats: Statistics::F64(StatValues {
+                        min: 1.0,
+                        max: 2.0,
+                        count: 2,
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: "time".to_string(),
+                    stats: Statistics::I64(StatValues {
+                        min: 10,
+                        max: 20,
+                        count: 2,
+                        distinct_count: None,
+                    }),
+                },
+            ],
+        };
+
+        IoxMetadata {
+            partition_key: "1970-01-01T00".to_string(),
+            chunk_id: 7,
+            sequence_range: Some(SequenceRange { min: 3, max: 5 }),
+            table: TableMetadata {
+                summary,
+                schema,
+                time_range: Some(TimestampRange::new(10, 21)),
+                sort_key: vec!["host".to_string(), "time".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    fn metadata_roundtrip() {
+        let metadata = make_metadata();
+
+        let kv = metadata.to_key_value().unwrap();
+        assert_eq!(kv.key, METADATA_KEY);
+
+        let decoded = IoxMetadata::decode(kv.value.as_ref().unwrap()).unwrap();
+        assert_eq!(decoded, metadata);
+    }
+
+    #[test]
+    fn invalid_metadata_is_an_error() {
+        let err = IoxMetadata::decode("not base64!").unwrap_err();
+        assert!(matches!(err, Error::DecodingBase64 { .. }), "{}", err);
+
+        let message = proto::ParquetMetadata {
+            version: METADATA_VERSION + 1,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        message.encode(&mut buf).unwrap();
+        let err = IoxMetadata::decode(&base64::encode(buf)).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedVersion { .. }), "{}", err);
+
+        let message = proto::ParquetMetadata {
+            version: METADATA_VERSION,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        message.encode(&mut buf).unwrap();
+        let err = IoxMetadata::decode(&base64::encode(buf)).unwrap_err();
+        assert!(matches!(err, Error::TableNotFound { .. }), "{}", err);
+    }
+}
@@ -16,7 +16,13 @@ use arrow_deps::{
     parquet::{
         self,
         arrow::{arrow_reader::ParquetFileArrowReader, ArrowReader, ArrowWriter},
-        file::{reader::FileReader, serialized_reader::SerializedFileReader, writer::TryClone},
+        errors::ParquetError,
+        file::{
+            properties::WriterProperties,
+            reader::{ChunkReader, FileReader, Length},
+            serialized_reader::SerializedFileReader,
+            writer::TryClone,
+        },
     },
 };
 use internal_types::selection::Selection;
@@ -26,14 +32,16 @@ use object_store::{
 };
 use query::predicate::Predicate;
 
-use bytes::Bytes;
-use futures::{Stream, StreamExt};
+use crate::metadata::IoxMetadata;
+
+use bytes::{Buf, Bytes};
+use futures::{Stream, StreamExt, TryFutureExt};
 use parking_lot::Mutex;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
-    fs::File,
     io::{Cursor, Seek, SeekFrom, Write},
     num::NonZeroU32,
+    ops::Range,
     sync::Arc,
     task::{Context, Poll},
 };
@@ -69,11 +77,8 @@ pub enum Error {
     #[snafu(display("Error converting to vec[u8]: Nothing else should have a reference here"))]
     WritingToMemWriter {},
 
-    #[snafu(display("Non local file not supported"))]
-    NonLocalFile {},
-
-    #[snafu(display("Error opening file: {}", source))]
-    OpenFile { source: std::io::Error },
+    #[snafu(display("Error reading from object store: {}", source))]
+    ReadingObjectStore { source: object_store::Error },
 
     #[snafu(display("Error at serialized file reader: {}", source))]
     SerializedFileReaderError {
@@ -94,6 +99,15 @@ pub enum Error {
     SendResult {
         source: arrow_deps::datafusion::error::DataFusionError,
     },
+
+    #[snafu(display("Error encoding IOx metadata: {}", source))]
+    EncodingMetadata { source: crate::metadata::Error },
+
+    #[snafu(display("Error reading IOx metadata of {}: {}", path, source))]
+    DecodingMetadata {
+        path: String,
+        source: crate::metadata::Error,
+    },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -136,6 +150,29 @@ impl Storage {
         }
     }
 
+    /// Return the directory in the object store under which all chunk data of
+    /// this database is stored
+    pub fn data_location(&self) -> object_store::path::Path {
+        //    <writer id>/<database>/data/
+        let mut path = self.object_store.new_path();
+        path.push_dir(self.writer_id.to_string());
+        path.push_dir(self.db_name.clone());
+        path.push_dir("data");
+
+        path
+    }
+
+    /// Return the directory in the object store under which the table files of
+    /// a chunk are stored
+    pub fn chunk_location(&self, partition_key: String, chunk_id: u32) -> object_store::path::Path {
+        //    <writer id>/<database>/data/<partition key>/<chunk id>/
+        let mut path = self.data_location();
+        path.push_dir(partition_key);
+        path.push_dir(chunk_id.to_string());
+
+        path
+    }
+
     /// Return full path including filename in the object store to save a chunk
     /// table file
     pub fn location(
@@ -148,46 +185,58 @@ impl Storage {
         //    <writer id>/<database>/data/<partition key>/<chunk id>/<table
         // name>.parquet
 
-        let mut path = self.object_store.new_path();
-        path.push_dir(self.writer_id.to_string());
-        path.push_dir(self.db_name.clone());
-        path.push_dir("data");
-        path.push_dir(partition_key);
-        path.push_dir(chunk_id.to_string());
+        let mut path = self.chunk_location(partition_key, chunk_id);
         let file_name = format!("{}.parquet", table_name);
         path.set_file_name(file_name);
 
         path
     }
 
-    /// Write the given stream of data of a specified table of
-    // a specified partitioned chunk to a parquet file of this storage
+    /// Write the given stream of data of the table of a partitioned chunk
+    /// described by `metadata` to a parquet file of this storage, embedding
+    /// `metadata` in the file
     pub async fn write_to_object_store(
         &self,
-        partition_key: String,
-        chunk_id: u32,
-        table_name: String,
+        metadata: &IoxMetadata,
         stream: SendableRecordBatchStream,
     ) -> Result<Path> {
         // Create full path location of this file in object store
-        let path = self.location(partition_key, chunk_id, table_name);
+        let path = self.location(
+            metadata.partition_key.clone(),
+            metadata.chunk_id,
+            metadata.table.summary.name.clone(),
+        );
 
         let schema = stream.schema();
-        let data = Self::parquet_stream_to_bytes(stream, schema).await?;
+        let data = Self::parquet_stream_to_bytes(stream, schema, Some(metadata)).await?;
         self.to_object_store(data, &path).await?;
 
         Ok(path.clone())
     }
 
-    /// Convert the given stream of RecordBatches to bytes
+    /// Convert the given stream of RecordBatches to bytes, storing
+    /// `metadata` in the key-value metadata of the parquet file if given
 
     pub async fn parquet_stream_to_bytes(
         mut stream: SendableRecordBatchStream,
         schema: SchemaRef,
+        metadata: Option<&IoxMetadata>,
     ) -> Result<Vec<u8>> {
+        let props = match metadata {
+            Some(metadata) => {
+                let kv = metadata.to_key_value().context(EncodingMetadata)?;
+                Some(
+                    WriterProperties::builder()
+                        .set_key_value_metadata(Some(vec![kv]))
+                        .build(),
+                )
+            }
+            None => None,
+        };
+
         let mem_writer = MemWriter::default();
         {
-            let mut writer = ArrowWriter::try_new(mem_writer.clone(), schema, None)
+            let mut writer = ArrowWriter::try_new(mem_writer.clone(), schema, props)
                 .context(OpeningParquetWriter)?;
             while let Some(batch) = stream.next().await {
                 let batch = batch.context(ReadingStream)?;
@@ -252,17 +301,16 @@ impl Storage {
         }
     }
 
+    /// Return a stream of the data in the parquet file at `path` in
+    /// `object_store`, reading only the `selection` columns of the row
+    /// groups that may match `predicate`
     pub fn read_filter(
         predicate: &Predicate,
         selection: Selection<'_>,
         schema: SchemaRef,
         path: &Path,
+        object_store: Arc<ObjectStore>,
     ) -> Result<SendableRecordBatchStream> {
-        // TODO: support non local file object store
-        if !path.local_file() {
-            panic!("Non local file object store not supported")
-        }
-
         // The below code is based on
         // datafusion::physical_plan::parquet::ParquetExec::execute
         // Will be improved as we go
@@ -272,11 +320,7 @@ impl Storage {
             Receiver<ArrowResult<RecordBatch>>,
         ) = channel(2);
 
-        // Full path of the parquet file
-        // Might need different function if this display function does not show the full
-        // path
-        let filename = path.display();
-        // println!("Parquet filename: {}", filename); // TODO: remove after tests
+        let path = path.clone();
 
         // Indices of columns in the schema needed to read
         let projection: Vec<usize> = Self::column_indices(selection, Arc::clone(&schema));
@@ -291,19 +335,42 @@ impl Storage {
         // Limit of total rows to read
         let limit: Option<usize> = None; // Todo: this should be a parameter of the function
 
-        // Todo: Convert this tokio into using thread pool Andrew has implemented
-        // recently
-        task::spawn_blocking(move || {
-            if let Err(e) = Self::read_file(
-                filename,
+        task::spawn(async move {
+            let data = match Self::read_object(
+                &object_store,
+                &path,
                 projection.as_slice(),
                 predicate_builder.as_ref(),
-                batch_size,
-                response_tx,
-                limit,
-            ) {
-                println!("Parquet reader thread terminated due to error: {:?}", e);
-            }
+            )
+            .await
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    let err_msg = format!("Error reading {}: {}", path.display(), e);
+                    // the receiver may have gone away, in which case
+                    // there is nobody to report the error to
+                    let _ = response_tx
+                        .send(Err(ArrowError::ParquetError(err_msg)))
+                        .await;
+                    return;
+                }
+            };
+
+            // Todo: Convert this tokio into using thread pool Andrew has implemented
+            // recently
+            task::spawn_blocking(move || {
+                if let Err(e) = Self::read_data(
+                    path.display(),
+                    data,
+                    projection.as_slice(),
+                    predicate_builder.as_ref(),
+                    batch_size,
+                    response_tx,
+                    limit,
+                ) {
+                    println!("Parquet reader thread terminated due to error: {:?}", e);
+                }
+            });
         });
 
         Ok(Box::pin(ParquetStream {
@@ -312,6 +379,112 @@ impl Storage {
         }))
     }
 
+    /// Read the IOx metadata embedded in the parquet file at `path` in
+    /// `object_store`, fetching only the metadata and footer of the file
+    pub async fn read_iox_metadata(object_store: &ObjectStore, path: &Path) -> Result<IoxMetadata> {
+        let data = Self::read_footer(object_store, path).await?;
+        let file_reader = SerializedFileReader::new(data).context(SerializedFileReaderError)?;
+
+        IoxMetadata::from_parquet_metadata(file_reader.metadata()).context(DecodingMetadata {
+            path: path.display(),
+        })
+    }
+
+    /// Fetch the metadata and footer of the parquet file at `path` in
+    /// `object_store`
+    async fn read_footer(object_store: &ObjectStore, path: &Path) -> Result<ParquetData> {
+        let size = object_store
+            .head(path)
+            .await
+            .context(ReadingObjectStore)?
+            .size;
+        let mut data = ParquetData::new(size);
+
+        // Fetch the end of the file, which will usually include all of
+        // the metadata as well as the footer
+        let tail_start = size.saturating_sub(TAIL_FETCH_SIZE);
+        let tail = object_store
+            .get_range(path, tail_start..size)
+            .await
+            .context(ReadingObjectStore)?;
+        data.insert(tail_start, tail);
+
+        let metadata_start = size.saturating_sub(FOOTER_SIZE + data.metadata_len());
+        if metadata_start < tail_start {
+            let metadata = object_store
+                .get_range(path, metadata_start..tail_start)
+                .await
+                .context(ReadingObjectStore)?;
+            data.insert(metadata_start, metadata);
+        }
+
+        Ok(data)
+    }
+
+    /// Fetch the parts of the parquet file at `path` in `object_store`
+    /// needed to read the `projection` columns of the row groups that
+    /// may match the predicate of `predicate_builder`
+    async fn read_object(
+        object_store: &ObjectStore,
+        path: &Path,
+        projection: &[usize],
+        predicate_builder: Option<&RowGroupPredicateBuilder>,
+    ) -> Result<ParquetData> {
+        let mut data = Self::read_footer(object_store, path).await?;
+
+        // Find the byte ranges of the column chunks to read
+        let mut ranges: Vec<Range<usize>> = {
+            let file_reader =
+                SerializedFileReader::new(data.clone()).context(SerializedFileReaderError)?;
+            let row_groups = file_reader.metadata().row_groups();
+            let row_group_predicate =
+                predicate_builder.map(|builder| builder.build_row_group_predicate(row_groups));
+
+            row_groups
+                .iter()
+                .enumerate()
+                .filter(|(i, row_group)| match &row_group_predicate {
+                    Some(predicate) => predicate(*row_group, *i),
+                    None => true,
+                })
+                .flat_map(|(_, row_group)| {
+                    projection.iter().map(move |&column| {
+                        let (start, length) = row_group.column(column).byte_range();
+                        start as usize..(start + length) as usize
+                    })
+                })
+                .filter(|range| !data.contains(range))
+                .collect()
+        };
+
+        // Fetch adjacent column chunks in a single request
+        ranges.sort_by_key(|range| range.start);
+        let ranges = ranges
+            .into_iter()
+            .fold(Vec::<Range<usize>>::new(), |mut merged, range| {
+                match merged.last_mut() {
+                    Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                    _ => merged.push(range),
+                }
+                merged
+            });
+
+        let chunks = futures::future::try_join_all(ranges.into_iter().map(|range| {
+            let start = range.start;
+            object_store
+                .get_range(path, range)
+                .map_ok(move |bytes| (start, bytes))
+        }))
+        .await
+        .context(ReadingObjectStore)?;
+
+        for (start, bytes) in chunks {
+            data.insert(start, bytes);
+        }
+
+        Ok(data)
+    }
+
     fn send_result(
         response_tx: &Sender<ArrowResult<RecordBatch>>,
         result: ArrowResult<RecordBatch>,
@@ -324,8 +497,9 @@ impl Storage {
             .context(SendResult)?;
         Ok(())
     }
-    fn read_file(
+    fn read_data(
         filename: String,
+        data: ParquetData,
         projection: &[usize],
         predicate_builder: Option<&RowGroupPredicateBuilder>,
         batch_size: usize,
@@ -334,8 +508,7 @@ impl Storage {
     ) -> Result<()> {
         let mut total_rows = 0;
 
-        let file = File::open(&filename).context(OpenFile)?;
-        let mut file_reader = SerializedFileReader::new(file).context(SerializedFileReaderError)?;
+        let mut file_reader = SerializedFileReader::new(data).context(SerializedFileReaderError)?;
         if let Some(predicate_builder) = predicate_builder {
             let row_group_predicate =
                 predicate_builder.build_row_group_predicate(file_reader.metadata().row_groups());
@@ -377,6 +550,94 @@ impl Storage {
     }
 }
 
+/// Number of bytes fetched from the end of a parquet file before
+/// reading its metadata
+const TAIL_FETCH_SIZE: usize = 64 * 1024;
+
+/// Size of the parquet footer: the length of the metadata followed by
+/// the "PAR1" magic number
+const FOOTER_SIZE: usize = 8;
+
+/// The parts of a parquet file that have been fetched from the object
+/// store, which are enough to read the file's metadata and the column
+/// chunks needed by a query.
+#[derive(Debug, Clone)]
+struct ParquetData {
+    /// Size of the entire file
+    len: usize,
+
+    /// Fetched ranges of the file, as (offset, bytes)
+    ranges: Vec<(usize, Bytes)>,
+}
+
+impl ParquetData {
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            ranges: vec![],
+        }
+    }
+
+    fn insert(&mut self, start: usize, bytes: Bytes) {
+        self.ranges.push((start, bytes));
+    }
+
+    /// Returns the fetched bytes covering all of `range`, if any
+    fn get(&self, range: &Range<usize>) -> Option<Bytes> {
+        self.ranges.iter().find_map(|(start, bytes)| {
+            if *start <= range.start && range.end <= start + bytes.len() {
+                Some(bytes.slice(range.start - start..range.end - start))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn contains(&self, range: &Range<usize>) -> bool {
+        self.get(range).is_some()
+    }
+
+    /// Returns the length of the file's metadata, as recorded in the
+    /// footer, or 0 if the footer has not been fetched or is invalid
+    /// (which will be reported when the metadata is parsed).
+    fn metadata_len(&self) -> usize {
+        let footer_start = self.len.saturating_sub(FOOTER_SIZE);
+        self.get(&(footer_start..footer_start + 4))
+            .filter(|_| self.len >= FOOTER_SIZE)
+            .map(|len| u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+            .unwrap_or(0)
+    }
+}
+
+impl Length for ParquetData {
+    fn len(&self) -> u64 {
+        self.len as u64
+    }
+}
+
+impl ChunkReader for ParquetData {
+    type T = bytes::buf::Reader<Bytes>;
+
+    fn get_read(&self, start: u64, length: usize) -> parquet::errors::Result<Self::T> {
+        let start = start as usize;
+        let range = start..start + length;
+        if range.end > self.len {
+            return Err(ParquetError::EOF(format!(
+                "Expected to read {} bytes at offset {}, but the file is only {} bytes",
+                length, start, self.len
+            )));
+        }
+
+        match self.get(&range) {
+            Some(bytes) => Ok(bytes.reader()),
+            None => Err(ParquetError::General(format!(
+                "{} bytes at offset {} were not fetched from the object store",
+                length, start
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct MemWriter {
     mem: Arc<Mutex<Cursor<Vec<u8>>>>,
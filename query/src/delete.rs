@@ -0,0 +1,337 @@
+//! Removal of deleted rows from the data of a chunk
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow_deps::{
+    arrow::{
+        array::{Array, Int64Array, StringArray, UInt32Array},
+        compute::{cast, take},
+        datatypes::{DataType, Schema as ArrowSchema, SchemaRef},
+        error::Result as ArrowResult,
+        record_batch::RecordBatch,
+    },
+    datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use data_types::delete::DeletePredicate;
+use futures::Stream;
+use internal_types::schema::TIME_COLUMN_NAME;
+
+/// Returns the names of the columns needed to evaluate `predicates`
+pub fn delete_columns(predicates: &[Arc<DeletePredicate>]) -> BTreeSet<&str> {
+    let mut columns = BTreeSet::new();
+    for predicate in predicates {
+        columns.insert(TIME_COLUMN_NAME);
+        columns.extend(predicate.exprs.iter().map(|expr| expr.column.as_str()));
+    }
+    columns
+}
+
+/// Returns the rows of `batch` that are not deleted by any of `predicates`,
+/// which must all be predicates of the table the rows of `batch` belong to.
+///
+/// A row is deleted by a predicate if its timestamp is within the time range
+/// of the predicate and it matches all expressions of the predicate. Rows
+/// without a timestamp are never deleted.
+pub fn filter_deleted(
+    batch: RecordBatch,
+    predicates: &[Arc<DeletePredicate>],
+) -> ArrowResult<RecordBatch> {
+    let num_rows = batch.num_rows();
+    if predicates.is_empty() || num_rows == 0 {
+        return Ok(batch);
+    }
+
+    let schema = batch.schema();
+    let time = match schema.index_of(TIME_COLUMN_NAME) {
+        Ok(idx) => Int64Array::from(cast(batch.column(idx), &DataType::Int64)?.data().clone()),
+        Err(_) => return Ok(batch),
+    };
+
+    let mut deleted = vec![false; num_rows];
+    for predicate in predicates {
+        // The values of the expression columns, None for columns that are
+        // not part of the batch
+        let values = predicate
+            .exprs
+            .iter()
+            .map(|expr| match schema.index_of(&expr.column) {
+                Ok(idx) => Ok(Some(StringArray::from(
+                    cast(batch.column(idx), &DataType::Utf8)?.data().clone(),
+                ))),
+                Err(_) => Ok(None),
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+
+        for (row, deleted) in deleted.iter_mut().enumerate() {
+            if *deleted || !time.is_valid(row) || !predicate.range.contains(time.value(row)) {
+                continue;
+            }
+
+            *deleted = predicate
+                .exprs
+                .iter()
+                .zip(&values)
+                .all(|(expr, values)| match values {
+                    Some(values) if values.is_valid(row) => expr.matches(Some(values.value(row))),
+                    _ => expr.matches(None),
+                });
+        }
+    }
+
+    if !deleted.contains(&true) {
+        return Ok(batch);
+    }
+
+    let indices = UInt32Array::from(
+        (0..num_rows)
+            .filter(|&row| !deleted[row])
+            .map(|row| row as u32)
+            .collect::<Vec<_>>(),
+    );
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &indices, None))
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(schema, columns)
+}
+
+/// This stream wraps another underlying stream and removes the rows deleted
+/// by a set of delete predicates from its batches.
+///
+/// Evaluating the predicates may require columns that are not part of the
+/// desired output, so the stream also restricts its batches to the output
+/// columns.
+pub(crate) struct DeleteFilterStream {
+    input: SendableRecordBatchStream,
+    predicates: Vec<Arc<DeletePredicate>>,
+    /// Output schema of this stream
+    output_schema: SchemaRef,
+    /// The indexes of the output columns in the input schema
+    projection: Vec<usize>,
+}
+
+impl std::fmt::Debug for DeleteFilterStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeleteFilterStream")
+            .field("input", &"(OPAQUE STREAM)")
+            .field("predicates", &self.predicates)
+            .field("output_schema", &self.output_schema)
+            .field("projection", &self.projection)
+            .finish()
+    }
+}
+
+impl DeleteFilterStream {
+    /// Create a new stream that removes the rows deleted by `predicates`
+    /// from the batches of `input` and produces only the columns of `input`
+    /// named in `output_columns`
+    pub(crate) fn new(
+        input: SendableRecordBatchStream,
+        predicates: Vec<Arc<DeletePredicate>>,
+        output_columns: &[&str],
+    ) -> Self {
+        let input_schema = input.schema();
+        let projection: Vec<_> = input_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| output_columns.contains(&field.name().as_str()))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let fields = projection
+            .iter()
+            .map(|&idx| input_schema.field(idx).clone())
+            .collect();
+        let output_schema = Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            input_schema.metadata().clone(),
+        ));
+
+        Self {
+            input,
+            predicates,
+            output_schema,
+            projection,
+        }
+    }
+
+    fn filter_batch(&self, batch: RecordBatch) -> ArrowResult<RecordBatch> {
+        let batch = filter_deleted(batch, &self.predicates)?;
+        let columns = self
+            .projection
+            .iter()
+            .map(|&idx| Arc::clone(batch.column(idx)))
+            .collect();
+
+        RecordBatch::try_new(Arc::clone(&self.output_schema), columns)
+    }
+}
+
+impl RecordBatchStream for DeleteFilterStream {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.output_schema)
+    }
+}
+
+impl Stream for DeleteFilterStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        ctx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.input.as_mut().poll_next(ctx).map(|maybe_result| {
+            maybe_result.map(|batch| batch.and_then(|batch| self.filter_batch(batch)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::{
+        arrow::{
+            array::{Float64Array, TimestampNanosecondArray},
+            datatypes::Field,
+        },
+        assert_table_eq,
+        datafusion::physical_plan::common::{collect, SizedRecordBatchStream},
+    };
+    use data_types::{
+        delete::{DeleteExpr, DeleteOp},
+        timestamp::TimestampRange,
+    };
+
+    fn make_batch() -> RecordBatch {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new("usage", DataType::Float64, true),
+            Field::new(
+                TIME_COLUMN_NAME,
+                DataType::Timestamp(arrow_deps::arrow::datatypes::TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    None,
+                    Some("a"),
+                ])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0, 4.0])),
+                Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![10, 20, 30, 40],
+                    None,
+                )),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn predicate(start: i64, end: i64, exprs: Vec<DeleteExpr>) -> Arc<DeletePredicate> {
+        Arc::new(DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(start, end),
+            exprs,
+        })
+    }
+
+    fn expr(column: &str, op: DeleteOp, value: &str) -> DeleteExpr {
+        DeleteExpr {
+            column: column.to_string(),
+            op,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn filter_deleted_time_range() {
+        let batch = filter_deleted(make_batch(), &[predicate(15, 35, vec![])]).unwrap();
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| a    | 1     | 1970-01-01 00:00:00.000000010 |",
+            "| a    | 4     | 1970-01-01 00:00:00.000000040 |",
+            "+------+-------+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &[batch]);
+    }
+
+    #[test]
+    fn filter_deleted_exprs() {
+        // Rows without a host compare as the empty string
+        let predicates = vec![
+            predicate(0, 35, vec![expr("host", DeleteOp::Eq, "a")]),
+            predicate(0, 100, vec![expr("host", DeleteOp::Eq, "")]),
+        ];
+        let batch = filter_deleted(make_batch(), &predicates).unwrap();
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| b    | 2     | 1970-01-01 00:00:00.000000020 |",
+            "| a    | 4     | 1970-01-01 00:00:00.000000040 |",
+            "+------+-------+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &[batch]);
+
+        // Columns that are not part of the batch have no values
+        let predicates = vec![predicate(
+            0,
+            100,
+            vec![
+                expr("host", DeleteOp::Ne, "b"),
+                expr("region", DeleteOp::Eq, ""),
+            ],
+        )];
+        let batch = filter_deleted(make_batch(), &predicates).unwrap();
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| b    | 2     | 1970-01-01 00:00:00.000000020 |",
+            "+------+-------+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &[batch]);
+
+        let predicates = vec![predicate(0, 100, vec![expr("region", DeleteOp::Ne, "")])];
+        let batch = filter_deleted(make_batch(), &predicates).unwrap();
+        assert_eq!(batch.num_rows(), 4);
+    }
+
+    #[tokio::test]
+    async fn delete_filter_stream() {
+        let batch = make_batch();
+        let input = SizedRecordBatchStream::new(batch.schema(), vec![Arc::new(batch)]);
+
+        let predicates = vec![predicate(0, 100, vec![expr("host", DeleteOp::Eq, "a")])];
+        let stream = DeleteFilterStream::new(Box::pin(input), predicates, &["usage"]);
+        assert_eq!(stream.schema().fields().len(), 1);
+
+        let output = collect(Box::pin(stream)).await.unwrap();
+
+        let expected = vec![
+            "+-------+",
+            "| usage |",
+            "+-------+",
+            "| 2     |",
+            "| 3     |",
+            "+-------+",
+        ];
+        assert_table_eq!(expected, &output);
+    }
+}
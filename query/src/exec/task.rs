@@ -7,7 +7,7 @@ use tokio::sync::oneshot::Receiver;
 
 use futures::Future;
 
-use observability_deps::tracing::warn;
+use observability_deps::tracing::{debug, warn};
 
 /// The type of thing that the dedicated executor runs
 type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
@@ -99,15 +99,25 @@ impl DedicatedExecutor {
     ///
     /// Currently all tasks are added to the tokio executor
     /// immediately and compete for the threadpool's resources.
+    ///
+    /// Dropping the returned `Receiver` cancels the task, so that
+    /// plans stop running once nothing is waiting for their results.
     pub fn spawn<T>(&self, task: T) -> Receiver<T::Output>
     where
         T: Future + Send + 'static,
         T::Output: Send + 'static,
     {
-        let (tx, rx) = tokio::sync::oneshot::channel();
+        let (mut tx, rx) = tokio::sync::oneshot::channel();
 
         let job = Box::pin(async move {
-            let task_output = task.await;
+            let task_output = tokio::select! {
+                task_output = task => task_output,
+                _ = tx.closed() => {
+                    debug!("Spawned task cancelled: receiver dropped");
+                    return;
+                }
+            };
+
             if tx.send(task_output).is_err() {
                 warn!("Spawned task output ignored: receiver dropped")
             }
@@ -319,6 +329,24 @@ mod tests {
         dedicated_task.await.unwrap_err();
     }
 
+    #[tokio::test]
+    async fn drop_receiver_cancels_task() {
+        let exec = DedicatedExecutor::new("Test DedicatedExecutor", 1);
+
+        let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel::<()>();
+        let dedicated_task = exec.spawn(async move {
+            // dropped with the task
+            let _dropped_tx = dropped_tx;
+            futures::future::pending::<()>().await
+        });
+
+        drop(dedicated_task);
+
+        // the task is dropped without completing
+        dropped_rx.await.unwrap_err();
+        exec.join();
+    }
+
     #[tokio::test]
     async fn executor_join() {
         let exec = DedicatedExecutor::new("Test DedicatedExecutor", 1);
@@ -1,2 +1,3 @@
+pub mod influxql;
 pub mod influxrpc;
 pub mod sql;
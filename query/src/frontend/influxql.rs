@@ -0,0 +1,706 @@
+//! Query planner for InfluxQL, the query language of InfluxDB 1.x
+//!
+//! `SHOW` statements are planned using the same plans as the
+//! equivalent gRPC (storage) API requests. `SELECT` statements are
+//! planned as a scan of the measurement's table followed by optional
+//! aggregation (windowed by `GROUP BY time(..)`) and grouping by tags.
+//!
+//! Known differences from InfluxDB 1.x:
+//!
+//! * No gap filling is done: `fill(null)` and `fill(none)` both only return
+//!   windows that contain data, and `fill(<value>)` only replaces null
+//!   aggregate values. `fill(previous)` and `fill(linear)` are not supported.
+//! * `LIMIT` applies to the entire result rather than to each series.
+use std::collections::BTreeSet;
+
+use arrow_deps::{
+    datafusion::{
+        error::DataFusionError,
+        logical_plan::{binary_expr, col, Expr, LogicalPlan, Operator},
+        scalar::ScalarValue,
+    },
+    util::AsExpr,
+};
+use chrono::DateTime;
+use internal_types::schema::TIME_COLUMN_NAME;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    frontend::influxrpc::{InfluxRPCPlanner, TableScanAndFilter},
+    func::{
+        selectors::{selector_first, selector_last, selector_max, selector_min, SelectorOutput},
+        window::make_window_start_expr,
+    },
+    group_by::{Aggregate, WindowDuration},
+    plan::{fieldlist::FieldListPlan, stringset::StringSetPlan},
+    predicate::{Predicate, PredicateBuilder},
+    util::schema_has_all_expr_columns,
+    Database,
+};
+
+use self::parser::{
+    BinaryOp, Expr as InfluxQLExpr, FieldExpr, Fill, GroupByTags, SelectStatement, Statement,
+};
+
+pub mod parser;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("InfluxQL planner got error from gRPC planner: {}", source))]
+    InfluxRPC {
+        source: crate::frontend::influxrpc::Error,
+    },
+
+    #[snafu(display("InfluxQL planner got error building plan: {}", source))]
+    BuildingPlan { source: DataFusionError },
+
+    #[snafu(display("InfluxQL planner got error creating aggregate: {}", source))]
+    CreatingAggregates { source: crate::group_by::Error },
+
+    #[snafu(display("Unsupported InfluxQL condition: {}", message))]
+    UnsupportedCondition { message: String },
+
+    #[snafu(display("Unsupported InfluxQL function: {}", name))]
+    UnsupportedFunction { name: String },
+
+    #[snafu(display("Unsupported InfluxQL fill option: {:?}", fill))]
+    UnsupportedFill { fill: Fill },
+
+    #[snafu(display("mixing aggregate and non-aggregate queries is not supported"))]
+    MixedAggregateAndRaw {},
+
+    #[snafu(display("GROUP BY time requires an aggregate function"))]
+    GroupByTimeWithoutAggregate {},
+
+    #[snafu(display("Invalid InfluxQL time literal '{}': {}", value, source))]
+    InvalidTime {
+        value: String,
+        source: chrono::ParseError,
+    },
+
+    #[snafu(display("InfluxQL time expression overflowed"))]
+    TimeOverflow {},
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The plan for a single InfluxQL statement
+#[derive(Debug)]
+pub enum InfluxQLPlan {
+    /// The plan for a `SELECT` statement. The output columns are
+    /// `time`, followed by the `tag_columns` (whose values identify
+    /// each series) and then the selected fields. The output is
+    /// sorted by `tag_columns` and `time`.
+    ///
+    /// `plan` is `None` if the measurement does not exist or can not
+    /// have any rows that match
+    Select {
+        measurement: String,
+        tag_columns: Vec<String>,
+        plan: Option<LogicalPlan>,
+    },
+    ShowMeasurements(StringSetPlan),
+    ShowTagKeys(StringSetPlan),
+    ShowTagValues {
+        key: String,
+        plan: StringSetPlan,
+    },
+    ShowFieldKeys(FieldListPlan),
+}
+
+/// This struct can create plans for running InfluxQL statements
+/// against databases
+#[derive(Debug)]
+pub struct InfluxQLQueryPlanner {
+    /// The value of `now()`, in nanoseconds since the epoch
+    now: i64,
+}
+
+impl Default for InfluxQLQueryPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InfluxQLQueryPlanner {
+    /// Create a new planner where `now()` is the current time
+    pub fn new() -> Self {
+        Self::with_now(chrono::Utc::now().timestamp_nanos())
+    }
+
+    /// Create a new planner with a fixed value (in nanoseconds) for
+    /// `now()`
+    pub fn with_now(now: i64) -> Self {
+        Self { now }
+    }
+
+    /// Plan a single parsed InfluxQL statement against `database`
+    pub fn statement<D>(&self, database: &D, statement: &Statement) -> Result<InfluxQLPlan>
+    where
+        D: Database + 'static,
+    {
+        let planner = InfluxRPCPlanner::new();
+
+        match statement {
+            Statement::Select(select) => self.select(database, select),
+            Statement::ShowMeasurements { condition } => {
+                let predicate = self.predicate(None, condition.as_ref())?;
+                let plan = planner
+                    .table_names(database, predicate)
+                    .context(InfluxRPC)?;
+                Ok(InfluxQLPlan::ShowMeasurements(plan))
+            }
+            Statement::ShowTagKeys { from, condition } => {
+                let predicate = self.predicate(from.as_deref(), condition.as_ref())?;
+                let plan = planner.tag_keys(database, predicate).context(InfluxRPC)?;
+                Ok(InfluxQLPlan::ShowTagKeys(plan))
+            }
+            Statement::ShowTagValues {
+                from,
+                key,
+                condition,
+            } => {
+                let predicate = self.predicate(from.as_deref(), condition.as_ref())?;
+                let plan = planner
+                    .tag_values(database, key, predicate)
+                    .context(InfluxRPC)?;
+                Ok(InfluxQLPlan::ShowTagValues {
+                    key: key.clone(),
+                    plan,
+                })
+            }
+            Statement::ShowFieldKeys { from } => {
+                let predicate = self.predicate(from.as_deref(), None)?;
+                let plan = planner
+                    .field_columns(database, predicate)
+                    .context(InfluxRPC)?;
+                Ok(InfluxQLPlan::ShowFieldKeys(plan))
+            }
+        }
+    }
+
+    /// Creates a `Predicate` for the `SHOW` statements
+    fn predicate(
+        &self,
+        table: Option<&str>,
+        condition: Option<&InfluxQLExpr>,
+    ) -> Result<Predicate> {
+        let condition = self.condition(condition)?;
+
+        let mut builder = PredicateBuilder::new().table_option(table.map(|t| t.to_string()));
+        if let Some((start, end)) = condition.range() {
+            builder = builder.timestamp_range(start, end);
+        }
+
+        Ok(condition
+            .exprs
+            .into_iter()
+            .fold(builder, |builder, expr| builder.add_expr(expr))
+            .build())
+    }
+
+    fn select<D>(&self, database: &D, select: &SelectStatement) -> Result<InfluxQLPlan>
+    where
+        D: Database + 'static,
+    {
+        let (tag_columns, plan) = match self.select_plan(database, select)? {
+            Some((tag_columns, plan)) => (tag_columns, Some(plan)),
+            None => (vec![], None),
+        };
+
+        Ok(InfluxQLPlan::Select {
+            measurement: select.from.clone(),
+            tag_columns,
+            plan,
+        })
+    }
+
+    /// Returns the tag columns and plan for `select`, or `None` if no
+    /// rows could match
+    fn select_plan<D>(
+        &self,
+        database: &D,
+        select: &SelectStatement,
+    ) -> Result<Option<(Vec<String>, LogicalPlan)>>
+    where
+        D: Database + 'static,
+    {
+        let table_name = select.from.as_str();
+        let condition = self.condition(select.condition.as_ref())?;
+
+        // Only the measurement and time range are handled by the
+        // predicate as the gRPC planner doesn't support all the
+        // comparisons InfluxQL allows; the rest of the condition is
+        // applied as a separate filter
+        let mut builder = PredicateBuilder::new().table(table_name);
+        if let Some((start, end)) = condition.range() {
+            builder = builder.timestamp_range(start, end);
+        }
+        let predicate = builder.build();
+
+        let planner = InfluxRPCPlanner::new();
+        let chunks = planner
+            .filtered_chunks(database, &predicate)
+            .context(InfluxRPC)?;
+        let mut table_chunks = planner
+            .group_chunks_by_table(&predicate, chunks)
+            .context(InfluxRPC)?;
+
+        let scan = match table_chunks.remove(table_name) {
+            Some(chunks) => planner
+                .scan_and_filter(table_name, &predicate, chunks)
+                .context(InfluxRPC)?,
+            None => None,
+        };
+        let TableScanAndFilter {
+            mut plan_builder,
+            schema,
+        } = match scan {
+            Some(scan) => scan,
+            None => return Ok(None),
+        };
+
+        for expr in &condition.exprs {
+            // Like the gRPC planner, assume no rows can match if the
+            // table doesn't have all the columns referenced
+            if !schema_has_all_expr_columns(&schema, expr) {
+                return Ok(None);
+            }
+            plan_builder = plan_builder.filter(expr.clone()).context(BuildingPlan)?;
+        }
+
+        let table_tags: BTreeSet<_> = schema.tags_iter().map(|f| f.name().as_str()).collect();
+        let tag_columns: Vec<String> = match &select.group_by.tags {
+            GroupByTags::All => table_tags.iter().map(|t| t.to_string()).collect(),
+            GroupByTags::Some(tags) => {
+                let mut tag_columns: Vec<String> = vec![];
+                for tag in tags {
+                    if table_tags.contains(tag.as_str()) && !tag_columns.contains(tag) {
+                        tag_columns.push(tag.clone())
+                    }
+                }
+                tag_columns
+            }
+        };
+
+        // Output names that are already taken
+        let mut used_names: BTreeSet<String> = tag_columns.iter().cloned().collect();
+        used_names.insert(TIME_COLUMN_NAME.to_string());
+
+        let is_aggregate = select
+            .fields
+            .iter()
+            .any(|field| matches!(field.expr, FieldExpr::Call { .. }));
+
+        let mut select_exprs = vec![];
+        let mut field_exprs = vec![];
+
+        if is_aggregate {
+            let mut group_exprs: Vec<Expr> = tag_columns.iter().map(|t| col(t)).collect();
+            let time_expr = match select.group_by.time {
+                Some((every, offset)) => {
+                    let every = WindowDuration::from_nanoseconds(every);
+                    let offset = WindowDuration::from_nanoseconds(offset);
+                    group_exprs.push(
+                        make_window_start_expr(TIME_COLUMN_NAME.as_expr(), &every, &offset)
+                            .alias(TIME_COLUMN_NAME),
+                    );
+                    TIME_COLUMN_NAME.as_expr()
+                }
+                // InfluxQL reports the start of the time range for
+                // aggregates over all time
+                None => Expr::Literal(ScalarValue::TimestampNanosecond(Some(
+                    condition.start.unwrap_or(0),
+                )))
+                .alias(TIME_COLUMN_NAME),
+            };
+
+            let mut agg_exprs = vec![];
+            let mut output_names = vec![];
+            for field in &select.fields {
+                let (name, arg) = match &field.expr {
+                    FieldExpr::Call { name, arg } => (name, arg),
+                    _ => return MixedAggregateAndRaw.fail(),
+                };
+                ensure!(
+                    arg != "*",
+                    UnsupportedFunction {
+                        name: format!("{}(*)", name)
+                    }
+                );
+
+                // Like InfluxDB, missing fields are silently ignored
+                let data_type = match schema.find_index_of(arg) {
+                    Some(idx) => schema.field(idx).1.data_type(),
+                    None => continue,
+                };
+
+                let agg_expr = match name.as_str() {
+                    "count" => Aggregate::Count.to_datafusion_expr(col(arg)),
+                    "sum" => Aggregate::Sum.to_datafusion_expr(col(arg)),
+                    "mean" => Aggregate::Mean.to_datafusion_expr(col(arg)),
+                    _ => {
+                        let selector = match name.as_str() {
+                            "min" => selector_min(data_type, SelectorOutput::Value),
+                            "max" => selector_max(data_type, SelectorOutput::Value),
+                            "first" => selector_first(data_type, SelectorOutput::Value),
+                            "last" => selector_last(data_type, SelectorOutput::Value),
+                            _ => return UnsupportedFunction { name }.fail(),
+                        };
+                        Ok(selector.call(vec![col(arg), TIME_COLUMN_NAME.as_expr()]))
+                    }
+                }
+                .context(CreatingAggregates)?;
+
+                let output_name =
+                    unique_name(&mut used_names, field.alias.as_deref().unwrap_or(name));
+                agg_exprs.push(agg_expr.alias(&output_name));
+                output_names.push(output_name);
+            }
+
+            if agg_exprs.is_empty() {
+                return Ok(None);
+            }
+
+            let fill_value = match select.fill {
+                Fill::Null | Fill::None => None,
+                Fill::Integer(i) => Some(ScalarValue::Int64(Some(i))),
+                Fill::Float(f) => Some(ScalarValue::Float64(Some(f))),
+                fill => return UnsupportedFill { fill }.fail(),
+            };
+
+            plan_builder = plan_builder
+                .aggregate(group_exprs, agg_exprs)
+                .context(BuildingPlan)?;
+
+            select_exprs.push(time_expr);
+            for output_name in &output_names {
+                let expr = match &fill_value {
+                    None => col(output_name),
+                    Some(value) => {
+                        let data_type = plan_builder
+                            .schema()
+                            .field_with_unqualified_name(output_name)
+                            .context(BuildingPlan)?
+                            .data_type()
+                            .clone();
+
+                        // CASE WHEN <agg> IS NULL THEN CAST(<value> AS <type>) ELSE <agg> END
+                        Expr::Case {
+                            expr: None,
+                            when_then_expr: vec![(
+                                Box::new(Expr::IsNull(Box::new(col(output_name)))),
+                                Box::new(Expr::Cast {
+                                    expr: Box::new(Expr::Literal(value.clone())),
+                                    data_type,
+                                }),
+                            )],
+                            else_expr: Some(Box::new(col(output_name))),
+                        }
+                        .alias(output_name)
+                    }
+                };
+                field_exprs.push(expr);
+            }
+        } else {
+            ensure!(select.group_by.time.is_none(), GroupByTimeWithoutAggregate);
+
+            select_exprs.push(TIME_COLUMN_NAME.as_expr());
+            for field in &select.fields {
+                match &field.expr {
+                    FieldExpr::Wildcard => {
+                        let mut names: Vec<_> = schema
+                            .iter()
+                            .map(|(_, f)| f.name())
+                            .filter(|name| {
+                                name.as_str() != TIME_COLUMN_NAME && !tag_columns.contains(name)
+                            })
+                            .collect();
+                        names.sort();
+
+                        for name in names {
+                            let output_name = unique_name(&mut used_names, name);
+                            field_exprs.push(col(name).alias(&output_name));
+                        }
+                    }
+                    FieldExpr::Column(name) => {
+                        if name == TIME_COLUMN_NAME || schema.find_index_of(name).is_none() {
+                            continue;
+                        }
+                        let output_name =
+                            unique_name(&mut used_names, field.alias.as_deref().unwrap_or(name));
+                        field_exprs.push(col(name).alias(&output_name));
+                    }
+                    FieldExpr::Call { .. } => return MixedAggregateAndRaw.fail(),
+                }
+            }
+        }
+
+        if field_exprs.is_empty() {
+            return Ok(None);
+        }
+
+        select_exprs.extend(tag_columns.iter().map(|t| col(t)));
+        select_exprs.extend(field_exprs);
+
+        let sort_exprs: Vec<_> = tag_columns
+            .iter()
+            .map(|t| t.as_str().as_sort_expr())
+            .chain(std::iter::once(TIME_COLUMN_NAME.as_sort_expr()))
+            .collect();
+
+        plan_builder = plan_builder
+            .project(select_exprs)
+            .context(BuildingPlan)?
+            .sort(sort_exprs)
+            .context(BuildingPlan)?;
+
+        if let Some(limit) = select.limit {
+            plan_builder = plan_builder.limit(limit).context(BuildingPlan)?;
+        }
+
+        let plan = plan_builder.build().context(BuildingPlan)?;
+        Ok(Some((tag_columns, plan)))
+    }
+
+    /// Splits an InfluxQL condition into a time range and DataFusion
+    /// expressions for everything else
+    fn condition(&self, condition: Option<&InfluxQLExpr>) -> Result<Condition> {
+        let mut conjuncts = vec![];
+        if let Some(condition) = condition {
+            split_conjunction(condition, &mut conjuncts);
+        }
+
+        let mut result = Condition::default();
+        for expr in conjuncts {
+            match time_comparison(expr) {
+                Some((op, value)) => {
+                    let value = self.time_value(value)?;
+                    let (start, end) = match op {
+                        BinaryOp::Eq => (Some(value), value.checked_add(1)),
+                        BinaryOp::Gt => (value.checked_add(1), None),
+                        BinaryOp::GtEq => (Some(value), None),
+                        BinaryOp::Lt => (None, Some(value)),
+                        BinaryOp::LtEq => (None, value.checked_add(1)),
+                        _ => {
+                            return UnsupportedCondition {
+                                message: format!("time {} {:?}", op, value),
+                            }
+                            .fail()
+                        }
+                    };
+                    if let Some(start) = start {
+                        result.start = Some(result.start.map_or(start, |s| s.max(start)));
+                    }
+                    if let Some(end) = end {
+                        result.end = Some(result.end.map_or(end, |e| e.min(end)));
+                    }
+                }
+                None => result.exprs.push(to_datafusion_expr(expr)?),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Evaluates the right hand side of a comparison with `time` to
+    /// nanoseconds since the epoch
+    fn time_value(&self, expr: &InfluxQLExpr) -> Result<i64> {
+        match expr {
+            InfluxQLExpr::Now => Ok(self.now),
+            InfluxQLExpr::Integer(i) => Ok(*i),
+            InfluxQLExpr::Duration(d) => Ok(*d),
+            InfluxQLExpr::String(s) => Ok(DateTime::parse_from_rfc3339(s)
+                .context(InvalidTime { value: s })?
+                .timestamp_nanos()),
+            InfluxQLExpr::Binary { left, op, right } => {
+                let left = self.time_value(left)?;
+                let right = self.time_value(right)?;
+                match op {
+                    BinaryOp::Add => left.checked_add(right).context(TimeOverflow),
+                    BinaryOp::Sub => left.checked_sub(right).context(TimeOverflow),
+                    _ => UnsupportedCondition {
+                        message: format!("invalid time expression {:?}", expr),
+                    }
+                    .fail(),
+                }
+            }
+            _ => UnsupportedCondition {
+                message: format!("invalid time expression {:?}", expr),
+            }
+            .fail(),
+        }
+    }
+}
+
+/// A condition split into a time range and other expressions
+#[derive(Debug, Default)]
+struct Condition {
+    /// Inclusive lower time bound
+    start: Option<i64>,
+    /// Exclusive upper time bound
+    end: Option<i64>,
+    /// Expressions that are AND'ed together
+    exprs: Vec<Expr>,
+}
+
+impl Condition {
+    fn range(&self) -> Option<(i64, i64)> {
+        if self.start.is_none() && self.end.is_none() {
+            return None;
+        }
+        Some((self.start.unwrap_or(i64::MIN), self.end.unwrap_or(i64::MAX)))
+    }
+}
+
+/// Appends the terms of the `AND`s in `expr` to `out`
+fn split_conjunction<'a>(expr: &'a InfluxQLExpr, out: &mut Vec<&'a InfluxQLExpr>) {
+    match expr {
+        InfluxQLExpr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => {
+            split_conjunction(left, out);
+            split_conjunction(right, out);
+        }
+        _ => out.push(expr),
+    }
+}
+
+/// If `expr` compares `time` to a value, returns the comparison
+/// (normalized so that `time` is on the left) and the value
+fn time_comparison(expr: &InfluxQLExpr) -> Option<(BinaryOp, &InfluxQLExpr)> {
+    let is_time = |expr: &InfluxQLExpr| matches!(expr, InfluxQLExpr::Column(name) if name == TIME_COLUMN_NAME);
+
+    match expr {
+        InfluxQLExpr::Binary { left, op, right } if is_time(left) => Some((*op, right)),
+        InfluxQLExpr::Binary { left, op, right } if is_time(right) => {
+            let op = match op {
+                BinaryOp::Lt => BinaryOp::Gt,
+                BinaryOp::LtEq => BinaryOp::GtEq,
+                BinaryOp::Gt => BinaryOp::Lt,
+                BinaryOp::GtEq => BinaryOp::LtEq,
+                op => *op,
+            };
+            Some((op, left))
+        }
+        _ => None,
+    }
+}
+
+/// Converts a (non time) InfluxQL condition to a DataFusion expression
+fn to_datafusion_expr(expr: &InfluxQLExpr) -> Result<Expr> {
+    let expr = match expr {
+        InfluxQLExpr::Column(name) if name == TIME_COLUMN_NAME => {
+            return UnsupportedCondition {
+                message: "time conditions can only be combined using AND",
+            }
+            .fail()
+        }
+        InfluxQLExpr::Column(name) => col(name),
+        InfluxQLExpr::String(s) => Expr::Literal(ScalarValue::Utf8(Some(s.clone()))),
+        InfluxQLExpr::Integer(i) => Expr::Literal(ScalarValue::Int64(Some(*i))),
+        InfluxQLExpr::Float(f) => Expr::Literal(ScalarValue::Float64(Some(*f))),
+        InfluxQLExpr::Boolean(b) => Expr::Literal(ScalarValue::Boolean(Some(*b))),
+        InfluxQLExpr::Binary { left, op, right } => {
+            let op = match op {
+                BinaryOp::Eq => Operator::Eq,
+                BinaryOp::NotEq => Operator::NotEq,
+                BinaryOp::Lt => Operator::Lt,
+                BinaryOp::LtEq => Operator::LtEq,
+                BinaryOp::Gt => Operator::Gt,
+                BinaryOp::GtEq => Operator::GtEq,
+                BinaryOp::And => Operator::And,
+                BinaryOp::Or => Operator::Or,
+                BinaryOp::Add => Operator::Plus,
+                BinaryOp::Sub => Operator::Minus,
+            };
+            binary_expr(to_datafusion_expr(left)?, op, to_datafusion_expr(right)?)
+        }
+        InfluxQLExpr::Duration(_) | InfluxQLExpr::Now => {
+            return UnsupportedCondition {
+                message: format!("{:?} can only be compared with time", expr),
+            }
+            .fail()
+        }
+    };
+    Ok(expr)
+}
+
+/// Returns `name`, or if that is already used, `name_1`, `name_2`,
+/// ..., as InfluxQL does for duplicate output columns
+fn unique_name(used_names: &mut BTreeSet<String>, name: &str) -> String {
+    let mut output_name = name.to_string();
+    let mut i = 1;
+    while used_names.contains(&output_name) {
+        output_name = format!("{}_{}", name, i);
+        i += 1;
+    }
+    used_names.insert(output_name.clone());
+    output_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::influxql::parser::parse_statements;
+
+    fn condition(q: &str) -> Condition {
+        let statement = parse_statements(&format!("SELECT * FROM cpu WHERE {}", q))
+            .unwrap()
+            .remove(0);
+        let condition = match statement {
+            Statement::Select(select) => select.condition,
+            _ => unreachable!(),
+        };
+        InfluxQLQueryPlanner::with_now(1_000)
+            .condition(condition.as_ref())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_condition_time_range() {
+        let c = condition("time > now() - 100ns AND time <= 900 AND host = 'a'");
+        assert_eq!(c.range(), Some((901, 901)));
+        assert_eq!(c.exprs.len(), 1);
+
+        let c = condition("'1970-01-01T00:00:00.000000500Z' <= time");
+        assert_eq!(c.range(), Some((500, i64::MAX)));
+        assert!(c.exprs.is_empty());
+
+        let c = condition("host = 'a' OR region != 'west'");
+        assert_eq!(c.range(), None);
+        assert_eq!(
+            format!("{:?}", c.exprs[0]),
+            r#"#host Eq Utf8("a") Or #region NotEq Utf8("west")"#
+        );
+    }
+
+    #[test]
+    fn test_condition_unsupported() {
+        let statement = parse_statements("SELECT * FROM cpu WHERE time > 0 OR host = 'a'")
+            .unwrap()
+            .remove(0);
+        let condition = match statement {
+            Statement::Select(select) => select.condition,
+            _ => unreachable!(),
+        };
+        let err = InfluxQLQueryPlanner::new()
+            .condition(condition.as_ref())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported InfluxQL condition: time conditions can only be combined using AND"
+        );
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut used = BTreeSet::new();
+        assert_eq!(unique_name(&mut used, "mean"), "mean");
+        assert_eq!(unique_name(&mut used, "mean"), "mean_1");
+        assert_eq!(unique_name(&mut used, "mean"), "mean_2");
+        assert_eq!(unique_name(&mut used, "max"), "max");
+    }
+}
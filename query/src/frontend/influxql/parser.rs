@@ -0,0 +1,989 @@
+//! A parser for the subset of InfluxQL supported by IOx.
+//!
+//! The grammar follows the InfluxDB 1.x [InfluxQL
+//! specification](https://docs.influxdata.com/influxdb/v1.8/query_language/spec/)
+//! but only the statements that IOx can plan are recognized:
+//!
+//! ```text
+//! SELECT <fields> FROM <measurement> [WHERE <condition>]
+//!     [GROUP BY time(<interval>[, <offset>]) | <tag>, ... | *] [FILL(<fill>)] [LIMIT <n>]
+//! SHOW MEASUREMENTS [WHERE <condition>]
+//! SHOW TAG KEYS [FROM <measurement>] [WHERE <condition>]
+//! SHOW TAG VALUES [FROM <measurement>] WITH KEY = <tag> [WHERE <condition>]
+//! SHOW FIELD KEYS [FROM <measurement>]
+//! ```
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("error parsing InfluxQL at position {}: {}", pos, message))]
+    Syntax { pos: usize, message: String },
+
+    #[snafu(display(
+        "error parsing InfluxQL: unexpected end of statement, expected {}",
+        expected
+    ))]
+    UnexpectedEnd { expected: String },
+
+    #[snafu(display("error parsing InfluxQL: invalid number '{}': {}", value, source))]
+    InvalidInteger {
+        value: String,
+        source: std::num::ParseIntError,
+    },
+
+    #[snafu(display("error parsing InfluxQL: invalid number '{}': {}", value, source))]
+    InvalidFloat {
+        value: String,
+        source: std::num::ParseFloatError,
+    },
+
+    #[snafu(display("error parsing InfluxQL: invalid duration '{}'", value))]
+    InvalidDuration { value: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A single InfluxQL statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Select(SelectStatement),
+    ShowMeasurements {
+        condition: Option<Expr>,
+    },
+    ShowTagKeys {
+        from: Option<String>,
+        condition: Option<Expr>,
+    },
+    ShowTagValues {
+        from: Option<String>,
+        key: String,
+        condition: Option<Expr>,
+    },
+    ShowFieldKeys {
+        from: Option<String>,
+    },
+}
+
+/// A `SELECT` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectStatement {
+    pub fields: Vec<Field>,
+    pub from: String,
+    pub condition: Option<Expr>,
+    pub group_by: GroupBy,
+    pub fill: Fill,
+    pub limit: Option<usize>,
+}
+
+/// One of the expressions in the projection of a `SELECT` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub expr: FieldExpr,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldExpr {
+    /// `*`: all fields and tags
+    Wildcard,
+    /// A single field or tag
+    Column(String),
+    /// An aggregate or selector function applied to a field, such as
+    /// `mean(usage)`
+    Call { name: String, arg: String },
+}
+
+impl FieldExpr {
+    /// The default name of the output column for this expression
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Wildcard => "*",
+            Self::Column(name) => name,
+            Self::Call { name, .. } => name,
+        }
+    }
+}
+
+/// The `GROUP BY` clause of a `SELECT` statement
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupBy {
+    /// `time(interval, offset)`, in nanoseconds
+    pub time: Option<(i64, i64)>,
+    pub tags: GroupByTags,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupByTags {
+    /// `GROUP BY *`
+    All,
+    /// `GROUP BY tag1, tag2`, possibly empty
+    Some(Vec<String>),
+}
+
+impl Default for GroupByTags {
+    fn default() -> Self {
+        Self::Some(vec![])
+    }
+}
+
+/// The `FILL` option of a `SELECT` statement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Null,
+    None,
+    Previous,
+    Linear,
+    Integer(i64),
+    Float(f64),
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
+/// A conditional expression, as found in `WHERE` clauses
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    /// A duration literal, in nanoseconds
+    Duration(i64),
+    /// `now()`
+    Now,
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    Add,
+    Sub,
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Self::Eq => "=",
+            Self::NotEq => "!=",
+            Self::Lt => "<",
+            Self::LtEq => "<=",
+            Self::Gt => ">",
+            Self::GtEq => ">=",
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::Add => "+",
+            Self::Sub => "-",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Parses `input` into its `;` separated statements
+pub fn parse_statements(input: &str) -> Result<Vec<Statement>> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let mut statements = vec![];
+    loop {
+        while parser.consume(&Token::Semicolon) {}
+        if parser.peek().is_none() {
+            break;
+        }
+
+        statements.push(parser.statement()?);
+
+        if parser.peek().is_some() {
+            parser.expect(&Token::Semicolon, "';'")?;
+        }
+    }
+    Ok(statements)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An unquoted identifier or keyword
+    Ident(String),
+    /// A double quoted identifier
+    QuotedIdent(String),
+    /// A single quoted string
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Duration(i64),
+    Comma,
+    Dot,
+    LParen,
+    RParen,
+    Semicolon,
+    Star,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Plus,
+    Minus,
+}
+
+impl Token {
+    /// Returns true if this token is the (case insensitive) keyword
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Self::Ident(ident) if ident.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Splits `input` into tokens, recording the byte offset of each
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(pos, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            ',' => single(&mut chars, Token::Comma),
+            '.' => single(&mut chars, Token::Dot),
+            '(' => single(&mut chars, Token::LParen),
+            ')' => single(&mut chars, Token::RParen),
+            ';' => single(&mut chars, Token::Semicolon),
+            '*' => single(&mut chars, Token::Star),
+            '+' => single(&mut chars, Token::Plus),
+            '-' => single(&mut chars, Token::Minus),
+            '=' => single(&mut chars, Token::Eq),
+            '!' => {
+                chars.next();
+                match chars.next() {
+                    Some((_, '=')) => Token::NotEq,
+                    _ => {
+                        return Syntax {
+                            pos,
+                            message: "expected '!='",
+                        }
+                        .fail()
+                    }
+                }
+            }
+            '<' => {
+                chars.next();
+                match chars.peek() {
+                    Some((_, '=')) => single(&mut chars, Token::LtEq),
+                    Some((_, '>')) => single(&mut chars, Token::NotEq),
+                    _ => Token::Lt,
+                }
+            }
+            '>' => {
+                chars.next();
+                match chars.peek() {
+                    Some((_, '=')) => single(&mut chars, Token::GtEq),
+                    _ => Token::Gt,
+                }
+            }
+            '\'' => Token::String(quoted(&mut chars, pos, '\'')?),
+            '"' => Token::QuotedIdent(quoted(&mut chars, pos, '"')?),
+            c if c.is_ascii_digit() => number(input, &mut chars)?,
+            c if c.is_alphabetic() || c == '_' => {
+                let ident = take_while(input, &mut chars, |c| c.is_alphanumeric() || c == '_');
+                Token::Ident(ident.to_string())
+            }
+            _ => {
+                return Syntax {
+                    pos,
+                    message: format!("unexpected character '{}'", c),
+                }
+                .fail()
+            }
+        };
+        tokens.push((pos, token));
+    }
+
+    Ok(tokens)
+}
+
+fn single(chars: &mut Peekable<CharIndices<'_>>, token: Token) -> Token {
+    chars.next();
+    token
+}
+
+fn take_while<'a>(
+    input: &'a str,
+    chars: &mut Peekable<CharIndices<'_>>,
+    f: impl Fn(char) -> bool,
+) -> &'a str {
+    let start = chars
+        .peek()
+        .map(|(pos, _)| *pos)
+        .unwrap_or_else(|| input.len());
+    let mut end = start;
+    while let Some(&(pos, c)) = chars.peek() {
+        if !f(c) {
+            break;
+        }
+        end = pos + c.len_utf8();
+        chars.next();
+    }
+    &input[start..end]
+}
+
+/// Reads a quoted string or identifier, handling `\` escapes
+fn quoted(chars: &mut Peekable<CharIndices<'_>>, pos: usize, quote: char) -> Result<String> {
+    chars.next();
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some((_, '\\')) => match chars.next() {
+                Some((_, c)) => value.push(c),
+                None => break,
+            },
+            Some((_, c)) if c == quote => return Ok(value),
+            Some((_, c)) => value.push(c),
+            None => break,
+        }
+    }
+    Syntax {
+        pos,
+        message: format!("unterminated {}", quote),
+    }
+    .fail()
+}
+
+/// Reads an integer, float or duration literal
+fn number(input: &str, chars: &mut Peekable<CharIndices<'_>>) -> Result<Token> {
+    let digits = take_while(input, chars, |c| c.is_ascii_digit() || c == '.');
+    let unit = take_while(input, chars, |c| c.is_alphabetic());
+
+    if !unit.is_empty() {
+        let value: i64 = digits.parse().context(InvalidInteger { value: digits })?;
+        let multiplier = match unit {
+            "ns" => 1,
+            "u" | "µ" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60_000_000_000,
+            "h" => 3_600_000_000_000,
+            "d" => 86_400_000_000_000,
+            "w" => 604_800_000_000_000,
+            _ => {
+                return InvalidDuration {
+                    value: format!("{}{}", digits, unit),
+                }
+                .fail()
+            }
+        };
+        let nanos = value.checked_mul(multiplier).context(InvalidDuration {
+            value: format!("{}{}", digits, unit),
+        })?;
+        Ok(Token::Duration(nanos))
+    } else if digits.contains('.') {
+        Ok(Token::Float(
+            digits.parse().context(InvalidFloat { value: digits })?,
+        ))
+    } else {
+        Ok(Token::Integer(
+            digits.parse().context(InvalidInteger { value: digits })?,
+        ))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(_, t)| t.clone())
+            .context(UnexpectedEnd { expected })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it is `token`
+    fn consume(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the next token if it is the keyword `keyword`
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().map_or(false, |t| t.is_keyword(keyword)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, expected: &str) -> Result<()> {
+        ensure!(self.peek().is_some(), UnexpectedEnd { expected });
+        ensure!(self.consume(token), self.syntax_error(expected));
+        Ok(())
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        ensure!(self.peek().is_some(), UnexpectedEnd { expected: keyword });
+        ensure!(self.consume_keyword(keyword), self.syntax_error(keyword));
+        Ok(())
+    }
+
+    /// Returns a context selector for an error at the current token
+    fn syntax_error(&self, expected: &str) -> Syntax<usize, String> {
+        let pos = self
+            .tokens
+            .get(self.pos)
+            .map(|(pos, _)| *pos)
+            .unwrap_or_default();
+        let found = self
+            .peek()
+            .map(|t| format!("{:?}", t))
+            .unwrap_or_else(|| "end of statement".to_string());
+        Syntax {
+            pos,
+            message: format!("expected {}, found {}", expected, found),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement> {
+        if self.consume_keyword("SELECT") {
+            self.select().map(Statement::Select)
+        } else if self.consume_keyword("SHOW") {
+            self.show()
+        } else {
+            self.syntax_error("SELECT or SHOW").fail()
+        }
+    }
+
+    fn select(&mut self) -> Result<SelectStatement> {
+        let mut fields = vec![self.field()?];
+        while self.consume(&Token::Comma) {
+            fields.push(self.field()?);
+        }
+
+        self.expect_keyword("FROM")?;
+        let from = self.measurement()?;
+        let condition = self.condition()?;
+
+        let mut group_by = GroupBy::default();
+        if self.consume_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by = self.group_by()?;
+        }
+
+        let mut fill = Fill::default();
+        if self.consume_keyword("FILL") {
+            self.expect(&Token::LParen, "'('")?;
+            fill = match self.next("fill option")? {
+                Token::Ident(i) if i.eq_ignore_ascii_case("null") => Fill::Null,
+                Token::Ident(i) if i.eq_ignore_ascii_case("none") => Fill::None,
+                Token::Ident(i) if i.eq_ignore_ascii_case("previous") => Fill::Previous,
+                Token::Ident(i) if i.eq_ignore_ascii_case("linear") => Fill::Linear,
+                Token::Integer(i) => Fill::Integer(i),
+                Token::Float(f) => Fill::Float(f),
+                Token::Minus => match self.next("number")? {
+                    Token::Integer(i) => Fill::Integer(-i),
+                    Token::Float(f) => Fill::Float(-f),
+                    _ => {
+                        self.pos -= 1;
+                        return self.syntax_error("number").fail();
+                    }
+                },
+                _ => {
+                    self.pos -= 1;
+                    return self.syntax_error("fill option").fail();
+                }
+            };
+            self.expect(&Token::RParen, "')'")?;
+        }
+
+        let mut limit = None;
+        if self.consume_keyword("LIMIT") {
+            limit = match self.next("limit")? {
+                Token::Integer(i) if i >= 0 => Some(i as usize),
+                _ => {
+                    self.pos -= 1;
+                    return self.syntax_error("non negative integer").fail();
+                }
+            };
+        }
+
+        Ok(SelectStatement {
+            fields,
+            from,
+            condition,
+            group_by,
+            fill,
+            limit,
+        })
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let expr = if self.consume(&Token::Star) {
+            FieldExpr::Wildcard
+        } else {
+            let name = self.identifier()?;
+            if self.consume(&Token::LParen) {
+                let arg = if self.consume(&Token::Star) {
+                    "*".to_string()
+                } else {
+                    self.identifier()?
+                };
+                self.expect(&Token::RParen, "')'")?;
+                FieldExpr::Call {
+                    name: name.to_ascii_lowercase(),
+                    arg,
+                }
+            } else {
+                FieldExpr::Column(name)
+            }
+        };
+
+        let alias = if self.consume_keyword("AS") {
+            Some(self.identifier()?)
+        } else {
+            None
+        };
+
+        Ok(Field { expr, alias })
+    }
+
+    fn group_by(&mut self) -> Result<GroupBy> {
+        let mut group_by = GroupBy::default();
+        let mut tags = vec![];
+        loop {
+            if self.consume(&Token::Star) {
+                group_by.tags = GroupByTags::All;
+            } else if self.peek().map_or(false, |t| t.is_keyword("time"))
+                && self.tokens.get(self.pos + 1).map(|(_, t)| t) == Some(&Token::LParen)
+            {
+                self.pos += 2;
+                let start = self.pos;
+                let interval = self.duration()?;
+                if interval <= 0 {
+                    // an empty window would never advance
+                    self.pos = start;
+                    return self.syntax_error("positive duration").fail();
+                }
+                let offset = if self.consume(&Token::Comma) {
+                    self.duration()?
+                } else {
+                    0
+                };
+                self.expect(&Token::RParen, "')'")?;
+                group_by.time = Some((interval, offset));
+            } else {
+                tags.push(self.identifier()?);
+            }
+
+            if !self.consume(&Token::Comma) {
+                break;
+            }
+        }
+
+        if group_by.tags != GroupByTags::All {
+            group_by.tags = GroupByTags::Some(tags);
+        }
+        Ok(group_by)
+    }
+
+    fn duration(&mut self) -> Result<i64> {
+        let negative = self.consume(&Token::Minus);
+        match self.next("duration")? {
+            Token::Duration(d) if negative => Ok(-d),
+            Token::Duration(d) => Ok(d),
+            _ => {
+                self.pos -= 1;
+                self.syntax_error("duration").fail()
+            }
+        }
+    }
+
+    fn show(&mut self) -> Result<Statement> {
+        if self.consume_keyword("MEASUREMENTS") {
+            let condition = self.condition()?;
+            Ok(Statement::ShowMeasurements { condition })
+        } else if self.consume_keyword("TAG") {
+            if self.consume_keyword("KEYS") {
+                let from = self.from()?;
+                let condition = self.condition()?;
+                Ok(Statement::ShowTagKeys { from, condition })
+            } else {
+                self.expect_keyword("VALUES")?;
+                let from = self.from()?;
+                self.expect_keyword("WITH")?;
+                self.expect_keyword("KEY")?;
+                self.expect(&Token::Eq, "'='")?;
+                let key = self.identifier()?;
+                let condition = self.condition()?;
+                Ok(Statement::ShowTagValues {
+                    from,
+                    key,
+                    condition,
+                })
+            }
+        } else if self.consume_keyword("FIELD") {
+            self.expect_keyword("KEYS")?;
+            let from = self.from()?;
+            Ok(Statement::ShowFieldKeys { from })
+        } else {
+            self.syntax_error("MEASUREMENTS, TAG KEYS, TAG VALUES or FIELD KEYS")
+                .fail()
+        }
+    }
+
+    /// Parses an optional `FROM <measurement>` clause
+    fn from(&mut self) -> Result<Option<String>> {
+        if self.consume_keyword("FROM") {
+            self.measurement().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses an optional `WHERE <condition>` clause
+    fn condition(&mut self) -> Result<Option<Expr>> {
+        if self.consume_keyword("WHERE") {
+            self.expr().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses a measurement name, which may be qualified with the
+    /// database and retention policy (which are ignored)
+    fn measurement(&mut self) -> Result<String> {
+        let mut name = self.identifier()?;
+        while self.consume(&Token::Dot) {
+            name = self.identifier()?;
+        }
+        Ok(name)
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match self.next("identifier")? {
+            Token::Ident(ident) | Token::QuotedIdent(ident) => Ok(ident),
+            _ => {
+                self.pos -= 1;
+                self.syntax_error("identifier").fail()
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.binary_expr(0)
+    }
+
+    /// Parses binary expressions using precedence climbing, where the
+    /// operators with the lowest precedence are listed first
+    fn binary_expr(&mut self, precedence: usize) -> Result<Expr> {
+        let levels: [&[BinaryOp]; 4] = [
+            &[BinaryOp::Or],
+            &[BinaryOp::And],
+            &[
+                BinaryOp::Eq,
+                BinaryOp::NotEq,
+                BinaryOp::Lt,
+                BinaryOp::LtEq,
+                BinaryOp::Gt,
+                BinaryOp::GtEq,
+            ],
+            &[BinaryOp::Add, BinaryOp::Sub],
+        ];
+
+        if precedence == levels.len() {
+            return self.unary_expr();
+        }
+
+        let mut left = self.binary_expr(precedence + 1)?;
+        while let Some(op) = self.peek_binary_op() {
+            if !levels[precedence].contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary_expr(precedence + 1)?;
+            left = Expr::Binary {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn peek_binary_op(&self) -> Option<BinaryOp> {
+        let op = match self.peek()? {
+            Token::Eq => BinaryOp::Eq,
+            Token::NotEq => BinaryOp::NotEq,
+            Token::Lt => BinaryOp::Lt,
+            Token::LtEq => BinaryOp::LtEq,
+            Token::Gt => BinaryOp::Gt,
+            Token::GtEq => BinaryOp::GtEq,
+            Token::Plus => BinaryOp::Add,
+            Token::Minus => BinaryOp::Sub,
+            t if t.is_keyword("AND") => BinaryOp::And,
+            t if t.is_keyword("OR") => BinaryOp::Or,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn unary_expr(&mut self) -> Result<Expr> {
+        let expr = match self.next("expression")? {
+            Token::LParen => {
+                let expr = self.expr()?;
+                self.expect(&Token::RParen, "')'")?;
+                expr
+            }
+            Token::Minus => match self.next("number")? {
+                Token::Integer(i) => Expr::Integer(-i),
+                Token::Float(f) => Expr::Float(-f),
+                Token::Duration(d) => Expr::Duration(-d),
+                _ => {
+                    self.pos -= 1;
+                    return self.syntax_error("number").fail();
+                }
+            },
+            Token::Ident(i) if i.eq_ignore_ascii_case("now") && self.consume(&Token::LParen) => {
+                self.expect(&Token::RParen, "')'")?;
+                Expr::Now
+            }
+            Token::Ident(i) if i.eq_ignore_ascii_case("true") => Expr::Boolean(true),
+            Token::Ident(i) if i.eq_ignore_ascii_case("false") => Expr::Boolean(false),
+            Token::Ident(i) | Token::QuotedIdent(i) => Expr::Column(i),
+            Token::String(s) => Expr::String(s),
+            Token::Integer(i) => Expr::Integer(i),
+            Token::Float(f) => Expr::Float(f),
+            Token::Duration(d) => Expr::Duration(d),
+            _ => {
+                self.pos -= 1;
+                return self.syntax_error("expression").fail();
+            }
+        };
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(input: &str) -> Statement {
+        let mut statements = parse_statements(input).unwrap();
+        assert_eq!(statements.len(), 1, "{:?}", statements);
+        statements.pop().unwrap()
+    }
+
+    fn col(name: &str) -> Box<Expr> {
+        Box::new(Expr::Column(name.to_string()))
+    }
+
+    #[test]
+    fn test_select_raw() {
+        let statement = parse_one(r#"SELECT usage, "host" AS h FROM "telegraf"."autogen".cpu"#);
+        assert_eq!(
+            statement,
+            Statement::Select(SelectStatement {
+                fields: vec![
+                    Field {
+                        expr: FieldExpr::Column("usage".to_string()),
+                        alias: None
+                    },
+                    Field {
+                        expr: FieldExpr::Column("host".to_string()),
+                        alias: Some("h".to_string())
+                    },
+                ],
+                from: "cpu".to_string(),
+                condition: None,
+                group_by: GroupBy::default(),
+                fill: Fill::Null,
+                limit: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_aggregate() {
+        let statement = parse_one(
+            "select MEAN(usage), max(usage) as peak from cpu \
+             where host = 'a' and time > now() - 1h \
+             group by time(10m, 1m), region fill(0) limit 10",
+        );
+
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => panic!("expected select, got {:?}", statement),
+        };
+
+        assert_eq!(
+            select.fields,
+            vec![
+                Field {
+                    expr: FieldExpr::Call {
+                        name: "mean".to_string(),
+                        arg: "usage".to_string()
+                    },
+                    alias: None
+                },
+                Field {
+                    expr: FieldExpr::Call {
+                        name: "max".to_string(),
+                        arg: "usage".to_string()
+                    },
+                    alias: Some("peak".to_string())
+                },
+            ]
+        );
+        assert_eq!(
+            select.condition,
+            Some(Expr::Binary {
+                left: Box::new(Expr::Binary {
+                    left: col("host"),
+                    op: BinaryOp::Eq,
+                    right: Box::new(Expr::String("a".to_string())),
+                }),
+                op: BinaryOp::And,
+                right: Box::new(Expr::Binary {
+                    left: col("time"),
+                    op: BinaryOp::Gt,
+                    right: Box::new(Expr::Binary {
+                        left: Box::new(Expr::Now),
+                        op: BinaryOp::Sub,
+                        right: Box::new(Expr::Duration(3_600_000_000_000)),
+                    }),
+                }),
+            })
+        );
+        assert_eq!(
+            select.group_by,
+            GroupBy {
+                time: Some((600_000_000_000, 60_000_000_000)),
+                tags: GroupByTags::Some(vec!["region".to_string()]),
+            }
+        );
+        assert_eq!(select.fill, Fill::Integer(0));
+        assert_eq!(select.limit, Some(10));
+    }
+
+    #[test]
+    fn test_select_group_by_all() {
+        let statement = parse_one("SELECT count(*) FROM cpu GROUP BY *, time(1d) FILL(none)");
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => panic!("expected select, got {:?}", statement),
+        };
+        assert_eq!(
+            select.group_by,
+            GroupBy {
+                time: Some((86_400_000_000_000, 0)),
+                tags: GroupByTags::All,
+            }
+        );
+        assert_eq!(select.fill, Fill::None);
+    }
+
+    #[test]
+    fn test_show() {
+        assert_eq!(
+            parse_one("SHOW MEASUREMENTS"),
+            Statement::ShowMeasurements { condition: None }
+        );
+        assert_eq!(
+            parse_one("show tag keys from cpu"),
+            Statement::ShowTagKeys {
+                from: Some("cpu".to_string()),
+                condition: None
+            }
+        );
+        assert_eq!(
+            parse_one(r#"SHOW TAG VALUES FROM cpu WITH KEY = "host" WHERE region <> 'west'"#),
+            Statement::ShowTagValues {
+                from: Some("cpu".to_string()),
+                key: "host".to_string(),
+                condition: Some(Expr::Binary {
+                    left: col("region"),
+                    op: BinaryOp::NotEq,
+                    right: Box::new(Expr::String("west".to_string())),
+                }),
+            }
+        );
+        assert_eq!(
+            parse_one("SHOW FIELD KEYS"),
+            Statement::ShowFieldKeys { from: None }
+        );
+    }
+
+    #[test]
+    fn test_multiple_statements() {
+        let err = parse_statements("SHOW MEASUREMENTS; SELECT * FROM 'cpu;'; ").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing InfluxQL at position 33: expected identifier, found String(\"cpu;\")"
+        );
+
+        let statements = parse_statements("SHOW MEASUREMENTS; SELECT * FROM \"cpu;\";").unwrap();
+        assert_eq!(statements.len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse_statements("DROP MEASUREMENT cpu")
+                .unwrap_err()
+                .to_string(),
+            "error parsing InfluxQL at position 0: expected SELECT or SHOW, found Ident(\"DROP\")"
+        );
+        assert_eq!(
+            parse_statements("SELECT * FROM").unwrap_err().to_string(),
+            "error parsing InfluxQL: unexpected end of statement, expected identifier"
+        );
+        assert_eq!(
+            parse_statements("SELECT * FROM cpu GROUP BY time(10x)")
+                .unwrap_err()
+                .to_string(),
+            "error parsing InfluxQL: invalid duration '10x'"
+        );
+        assert_eq!(
+            parse_statements("SELECT count(*) FROM cpu GROUP BY time(0s)")
+                .unwrap_err()
+                .to_string(),
+            "error parsing InfluxQL at position 39: expected positive duration, found Duration(0)"
+        );
+        assert_eq!(
+            parse_statements("SELECT count(*) FROM cpu GROUP BY time(-1m)")
+                .unwrap_err()
+                .to_string(),
+            "error parsing InfluxQL at position 39: expected positive duration, found Minus"
+        );
+    }
+}
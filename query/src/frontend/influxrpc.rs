@@ -10,7 +10,7 @@ use arrow_deps::{
         logical_plan::{
             Expr, ExpressionVisitor, LogicalPlan, LogicalPlanBuilder, Operator, Recursion,
         },
-        prelude::col,
+        prelude::{col, lit},
     },
     util::AsExpr,
 };
@@ -33,7 +33,7 @@ use crate::{
         seriesset::{SeriesSetPlan, SeriesSetPlans},
         stringset::{Error as StringSetError, StringSetPlan, StringSetPlanBuilder},
     },
-    predicate::{Predicate, PredicateBuilder},
+    predicate::Predicate,
     provider::ProviderBuilder,
     util::schema_has_all_expr_columns,
     Database, PartitionChunk,
@@ -61,20 +61,6 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
-    #[snafu(display(
-        "gRPC planner got internal error making table_name with default predicate: {}",
-        source
-    ))]
-    InternalTableNamePlanForDefault {
-        source: Box<dyn std::error::Error + Send + Sync>,
-    },
-
-    #[snafu(display("gRPC planner could not get table_names with default predicate, which should always return values"))]
-    InternalTableNameCannotGetPlanForDefault {},
-
-    #[snafu(display("Unsupported predicate in gRPC table_names: {:?}", predicate))]
-    UnsupportedPredicateForTableNames { predicate: Predicate },
-
     #[snafu(display(
         "gRPC planner got error checking if chunk {} could pass predicate: {}",
         chunk_id,
@@ -201,24 +187,43 @@ impl InfluxRPCPlanner {
     {
         let mut builder = StringSetPlanBuilder::new();
 
+        // Key is table name, value is set of chunks which had data
+        // for that table but that we couldn't evaluate the predicate
+        // entirely using the metadata
+        let mut need_full_plans = BTreeMap::new();
+
         for chunk in self.filtered_chunks(database, &predicate)? {
             let new_table_names = chunk
                 .table_names(&predicate, builder.known_strings())
                 .map_err(|e| Box::new(e) as _)
                 .context(TableNamePlan)?;
 
-            builder = match new_table_names {
-                Some(new_table_names) => builder.append(new_table_names.into()),
+            match new_table_names {
+                Some(new_table_names) => builder = builder.append(new_table_names.into()),
                 None => {
-                    // TODO: General purpose plans for
-                    // table_names. For now, if we couldn't figure out
-                    // the table names from only metadata, generate an
-                    // error
-                    return UnsupportedPredicateForTableNames { predicate }.fail();
+                    // can't get table names only from metadata, need
+                    // a general purpose plan
+                    for table_name in Self::all_chunk_table_names(chunk.as_ref(), &predicate) {
+                        need_full_plans
+                            .entry(table_name)
+                            .or_insert_with(Vec::new)
+                            .push(Arc::clone(&chunk));
+                    }
                 }
             }
         }
 
+        for (table_name, chunks) in need_full_plans.into_iter() {
+            // no need to scan for a table already known to have rows
+            if builder.known_strings().contains(&table_name) {
+                continue;
+            }
+
+            if let Some(plan) = self.table_name_plan(&table_name, &predicate, chunks)? {
+                builder = builder.append(plan)
+            }
+        }
+
         let plan = builder.build().context(CreatingStringSet)?;
         Ok(plan)
     }
@@ -614,7 +619,7 @@ impl InfluxRPCPlanner {
     }
 
     /// Creates a map of table_name --> Chunks that have that table
-    fn group_chunks_by_table<C>(
+    pub(crate) fn group_chunks_by_table<C>(
         &self,
         predicate: &Predicate,
         chunks: Vec<Arc<C>>,
@@ -658,22 +663,75 @@ impl InfluxRPCPlanner {
             None => {
                 // couldn't find table names with predicate, get all chunk tables,
                 // fall back to filtering ourself
-                let table_name_predicate = if let Some(table_names) = &predicate.table_names {
-                    PredicateBuilder::new().tables(table_names).build()
-                } else {
-                    Predicate::default()
-                };
-                chunk
-                    .table_names(&table_name_predicate, &no_tables)
-                    .map_err(|e| Box::new(e) as _)
-                    .context(InternalTableNamePlanForDefault)?
-                    // unwrap the Option
-                    .context(InternalTableNameCannotGetPlanForDefault)?
+                Self::all_chunk_table_names(chunk, predicate)
             }
         };
         Ok(table_names)
     }
 
+    /// Find all the table names in the specified chunk that are not
+    /// excluded by the table restrictions of the predicate, regardless
+    /// of whether any of their rows pass it
+    fn all_chunk_table_names<C>(chunk: &C, predicate: &Predicate) -> BTreeSet<String>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let mut table_names = StringSet::new();
+        chunk.all_table_names(&mut table_names);
+        table_names
+            .into_iter()
+            .filter(|table_name| predicate.should_include_table(table_name))
+            .collect()
+    }
+
+    /// Creates a DataFusion LogicalPlan that returns the name of the
+    /// specified table if at least one of its rows in `chunks` passes
+    /// the predicate, or no rows otherwise
+    ///
+    /// returns `None` if the table contains no rows that would pass
+    /// the predicate.
+    ///
+    /// The created plan looks like:
+    ///
+    /// ```text
+    ///  Limit(1)
+    ///    Projection (the table name)
+    ///      Filter(predicate) [optional]
+    ///        Scan
+    /// ```
+    fn table_name_plan<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        chunks: Vec<Arc<C>>,
+    ) -> Result<Option<StringSetPlan>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let scan_and_filter = self.scan_and_filter(table_name, predicate, chunks)?;
+
+        let TableScanAndFilter {
+            plan_builder,
+            schema: _,
+        } = match scan_and_filter {
+            None => return Ok(None),
+            Some(t) => t,
+        };
+
+        let plan = plan_builder
+            .project(vec![lit(table_name).alias("table_name")])
+            .context(BuildingPlan)?
+            .limit(1)
+            .context(BuildingPlan)?
+            .build()
+            .context(BuildingPlan)?;
+
+        debug!(table_name=table_name, plan=%plan.display_indent_schema(),
+               "created table_name plan for table");
+
+        Ok(Some(plan.into()))
+    }
+
     /// Creates a DataFusion LogicalPlan that returns column *names* as a
     /// single column of Strings for a specific table
     ///
@@ -1093,7 +1151,7 @@ impl InfluxRPCPlanner {
     ///   Filter(predicate) [optional]
     ///     Scan
     /// ```
-    fn scan_and_filter<C>(
+    pub(crate) fn scan_and_filter<C>(
         &self,
         table_name: &str,
         predicate: &Predicate,
@@ -1169,7 +1227,7 @@ impl InfluxRPCPlanner {
 
     /// Returns a list of chunks across all partitions which may
     /// contain data that pass the predicate
-    fn filtered_chunks<D>(
+    pub(crate) fn filtered_chunks<D>(
         &self,
         database: &D,
         predicate: &Predicate,
@@ -1267,11 +1325,11 @@ impl ExpressionVisitor for SupportVisitor {
     }
 }
 
-struct TableScanAndFilter {
+pub(crate) struct TableScanAndFilter {
     /// Represents plan that scans a table and applies optional filtering
-    plan_builder: LogicalPlanBuilder,
+    pub(crate) plan_builder: LogicalPlanBuilder,
     /// The IOx schema of the result
-    schema: Schema,
+    pub(crate) schema: Schema,
 }
 
 /// Reorders tag_columns so that its prefix matches exactly
@@ -15,6 +15,15 @@ use crate::group_by::WindowDuration;
 // Reuse DataFusion error and Result types for this module
 pub use arrow_deps::datafusion::error::{DataFusionError as Error, Result};
 
+/// Which of the boundaries of a window to compute
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    /// The (inclusive) start of the window, as used by InfluxQL
+    Start,
+    /// The (exclusive) end of the window, as used by the gRPC API
+    Stop,
+}
+
 /// This is the implementation of the `window_bounds` user defined
 /// function used in IOx to compute window boundaries when doing
 /// grouping by windows.
@@ -22,6 +31,7 @@ fn window_bounds(
     args: &[ArrayRef],
     every: &WindowDuration,
     offset: &WindowDuration,
+    bound: Bound,
 ) -> Result<ArrayRef> {
     // Note:  At the time of writing, DataFusion creates arrays of constants for
     // constant arguments (which 4 of 5 arguments to window bounds are). We
@@ -50,8 +60,12 @@ fn window_bounds(
     // Note: the Go code uses the `Stop` field of the `GetEarliestBounds` call as
     // the window boundary https://github.com/influxdata/influxdb/blob/master/storage/reads/array_cursor.gen.go#L546
 
-    // Note window doesn't use the period argument
-    let period = internal::Duration::from_nsecs(0);
+    // Note the stop bound doesn't use the period argument, but the
+    // start is calculated as `stop - period`
+    let period = match bound {
+        Bound::Start => every.into(),
+        Bound::Stop => internal::Duration::from_nsecs(0),
+    };
     let window = internal::Window::new(every.into(), period, offset.into());
 
     // calculate the output times, one at a time, one element at a time
@@ -59,7 +73,10 @@ fn window_bounds(
     let values = time.iter().map(|ts| {
         ts.map(|ts| {
             let bounds = window.get_earliest_bounds(ts);
-            bounds.stop
+            match bound {
+                Bound::Start => bounds.start,
+                Bound::Stop => bounds.stop,
+            }
         })
     });
 
@@ -73,6 +90,28 @@ pub fn make_window_bound_expr(
     time_arg: Expr,
     every: &WindowDuration,
     offset: &WindowDuration,
+) -> Expr {
+    make_window_expr("window_bounds", time_arg, every, offset, Bound::Stop)
+}
+
+/// Create a DataFusion `Expr` that invokes `window_start` with the
+/// appropriate every and offset arguments at runtime. Unlike
+/// `window_bounds`, this computes the start of the window each
+/// timestamp falls in, which is how InfluxQL labels windows.
+pub fn make_window_start_expr(
+    time_arg: Expr,
+    every: &WindowDuration,
+    offset: &WindowDuration,
+) -> Expr {
+    make_window_expr("window_start", time_arg, every, offset, Bound::Start)
+}
+
+fn make_window_expr(
+    name: &str,
+    time_arg: Expr,
+    every: &WindowDuration,
+    offset: &WindowDuration,
+    bound: Bound,
 ) -> Expr {
     // Bind a copy of the arguments in a closure
     let every = every.clone();
@@ -80,10 +119,10 @@ pub fn make_window_bound_expr(
 
     // TODO provide optimized implementations (that took every/offset
     // as a constant rather than arrays)
-    let func_ptr = make_scalar_function(move |args| window_bounds(args, &every, &offset));
+    let func_ptr = make_scalar_function(move |args| window_bounds(args, &every, &offset, bound));
 
     let udf = create_udf(
-        "window_bounds",
+        name,
         vec![TIME_DATA_TYPE()],     // argument types
         Arc::new(TIME_DATA_TYPE()), // return type
         func_ptr,
@@ -109,8 +148,8 @@ mod tests {
         let every = WindowDuration::from_nanoseconds(200);
         let offset = WindowDuration::from_nanoseconds(50);
 
-        let bounds_array =
-            window_bounds(&[input], &every, &offset).expect("window_bounds executed correctly");
+        let bounds_array = window_bounds(&[input], &every, &offset, Bound::Stop)
+            .expect("window_bounds executed correctly");
 
         let expected_array: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
             vec![Some(250), None, Some(250), Some(450), Some(450)],
@@ -123,4 +162,29 @@ mod tests {
             expected_array, bounds_array,
         );
     }
+
+    #[test]
+    fn test_window_start() {
+        let input: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
+            vec![Some(100), None, Some(200), Some(250), Some(400)],
+            TIME_DATA_TIMEZONE(),
+        ));
+
+        let every = WindowDuration::from_nanoseconds(200);
+        let offset = WindowDuration::from_nanoseconds(50);
+
+        let bounds_array = window_bounds(&[input], &every, &offset, Bound::Start)
+            .expect("window_bounds executed correctly");
+
+        let expected_array: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
+            vec![Some(50), None, Some(50), Some(250), Some(250)],
+            TIME_DATA_TIMEZONE(),
+        ));
+
+        assert_eq!(
+            &expected_array, &bounds_array,
+            "Expected:\n{:?}\nActual:\n{:?}",
+            expected_array, bounds_array,
+        );
+    }
 }
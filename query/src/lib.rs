@@ -8,12 +8,14 @@
 
 use arrow_deps::datafusion::physical_plan::SendableRecordBatchStream;
 use async_trait::async_trait;
-use data_types::chunk::ChunkSummary;
+use data_types::{chunk::ChunkSummary, delete::DeletePredicate};
 use exec::{stringset::StringSet, Executor};
 use internal_types::{schema::Schema, selection::Selection};
+use snafu::Snafu;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, future::Future, sync::Arc, time::Duration};
 
+pub mod delete;
 pub mod exec;
 pub mod frontend;
 pub mod func;
@@ -48,6 +50,10 @@ pub trait Database: Debug + Send + Sync {
     fn chunks(&self, partition_key: &str) -> Vec<Arc<Self::Chunk>>;
 
     /// Return a summary of all chunks in this database, in all partitions
+    ///
+    /// The summaries describe the stored data of each chunk, so they
+    /// still count rows that have been deleted but not yet removed from
+    /// that data (see `PartitionChunk::delete_predicates`)
     fn chunk_summaries(&self) -> Result<Vec<ChunkSummary>, Self::Error>;
 }
 
@@ -72,12 +78,19 @@ pub trait PartitionChunk: Debug + Send + Sync {
     /// Returns true if this chunk contains data for the specified table
     fn has_table(&self, table_name: &str) -> bool;
 
+    /// Returns the delete predicates of this chunk. Rows of this chunk
+    /// that match any of them have been deleted and must be removed from
+    /// the data returned by `read_filter`
+    fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
+        &[]
+    }
+
     /// Returns all table names from this chunk that have at least one
     /// row that matches the `predicate` and are not already in `known_tables`.
     ///
     /// If the predicate cannot be evaluated (e.g it has predicates
-    /// that cannot be directly evaluated in the chunk), `None` is
-    /// returned.
+    /// that cannot be directly evaluated in the chunk, or rows of a
+    /// table have been deleted), `None` is returned.
     ///
     /// `known_tables` is a list of table names already known to be in
     /// other chunks from the same partition. It may be empty or
@@ -158,6 +171,10 @@ pub trait DatabaseStore: Debug + Send + Sync {
     /// The type of error this DataBase store generates
     type Error: std::error::Error + Send + Sync + 'static;
 
+    /// The type of query admitted by this DatabaseStore, see
+    /// [`DatabaseStore::start_query`]
+    type AdmittedQuery: AdmittedQuery;
+
     /// List the database names.
     fn db_names_sorted(&self) -> Vec<String>;
 
@@ -172,6 +189,36 @@ pub trait DatabaseStore: Debug + Send + Sync {
     /// Provide a query executor to use for running queries on
     /// databases in this `DatabaseStore`
     fn executor(&self) -> Arc<Executor>;
+
+    /// Admit `query` (e.g. its SQL text) of the database specified by
+    /// `name`, returning the admitted query to run it with, or an error
+    /// if the query is rejected by the limits of the database
+    fn start_query(&self, name: &str, query: &str) -> Result<Self::AdmittedQuery, Self::Error>;
+}
+
+/// A query admitted to run against a database by a [`DatabaseStore`]
+#[async_trait]
+pub trait AdmittedQuery: Debug + Send + 'static {
+    /// Runs `query` until it completes, or returns an error if the
+    /// query is cancelled or times out first, in which case `query` is
+    /// dropped.
+    ///
+    /// Dropping the returned future also drops `query`, so any plans
+    /// it runs on the [`Executor`] are cancelled.
+    async fn run<F>(self, query: F) -> Result<F::Output, QueryError>
+    where
+        F: Future + Send,
+        F::Output: Send;
+}
+
+/// Why an admitted query did not complete
+#[derive(Debug, Snafu)]
+pub enum QueryError {
+    #[snafu(display("query cancelled"))]
+    Cancelled,
+
+    #[snafu(display("query timed out after {:?}", timeout))]
+    TimedOut { timeout: Duration },
 }
 
 // Note: I would like to compile this module only in the 'test' cfg,
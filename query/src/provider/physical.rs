@@ -11,7 +11,11 @@ use arrow_deps::{
 };
 use internal_types::{schema::Schema, selection::Selection};
 
-use crate::{predicate::Predicate, PartitionChunk};
+use crate::{
+    delete::{delete_columns, DeleteFilterStream},
+    predicate::Predicate,
+    PartitionChunk,
+};
 
 use async_trait::async_trait;
 
@@ -100,7 +104,23 @@ impl<C: PartitionChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
         // available, and use SchemaAdapterStream to pad the rest of
         // the columns with NULLs if necessary
         let selection_cols = restrict_selection(selection_cols, &chunk_table_schema);
-        let selection = Selection::Some(&selection_cols);
+
+        // Rows deleted from this chunk are removed from its data, which
+        // may require reading columns that are not part of the output
+        let delete_predicates: Vec<_> = chunk
+            .delete_predicates()
+            .iter()
+            .filter(|predicate| predicate.table_name == *self.table_name)
+            .cloned()
+            .collect();
+        let mut read_cols = selection_cols.clone();
+        for column in delete_columns(&delete_predicates) {
+            if !read_cols.contains(&column) {
+                read_cols.push(column);
+            }
+        }
+        let read_cols = restrict_selection(read_cols, &chunk_table_schema);
+        let selection = Selection::Some(&read_cols);
 
         let stream = chunk
             .read_filter(&self.table_name, &self.predicate, selection)
@@ -113,6 +133,16 @@ impl<C: PartitionChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
                 ))
             })?;
 
+        let stream: SendableRecordBatchStream = if delete_predicates.is_empty() {
+            stream
+        } else {
+            Box::pin(DeleteFilterStream::new(
+                stream,
+                delete_predicates,
+                &selection_cols,
+            ))
+        };
+
         let adapter = SchemaAdapterStream::try_new(stream, Arc::clone(&self.schema))
             .map_err(|e| DataFusionError::Internal(e.to_string()))?;
 
@@ -15,7 +15,7 @@ use arrow_deps::{
 use crate::exec::Executor;
 use crate::{
     exec::stringset::{StringSet, StringSetRef},
-    Database, DatabaseStore, PartitionChunk, Predicate,
+    AdmittedQuery, Database, DatabaseStore, PartitionChunk, Predicate, QueryError,
 };
 
 use internal_types::{
@@ -29,7 +29,7 @@ use internal_types::{
 use async_trait::async_trait;
 use parking_lot::Mutex;
 use snafu::{OptionExt, Snafu};
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, future::Future, sync::Arc};
 
 #[derive(Debug, Default)]
 pub struct TestDatabase {
@@ -422,6 +422,7 @@ impl Default for TestDatabaseStore {
 impl DatabaseStore for TestDatabaseStore {
     type Database = TestDatabase;
     type Error = TestError;
+    type AdmittedQuery = TestQuery;
 
     /// List the database names.
     fn db_names_sorted(&self) -> Vec<String> {
@@ -454,4 +455,24 @@ impl DatabaseStore for TestDatabaseStore {
     fn executor(&self) -> Arc<Executor> {
         Arc::clone(&self.executor)
     }
+
+    fn start_query(&self, _name: &str, _query: &str) -> Result<Self::AdmittedQuery, Self::Error> {
+        Ok(TestQuery {})
+    }
+}
+
+/// A query admitted by [`TestDatabaseStore`], which always runs to
+/// completion
+#[derive(Debug)]
+pub struct TestQuery {}
+
+#[async_trait]
+impl AdmittedQuery for TestQuery {
+    async fn run<F>(self, query: F) -> Result<F::Output, QueryError>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        Ok(query.await)
+    }
 }
@@ -885,6 +885,7 @@ mod test {
                         min: false,
                         max: true,
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -893,6 +894,7 @@ mod test {
                         min: 1000,
                         max: 5000,
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -901,6 +903,7 @@ mod test {
                         min: "dev".into(),
                         max: "prod".into(),
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -909,6 +912,7 @@ mod test {
                         min: -1000,
                         max: 4000,
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -917,6 +921,7 @@ mod test {
                         min: "msg a".into(),
                         max: "msg b".into(),
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -925,6 +930,7 @@ mod test {
                         min: 10.0,
                         max: 30000.0,
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
                 ColumnSummary {
@@ -933,6 +939,7 @@ mod test {
                         min: 3333,
                         max: 11111111,
                         count: 3,
+                        distinct_count: None,
                     }),
                 },
             ],
@@ -660,6 +660,7 @@ impl MetaData {
                             min: min.to_string(),
                             max: max.to_string(),
                             count,
+                            distinct_count: None,
                         })
                     }
                     (OwnedValue::Boolean(min), OwnedValue::Boolean(max)) => {
@@ -667,6 +668,7 @@ impl MetaData {
                             min: *min,
                             max: *max,
                             count,
+                            distinct_count: None,
                         })
                     }
                     (OwnedValue::Scalar(min), OwnedValue::Scalar(max)) => match (min, max) {
@@ -674,16 +676,19 @@ impl MetaData {
                             min: *min,
                             max: *max,
                             count,
+                            distinct_count: None,
                         }),
                         (Scalar::U64(min), Scalar::U64(max)) => Statistics::U64(StatValues {
                             min: *min,
                             max: *max,
                             count,
+                            distinct_count: None,
                         }),
                         (Scalar::F64(min), Scalar::F64(max)) => Statistics::F64(StatValues {
                             min: *min,
                             max: *max,
                             count,
+                            distinct_count: None,
                         }),
                         _ => panic!(
                             "unsupported type scalar stats in read buffer: {:?}, {:?}",
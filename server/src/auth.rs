@@ -0,0 +1,404 @@
+//! API tokens authenticating requests to the server.
+//!
+//! Authentication is enabled by configuring an admin token, which grants
+//! admin access to all databases. Further tokens are created, listed and
+//! revoked through the management API and are stored in the object store
+//! next to the database rules, at `<writer_id>/tokens.pb`. They are loaded
+//! when the writer id is set or restored from the object store.
+//!
+//! Only the SHA-256 hashes of the secrets are kept, both in memory and in the
+//! object store, and they are compared in constant time.
+use std::num::NonZeroU32;
+
+use bytes::BytesMut;
+use sha2::{Digest, Sha256};
+use snafu::{OptionExt, ResultExt, Snafu};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use data_types::auth::{decode_tokens, encode_tokens, ApiToken, Permission, Scope};
+use object_store::{path::ObjectStorePath, ObjectStoreApi};
+
+use crate::{
+    get_store_bytes, ConnectionManager, ErrorDeserializingTokens, ErrorSerializingTokens, Result,
+    Server, StoreError, TokenNotFound,
+};
+
+/// The name of the file the API tokens are stored in
+pub(crate) const TOKENS_FILE_NAME: &str = "tokens.pb";
+
+/// Errors authorizing a request
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("missing or invalid API token"))]
+    Unauthenticated,
+
+    #[snafu(display("API token does not grant {:?} access to {}", scope, resource))]
+    PermissionDenied { scope: Scope, resource: String },
+}
+
+impl<M: ConnectionManager> Server<M> {
+    /// Returns true if requests must be authenticated with an API token
+    pub fn auth_enabled(&self) -> bool {
+        self.admin_token.is_some()
+    }
+
+    /// Checks that `token` is a valid API token
+    pub fn authenticate(&self, token: Option<&str>) -> Result<(), Error> {
+        self.check_token(token, |_| true).map(|_| ())
+    }
+
+    /// Checks that `token` grants the operations of `scope` on the database
+    /// `db_name`, or on the server if `db_name` is `None`
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        db_name: Option<&str>,
+        scope: Scope,
+    ) -> Result<(), Error> {
+        if self.check_token(token, |api_token| api_token.allows(db_name, scope))? {
+            return Ok(());
+        }
+
+        PermissionDenied {
+            scope,
+            resource: match db_name {
+                Some(db_name) => format!("database {}", db_name),
+                None => "the server".to_string(),
+            },
+        }
+        .fail()
+    }
+
+    /// Returns whether `token` passes `allows`, or an error if it is not a
+    /// valid token. All requests are allowed if authentication is disabled
+    fn check_token(
+        &self,
+        token: Option<&str>,
+        allows: impl Fn(&ApiToken) -> bool,
+    ) -> Result<bool, Error> {
+        let admin_token = match &self.admin_token {
+            Some(admin_token) => admin_token,
+            None => return Ok(true),
+        };
+
+        let token = hash_token(token.context(Unauthenticated)?);
+        if hashes_equal(&token, admin_token) {
+            return Ok(true);
+        }
+
+        let tokens = self.tokens.read();
+        let api_token = tokens
+            .iter()
+            .find(|api_token| hashes_equal(&api_token.token, &token))
+            .context(Unauthenticated)?;
+
+        Ok(allows(api_token))
+    }
+
+    /// Creates a new API token granting `permissions`, returning it
+    /// including its secret
+    pub async fn create_token(
+        &self,
+        description: impl Into<String>,
+        permissions: Vec<Permission>,
+    ) -> Result<ApiToken> {
+        // Tokens are stored below the server id
+        self.require_id()?;
+
+        let secret = Uuid::new_v4().to_simple().to_string();
+        let token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            token: hash_token(&secret),
+            description: description.into(),
+            permissions,
+        };
+
+        let tokens = {
+            let mut tokens = self.tokens.write();
+            tokens.push(token.clone());
+            tokens.clone()
+        };
+        self.persist_tokens(tokens).await?;
+
+        Ok(ApiToken {
+            token: secret,
+            ..token
+        })
+    }
+
+    /// Returns the API tokens, without their secrets
+    pub fn list_tokens(&self) -> Vec<ApiToken> {
+        self.tokens
+            .read()
+            .iter()
+            .map(|token| ApiToken {
+                token: String::new(),
+                ..token.clone()
+            })
+            .collect()
+    }
+
+    /// Revokes the API token with the given `id`
+    pub async fn revoke_token(&self, id: &str) -> Result<()> {
+        self.require_id()?;
+
+        let tokens = {
+            let mut tokens = self.tokens.write();
+            let len = tokens.len();
+            tokens.retain(|token| token.id != id);
+            if tokens.len() == len {
+                return TokenNotFound { id }.fail();
+            }
+            tokens.clone()
+        };
+        self.persist_tokens(tokens).await
+    }
+
+    /// Loads the API tokens from the object store, replacing any tokens
+    /// already loaded
+    pub async fn load_tokens(&self) -> Result<()> {
+        if let Some(tokens) = self.stored_tokens(self.require_id()?).await? {
+            *self.tokens.write() = tokens;
+        }
+        Ok(())
+    }
+
+    /// Returns the API tokens stored for the writer `id`, if any. They are
+    /// loaded before the id is set, so that tokens created once the id is
+    /// known never overwrite the stored ones
+    pub(crate) async fn stored_tokens(&self, id: NonZeroU32) -> Result<Option<Vec<ApiToken>>> {
+        let location = self.tokens_path_of(id);
+
+        // Only servers that have created tokens have a tokens file
+        let mut prefix = self.store.new_path();
+        prefix.push_dir(format!("{}", id));
+        let list_result = self
+            .store
+            .list_with_delimiter(&prefix)
+            .await
+            .context(StoreError)?;
+        if !list_result
+            .objects
+            .iter()
+            .any(|object| object.location == location)
+        {
+            return Ok(None);
+        }
+
+        let bytes = get_store_bytes(&location, &self.store).await?;
+        let tokens = decode_tokens(bytes.freeze()).context(ErrorDeserializingTokens)?;
+        Ok(Some(tokens))
+    }
+
+    async fn persist_tokens(&self, tokens: Vec<ApiToken>) -> Result<()> {
+        let location = self.tokens_path()?;
+
+        let mut data = BytesMut::new();
+        encode_tokens(tokens, &mut data).context(ErrorSerializingTokens)?;
+
+        let len = data.len();
+
+        let stream_data = std::io::Result::Ok(data.freeze());
+        self.store
+            .put(
+                &location,
+                futures::stream::once(async move { stream_data }),
+                Some(len),
+            )
+            .await
+            .context(StoreError)?;
+        Ok(())
+    }
+
+    fn tokens_path(&self) -> Result<object_store::path::Path> {
+        Ok(self.tokens_path_of(self.require_id()?))
+    }
+
+    fn tokens_path_of(&self, id: NonZeroU32) -> object_store::path::Path {
+        let mut path = self.store.new_path();
+        path.push_dir(format!("{}", id));
+        path.set_file_name(TOKENS_FILE_NAME);
+        path
+    }
+}
+
+/// Returns the hex encoded SHA-256 hash of the token `secret`, which is
+/// what is kept of it
+pub(crate) fn hash_token(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Compares two token hashes without leaking where they differ
+fn hashes_equal(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectionManagerImpl, ServerConfig};
+    use data_types::DatabaseName;
+    use object_store::{memory::InMemory, ObjectStore};
+    use std::{num::NonZeroU32, sync::Arc};
+
+    async fn make_server(store: Arc<ObjectStore>) -> Server<ConnectionManagerImpl> {
+        let config = ServerConfig::new(store)
+            .with_num_worker_threads(1)
+            .with_admin_token("admin");
+        let server = Server::new(ConnectionManagerImpl {}, config);
+
+        // The writer id is restored if the store has been used before
+        server.load_database_configs().await.unwrap();
+        if server.require_id().is_err() {
+            server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn auth_disabled() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let config = ServerConfig::new(store).with_num_worker_threads(1);
+        let server = Server::new(ConnectionManagerImpl {}, config);
+
+        assert!(!server.auth_enabled());
+        server.authenticate(None).unwrap();
+        server.authorize(None, None, Scope::Admin).unwrap();
+    }
+
+    #[tokio::test]
+    async fn authorize() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = make_server(store).await;
+        assert!(server.auth_enabled());
+
+        let token = server
+            .create_token(
+                "writer",
+                vec![Permission {
+                    db_name: Some(DatabaseName::new("db1").unwrap()),
+                    scope: Scope::Write,
+                }],
+            )
+            .await
+            .unwrap();
+
+        server.authenticate(Some("admin")).unwrap();
+        server.authorize(Some("admin"), None, Scope::Admin).unwrap();
+
+        let secret = Some(token.token.as_str());
+        server.authenticate(secret).unwrap();
+        server.authorize(secret, Some("db1"), Scope::Write).unwrap();
+
+        let err = server
+            .authorize(secret, Some("db1"), Scope::Admin)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "API token does not grant Admin access to database db1"
+        );
+        let err = server
+            .authorize(secret, Some("db2"), Scope::Read)
+            .unwrap_err();
+        assert!(matches!(err, Error::PermissionDenied { .. }));
+        let err = server.authorize(secret, None, Scope::Read).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "API token does not grant Read access to the server"
+        );
+
+        let err = server.authenticate(None).unwrap_err();
+        assert!(matches!(err, Error::Unauthenticated));
+        let err = server.authenticate(Some("invalid")).unwrap_err();
+        assert!(matches!(err, Error::Unauthenticated));
+
+        server.revoke_token(&token.id).await.unwrap();
+        let err = server.authenticate(secret).unwrap_err();
+        assert!(matches!(err, Error::Unauthenticated));
+
+        let err = server.revoke_token(&token.id).await.unwrap_err();
+        assert!(matches!(err, crate::Error::TokenNotFound { .. }));
+    }
+
+    #[tokio::test]
+    async fn tokens_are_persisted() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = make_server(Arc::clone(&store)).await;
+
+        // Nothing to load yet
+        server.load_tokens().await.unwrap();
+        assert!(server.list_tokens().is_empty());
+
+        let token1 = server.create_token("token1", vec![]).await.unwrap();
+        let token2 = server
+            .create_token(
+                "token2",
+                vec![Permission {
+                    db_name: None,
+                    scope: Scope::Read,
+                }],
+            )
+            .await
+            .unwrap();
+        server.revoke_token(&token1.id).await.unwrap();
+
+        let server2 = make_server(Arc::clone(&store)).await;
+        server2.load_tokens().await.unwrap();
+
+        let tokens = server2.list_tokens();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].id, token2.id);
+        assert_eq!(tokens[0].description, "token2");
+        assert_eq!(tokens[0].permissions, token2.permissions);
+        // Secrets are not listed
+        assert!(tokens[0].token.is_empty());
+
+        // Only the hashes of the secrets are stored
+        let mut location = store.new_path();
+        location.push_dir("1");
+        location.set_file_name(TOKENS_FILE_NAME);
+        let bytes = get_store_bytes(&location, &store).await.unwrap();
+        let stored = decode_tokens(bytes.freeze()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].token, hash_token(&token2.token));
+        assert_ne!(stored[0].token, token2.token);
+
+        server2
+            .authorize(Some(&token2.token), Some("db1"), Scope::Read)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn tokens_are_loaded_when_the_id_is_set() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = make_server(Arc::clone(&store)).await;
+        let token1 = server.create_token("token1", vec![]).await.unwrap();
+
+        // The id of a fresh server is set through the management API
+        let config = ServerConfig::new(Arc::clone(&store))
+            .with_num_worker_threads(1)
+            .with_admin_token("admin");
+        let server2 = Server::new(ConnectionManagerImpl {}, config);
+        server2.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+        let token2 = server2.create_token("token2", vec![]).await.unwrap();
+
+        let tokens = server2.list_tokens();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].id, token1.id);
+        assert_eq!(tokens[1].id, token2.id);
+
+        // Creating the token did not overwrite the stored one
+        let mut location = store.new_path();
+        location.push_dir("1");
+        location.set_file_name(TOKENS_FILE_NAME);
+        let bytes = get_store_bytes(&location, &store).await.unwrap();
+        let stored = decode_tokens(bytes.freeze()).unwrap();
+        assert_eq!(stored.len(), 2);
+
+        server2.authenticate(Some(&token1.token)).unwrap();
+    }
+}
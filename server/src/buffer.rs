@@ -374,8 +374,9 @@ impl Segment {
         self.persisted.lock().clone()
     }
 
-    /// Spawns a tokio task that will continuously try to persist the bytes to
-    /// the given object store location.
+    /// Spawns a tokio task that will try to persist the bytes to the given
+    /// object store location. Transient errors are retried by the store; if
+    /// it gives up, the error is logged and the segment is not persisted.
     pub fn persist_bytes_in_background(
         &self,
         tracker: TaskRegistration,
@@ -386,13 +387,14 @@ impl Segment {
         let data = self.to_file_bytes(writer_id)?;
         let location = database_object_store_path(writer_id, db_name, &store);
         let location = object_store_path_for_segment(&location, self.id)?;
+        let segment_id = self.id;
 
         let len = data.len();
-        let mut stream_data = std::io::Result::Ok(data.clone());
+        let stream_data = std::io::Result::Ok(data);
 
         tokio::task::spawn(
             async move {
-                while let Err(err) = store
+                if let Err(err) = store
                     .put(
                         &location,
                         futures::stream::once(async move { stream_data }),
@@ -400,12 +402,8 @@ impl Segment {
                     )
                     .await
                 {
-                    error!("error writing bytes to store: {}", err);
-                    tokio::time::sleep(tokio::time::Duration::from_secs(
-                        super::STORE_ERROR_PAUSE_SECONDS,
-                    ))
-                    .await;
-                    stream_data = std::io::Result::Ok(data.clone());
+                    error!(segment_id, "error writing segment to store: {}", err);
+                    return;
                 }
 
                 // TODO: Mark segment as persisted
@@ -19,6 +19,11 @@ use tokio_util::sync::CancellationToken;
 
 pub(crate) const DB_RULES_FILE_NAME: &str = "rules.pb";
 
+/// The name of the file the writer id and remotes of the server are stored
+/// in. It is stored at the root of the object store, as the writer id is
+/// needed to locate everything else
+pub(crate) const SERVER_CONFIG_FILE_NAME: &str = "config.pb";
+
 /// The Config tracks the configuration of databases and their rules along
 /// with host groups for replication. It is used as an in-memory structure
 /// that can be loaded incrementally from object storage.
@@ -69,6 +74,33 @@ impl Config {
         })
     }
 
+    /// Removes the database `name` from the config and cancels its background
+    /// worker.
+    ///
+    /// The name remains reserved until the returned handle is dropped, so
+    /// that no database with the same name can be created while the removed
+    /// database is being torn down.
+    pub(crate) fn remove_db(
+        &self,
+        name: &DatabaseName<'static>,
+    ) -> Result<RemoveDatabaseHandle<'_>> {
+        let mut state = self.state.write().expect("mutex poisoned");
+        let mut db_state = state
+            .databases
+            .remove(name)
+            .ok_or_else(|| Error::DatabaseNotFound {
+                db_name: name.to_string(),
+            })?;
+        state.reservations.insert(name.clone());
+
+        db_state.shutdown.cancel();
+        Ok(RemoveDatabaseHandle {
+            name: name.clone(),
+            handle: db_state.join(),
+            config: &self,
+        })
+    }
+
     pub(crate) fn db(&self, name: &DatabaseName<'_>) -> Option<Arc<Db>> {
         let state = self.state.read().expect("mutex poisoned");
         state.databases.get(name).map(|x| Arc::clone(&x.db))
@@ -105,14 +137,14 @@ impl Config {
         state.remotes.iter().map(|(&a, b)| (a, b.clone())).collect()
     }
 
-    pub(crate) fn update_remote(&self, id: WriterId, addr: GRPCConnectionString) {
-        let mut state = self.state.write().expect("mutex poisoned");
-        state.remotes.insert(id, addr);
+    pub(crate) fn remotes(&self) -> BTreeMap<WriterId, GRPCConnectionString> {
+        let state = self.state.read().expect("mutex poisoned");
+        state.remotes.clone()
     }
 
-    pub(crate) fn delete_remote(&self, id: WriterId) -> Option<GRPCConnectionString> {
+    pub(crate) fn set_remotes(&self, remotes: BTreeMap<WriterId, GRPCConnectionString>) {
         let mut state = self.state.write().expect("mutex poisoned");
-        state.remotes.remove(&id)
+        state.remotes = remotes;
     }
 
     fn commit(
@@ -284,6 +316,31 @@ impl<'a> Drop for CreateDatabaseHandle<'a> {
     }
 }
 
+/// RemoveDatabaseHandle is returned when a call is made to `remove_db` on
+/// the Config struct. It holds a reservation for the name of the removed
+/// database, which is released when the handle is dropped.
+#[derive(Debug)]
+pub(crate) struct RemoveDatabaseHandle<'a> {
+    name: DatabaseName<'static>,
+    handle: Option<JoinHandle<()>>,
+    config: &'a Config,
+}
+
+impl<'a> RemoveDatabaseHandle<'a> {
+    /// Waits for the background worker of the removed database to stop
+    pub(crate) async fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl<'a> Drop for RemoveDatabaseHandle<'a> {
+    fn drop(&mut self) {
+        self.config.rollback(&self.name)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use object_store::{memory::InMemory, ObjectStore, ObjectStoreApi};
@@ -323,6 +380,38 @@ mod test {
         config.drain().await
     }
 
+    #[tokio::test]
+    async fn remove_db() {
+        let name = DatabaseName::new("foo").unwrap();
+        let config = Config::new(Arc::new(JobRegistry::new()));
+        let rules = DatabaseRules::new(name.clone());
+
+        let err = config.remove_db(&name).unwrap_err();
+        assert!(matches!(err, Error::DatabaseNotFound { .. }));
+
+        let db_reservation = config.create_db(rules.clone()).unwrap();
+        let server_id = NonZeroU32::new(1).unwrap();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let exec = Arc::new(Executor::new(1));
+        db_reservation.commit(server_id, store, exec);
+
+        {
+            let mut handle = config.remove_db(&name).unwrap();
+            assert!(config.db(&name).is_none());
+            assert!(config.db_names_sorted().is_empty());
+
+            // the background worker is stopped
+            handle.join().await;
+
+            // the name cannot be reused until the handle is dropped
+            let err = config.create_db(rules.clone()).unwrap_err();
+            assert!(matches!(err, Error::DatabaseAlreadyExists { .. }));
+        }
+
+        config.create_db(rules).unwrap();
+        config.drain().await
+    }
+
     #[tokio::test]
     async fn test_db_drop() {
         let name = DatabaseName::new("foo").unwrap();
@@ -3,8 +3,9 @@
 
 use std::any::Any;
 use std::{
+    collections::BTreeMap,
     convert::TryInto,
-    num::NonZeroU32,
+    num::{NonZeroU32, NonZeroU64},
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
@@ -12,7 +13,8 @@ use std::{
 };
 
 use async_trait::async_trait;
-use observability_deps::tracing::{debug, info};
+use chrono::Utc;
+use observability_deps::tracing::{debug, info, warn};
 use parking_lot::{Mutex, RwLock};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
@@ -27,17 +29,29 @@ use arrow_deps::{
 use catalog::{chunk::ChunkState, Catalog};
 pub(crate) use chunk::DBChunk;
 use data_types::{
-    chunk::ChunkSummary, database_rules::DatabaseRules, partition_metadata::PartitionSummary,
-    timestamp::TimestampRange,
+    chunk::ChunkSummary, database_rules::DatabaseRules, delete::DeletePredicate,
+    partition_metadata::PartitionSummary, timestamp::TimestampRange,
 };
 use internal_types::selection::Selection;
 use object_store::ObjectStore;
-use parquet_file::{chunk::Chunk, storage::Storage};
+use parquet_file::{
+    catalog::{
+        Action, ChunkMetadata, PreservedCatalog, SequenceRange, TableMetadata,
+        DEFAULT_CHECKPOINT_INTERVAL,
+    },
+    chunk::Chunk,
+    metadata::IoxMetadata,
+    storage::Storage,
+};
 use query::{exec::Executor, Database, DEFAULT_SCHEMA};
 use read_buffer::Chunk as ReadBufferChunk;
 use tracker::{MemRegistry, TaskTracker, TrackedFutureExt};
 
-use super::{buffer::Buffer, JobRegistry};
+use super::{
+    buffer::Buffer,
+    limits::{self, Limiter},
+    JobRegistry,
+};
 use data_types::job::Job;
 
 use data_types::partition_metadata::TableSummary;
@@ -45,12 +59,21 @@ use internal_types::entry::{self, ClockValue, Entry, SequencedEntry};
 use lifecycle::LifecycleManager;
 use system_tables::{SystemSchemaProvider, SYSTEM_SCHEMA};
 
+pub use backup::Backup;
+pub use tracked_query::TrackedQuery;
+
+mod backup;
 pub mod catalog;
 mod chunk;
+mod compact;
+mod garbage;
 mod lifecycle;
 pub mod pred;
+mod retention;
+mod sort;
 mod streams;
 mod system_tables;
+mod tracked_query;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -137,6 +160,144 @@ pub enum Error {
         source: parquet_file::storage::Error,
     },
 
+    #[snafu(display("Error committing preserved catalog transaction: {}", source))]
+    CommittingCatalogTransaction {
+        source: parquet_file::catalog::Error,
+    },
+
+    #[snafu(display("Error reading preserved catalog: {}", source))]
+    ReadingPreservedCatalog {
+        source: parquet_file::catalog::Error,
+    },
+
+    #[snafu(display("Chunk {}:{} has already been persisted", partition_key, chunk_id))]
+    ChunkAlreadyPersisted {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display("Error listing object store: {}", source))]
+    ListingObjectStore { source: object_store::Error },
+
+    #[snafu(display("Error reading metadata of {} from object store: {}", path, source))]
+    ReadingObjectMetadata {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error deleting orphaned file {} from object store: {}", path, source))]
+    DeletingOrphanedFile {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error deleting expired file {} from object store: {}", path, source))]
+    DeletingExpiredFile {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Can not compact chunks of partition {}: {}", partition_key, source))]
+    CompactingChunks {
+        partition_key: String,
+        source: catalog::Error,
+    },
+
+    #[snafu(display(
+        "Can not compact chunk {} {} which is {}. Only persisted chunks can be compacted",
+        partition_key,
+        chunk_id,
+        chunk_state
+    ))]
+    CompactingUnpersistedChunk {
+        partition_key: String,
+        chunk_id: u32,
+        chunk_state: String,
+    },
+
+    #[snafu(display("No chunks of partition {} given to compact", partition_key))]
+    NoChunksToCompact { partition_key: String },
+
+    #[snafu(display("Can not merge schemas of table {}: {}", table_name, source))]
+    MergingChunkSchemas {
+        table_name: String,
+        source: internal_types::schema::Error,
+    },
+
+    #[snafu(display("Can not merge data of table {}: {}", table_name, source))]
+    MergingChunkData {
+        table_name: String,
+        source: arrow_deps::arrow::error::ArrowError,
+    },
+
+    #[snafu(display("Error sorting data of table {}: {}", table_name, source))]
+    SortingTableData {
+        table_name: String,
+        source: arrow_deps::arrow::error::ArrowError,
+    },
+
+    #[snafu(display("Error removing deleted rows of table {}: {}", table_name, source))]
+    FilteringDeletedRows {
+        table_name: String,
+        source: arrow_deps::arrow::error::ArrowError,
+    },
+
+    #[snafu(display("Can not write backup to a location that is not empty"))]
+    BackupTargetNotEmpty,
+
+    #[snafu(display(
+        "Can not back up database, {} chunks have not been persisted yet",
+        num_chunks
+    ))]
+    BackupUnpersistedChunks { num_chunks: usize },
+
+    #[snafu(display("Error writing backup: {}", source))]
+    WritingBackup { source: object_store::Error },
+
+    #[snafu(display("Error reading backup: {}", source))]
+    ReadingBackup { source: object_store::Error },
+
+    #[snafu(display("Invalid backup: {}", description))]
+    InvalidBackup { description: String },
+
+    #[snafu(display("Error encoding rules of backup: {}", source))]
+    EncodingBackupRules {
+        source: data_types::database_rules::Error,
+    },
+
+    #[snafu(display("Error decoding rules of backup: {}", source))]
+    DecodingBackupRules {
+        source: data_types::database_rules::Error,
+    },
+
+    #[snafu(display("Error copying file {}: {}", path, source))]
+    CopyingFile {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error writing catalog of backup: {}", source))]
+    WritingBackupCatalog {
+        source: parquet_file::catalog::Error,
+    },
+
+    #[snafu(display("Error reading catalog of backup: {}", source))]
+    ReadingBackupCatalog {
+        source: parquet_file::catalog::Error,
+    },
+
+    #[snafu(display("Error reading restored table {}: {}", table_name, source))]
+    ReadingRestoredTable {
+        table_name: String,
+        source: parquet_file::chunk::Error,
+    },
+
+    #[snafu(display("Error loading restored table {}: {}", table_name, source))]
+    LoadingRestoredTable {
+        table_name: String,
+        source: arrow_deps::arrow::error::ArrowError,
+    },
+
     #[snafu(display("Unknown Mutable Buffer Chunk {}", chunk_id))]
     UnknownMutableBufferChunk { chunk_id: u32 },
 
@@ -146,6 +307,18 @@ pub enum Error {
     #[snafu(display("Hard buffer size limit reached"))]
     HardLimitReached {},
 
+    #[snafu(display(
+        "Can not write to table {} at {}, which is older than the retention period (cutoff {})",
+        table_name,
+        timestamp,
+        cutoff
+    ))]
+    WriteOutsideRetentionPeriod {
+        table_name: String,
+        timestamp: i64,
+        cutoff: i64,
+    },
+
     #[snafu(display("Can not write entry {} {}: {}", partition_key, chunk_id, source))]
     WriteEntry {
         partition_key: String,
@@ -229,6 +402,10 @@ pub struct Db {
     /// Interface to use for peristence
     pub store: Arc<ObjectStore>,
 
+    /// The catalog of the chunks written to object storage, preserved in
+    /// object storage
+    preserved_catalog: PreservedCatalog,
+
     /// Executor for running queries
     exec: Arc<Executor>,
 
@@ -262,6 +439,9 @@ pub struct Db {
 
     /// Number of iterations of the worker loop for this Db
     worker_iterations: AtomicUsize,
+
+    /// Enforces the limits on the writes and queries of this Db
+    limiter: Limiter,
 }
 
 #[derive(Debug, Default)]
@@ -289,6 +469,12 @@ impl Db {
         wal_buffer: Option<Buffer>,
         jobs: Arc<JobRegistry>,
     ) -> Self {
+        let preserved_catalog = PreservedCatalog::new(
+            Arc::clone(&object_store),
+            server_id,
+            rules.name.to_string(),
+            DEFAULT_CHECKPOINT_INTERVAL,
+        );
         let rules = RwLock::new(rules);
         let server_id = server_id;
         let store = Arc::clone(&object_store);
@@ -299,6 +485,7 @@ impl Db {
             rules,
             server_id,
             store,
+            preserved_catalog,
             exec,
             catalog,
             wal_buffer,
@@ -307,6 +494,7 @@ impl Db {
             memory_registries: Default::default(),
             sequence: AtomicU64::new(STARTING_SEQUENCE),
             worker_iterations: AtomicUsize::new(0),
+            limiter: Default::default(),
         }
     }
 
@@ -315,6 +503,38 @@ impl Db {
         Arc::clone(&self.exec)
     }
 
+    /// Return the catalog of the chunks written to object storage
+    pub fn preserved_catalog(&self) -> &PreservedCatalog {
+        &self.preserved_catalog
+    }
+
+    /// Loads the preserved catalog, e.g. after a restart, so that the ids of
+    /// the chunks in it are not allocated to new chunks again
+    pub async fn load_preserved_catalog(&self) -> Result<()> {
+        let chunks = self
+            .preserved_catalog
+            .chunks()
+            .await
+            .context(ReadingPreservedCatalog)?;
+        for chunk in &chunks {
+            let partition = self
+                .catalog
+                .get_or_create_partition(chunk.partition_key.as_str());
+            partition.write().skip_chunk_ids_below(chunk.chunk_id + 1);
+        }
+
+        debug!(num_chunks=%chunks.len(), "loaded preserved catalog");
+
+        Ok(())
+    }
+
+    /// Admits a write of `rows` rows and `bytes` bytes, or returns an error
+    /// if it exceeds the limits of this database
+    pub fn admit_write(&self, rows: usize, bytes: usize) -> Result<(), limits::Error> {
+        self.limiter
+            .admit_write(&self.rules.read().limits, rows, bytes)
+    }
+
     /// Rolls over the active chunk in the database's specified
     /// partition. Returns the previously open (now closed) Chunk
     pub async fn rollover_partition(&self, partition_key: &str) -> Result<Arc<DBChunk>> {
@@ -339,8 +559,43 @@ impl Db {
         return Ok(DBChunk::snapshot(&chunk));
     }
 
-    /// Drops the specified chunk from the catalog and all storage systems
-    pub fn drop_chunk(&self, partition_key: &str, chunk_id: u32) -> Result<()> {
+    /// Drops the specified chunk from the catalog and all storage systems.
+    ///
+    /// A persisted chunk is removed from the preserved catalog, after which
+    /// its files are deleted by the next garbage collection. This also drops
+    /// persisted chunks that have already been unloaded from memory.
+    pub async fn drop_chunk(&self, partition_key: &str, chunk_id: u32) -> Result<()> {
+        let persisted = self
+            .preserved_catalog
+            .chunks()
+            .await
+            .context(ReadingPreservedCatalog)?
+            .iter()
+            .any(|chunk| chunk.partition_key == partition_key && chunk.chunk_id == chunk_id);
+
+        match self.unload_chunk(partition_key, chunk_id) {
+            Ok(()) => {}
+            // only in the preserved catalog
+            Err(Error::DroppingChunk { .. }) if persisted => {}
+            Err(e) => return Err(e),
+        }
+
+        if persisted {
+            debug!(%partition_key, %chunk_id, "removing chunk from preserved catalog");
+            self.preserved_catalog
+                .remove_chunk(partition_key, chunk_id)
+                .await
+                .context(CommittingCatalogTransaction)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops the specified chunk from memory only, e.g. to free up memory.
+    ///
+    /// The files of a persisted chunk stay referenced by the preserved
+    /// catalog.
+    pub fn unload_chunk(&self, partition_key: &str, chunk_id: u32) -> Result<()> {
         debug!(%partition_key, %chunk_id, "dropping chunk");
 
         let partition = self
@@ -388,6 +643,65 @@ impl Db {
         })
     }
 
+    /// Deletes the rows matching `predicate` that have been written to the
+    /// database so far.
+    ///
+    /// The predicate is recorded in every chunk that contains the table, and
+    /// the rows are excluded from queries from then on. They are removed
+    /// from storage when the chunk is next persisted or compacted. For
+    /// chunks that have already been persisted, the predicate is recorded in
+    /// the preserved catalog too. Open chunks that contain the table are
+    /// closed, so that rows written after the delete are not deleted.
+    pub async fn delete(&self, predicate: Arc<DeletePredicate>) -> Result<()> {
+        let table_name = predicate.table_name.as_str();
+        debug!(%table_name, range=?predicate.range, "deleting rows");
+
+        let mut actions = vec![];
+        for partition in self.catalog.partitions() {
+            let mut partition = partition.write();
+            let partition_key = partition.key().to_string();
+
+            let mut closed_open_chunk = false;
+            for chunk in partition.chunks() {
+                let mut chunk = chunk.write();
+                if !chunk.has_table(table_name) {
+                    continue;
+                }
+
+                if matches!(chunk.state(), ChunkState::Open(_)) {
+                    chunk.set_closing().context(RollingOverPartition {
+                        partition_key: &partition_key,
+                    })?;
+                    closed_open_chunk = true;
+                }
+                chunk.add_delete_predicate(Arc::clone(&predicate));
+
+                // Chunks that are being persisted record the predicate once
+                // they have been written
+                if matches!(chunk.state(), ChunkState::WrittenToObjectStore(_, _)) {
+                    actions.push(Action::AddDeletePredicate {
+                        partition_key: partition_key.clone(),
+                        chunk_id: chunk.id(),
+                        predicate: predicate.as_ref().clone(),
+                    });
+                }
+            }
+
+            if closed_open_chunk {
+                partition.create_open_chunk(self.memory_registries.mutable_buffer.as_ref());
+            }
+        }
+
+        if !actions.is_empty() {
+            self.preserved_catalog
+                .commit(actions)
+                .await
+                .context(CommittingCatalogTransaction)?;
+        }
+
+        Ok(())
+    }
+
     /// Copies a chunk in the Closing state into the ReadBuffer from
     /// the mutable buffer and marks the chunk with `Moved` state
     ///
@@ -492,19 +806,131 @@ impl Db {
 
         // update the catalog to say we are processing this chunk and
         // then drop the lock while we do the work
-        let rb_chunk = {
+        let (rb_chunk, sequence_range, delete_predicates) = {
             let mut chunk = chunk.write();
 
-            chunk
+            let rb_chunk = chunk
                 .set_writing_to_object_store()
                 .context(LoadingChunkToParquet {
                     partition_key,
                     chunk_id,
-                })?
+                })?;
+
+            (
+                rb_chunk,
+                chunk.sequence_range(),
+                chunk.delete_predicates().to_vec(),
+            )
         };
 
         debug!(%partition_key, %chunk_id, "chunk marked WRITING , loading tables into object store");
 
+        let (parquet_chunk, tables) = self
+            .write_read_buffer_chunk(
+                partition_key,
+                chunk_id,
+                &rb_chunk,
+                sequence_range,
+                &delete_predicates,
+            )
+            .await?;
+
+        // Record the written files in the preserved catalog before exposing
+        // the chunk as persisted
+        self.preserved_catalog
+            .add_chunk(ChunkMetadata {
+                partition_key: partition_key.to_string(),
+                chunk_id,
+                sequence_range,
+                tables,
+                delete_predicates: vec![],
+            })
+            .await
+            .context(CommittingCatalogTransaction)?;
+
+        // Relock the chunk again (nothing else should have been able
+        // to modify the chunk state while we were moving it
+        let (snapshot, late_predicates) = {
+            let mut chunk = chunk.write();
+            // update the catalog to say we are done processing
+            let parquet_chunk = Arc::clone(&Arc::new(parquet_chunk));
+            chunk
+                .set_written_to_object_store(parquet_chunk)
+                .context(LoadingChunkToParquet {
+                    partition_key,
+                    chunk_id,
+                })?;
+
+            // Rows deleted while the chunk was written are still in the
+            // parquet files. Deletes from now on record themselves
+            (
+                DBChunk::snapshot(&chunk),
+                chunk.delete_predicates()[delete_predicates.len()..].to_vec(),
+            )
+        };
+        self.record_delete_predicates(partition_key, chunk_id, &late_predicates)
+            .await?;
+
+        debug!(%partition_key, %chunk_id, "chunk marked MOVED. Persisting to object store complete");
+
+        Ok(snapshot)
+    }
+
+    /// Records `predicates` of rows deleted from the persisted chunk
+    /// `chunk_id` in the preserved catalog
+    async fn record_delete_predicates(
+        &self,
+        partition_key: &str,
+        chunk_id: u32,
+        predicates: &[Arc<DeletePredicate>],
+    ) -> Result<()> {
+        if predicates.is_empty() {
+            return Ok(());
+        }
+
+        let actions = predicates
+            .iter()
+            .map(|predicate| Action::AddDeletePredicate {
+                partition_key: partition_key.to_string(),
+                chunk_id,
+                predicate: predicate.as_ref().clone(),
+            })
+            .collect();
+        self.preserved_catalog
+            .commit(actions)
+            .await
+            .context(CommittingCatalogTransaction)?;
+        Ok(())
+    }
+
+    /// Write all tables of `rb_chunk` to parquet files in object storage,
+    /// returning a parquet chunk that reads the written files together with
+    /// the metadata of the written tables.
+    ///
+    /// Rows matching any of `delete_predicates` are not written. The table
+    /// summaries of the written tables still include them.
+    async fn write_read_buffer_chunk(
+        &self,
+        partition_key: &str,
+        chunk_id: u32,
+        rb_chunk: &ReadBufferChunk,
+        sequence_range: Option<SequenceRange>,
+        delete_predicates: &[Arc<DeletePredicate>],
+    ) -> Result<(Chunk, Vec<TableMetadata>)> {
+        // Never overwrite the files of a persisted chunk
+        let persisted = self
+            .preserved_catalog
+            .contains_chunk(partition_key, chunk_id)
+            .await
+            .context(ReadingPreservedCatalog)?;
+        ensure!(
+            !persisted,
+            ChunkAlreadyPersisted {
+                partition_key,
+                chunk_id
+            }
+        );
+
         // Get all tables in this chunk
         let table_stats = rb_chunk.table_summaries();
 
@@ -512,6 +938,7 @@ impl Db {
         let mut parquet_chunk = Chunk::new(
             partition_key.to_string(),
             chunk_id,
+            Arc::clone(&self.store),
             self.memory_registries.parquet.as_ref(),
         );
         // Create a storage to save data of this chunk
@@ -521,12 +948,15 @@ impl Db {
             self.rules.read().name.to_string(),
         );
 
-        for stats in table_stats {
+        let configured_sort_key = self.rules.read().lifecycle_rules.persisted_sort_key.clone();
+
+        let mut tables = Vec::with_capacity(table_stats.len());
+        for mut stats in table_stats {
             debug!(%partition_key, %chunk_id, table=%stats.name, "loading table to object store");
 
             let predicate = read_buffer::Predicate::default();
 
-            // Get RecordBatchStream of data from the read buffer chunk
+            // Get the data of the table from the read buffer chunk
             let read_results = rb_chunk
                 .read_filter(stats.name.as_str(), predicate, Selection::All)
                 .context(ReadBufferChunkError { chunk_id })?;
@@ -537,44 +967,79 @@ impl Db {
             let time_range = rb_chunk
                 .table_time_range(stats.name.as_str())
                 .context(ReadBufferChunkTimestampError { chunk_id })?;
-            let stream: SendableRecordBatchStream = Box::pin(
-                streams::ReadFilterResultsStream::new(read_results, Arc::clone(&arrow_schema)),
-            );
+
+            let schema: internal_types::schema::Schema = Arc::clone(&arrow_schema)
+                .try_into()
+                .context(SchemaConversion)?;
+
+            // Record the cardinality of the tag columns, from which the sort
+            // key is derived
+            let tag_names: Vec<&str> = schema
+                .tags_iter()
+                .map(|field| field.name().as_str())
+                .collect();
+            if !tag_names.is_empty() {
+                let tag_values = rb_chunk
+                    .column_values(
+                        stats.name.as_str(),
+                        read_buffer::Predicate::default(),
+                        Selection::Some(&tag_names),
+                        BTreeMap::new(),
+                    )
+                    .context(ReadBufferChunkError { chunk_id })?;
+                for column in &mut stats.columns {
+                    if let Some(values) = tag_values.get(&column.name) {
+                        column
+                            .stats
+                            .set_distinct_count(NonZeroU64::new(values.len() as u64));
+                    }
+                }
+            }
+
+            // Sort the data on the sort key of the table
+            let sort_key = sort::sort_key(&schema, &stats, &configured_sort_key);
+            let table_deletes: Vec<_> = delete_predicates
+                .iter()
+                .filter(|predicate| predicate.table_name == stats.name)
+                .cloned()
+                .collect();
+            let batches = read_results
+                .map(|batch| query::delete::filter_deleted(batch, &table_deletes))
+                .collect::<arrow_deps::arrow::error::Result<Vec<_>>>()
+                .context(FilteringDeletedRows {
+                    table_name: &stats.name,
+                })?;
+            let batch =
+                sort::sort_batches(&schema, &batches, &sort_key).context(SortingTableData {
+                    table_name: &stats.name,
+                })?;
+            let stream: SendableRecordBatchStream = Box::pin(streams::MemoryStream::new(batch));
+
+            let table_time_range = time_range.map(|(start, end)| TimestampRange::new(start, end));
+            let metadata = IoxMetadata {
+                partition_key: partition_key.to_string(),
+                chunk_id,
+                sequence_range,
+                table: TableMetadata {
+                    summary: stats.clone(),
+                    schema: schema.clone(),
+                    time_range: table_time_range,
+                    sort_key,
+                },
+            };
 
             // Write this table data into the object store
             let path = storage
-                .write_to_object_store(
-                    partition_key.to_string(),
-                    chunk_id,
-                    stats.name.to_string(),
-                    stream,
-                )
+                .write_to_object_store(&metadata, stream)
                 .await
                 .context(WritingToObjectStore)?;
 
             // Now add the saved info into the parquet_chunk
-            let schema = Arc::clone(&arrow_schema)
-                .try_into()
-                .context(SchemaConversion)?;
-            let table_time_range = time_range.map(|(start, end)| TimestampRange::new(start, end));
+            tables.push(metadata.table);
             parquet_chunk.add_table(stats, path, schema, table_time_range);
         }
 
-        // Relock the chunk again (nothing else should have been able
-        // to modify the chunk state while we were moving it
-        let mut chunk = chunk.write();
-        // update the catalog to say we are done processing
-        let parquet_chunk = Arc::clone(&Arc::new(parquet_chunk));
-        chunk
-            .set_written_to_object_store(parquet_chunk)
-            .context(LoadingChunkToParquet {
-                partition_key,
-                chunk_id,
-            })?;
-
-        debug!(%partition_key, %chunk_id, "chunk marked MOVED. Persisting to object store complete");
-
-        Ok(DBChunk::snapshot(&chunk))
+        Ok((parquet_chunk, tables))
     }
 
     /// Spawns a task to perform
@@ -647,6 +1112,38 @@ impl Db {
         tracker
     }
 
+    /// Spawns a task to perform [`drop_chunk`](Self::drop_chunk)
+    pub fn drop_chunk_in_background(
+        self: &Arc<Self>,
+        partition_key: String,
+        chunk_id: u32,
+    ) -> TaskTracker<Job> {
+        let name = self.rules.read().name.clone();
+        let (tracker, registration) = self.jobs.register(Job::DropChunk {
+            db_name: name.to_string(),
+            partition_key: partition_key.clone(),
+            chunk_id,
+        });
+
+        let captured = Arc::clone(&self);
+        let task = async move {
+            debug!(%name, %partition_key, %chunk_id, "background task dropping chunk");
+            let result = captured.drop_chunk(&partition_key, chunk_id).await;
+            if let Err(e) = result {
+                info!(?e, %name, %partition_key, %chunk_id, "background task error dropping chunk");
+                return Err(e);
+            }
+
+            debug!(%name, %partition_key, %chunk_id, "background task completed dropping chunk");
+
+            Ok(())
+        };
+
+        tokio::spawn(task.track(registration));
+
+        tracker
+    }
+
     /// Returns the next write sequence number
     pub fn next_sequence(&self) -> u64 {
         self.sequence.fetch_add(1, Ordering::SeqCst)
@@ -705,6 +1202,10 @@ impl Db {
 
             lifecycle_manager.check_for_work();
 
+            if let Err(e) = self.enforce_retention(Utc::now()).await {
+                warn!(?e, "error enforcing retention period");
+            }
+
             tokio::select! {
                 _ = interval.tick() => {},
                 _ = shutdown.cancelled() => break
@@ -749,6 +1250,34 @@ impl Db {
         }
         std::mem::drop(rules);
 
+        // Reject the entire entry if any of its data would be dropped
+        // straight away
+        if let Some(cutoff) = self.retention_cutoff(Utc::now()) {
+            for write in sequenced_entry.partition_writes().unwrap_or_default() {
+                for table_batch in write.table_batches() {
+                    let min_time = table_batch
+                        .columns()
+                        .into_iter()
+                        .filter(|column| column.is_time())
+                        .filter_map(|column| column.values().i64_values())
+                        .flatten()
+                        .flatten()
+                        .min();
+
+                    if let Some(timestamp) = min_time {
+                        ensure!(
+                            timestamp >= cutoff,
+                            WriteOutsideRetentionPeriod {
+                                table_name: table_batch.name(),
+                                timestamp,
+                                cutoff,
+                            }
+                        );
+                    }
+                }
+            }
+        }
+
         // TODO: Direct writes to closing chunks
 
         if let Some(partitioned_writes) = sequenced_entry.partition_writes() {
@@ -763,7 +1292,7 @@ impl Db {
                 });
 
                 let mut chunk = chunk.write();
-                chunk.record_write();
+                chunk.record_write(sequenced_entry.clock_value());
                 let chunk_id = chunk.id();
 
                 let mb_chunk = chunk.mutable_buffer().expect("cannot mutate open chunk");
@@ -871,13 +1400,18 @@ mod tests {
     use crate::query_tests::utils::{make_database, make_db};
     use ::test_helpers::assert_contains;
     use arrow_deps::{
-        arrow::record_batch::RecordBatch, assert_batches_sorted_eq, assert_table_eq,
+        arrow::{
+            array::{Array, StringArray},
+            record_batch::RecordBatch,
+        },
+        assert_batches_sorted_eq, assert_table_eq,
         datafusion::execution::context,
     };
     use chrono::Utc;
     use data_types::{
         chunk::ChunkStorage,
         database_rules::{Order, Sort, SortOrder},
+        delete::{DeleteExpr, DeleteOp},
         partition_metadata::{ColumnSummary, StatValues, Statistics, TableSummary},
     };
     use object_store::{
@@ -891,7 +1425,7 @@ mod tests {
     use std::iter::Iterator;
 
     use super::test_helpers::{try_write_lp, write_lp};
-    use internal_types::entry::test_helpers::lp_to_entry;
+    use internal_types::{entry::test_helpers::lp_to_entry, schema::InfluxColumnType};
     use std::num::NonZeroUsize;
     use std::str;
     use tempfile::TempDir;
@@ -1036,7 +1570,7 @@ mod tests {
         assert_table_eq!(&expected, &batches);
 
         // drop, the chunk from the read buffer
-        db.drop_chunk(partition_key, mb_chunk.id()).unwrap();
+        db.drop_chunk(partition_key, mb_chunk.id()).await.unwrap();
         assert_eq!(
             read_buffer_chunk_ids(db.as_ref(), partition_key),
             vec![] as Vec<u32>
@@ -1149,6 +1683,42 @@ mod tests {
         assert_table_eq!(expected, &content);
     }
 
+    #[tokio::test]
+    async fn read_parquet_file_from_in_memory_store() {
+        // Test that data written to a non local object store can be queried back
+        let db = Arc::new(make_db());
+
+        write_lp(db.as_ref(), "cpu,tag1=a bar=1 10");
+        write_lp(db.as_ref(), "cpu,tag1=b bar=2 20");
+        write_lp(db.as_ref(), "mem foo=3 10");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        assert_eq!(read_parquet_file_chunk_ids(&db, partition_key), vec![0]);
+
+        // the chunk is now queried from its parquet files
+        let batches = run_query(Arc::clone(&db), "select tag1, bar, time from cpu").await;
+        let expected = vec![
+            "+------+-----+-------------------------------+",
+            "| tag1 | bar | time                          |",
+            "+------+-----+-------------------------------+",
+            "| a    | 1   | 1970-01-01 00:00:00.000000010 |",
+            "| b    | 2   | 1970-01-01 00:00:00.000000020 |",
+            "+------+-----+-------------------------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = run_query(db, "select foo from mem").await;
+        let expected = vec!["+-----+", "| foo |", "+-----+", "| 3   |", "+-----+"];
+        assert_batches_sorted_eq!(expected, &batches);
+    }
+
     #[tokio::test]
     async fn write_one_chunk_many_tables_to_parquet_files() {
         // Test that data can be written into parquet files
@@ -1198,7 +1768,8 @@ mod tests {
         assert_eq!(paths.len(), 2);
 
         // Check that the path must exist in the object store
-        let prefix = object_store.new_path();
+        let prefix =
+            Storage::new(Arc::clone(&object_store), server_id, db_name.to_string()).data_location();
         let path_list = flatten_list_stream(Arc::clone(&object_store), Some(&prefix))
             .await
             .unwrap();
@@ -1621,6 +2192,7 @@ mod tests {
                                     min: 1.0,
                                     max: 2.0,
                                     count: 2,
+                                    distinct_count: None,
                                 }),
                             },
                             ColumnSummary {
@@ -1629,6 +2201,7 @@ mod tests {
                                     min: 1,
                                     max: 2,
                                     count: 2,
+                                    distinct_count: None,
                                 }),
                             },
                             ColumnSummary {
@@ -1637,6 +2210,7 @@ mod tests {
                                     min: 3.0,
                                     max: 3.0,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                         ],
@@ -1650,6 +2224,7 @@ mod tests {
                                     min: 1,
                                     max: 1,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                             ColumnSummary {
@@ -1658,6 +2233,7 @@ mod tests {
                                     min: 1.0,
                                     max: 1.0,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                         ],
@@ -1676,6 +2252,7 @@ mod tests {
                                     min: 1.0,
                                     max: 1.0,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                             ColumnSummary {
@@ -1684,6 +2261,7 @@ mod tests {
                                     min: 400000000000000,
                                     max: 400000000000000,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                         ],
@@ -1697,6 +2275,7 @@ mod tests {
                                     min: 400000000000001,
                                     max: 400000000000001,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                             ColumnSummary {
@@ -1705,6 +2284,7 @@ mod tests {
                                     min: 3.0,
                                     max: 3.0,
                                     count: 1,
+                                    distinct_count: None,
                                 }),
                             },
                         ],
@@ -1810,6 +2390,391 @@ mod tests {
         assert_eq!(read_parquet_file_chunk_ids(&db, partition_key), vec![0]);
     }
 
+    #[tokio::test]
+    async fn drop_chunk_in_background() {
+        let db = Arc::new(make_db());
+
+        write_lp(db.as_ref(), "cpu bar=1 10");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let task = db.drop_chunk_in_background(partition_key.to_string(), mb_chunk.id());
+        let t_start = std::time::Instant::now();
+        while !task.is_complete() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            assert!(
+                std::time::Instant::now() - t_start < std::time::Duration::from_secs(10),
+                "task deadline exceeded"
+            );
+        }
+
+        assert_eq!(
+            task.metadata(),
+            &Job::DropChunk {
+                db_name: "placeholder".to_string(),
+                partition_key: partition_key.to_string(),
+                chunk_id: mb_chunk.id(),
+            }
+        );
+        assert!(read_buffer_chunk_ids(&db, partition_key).is_empty());
+        assert!(db
+            .partition_chunk_summaries(partition_key)
+            .iter()
+            .all(|summary| summary.id != mb_chunk.id()));
+    }
+
+    #[tokio::test]
+    async fn drop_persisted_chunk_in_background() {
+        let db = Arc::new(make_db());
+
+        write_lp(db.as_ref(), "cpu bar=1 10");
+        write_lp(db.as_ref(), "mem foo=1 20");
+
+        // persist two chunks, one of which is then unloaded from memory
+        let partition_key = "1970-01-01T00";
+        for _ in 0..2 {
+            let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+            db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+                .await
+                .unwrap();
+            db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+                .await
+                .unwrap();
+            write_lp(db.as_ref(), "cpu bar=2 30");
+        }
+        db.unload_chunk(partition_key, 1).unwrap();
+        assert_eq!(db.preserved_catalog().chunks().await.unwrap().len(), 2);
+
+        for chunk_id in 0..2 {
+            let task = db.drop_chunk_in_background(partition_key.to_string(), chunk_id);
+            let t_start = std::time::Instant::now();
+            while !task.is_complete() {
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                assert!(
+                    std::time::Instant::now() - t_start < std::time::Duration::from_secs(10),
+                    "task deadline exceeded"
+                );
+            }
+        }
+
+        assert!(read_parquet_file_chunk_ids(&db, partition_key).is_empty());
+        assert!(db.preserved_catalog().chunks().await.unwrap().is_empty());
+
+        // dropping a chunk that is in neither catalog fails
+        let err = db.drop_chunk(partition_key, 0).await.unwrap_err();
+        assert!(matches!(err, Error::DroppingChunk { .. }));
+    }
+
+    #[tokio::test]
+    async fn write_chunk_to_object_store_records_preserved_catalog() {
+        let db = Arc::new(make_db());
+
+        write_lp(db.as_ref(), "cpu bar=1 10");
+        write_lp(db.as_ref(), "cpu bar=2 20\nmem foo=1 30");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let chunks = db.preserved_catalog().chunks().await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].partition_key, partition_key);
+        assert_eq!(chunks[0].chunk_id, mb_chunk.id());
+
+        let sequence_range = chunks[0].sequence_range.unwrap();
+        assert!(sequence_range.min < sequence_range.max);
+
+        let mut table_names: Vec<_> = chunks[0]
+            .tables
+            .iter()
+            .map(|t| t.summary.name.as_str())
+            .collect();
+        table_names.sort_unstable();
+        assert_eq!(table_names, vec!["cpu", "mem"]);
+
+        // The catalog can be rebuilt from object storage
+        let rebuilt = PreservedCatalog::new(
+            Arc::clone(&db.store),
+            db.server_id,
+            db.rules.read().name.to_string(),
+            DEFAULT_CHECKPOINT_INTERVAL,
+        );
+        assert_eq!(rebuilt.revision().await.unwrap(), 1);
+        assert_eq!(rebuilt.chunks().await.unwrap(), chunks);
+    }
+
+    #[tokio::test]
+    async fn write_chunk_to_object_store_embeds_iox_metadata() {
+        let db = Arc::new(make_db());
+
+        write_lp(db.as_ref(), "cpu,tag1=a bar=1 10");
+        write_lp(db.as_ref(), "cpu,tag1=b bar=2 20\nmem foo=1 30");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let storage = Storage::new(
+            Arc::clone(&db.store),
+            db.server_id,
+            db.rules.read().name.to_string(),
+        );
+        let chunk = db.preserved_catalog().chunks().await.unwrap().remove(0);
+        assert_eq!(chunk.tables.len(), 2);
+
+        // The schema and statistics of each table can be recovered from
+        // its parquet file alone
+        for (table, path) in chunk.tables.iter().zip(chunk.paths(&storage)) {
+            let metadata = Storage::read_iox_metadata(&db.store, &path).await.unwrap();
+
+            assert_eq!(
+                metadata,
+                IoxMetadata {
+                    partition_key: partition_key.to_string(),
+                    chunk_id: mb_chunk.id(),
+                    sequence_range: chunk.sequence_range,
+                    table: table.clone(),
+                }
+            );
+        }
+
+        let cpu = chunk
+            .tables
+            .iter()
+            .find(|t| t.summary.name == "cpu")
+            .unwrap();
+        let (tag_type, _) = cpu.schema.field(cpu.schema.find_index_of("tag1").unwrap());
+        assert_eq!(tag_type, Some(InfluxColumnType::Tag));
+    }
+
+    #[tokio::test]
+    async fn write_chunk_to_object_store_sorts_data() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        write_lp(
+            db.as_ref(),
+            "cpu,region=west,host=b usage=1 30\n\
+             cpu,region=east,host=c usage=2 10\n\
+             cpu,region=west,host=a usage=3 20\n\
+             cpu,region=east,host=a usage=4 20",
+        );
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let storage = Storage::new(
+            Arc::clone(&db.store),
+            db.server_id,
+            db.rules.read().name.to_string(),
+        );
+        let chunk = db.preserved_catalog().chunks().await.unwrap().remove(0);
+        let cpu = &chunk.tables[0];
+
+        // tags are sorted on from low to high cardinality
+        assert_eq!(cpu.sort_key, vec!["region", "host", "time"]);
+        let distinct_count = |name| cpu.summary.column(name).unwrap().distinct_count();
+        assert_eq!(distinct_count("region"), NonZeroU64::new(2));
+        assert_eq!(distinct_count("host"), NonZeroU64::new(3));
+
+        let path = storage.location(partition_key.to_string(), mb_chunk.id(), "cpu".to_string());
+        let batches: Vec<RecordBatch> = Storage::read_filter(
+            &query::predicate::Predicate::default(),
+            Selection::All,
+            cpu.schema.as_arrow(),
+            &path,
+            Arc::clone(&db.store),
+        )
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+        let column_values = |name: &str| -> Vec<String> {
+            batches
+                .iter()
+                .flat_map(|batch| {
+                    let column = batch.column(batch.schema().index_of(name).unwrap());
+                    let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+                    (0..column.len())
+                        .map(|i| column.value(i).to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        assert_eq!(
+            column_values("region"),
+            vec!["east", "east", "west", "west"]
+        );
+        assert_eq!(column_values("host"), vec!["a", "c", "a", "b"]);
+
+        // a configured sort key takes precedence over the cardinality
+        db.rules.write().lifecycle_rules.persisted_sort_key = vec!["host".to_string()];
+        write_lp(db.as_ref(), "cpu,region=west,host=b usage=1 30");
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        let pq_chunk = db
+            .write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let chunk = db
+            .preserved_catalog()
+            .chunks()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|chunk| chunk.chunk_id == pq_chunk.id())
+            .unwrap();
+        assert_eq!(chunk.tables[0].sort_key, vec!["host", "region", "time"]);
+    }
+
+    #[tokio::test]
+    async fn delete_rows() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        write_lp(
+            db.as_ref(),
+            "cpu,host=a usage=1 10\n\
+             cpu,host=b usage=2 20\n\
+             cpu,host=a usage=3 30\n\
+             mem,host=a free=1 10",
+        );
+
+        db.delete(Arc::new(DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(0, 25),
+            exprs: vec![DeleteExpr {
+                column: "host".to_string(),
+                op: DeleteOp::Eq,
+                value: "a".to_string(),
+            }],
+        }))
+        .await
+        .unwrap();
+
+        // the open chunk was closed so that later writes are not deleted
+        assert_eq!(mutable_chunk_ids(&db, partition_key), vec![0, 1]);
+        write_lp(db.as_ref(), "cpu,host=a usage=4 10");
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| a    | 3     | 1970-01-01 00:00:00.000000030 |",
+            "| a    | 4     | 1970-01-01 00:00:00.000000010 |",
+            "| b    | 2     | 1970-01-01 00:00:00.000000020 |",
+            "+------+-------+-------------------------------+",
+        ];
+        let batches = run_query(Arc::clone(&db), "select host, usage, time from cpu").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        let batches = run_query(Arc::clone(&db), "select count(*) from mem").await;
+        let expected = vec![
+            "+-----------------+",
+            "| COUNT(UInt8(1)) |",
+            "+-----------------+",
+            "| 1               |",
+            "+-----------------+",
+        ];
+        assert_table_eq!(&expected, &batches);
+
+        // the deleted rows are not written to object storage
+        db.load_chunk_to_read_buffer(partition_key, 0)
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, 0)
+            .await
+            .unwrap();
+
+        let storage = Storage::new(
+            Arc::clone(&db.store),
+            db.server_id,
+            db.rules.read().name.to_string(),
+        );
+        let chunk = db.preserved_catalog().chunks().await.unwrap().remove(0);
+        let cpu = chunk
+            .tables
+            .iter()
+            .find(|table| table.summary.name == "cpu")
+            .unwrap();
+        let path = storage.location(partition_key.to_string(), 0, "cpu".to_string());
+        let batches: Vec<RecordBatch> = Storage::read_filter(
+            &query::predicate::Predicate::default(),
+            Selection::All,
+            cpu.schema.as_arrow(),
+            &path,
+            Arc::clone(&db.store),
+        )
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+        let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(num_rows, 2);
+
+        let batches = run_query(Arc::clone(&db), "select host, usage, time from cpu").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn delete_rows_of_persisted_chunk() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        write_lp(db.as_ref(), "cpu,host=a usage=1 10\ncpu,host=b usage=2 20");
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let predicate = DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(0, 15),
+            exprs: vec![],
+        };
+        db.delete(Arc::new(predicate.clone())).await.unwrap();
+
+        // the parquet files still contain the deleted rows, so the predicate
+        // is recorded in the preserved catalog
+        let chunks = db.preserved_catalog().chunks().await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].delete_predicates, vec![predicate]);
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| b    | 2     | 1970-01-01 00:00:00.000000020 |",
+            "+------+-------+-------------------------------+",
+        ];
+        let batches = run_query(Arc::clone(&db), "select host, usage, time from cpu").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
     #[tokio::test]
     async fn write_hard_limit() {
         let db = Arc::new(make_db());
@@ -0,0 +1,522 @@
+//! Backup and restore of the persisted data of a database.
+//!
+//! A backup contains the rules of a database, the parquet files of its
+//! persisted chunks and a preserved catalog listing these chunks. It is laid
+//! out in its object store exactly like the database in the object store of
+//! the server it was taken from:
+//!
+//! ```text
+//! <writer id>/<database>/rules.pb
+//! <writer id>/<database>/transactions/<revision>.txn
+//! <writer id>/<database>/data/<partition key>/<chunk id>/<table>.parquet
+//! ```
+//!
+//! Only chunks that have been written to object storage can be backed up, so
+//! a backup fails while the database holds data that has not been persisted.
+//! Rows deleted from persisted chunks are recorded in the catalog of the
+//! backup. When a backup is restored its chunks are copied into the object
+//! store of the restoring server under new chunk ids, and loaded into the
+//! read buffer.
+use std::{num::NonZeroU32, sync::Arc};
+
+use arrow_deps::arrow::record_batch::RecordBatch;
+use bytes::BytesMut;
+use futures::TryStreamExt;
+use object_store::{
+    path::{ObjectStorePath, Path, DELIMITER},
+    ObjectStore, ObjectStoreApi,
+};
+use observability_deps::tracing::{debug, info};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use data_types::{database_rules::DatabaseRules, job::Job};
+use internal_types::selection::Selection;
+use parquet_file::{
+    catalog::{Action, PreservedCatalog},
+    storage::Storage,
+};
+use query::predicate::EMPTY_PREDICATE;
+use read_buffer::Chunk as ReadBufferChunk;
+use tracker::{TaskTracker, TrackedFutureExt};
+
+use super::{
+    catalog::chunk::{Chunk as CatalogChunk, ChunkState},
+    BackupTargetNotEmpty, BackupUnpersistedChunks, CommittingCatalogTransaction, CopyingFile, Db,
+    DecodingBackupRules, EncodingBackupRules, InvalidBackup, LoadingRestoredTable, ReadingBackup,
+    ReadingBackupCatalog, ReadingPreservedCatalog, ReadingRestoredTable, Result, WritingBackup,
+    WritingBackupCatalog,
+};
+use crate::config::{object_store_path_for_database_config, DB_RULES_FILE_NAME};
+
+/// A backup of a database, stored in an object store
+#[derive(Debug)]
+pub struct Backup {
+    store: Arc<ObjectStore>,
+
+    /// The writer id of the server the backup was taken from
+    writer_id: NonZeroU32,
+
+    /// The rules of the backed up database
+    rules: DatabaseRules,
+}
+
+impl Backup {
+    /// Opens the backup in `store`, reading the rules of the backed up
+    /// database
+    pub async fn open(store: Arc<ObjectStore>) -> Result<Self> {
+        let writer_dir = single_directory(&store, &store.new_path()).await?;
+        let writer_id = directory_name(&writer_dir)
+            .parse()
+            .ok()
+            .context(InvalidBackup {
+                description: format!("{} is not a writer id", writer_dir.display()),
+            })?;
+
+        let mut rules_path = single_directory(&store, &writer_dir).await?;
+        rules_path.set_file_name(DB_RULES_FILE_NAME);
+        let bytes = store
+            .get(&rules_path)
+            .await
+            .context(ReadingBackup)?
+            .map_ok(|b| BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .context(ReadingBackup)?;
+        let rules = DatabaseRules::decode(bytes.freeze()).context(DecodingBackupRules)?;
+
+        Ok(Self {
+            store,
+            writer_id,
+            rules,
+        })
+    }
+
+    /// The rules of the backed up database
+    pub fn rules(&self) -> &DatabaseRules {
+        &self.rules
+    }
+
+    fn catalog(&self) -> PreservedCatalog {
+        PreservedCatalog::new(
+            Arc::clone(&self.store),
+            self.writer_id,
+            self.rules.name.to_string(),
+            0,
+        )
+    }
+
+    fn storage(&self) -> Storage {
+        Storage::new(
+            Arc::clone(&self.store),
+            self.writer_id,
+            self.rules.name.to_string(),
+        )
+    }
+}
+
+impl Db {
+    /// Writes a backup of the persisted chunks of this database to `target`,
+    /// which must not contain any files yet. Fails if any chunk has not been
+    /// persisted, as its data would be missing from the backup.
+    ///
+    /// The rules are written last, so that an incomplete backup can not be
+    /// opened.
+    pub async fn backup(&self, target: Arc<ObjectStore>) -> Result<()> {
+        let num_chunks = self
+            .catalog
+            .chunks()
+            .iter()
+            .filter(|chunk| match chunk.read().state() {
+                ChunkState::Open(chunk) | ChunkState::Closing(chunk) => !chunk.is_empty(),
+                ChunkState::WrittenToObjectStore(_, _) => false,
+                _ => true,
+            })
+            .count();
+        ensure!(num_chunks == 0, BackupUnpersistedChunks { num_chunks });
+
+        let is_empty = target
+            .list(None)
+            .await
+            .context(WritingBackup)?
+            .try_next()
+            .await
+            .context(WritingBackup)?
+            .map_or(true, |paths| paths.is_empty());
+        ensure!(is_empty, BackupTargetNotEmpty);
+
+        let rules = self.rules.read().clone();
+        let db_name = rules.name.to_string();
+        let storage = Storage::new(Arc::clone(&self.store), self.server_id, db_name.clone());
+        let target_storage = Storage::new(Arc::clone(&target), self.server_id, db_name.clone());
+
+        let chunks = self
+            .preserved_catalog
+            .chunks()
+            .await
+            .context(ReadingPreservedCatalog)?;
+        for chunk in &chunks {
+            for (source, dest) in chunk
+                .paths(&storage)
+                .iter()
+                .zip(chunk.paths(&target_storage))
+            {
+                copy_file(&self.store, source, &target, &dest).await?;
+            }
+            debug!(%db_name, partition_key=%chunk.partition_key, chunk_id=chunk.chunk_id, "backed up chunk");
+        }
+
+        let catalog =
+            PreservedCatalog::new(Arc::clone(&target), self.server_id, db_name.clone(), 0);
+        let num_chunks = chunks.len();
+        catalog
+            .commit(chunks.into_iter().map(Action::AddChunk).collect())
+            .await
+            .context(WritingBackupCatalog)?;
+
+        let mut root = target.new_path();
+        root.push_dir(self.server_id.to_string());
+        let location = object_store_path_for_database_config(&root, &rules.name);
+        let mut data = BytesMut::new();
+        rules.encode(&mut data).context(EncodingBackupRules)?;
+        let len = data.len();
+        let stream_data = std::io::Result::Ok(data.freeze());
+        target
+            .put(
+                &location,
+                futures::stream::once(async move { stream_data }),
+                Some(len),
+            )
+            .await
+            .context(WritingBackup)?;
+
+        info!(%db_name, %num_chunks, "backed up database");
+
+        Ok(())
+    }
+
+    /// Spawns a task to perform [`backup`](Self::backup). `path` describes
+    /// the location of `target`
+    pub fn backup_in_background(
+        self: &Arc<Self>,
+        target: Arc<ObjectStore>,
+        path: String,
+    ) -> TaskTracker<Job> {
+        let name = self.rules.read().name.clone();
+        let (tracker, registration) = self.jobs.register(Job::BackupDatabase {
+            db_name: name.to_string(),
+            path: path.clone(),
+        });
+
+        let captured = Arc::clone(&self);
+        let task = async move {
+            debug!(%name, %path, "background task backing up database");
+            let result = captured.backup(target).await;
+            if let Err(e) = result {
+                info!(?e, %name, %path, "background task error backing up database");
+                return Err(e);
+            }
+
+            debug!(%name, %path, "background task completed backing up database");
+
+            Ok(())
+        };
+
+        tokio::spawn(task.track(registration));
+
+        tracker
+    }
+
+    /// Adds the chunks of `backup` to this database.
+    ///
+    /// The parquet files of each chunk are copied into the object store of
+    /// this database under a newly allocated chunk id and recorded in the
+    /// preserved catalog. The chunk is then loaded into the read buffer,
+    /// making its data available for queries.
+    pub async fn restore(&self, backup: &Backup) -> Result<()> {
+        let db_name = self.rules.read().name.to_string();
+        let source_storage = backup.storage();
+        let storage = Storage::new(Arc::clone(&self.store), self.server_id, db_name.clone());
+
+        let chunks = backup
+            .catalog()
+            .chunks()
+            .await
+            .context(ReadingBackupCatalog)?;
+        let num_chunks = chunks.len();
+
+        for mut metadata in chunks {
+            let partition = self
+                .catalog
+                .get_or_create_partition(metadata.partition_key.as_str());
+
+            let source_paths = metadata.paths(&source_storage);
+            metadata.chunk_id = partition.write().next_chunk_id();
+            let chunk_id = metadata.chunk_id;
+
+            for (source, dest) in source_paths.iter().zip(metadata.paths(&storage)) {
+                copy_file(&backup.store, source, &self.store, &dest).await?;
+            }
+
+            self.preserved_catalog
+                .add_chunk(metadata.clone())
+                .await
+                .context(CommittingCatalogTransaction)?;
+
+            let parquet_chunk = metadata.to_parquet_chunk(
+                &storage,
+                Arc::clone(&self.store),
+                self.memory_registries.parquet.as_ref(),
+            );
+            let rb_chunk = ReadBufferChunk::new_with_memory_tracker(
+                chunk_id,
+                &self.memory_registries.read_buffer,
+            );
+            for table in &metadata.tables {
+                let table_name = table.summary.name.as_str();
+                let batches: Vec<RecordBatch> = parquet_chunk
+                    .read_filter(table_name, &EMPTY_PREDICATE, Selection::All)
+                    .context(ReadingRestoredTable { table_name })?
+                    .try_collect()
+                    .await
+                    .context(LoadingRestoredTable { table_name })?;
+                for batch in batches {
+                    rb_chunk.upsert_table(table_name, batch);
+                }
+            }
+
+            // The files still contain the rows deleted after they were
+            // written, which are excluded from queries like before
+            let mut chunk = CatalogChunk::new_restored(
+                metadata.partition_key.as_str(),
+                chunk_id,
+                Arc::new(rb_chunk),
+                Arc::new(parquet_chunk),
+                metadata.sequence_range,
+            );
+            for predicate in metadata.delete_predicates {
+                chunk.add_delete_predicate(Arc::new(predicate));
+            }
+            partition.write().insert_chunk(chunk);
+            debug!(%db_name, partition_key=%metadata.partition_key, %chunk_id, "restored chunk");
+        }
+
+        info!(%db_name, %num_chunks, "restored database");
+
+        Ok(())
+    }
+
+    /// Spawns a task to perform [`restore`](Self::restore). `path`
+    /// describes the location of the backup
+    pub fn restore_in_background(
+        self: &Arc<Self>,
+        backup: Backup,
+        path: String,
+    ) -> TaskTracker<Job> {
+        let name = self.rules.read().name.clone();
+        let (tracker, registration) = self.jobs.register(Job::RestoreDatabase {
+            db_name: name.to_string(),
+            path: path.clone(),
+        });
+
+        let captured = Arc::clone(&self);
+        let task = async move {
+            debug!(%name, %path, "background task restoring database");
+            let result = captured.restore(&backup).await;
+            if let Err(e) = result {
+                info!(?e, %name, %path, "background task error restoring database");
+                return Err(e);
+            }
+
+            debug!(%name, %path, "background task completed restoring database");
+
+            Ok(())
+        };
+
+        tokio::spawn(task.track(registration));
+
+        tracker
+    }
+}
+
+/// Copies the file `source` in `source_store` to `dest` in `dest_store`
+async fn copy_file(
+    source_store: &ObjectStore,
+    source: &Path,
+    dest_store: &ObjectStore,
+    dest: &Path,
+) -> Result<()> {
+    let data = source_store
+        .get(source)
+        .await
+        .context(CopyingFile {
+            path: source.display(),
+        })?
+        .map_ok(|b| BytesMut::from(&b[..]))
+        .try_concat()
+        .await
+        .context(CopyingFile {
+            path: source.display(),
+        })?;
+
+    let len = data.len();
+    let stream_data = std::io::Result::Ok(data.freeze());
+    dest_store
+        .put(
+            dest,
+            futures::stream::once(async move { stream_data }),
+            Some(len),
+        )
+        .await
+        .context(CopyingFile {
+            path: source.display(),
+        })
+}
+
+/// Returns the only directory directly below `prefix`
+async fn single_directory(store: &ObjectStore, prefix: &Path) -> Result<Path> {
+    let mut directories = store
+        .list_with_delimiter(prefix)
+        .await
+        .context(ReadingBackup)?
+        .common_prefixes;
+    ensure!(
+        directories.len() == 1,
+        InvalidBackup {
+            description: format!(
+                "expected a single directory in {:?}, found {}",
+                prefix.display(),
+                directories.len()
+            ),
+        }
+    );
+    Ok(directories.remove(0))
+}
+
+/// Returns the name of the last directory of `path`
+fn directory_name(path: &Path) -> String {
+    path.display()
+        .trim_end_matches(DELIMITER)
+        .rsplit(DELIMITER)
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_helpers::write_lp, query_tests::utils::make_db};
+    use arrow_deps::assert_table_eq;
+    use data_types::{delete::DeletePredicate, timestamp::TimestampRange};
+    use object_store::memory::InMemory;
+    use query::{frontend::sql::SQLQueryPlanner, PartitionChunk};
+
+    async fn run_query(db: Arc<Db>, query: &str) -> Vec<RecordBatch> {
+        let planner = SQLQueryPlanner::default();
+        let executor = db.executor();
+
+        let physical_plan = planner.query(db, query, &executor).unwrap();
+
+        executor.collect(physical_plan).await.unwrap()
+    }
+
+    /// Writes `lp` into a new chunk of partition `1970-01-01T00` and
+    /// persists it
+    async fn persisted_chunk(db: &Db, lp: &str) {
+        write_lp(db, lp);
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore() {
+        let db = Arc::new(make_db());
+        persisted_chunk(&db, "cpu,region=west user=23.2 10").await;
+        persisted_chunk(&db, "cpu,region=east user=21.0 20").await;
+        persisted_chunk(&db, "cpu,region=north user=1.0 30").await;
+
+        // Rows deleted and chunks dropped after they were persisted do not
+        // come back when restored
+        db.delete(Arc::new(DeletePredicate {
+            table_name: "cpu".to_string(),
+            range: TimestampRange::new(0, 15),
+            exprs: vec![],
+        }))
+        .await
+        .unwrap();
+        db.drop_chunk("1970-01-01T00", 2).await.unwrap();
+
+        let target = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        db.backup(Arc::clone(&target)).await.unwrap();
+
+        // A backup is only written to an empty location
+        let err = db.backup(Arc::clone(&target)).await.unwrap_err();
+        assert!(matches!(err, super::super::Error::BackupTargetNotEmpty));
+
+        let backup = Backup::open(target).await.unwrap();
+        assert_eq!(backup.rules().name, db.rules.read().name);
+
+        let restored = Arc::new(make_db());
+        write_lp(&restored, "cpu,region=south user=2.0 40");
+        restored.restore(&backup).await.unwrap();
+
+        // The restored chunks are added with new ids
+        let mut chunk_ids: Vec<_> = restored
+            .preserved_catalog()
+            .chunks()
+            .await
+            .unwrap()
+            .iter()
+            .map(|chunk| chunk.chunk_id)
+            .collect();
+        chunk_ids.sort_unstable();
+        assert_eq!(chunk_ids, vec![1, 2]);
+
+        let expected = vec![
+            "+--------+-------------------------------+------+",
+            "| region | time                          | user |",
+            "+--------+-------------------------------+------+",
+            "| east   | 1970-01-01 00:00:00.000000020 | 21   |",
+            "| south  | 1970-01-01 00:00:00.000000040 | 2    |",
+            "+--------+-------------------------------+------+",
+        ];
+        let batches = run_query(Arc::clone(&restored), "select * from cpu order by region").await;
+        assert_table_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn backup_with_unpersisted_data() {
+        let db = Arc::new(make_db());
+        persisted_chunk(&db, "cpu,region=west user=23.2 10").await;
+        write_lp(&db, "cpu,region=north user=1.0 30");
+
+        // The backup would miss the data that has not been persisted
+        let target = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let err = db.backup(Arc::clone(&target)).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                super::super::Error::BackupUnpersistedChunks { num_chunks: 1 }
+            ),
+            "{}",
+            err
+        );
+
+        let err = Backup::open(target).await.unwrap_err();
+        assert!(matches!(err, super::super::Error::InvalidBackup { .. }));
+    }
+
+    #[tokio::test]
+    async fn open_invalid_backup() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let err = Backup::open(store).await.unwrap_err();
+        assert!(matches!(err, super::super::Error::InvalidBackup { .. }));
+    }
+}
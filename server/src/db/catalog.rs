@@ -1,10 +1,11 @@
 //! This module contains the implementation of the InfluxDB IOx Metadata catalog
 use std::any::Any;
 use std::{
-    collections::{btree_map::Entry, BTreeMap},
+    collections::{btree_map::Entry, BTreeMap, VecDeque},
     sync::Arc,
 };
 
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use snafu::{OptionExt, Snafu};
 
@@ -54,6 +55,9 @@ pub enum Error {
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// The number of evicted chunks remembered by the catalog
+const EVICTION_HISTORY_SIZE: usize = 1000;
+
 /// InfluxDB IOx Metadata Catalog
 ///
 /// The Catalog stores information such as which chunks exist, what
@@ -65,6 +69,10 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 pub struct Catalog {
     /// key is partition_key
     partitions: RwLock<BTreeMap<String, Arc<RwLock<Partition>>>>,
+
+    /// The most recently evicted chunks, oldest first, with the time they
+    /// were evicted at
+    evicted: RwLock<VecDeque<(ChunkSummary, DateTime<Utc>)>>,
 }
 
 impl Catalog {
@@ -154,6 +162,22 @@ impl Catalog {
         summaries
     }
 
+    /// Records that the chunk described by `summary` was evicted, as its
+    /// data was older than the retention period
+    pub fn record_eviction(&self, summary: ChunkSummary, time_evicted: DateTime<Utc>) {
+        let mut evicted = self.evicted.write();
+        if evicted.len() == EVICTION_HISTORY_SIZE {
+            evicted.pop_front();
+        }
+        evicted.push_back((summary, time_evicted));
+    }
+
+    /// Returns the summaries of the most recently evicted chunks, oldest
+    /// first, with the time they were evicted at
+    pub fn evicted_chunk_summaries(&self) -> Vec<(ChunkSummary, DateTime<Utc>)> {
+        self.evicted.read().iter().cloned().collect()
+    }
+
     /// Returns the chunks in the requested sort order
     pub fn chunks_sorted_by(&self, sort_rules: &SortOrder) -> Vec<Arc<RwLock<Chunk>>> {
         let mut chunks = self.chunks();
@@ -4,10 +4,12 @@ use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use data_types::{
     chunk::{ChunkStorage, ChunkSummary},
+    delete::DeletePredicate,
     partition_metadata::TableSummary,
 };
+use internal_types::entry::ClockValue;
 use mutable_buffer::chunk::Chunk as MBChunk;
-use parquet_file::chunk::Chunk as ParquetChunk;
+use parquet_file::{catalog::SequenceRange, chunk::Chunk as ParquetChunk};
 use read_buffer::Chunk as ReadBufferChunk;
 
 use super::{InternalChunkState, Result};
@@ -81,6 +83,13 @@ pub struct Chunk {
     /// Time at which this chunk was maked as closing. Note this is
     /// not the same as the timestamps on the data itself
     time_closing: Option<DateTime<Utc>>,
+
+    /// Sequence numbers of the writes into this chunk
+    sequence_range: Option<SequenceRange>,
+
+    /// Predicates describing rows that have been deleted from this chunk
+    /// but may still be present in its storage
+    delete_predicates: Vec<Arc<DeletePredicate>>,
 }
 
 macro_rules! unexpected_state {
@@ -106,6 +115,8 @@ impl Chunk {
             time_of_first_write: None,
             time_of_last_write: None,
             time_closing: None,
+            sequence_range: None,
+            delete_predicates: vec![],
         }
     }
 
@@ -119,6 +130,43 @@ impl Chunk {
         Self::new(partition_key, id, state)
     }
 
+    /// Creates a chunk that has been written to object storage, containing
+    /// the data of compacted chunks whose write times and sequence numbers it
+    /// inherits
+    pub(crate) fn new_compacted(
+        partition_key: impl Into<String>,
+        id: u32,
+        read_buffer: Arc<ReadBufferChunk>,
+        parquet_chunk: Arc<ParquetChunk>,
+        time_of_first_write: Option<DateTime<Utc>>,
+        time_of_last_write: Option<DateTime<Utc>>,
+        sequence_range: Option<SequenceRange>,
+    ) -> Self {
+        let state = ChunkState::WrittenToObjectStore(read_buffer, parquet_chunk);
+        Self {
+            time_of_first_write,
+            time_of_last_write,
+            sequence_range,
+            ..Self::new(partition_key, id, state)
+        }
+    }
+
+    /// Creates a chunk that has been restored from a backup and written to
+    /// object storage
+    pub(crate) fn new_restored(
+        partition_key: impl Into<String>,
+        id: u32,
+        read_buffer: Arc<ReadBufferChunk>,
+        parquet_chunk: Arc<ParquetChunk>,
+        sequence_range: Option<SequenceRange>,
+    ) -> Self {
+        let state = ChunkState::WrittenToObjectStore(read_buffer, parquet_chunk);
+        Self {
+            sequence_range,
+            ..Self::new(partition_key, id, state)
+        }
+    }
+
     /// Used for testing
     #[cfg(test)]
     pub(crate) fn set_timestamps(
@@ -154,13 +202,32 @@ impl Chunk {
         self.time_closing
     }
 
-    /// Update the write timestamps for this chunk
-    pub fn record_write(&mut self) {
+    pub fn sequence_range(&self) -> Option<SequenceRange> {
+        self.sequence_range
+    }
+
+    pub fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
+        &self.delete_predicates
+    }
+
+    /// Record that the rows matching `predicate` have been deleted from
+    /// this chunk
+    pub fn add_delete_predicate(&mut self, predicate: Arc<DeletePredicate>) {
+        self.delete_predicates.push(predicate)
+    }
+
+    /// Update the write timestamps and sequence range for this chunk
+    pub fn record_write(&mut self, clock_value: ClockValue) {
         let now = Utc::now();
         if self.time_of_first_write.is_none() {
             self.time_of_first_write = Some(now);
         }
         self.time_of_last_write = Some(now);
+
+        match &mut self.sequence_range {
+            Some(range) => range.update(clock_value.get()),
+            None => self.sequence_range = Some(SequenceRange::new(clock_value.get())),
+        }
     }
 
     /// Return ChunkSummary metadata for this chunk
@@ -76,16 +76,30 @@ impl Partition {
         self.last_write_at
     }
 
-    /// Create a new Chunk in the open state
-    pub fn create_open_chunk(&mut self, memory_registry: &MemRegistry) -> Arc<RwLock<Chunk>> {
+    /// Allocate the id of a new chunk in this partition
+    pub fn next_chunk_id(&mut self) -> u32 {
         let chunk_id = self.next_chunk_id;
         self.next_chunk_id += 1;
+        chunk_id
+    }
 
-        let chunk = Arc::new(RwLock::new(Chunk::new_open(
-            &self.key,
-            chunk_id,
-            memory_registry,
-        )));
+    /// Makes sure that no chunk id below `chunk_id` is allocated, e.g.
+    /// because these ids belong to chunks in the preserved catalog
+    pub fn skip_chunk_ids_below(&mut self, chunk_id: u32) {
+        self.next_chunk_id = self.next_chunk_id.max(chunk_id);
+    }
+
+    /// Create a new Chunk in the open state
+    pub fn create_open_chunk(&mut self, memory_registry: &MemRegistry) -> Arc<RwLock<Chunk>> {
+        let chunk_id = self.next_chunk_id();
+        self.insert_chunk(Chunk::new_open(&self.key, chunk_id, memory_registry))
+    }
+
+    /// Add a chunk whose id was allocated with
+    /// [`next_chunk_id`](Self::next_chunk_id) to this partition
+    pub fn insert_chunk(&mut self, chunk: Chunk) -> Arc<RwLock<Chunk>> {
+        let chunk_id = chunk.id();
+        let chunk = Arc::new(RwLock::new(chunk));
 
         if self.chunks.insert(chunk_id, Arc::clone(&chunk)).is_some() {
             // A fundamental invariant has been violated - abort
@@ -1,4 +1,5 @@
 use arrow_deps::datafusion::physical_plan::SendableRecordBatchStream;
+use data_types::delete::DeletePredicate;
 use internal_types::{schema::Schema, selection::Selection};
 use mutable_buffer::chunk::snapshot::ChunkSnapshot;
 use object_store::path::Path;
@@ -68,17 +69,23 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// A IOx DatabaseChunk can come from one of three places:
 /// MutableBuffer, ReadBuffer, or a ParquetFile
+///
+/// Each variant carries the delete predicates of the chunk at the time of
+/// the snapshot
 #[derive(Debug)]
 pub enum DBChunk {
     MutableBuffer {
         chunk: Arc<ChunkSnapshot>,
+        delete_predicates: Vec<Arc<DeletePredicate>>,
     },
     ReadBuffer {
         chunk: Arc<ReadBufferChunk>,
         partition_key: Arc<String>,
+        delete_predicates: Vec<Arc<DeletePredicate>>,
     },
     ParquetFile {
         chunk: Arc<ParquetChunk>,
+        delete_predicates: Vec<Arc<DeletePredicate>>,
     },
 }
 
@@ -87,6 +94,8 @@ impl DBChunk {
     pub fn snapshot(chunk: &super::catalog::chunk::Chunk) -> Arc<Self> {
         let partition_key = Arc::new(chunk.key().to_string());
 
+        let delete_predicates = chunk.delete_predicates().to_vec();
+
         use super::catalog::chunk::ChunkState;
 
         let db_chunk = match chunk.state() {
@@ -95,21 +104,28 @@ impl DBChunk {
             }
             ChunkState::Open(chunk) | ChunkState::Closing(chunk) => Self::MutableBuffer {
                 chunk: chunk.snapshot(),
+                delete_predicates,
             },
             ChunkState::Moving(chunk) => Self::MutableBuffer {
                 chunk: chunk.snapshot(),
+                delete_predicates,
             },
             ChunkState::Moved(chunk) => Self::ReadBuffer {
                 chunk: Arc::clone(chunk),
                 partition_key,
+                delete_predicates,
             },
             ChunkState::WritingToObjectStore(chunk) => Self::ReadBuffer {
                 chunk: Arc::clone(chunk),
                 partition_key,
+                delete_predicates,
             },
             ChunkState::WrittenToObjectStore(_, chunk) => {
                 let chunk = Arc::clone(chunk);
-                Self::ParquetFile { chunk }
+                Self::ParquetFile {
+                    chunk,
+                    delete_predicates,
+                }
             }
         };
         Arc::new(db_chunk)
@@ -118,10 +134,19 @@ impl DBChunk {
     /// Return object store paths
     pub fn object_store_paths(&self) -> Vec<Path> {
         match self {
-            Self::ParquetFile { chunk } => chunk.all_paths(),
+            Self::ParquetFile { chunk, .. } => chunk.all_paths(),
             _ => vec![],
         }
     }
+
+    /// Returns true if rows of the table `table_name` have been deleted
+    /// from this chunk, in which case questions about the table cannot be
+    /// answered from the metadata of the chunk alone
+    fn has_deletes(&self, table_name: &str) -> bool {
+        self.delete_predicates()
+            .iter()
+            .any(|predicate| predicate.table_name == table_name)
+    }
 }
 
 impl PartitionChunk for DBChunk {
@@ -182,12 +207,17 @@ impl PartitionChunk for DBChunk {
 
         // Prune out tables that should not be
         // present (based on additional table restrictions of the Predicate)
-        Ok(Some(
-            names
-                .into_iter()
-                .filter(|table_name| predicate.should_include_table(table_name))
-                .collect(),
-        ))
+        let names: StringSet = names
+            .into_iter()
+            .filter(|table_name| predicate.should_include_table(table_name))
+            .collect();
+
+        // Deleted rows may be the only rows of a table that match
+        if names.iter().any(|table_name| self.has_deletes(table_name)) {
+            return Ok(None);
+        }
+
+        Ok(Some(names))
     }
 
     fn table_schema(
@@ -240,6 +270,20 @@ impl PartitionChunk for DBChunk {
         }
     }
 
+    fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
+        match self {
+            Self::MutableBuffer {
+                delete_predicates, ..
+            }
+            | Self::ReadBuffer {
+                delete_predicates, ..
+            }
+            | Self::ParquetFile {
+                delete_predicates, ..
+            } => delete_predicates,
+        }
+    }
+
     fn read_filter(
         &self,
         table_name: &str,
@@ -320,6 +364,10 @@ impl PartitionChunk for DBChunk {
         predicate: &Predicate,
         columns: Selection<'_>,
     ) -> Result<Option<StringSet>, Self::Error> {
+        if self.has_deletes(table_name) {
+            return Ok(None);
+        }
+
         match self {
             Self::MutableBuffer { chunk, .. } => {
                 if !predicate.is_empty() {
@@ -361,6 +409,10 @@ impl PartitionChunk for DBChunk {
         column_name: &str,
         predicate: &Predicate,
     ) -> Result<Option<StringSet>, Self::Error> {
+        if self.has_deletes(table_name) {
+            return Ok(None);
+        }
+
         match self {
             Self::MutableBuffer { .. } => {
                 // There is no advantage to manually implementing this
@@ -0,0 +1,599 @@
+//! Compaction of the persisted chunks of a partition.
+//!
+//! Every chunk that moves through the lifecycle is written to its own set of
+//! parquet files. Compaction merges the data of several persisted chunks of
+//! a partition, sorts it on its tags and time, removes duplicate and deleted
+//! rows and writes the result as a single new chunk, which then replaces the
+//! compacted chunks in both the preserved and the in-memory catalog.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use arrow_deps::arrow::{
+    array::{Array, Int64Array, StringArray, UInt32Array},
+    compute::{cast, take},
+    datatypes::DataType,
+    error::Result as ArrowResult,
+    record_batch::RecordBatch,
+};
+use internal_types::{
+    schema::{Schema, TIME_COLUMN_NAME},
+    selection::Selection,
+};
+use observability_deps::tracing::{debug, info};
+use parquet_file::catalog::{Action, ChunkMetadata, SequenceRange};
+use read_buffer::Chunk as ReadBufferChunk;
+use snafu::{ensure, ResultExt};
+
+use data_types::{delete::DeletePredicate, job::Job};
+use tracker::{TaskTracker, TrackedFutureExt};
+
+use super::{
+    catalog::chunk::{Chunk as CatalogChunk, ChunkState},
+    sort::{concat_batches, sort_order},
+    CommittingCatalogTransaction, CompactingChunks, CompactingUnpersistedChunk, DBChunk, Db,
+    FilteringDeletedRows, MergingChunkData, MergingChunkSchemas, NoChunksToCompact,
+    ReadBufferChunkError, ReadBufferChunkSchemaError, Result,
+};
+
+impl Db {
+    /// Compacts the persisted chunks `chunk_ids` of the partition
+    /// `partition_key` into a single new chunk, which is written to object
+    /// storage and replaces the compacted chunks.
+    ///
+    /// The data of the new chunk is sorted on its tag columns and time.
+    /// Rows with the same tag values and timestamp are merged into a
+    /// single row, in which the value of each field is taken from the most
+    /// recent chunk that has the field set. Rows deleted from a compacted
+    /// chunk are removed.
+    ///
+    /// Returns a handle to the new chunk
+    pub async fn compact_chunks(
+        &self,
+        partition_key: &str,
+        chunk_ids: &[u32],
+    ) -> Result<Arc<DBChunk>> {
+        ensure!(!chunk_ids.is_empty(), NoChunksToCompact { partition_key });
+
+        let partition = self
+            .catalog
+            .valid_partition(partition_key)
+            .context(CompactingChunks { partition_key })?;
+
+        // Collect the read buffer chunks to compact, oldest first, and
+        // allocate the id of the new chunk
+        let mut chunk_ids = chunk_ids.to_vec();
+        chunk_ids.sort_unstable();
+        chunk_ids.dedup();
+
+        let mut rb_chunks = Vec::with_capacity(chunk_ids.len());
+        let mut time_of_first_write = None;
+        let mut time_of_last_write = None;
+        let mut sequence_range: Option<SequenceRange> = None;
+        let chunk_id = {
+            let mut partition = partition.write();
+
+            for &chunk_id in &chunk_ids {
+                let chunk = partition
+                    .chunk(chunk_id)
+                    .context(CompactingChunks { partition_key })?;
+                let chunk = chunk.read();
+
+                match chunk.state() {
+                    ChunkState::WrittenToObjectStore(rb_chunk, _) => {
+                        rb_chunks.push((Arc::clone(rb_chunk), chunk.delete_predicates().to_vec()))
+                    }
+                    state => {
+                        return CompactingUnpersistedChunk {
+                            partition_key,
+                            chunk_id,
+                            chunk_state: state.name(),
+                        }
+                        .fail()
+                    }
+                }
+
+                time_of_first_write = match (time_of_first_write, chunk.time_of_first_write()) {
+                    (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+                    (a, b) => a.or(b),
+                };
+                time_of_last_write = match (time_of_last_write, chunk.time_of_last_write()) {
+                    (Some(a), Some(b)) => Some(std::cmp::max(a, b)),
+                    (a, b) => a.or(b),
+                };
+                if let Some(range) = chunk.sequence_range() {
+                    match &mut sequence_range {
+                        Some(merged) => {
+                            merged.update(range.min);
+                            merged.update(range.max);
+                        }
+                        None => sequence_range = Some(range),
+                    }
+                }
+            }
+
+            partition.next_chunk_id()
+        };
+
+        info!(%partition_key, ?chunk_ids, %chunk_id, "compacting chunks");
+
+        let rb_chunk = self.merge_read_buffer_chunks(chunk_id, &rb_chunks)?;
+
+        // The deleted rows have been removed while merging
+        let (parquet_chunk, tables) = self
+            .write_read_buffer_chunk(partition_key, chunk_id, &rb_chunk, sequence_range, &[])
+            .await?;
+
+        // Swap the chunks in the preserved catalog in a single transaction
+        let mut actions = vec![Action::AddChunk(ChunkMetadata {
+            partition_key: partition_key.to_string(),
+            chunk_id,
+            sequence_range,
+            tables,
+            delete_predicates: vec![],
+        })];
+        actions.extend(chunk_ids.iter().map(|&chunk_id| Action::RemoveChunk {
+            partition_key: partition_key.to_string(),
+            chunk_id,
+        }));
+        self.preserved_catalog
+            .commit(actions)
+            .await
+            .context(CommittingCatalogTransaction)?;
+
+        // And then in memory, where compacted chunks may have been dropped
+        // in the meantime to free up memory. Rows that were deleted from the
+        // compacted chunks while they were merged are deleted from the new
+        // chunk instead
+        let mut delete_predicates: Vec<Arc<DeletePredicate>> = vec![];
+        let snapshot = {
+            let mut partition = partition.write();
+            for (&chunk_id, (_, applied)) in chunk_ids.iter().zip(&rb_chunks) {
+                if let Ok(chunk) = partition.chunk(chunk_id) {
+                    for predicate in &chunk.read().delete_predicates()[applied.len()..] {
+                        if !delete_predicates.iter().any(|p| Arc::ptr_eq(p, predicate)) {
+                            delete_predicates.push(Arc::clone(predicate));
+                        }
+                    }
+                }
+                if partition.drop_chunk(chunk_id).is_err() {
+                    debug!(%partition_key, %chunk_id, "compacted chunk was already dropped");
+                }
+            }
+            let mut new_chunk = CatalogChunk::new_compacted(
+                partition_key,
+                chunk_id,
+                Arc::new(rb_chunk),
+                Arc::new(parquet_chunk),
+                time_of_first_write,
+                time_of_last_write,
+                sequence_range,
+            );
+            for predicate in &delete_predicates {
+                new_chunk.add_delete_predicate(Arc::clone(predicate));
+            }
+            let chunk = partition.insert_chunk(new_chunk);
+            let chunk = chunk.read();
+            DBChunk::snapshot(&chunk)
+        };
+
+        // Deletes from now on record themselves for the new chunk
+        self.record_delete_predicates(partition_key, chunk_id, &delete_predicates)
+            .await?;
+
+        debug!(%partition_key, ?chunk_ids, %chunk_id, "compaction complete");
+
+        Ok(snapshot)
+    }
+
+    /// Spawns a task to perform
+    /// [`compact_chunks`](Self::compact_chunks)
+    pub fn compact_chunks_in_background(
+        self: &Arc<Self>,
+        partition_key: String,
+        chunk_ids: Vec<u32>,
+    ) -> TaskTracker<Job> {
+        let name = self.rules.read().name.clone();
+        let (tracker, registration) = self.jobs.register(Job::CompactChunks {
+            db_name: name.to_string(),
+            partition_key: partition_key.clone(),
+            chunk_ids: chunk_ids.clone(),
+        });
+
+        let captured = Arc::clone(&self);
+        let task = async move {
+            debug!(%name, %partition_key, ?chunk_ids, "background task compacting chunks");
+            let result = captured.compact_chunks(&partition_key, &chunk_ids).await;
+            if let Err(e) = result {
+                info!(?e, %name, %partition_key, ?chunk_ids, "background task error compacting chunks");
+                return Err(e);
+            }
+
+            debug!(%name, %partition_key, ?chunk_ids, "background task completed compacting chunks");
+
+            Ok(())
+        };
+
+        tokio::spawn(task.track(registration));
+
+        tracker
+    }
+
+    /// Merge the tables of `rb_chunks`, which must be ordered from oldest to
+    /// newest, into a new read buffer chunk, leaving out the rows matching
+    /// the delete predicates of each chunk
+    fn merge_read_buffer_chunks(
+        &self,
+        chunk_id: u32,
+        rb_chunks: &[(Arc<ReadBufferChunk>, Vec<Arc<DeletePredicate>>)],
+    ) -> Result<ReadBufferChunk> {
+        let mut tables: BTreeMap<String, (Option<Schema>, Vec<RecordBatch>)> = BTreeMap::new();
+
+        for (rb_chunk, delete_predicates) in rb_chunks {
+            for table_name in rb_chunk.all_table_names(&BTreeSet::new()) {
+                let chunk_schema = rb_chunk
+                    .read_filter_table_schema(&table_name, Selection::All)
+                    .context(ReadBufferChunkSchemaError {
+                        chunk_id: rb_chunk.id(),
+                    })?;
+                let read_results = rb_chunk
+                    .read_filter(
+                        &table_name,
+                        read_buffer::Predicate::default(),
+                        Selection::All,
+                    )
+                    .context(ReadBufferChunkError {
+                        chunk_id: rb_chunk.id(),
+                    })?;
+
+                let (schema, batches) = tables.entry(table_name.clone()).or_default();
+                *schema = Some(match schema.take() {
+                    Some(schema) => {
+                        schema
+                            .try_merge(chunk_schema)
+                            .context(MergingChunkSchemas {
+                                table_name: &table_name,
+                            })?
+                    }
+                    None => chunk_schema,
+                });
+
+                let table_deletes: Vec<_> = delete_predicates
+                    .iter()
+                    .filter(|predicate| predicate.table_name == table_name)
+                    .cloned()
+                    .collect();
+                for batch in read_results {
+                    batches.push(
+                        query::delete::filter_deleted(batch, &table_deletes).context(
+                            FilteringDeletedRows {
+                                table_name: &table_name,
+                            },
+                        )?,
+                    );
+                }
+            }
+        }
+
+        let rb_chunk =
+            ReadBufferChunk::new_with_memory_tracker(chunk_id, &self.memory_registries.read_buffer);
+
+        for (table_name, (schema, batches)) in tables {
+            if let (Some(schema), false) = (schema, batches.is_empty()) {
+                let batch = merge_batches(&schema, &batches).context(MergingChunkData {
+                    table_name: &table_name,
+                })?;
+                rb_chunk.upsert_table(table_name, batch);
+            }
+        }
+
+        Ok(rb_chunk)
+    }
+}
+
+/// Merge `batches`, which must be ordered from oldest to newest data, into a
+/// single batch with the given `schema`.
+///
+/// The rows of the merged batch are sorted on the tag columns (by column
+/// name) and time. Rows with the same tag values and timestamp are merged
+/// into a single row, in which each field has the value of the last such row
+/// that has the field set.
+fn merge_batches(schema: &Schema, batches: &[RecordBatch]) -> ArrowResult<RecordBatch> {
+    let arrow_schema = schema.as_arrow();
+    let columns = concat_batches(schema, batches)?;
+    let num_rows = batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+
+    let mut tag_names: Vec<_> = schema.tags_iter().map(|field| field.name()).collect();
+    tag_names.sort();
+    let tags = tag_names
+        .into_iter()
+        .filter_map(|name| schema.find_index_of(name))
+        .map(|idx| cast(&columns[idx], &DataType::Utf8))
+        .collect::<ArrowResult<Vec<_>>>()?;
+    let time = schema
+        .find_index_of(TIME_COLUMN_NAME)
+        .map(|idx| cast(&columns[idx], &DataType::Int64))
+        .transpose()?;
+
+    // Sort on the tags, time and finally the position of each row, so that
+    // duplicate rows remain ordered from oldest to newest
+    let key: Vec<_> = tags.iter().chain(time.iter()).cloned().collect();
+    let order = sort_order(&key, num_rows)?;
+
+    // Rows without a timestamp cannot be duplicates of each other
+    let mut indices: Vec<Vec<u32>> = vec![Vec::with_capacity(num_rows); columns.len()];
+    match time {
+        Some(time) => {
+            let tags: Vec<_> = tags
+                .iter()
+                .map(|tag| StringArray::from(tag.data().clone()))
+                .collect();
+            let time = Int64Array::from(time.data().clone());
+            let same_key = |a: usize, b: usize| {
+                time.value(a) == time.value(b)
+                    && tags
+                        .iter()
+                        .all(|tag| match (tag.is_valid(a), tag.is_valid(b)) {
+                            (true, true) => tag.value(a) == tag.value(b),
+                            (valid_a, valid_b) => valid_a == valid_b,
+                        })
+            };
+
+            let mut start = 0;
+            while start < order.len() {
+                let mut end = start + 1;
+                while end < order.len() && same_key(order[start], order[end]) {
+                    end += 1;
+                }
+
+                let group = &order[start..end];
+                let last = group[group.len() - 1];
+                for (column, indices) in columns.iter().zip(indices.iter_mut()) {
+                    let row = group
+                        .iter()
+                        .rev()
+                        .find(|&&row| column.is_valid(row))
+                        .copied()
+                        .unwrap_or(last);
+                    indices.push(row as u32);
+                }
+
+                start = end;
+            }
+        }
+        None => {
+            for indices in indices.iter_mut() {
+                indices.extend(order.iter().map(|&row| row as u32));
+            }
+        }
+    }
+
+    let columns = columns
+        .iter()
+        .zip(indices)
+        .map(|(column, indices)| take(column.as_ref(), &UInt32Array::from(indices), None))
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(arrow_schema, columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_helpers::write_lp, query_tests::utils::make_db};
+    use arrow_deps::{
+        arrow::array::{Float64Array, TimestampNanosecondArray},
+        assert_table_eq,
+    };
+    use internal_types::schema::{builder::SchemaBuilder, InfluxFieldType};
+    use query::{frontend::sql::SQLQueryPlanner, PartitionChunk};
+
+    async fn run_query(db: Arc<Db>, query: &str) -> Vec<RecordBatch> {
+        let planner = SQLQueryPlanner::default();
+        let executor = db.executor();
+
+        let physical_plan = planner.query(db, query, &executor).unwrap();
+
+        executor.collect(physical_plan).await.unwrap()
+    }
+
+    /// Writes `lp` into a new chunk of partition `1970-01-01T00` and
+    /// persists it, returning the id of the chunk
+    async fn persisted_chunk(db: &Db, lp: &str) -> u32 {
+        write_lp(db, lp);
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        mb_chunk.id()
+    }
+
+    fn chunk_ids(db: &Db) -> Vec<u32> {
+        let mut ids: Vec<_> = db
+            .partition_chunk_summaries("1970-01-01T00")
+            .into_iter()
+            .map(|summary| summary.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn merge_batches_sorts_and_deduplicates() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .influx_field("usage", InfluxFieldType::Float)
+            .influx_field("status", InfluxFieldType::String)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let old_schema = SchemaBuilder::new()
+            .tag("host")
+            .influx_field("usage", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+        let old = RecordBatch::try_new(
+            old_schema.as_arrow(),
+            vec![
+                Arc::new(StringArray::from(vec!["b", "a", "a"])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+                Arc::new(TimestampNanosecondArray::from_vec(vec![10, 20, 10], None)),
+            ],
+        )
+        .unwrap();
+
+        let new = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Float64Array::from(vec![None, Some(5.0)])),
+                Arc::new(StringArray::from(vec![Some("ok"), None])),
+                Arc::new(TimestampNanosecondArray::from_vec(vec![10, 30], None)),
+            ],
+        )
+        .unwrap();
+
+        let merged = merge_batches(&schema, &[old, new]).unwrap();
+
+        let expected = vec![
+            "+------+-------+--------+-------------------------------+",
+            "| host | usage | status | time                          |",
+            "+------+-------+--------+-------------------------------+",
+            "| a    | 3     | ok     | 1970-01-01 00:00:00.000000010 |",
+            "| a    | 2     |        | 1970-01-01 00:00:00.000000020 |",
+            "| b    | 1     |        | 1970-01-01 00:00:00.000000010 |",
+            "| b    | 5     |        | 1970-01-01 00:00:00.000000030 |",
+            "+------+-------+--------+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &[merged]);
+    }
+
+    #[tokio::test]
+    async fn compact_chunks() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        let first = persisted_chunk(&db, "cpu,host=a usage=1 10\ncpu,host=b usage=2 10").await;
+        let second = persisted_chunk(&db, "cpu,host=a usage=3 10\nmem,host=a free=1 20").await;
+        assert_eq!(chunk_ids(&db), vec![first, second, 2]);
+
+        let chunk = db
+            .compact_chunks(partition_key, &[first, second])
+            .await
+            .unwrap();
+        assert_eq!(chunk.id(), 3);
+
+        // the open chunk is unaffected
+        assert_eq!(chunk_ids(&db), vec![2, 3]);
+
+        let expected = vec![
+            "+------+-------+-------------------------------+",
+            "| host | usage | time                          |",
+            "+------+-------+-------------------------------+",
+            "| a    | 3     | 1970-01-01 00:00:00.000000010 |",
+            "| b    | 2     | 1970-01-01 00:00:00.000000010 |",
+            "+------+-------+-------------------------------+",
+        ];
+        let batches = run_query(
+            Arc::clone(&db),
+            "select host, usage, time from cpu order by host",
+        )
+        .await;
+        assert_table_eq!(expected, &batches);
+
+        let batches = run_query(Arc::clone(&db), "select * from mem").await;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        // the preserved catalog only contains the new chunk
+        let chunks = db.preserved_catalog().chunks().await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].chunk_id, 3);
+        assert_eq!(chunks[0].tables.len(), 2);
+
+        let summary = db
+            .partition_chunk_summaries(partition_key)
+            .into_iter()
+            .find(|summary| summary.id == 3)
+            .unwrap();
+        assert_eq!(
+            summary.storage,
+            data_types::chunk::ChunkStorage::ReadBufferAndObjectStore
+        );
+
+        // the files of the compacted chunks are no longer referenced
+        let orphaned = db
+            .find_orphaned_files(std::time::Duration::from_secs(0))
+            .await
+            .unwrap();
+        assert_eq!(orphaned.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn compact_unpersisted_chunk_is_an_error() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        let persisted = persisted_chunk(&db, "cpu bar=1 10").await;
+        write_lp(&db, "cpu bar=2 20");
+        let open = chunk_ids(&db)[1];
+
+        let err = db
+            .compact_chunks(partition_key, &[persisted, open])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, crate::db::Error::CompactingUnpersistedChunk { .. }),
+            "{}",
+            err
+        );
+
+        let err = db.compact_chunks(partition_key, &[]).await.unwrap_err();
+        assert!(
+            matches!(err, crate::db::Error::NoChunksToCompact { .. }),
+            "{}",
+            err
+        );
+
+        // nothing was changed
+        assert_eq!(chunk_ids(&db), vec![persisted, open]);
+        assert_eq!(db.preserved_catalog().chunks().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn compact_chunks_in_background() {
+        let db = Arc::new(make_db());
+        let partition_key = "1970-01-01T00";
+
+        let first = persisted_chunk(&db, "cpu bar=1 10").await;
+        let second = persisted_chunk(&db, "cpu bar=2 20").await;
+
+        let task = db.compact_chunks_in_background(partition_key.to_string(), vec![first, second]);
+        let t_start = std::time::Instant::now();
+        while !task.is_complete() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            assert!(
+                std::time::Instant::now() - t_start < std::time::Duration::from_secs(10),
+                "task deadline exceeded"
+            );
+        }
+
+        assert_eq!(chunk_ids(&db), vec![2, 3]);
+        assert_eq!(
+            task.metadata(),
+            &Job::CompactChunks {
+                db_name: "placeholder".to_string(),
+                partition_key: partition_key.to_string(),
+                chunk_ids: vec![first, second],
+            }
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Garbage collection of files in object storage that are no longer
+//! referenced by the catalog of a `Db`.
+//!
+//! Parquet files are orphaned when a chunk is dropped after it was written to
+//! object storage, or when a server crashes part way through writing a chunk.
+//! WAL segments are not collected: the catalog does not record which
+//! segments have been fully persisted, so there is nothing to tell an
+//! orphaned segment from one still needed to replay unpersisted writes.
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use object_store::{
+    path::{ObjectStorePath, Path, DELIMITER},
+    ObjectStoreApi,
+};
+use observability_deps::tracing::{debug, info};
+use snafu::ResultExt;
+
+use data_types::job::Job;
+use parquet_file::storage::Storage;
+use tracker::{TaskTracker, TrackedFutureExt};
+
+use super::{
+    catalog::chunk::ChunkState, Db, DeletingOrphanedFile, ListingObjectStore,
+    ReadingObjectMetadata, ReadingPreservedCatalog, Result,
+};
+
+impl Db {
+    /// Return the paths of all files below this database's data directory in
+    /// object storage that are neither referenced by the in-memory nor the
+    /// preserved catalog and were last modified at least `grace_period` ago.
+    ///
+    /// Files of chunks that are currently being written are never returned,
+    /// and the grace period protects files written by other processes that
+    /// have not made it into the catalog yet.
+    pub async fn find_orphaned_files(&self, grace_period: Duration) -> Result<Vec<Path>> {
+        let storage = Storage::new(
+            Arc::clone(&self.store),
+            self.server_id,
+            self.rules.read().name.to_string(),
+        );
+
+        // List before inspecting the catalog: any file that was present when
+        // listing belongs to a chunk that is either still in the catalog or
+        // has been dropped by the time the catalog is inspected.
+        let files: Vec<Path> = self
+            .store
+            .list(Some(&storage.data_location()))
+            .await
+            .context(ListingObjectStore)?
+            .try_concat()
+            .await
+            .context(ListingObjectStore)?;
+
+        let mut referenced: BTreeSet<_> = self
+            .preserved_catalog
+            .chunks()
+            .await
+            .context(ReadingPreservedCatalog)?
+            .iter()
+            .flat_map(|chunk| chunk.paths(&storage))
+            .map(|path| path.display())
+            .collect();
+        let mut in_progress = Vec::new();
+        for chunk in self.catalog.chunks() {
+            let chunk = chunk.read();
+            match chunk.state() {
+                ChunkState::WritingToObjectStore(_) => {
+                    let mut dir = storage
+                        .chunk_location(chunk.key().to_string(), chunk.id())
+                        .display();
+                    if !dir.ends_with(DELIMITER) {
+                        dir.push_str(DELIMITER);
+                    }
+                    in_progress.push(dir);
+                }
+                ChunkState::WrittenToObjectStore(_, parquet_chunk) => {
+                    referenced.extend(parquet_chunk.all_paths().iter().map(|p| p.display()));
+                }
+                _ => {}
+            }
+        }
+
+        let grace_period = chrono::Duration::from_std(grace_period)
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+        let now = Utc::now();
+
+        let mut orphaned = Vec::new();
+        for path in files {
+            let display = path.display();
+            if referenced.contains(&display)
+                || in_progress.iter().any(|dir| display.starts_with(dir))
+            {
+                continue;
+            }
+
+            let meta = self
+                .store
+                .head(&path)
+                .await
+                .context(ReadingObjectMetadata { path: &display })?;
+
+            if now.signed_duration_since(meta.last_modified) >= grace_period {
+                orphaned.push(path);
+            }
+        }
+
+        Ok(orphaned)
+    }
+
+    /// Delete all files returned by
+    /// [`find_orphaned_files`](Self::find_orphaned_files), returning the
+    /// paths of the deleted files.
+    pub async fn collect_garbage(&self, grace_period: Duration) -> Result<Vec<Path>> {
+        let orphaned = self.find_orphaned_files(grace_period).await?;
+
+        for path in &orphaned {
+            debug!(path=%path.display(), "deleting orphaned file");
+            self.store
+                .delete(path)
+                .await
+                .context(DeletingOrphanedFile {
+                    path: path.display(),
+                })?;
+        }
+
+        Ok(orphaned)
+    }
+
+    /// Spawns a task to perform
+    /// [`collect_garbage`](Self::collect_garbage)
+    pub fn collect_garbage_in_background(
+        self: &Arc<Self>,
+        grace_period: Duration,
+    ) -> TaskTracker<Job> {
+        let name = self.rules.read().name.clone();
+        let (tracker, registration) = self.jobs.register(Job::CollectGarbage {
+            db_name: name.to_string(),
+        });
+
+        let captured = Arc::clone(&self);
+        let task = async move {
+            debug!(%name, "background task collecting garbage");
+            let deleted = match captured.collect_garbage(grace_period).await {
+                Ok(deleted) => deleted,
+                Err(e) => {
+                    info!(?e, %name, "background task error collecting garbage");
+                    return Err(e);
+                }
+            };
+
+            info!(%name, deleted=deleted.len(), "background task completed collecting garbage");
+
+            Ok(())
+        };
+
+        tokio::spawn(task.track(registration));
+
+        tracker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_helpers::write_lp, query_tests::utils::make_database};
+    use bytes::Bytes;
+    use object_store::{disk::File, ObjectStore};
+    use std::num::NonZeroU32;
+    use tempfile::TempDir;
+
+    const ONE_HOUR: Duration = Duration::from_secs(60 * 60);
+
+    /// Creates a database backed by a local disk object store that has a
+    /// single chunk written to object storage in partition `1970-01-01T00`
+    async fn db_with_persisted_chunk(root: &TempDir) -> (Arc<Db>, Storage) {
+        let object_store = Arc::new(ObjectStore::new_file(File::new(root.path())));
+        let server_id = NonZeroU32::new(10).unwrap();
+        let db_name = "garbage_test_db";
+        let db = Arc::new(make_database(server_id, Arc::clone(&object_store), db_name));
+
+        write_lp(db.as_ref(), "cpu bar=1 10");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        let storage = Storage::new(object_store, server_id, db_name.to_string());
+        (db, storage)
+    }
+
+    async fn put_orphan(db: &Db, location: &Path) {
+        let data = Bytes::from("orphaned data");
+        let len = data.len();
+        db.store
+            .put(
+                location,
+                futures::stream::once(async move { std::io::Result::Ok(data) }),
+                Some(len),
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn list_data(db: &Db, storage: &Storage) -> Vec<String> {
+        let mut paths: Vec<_> = db
+            .store
+            .list(Some(&storage.data_location()))
+            .await
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.display())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn referenced_files_are_kept() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+
+        let before = list_data(&db, &storage).await;
+        assert_eq!(before.len(), 1);
+
+        assert!(db
+            .find_orphaned_files(Duration::from_secs(0))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .collect_garbage(Duration::from_secs(0))
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(list_data(&db, &storage).await, before);
+    }
+
+    #[tokio::test]
+    async fn orphaned_files_are_deleted() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+        let referenced = list_data(&db, &storage).await;
+
+        let orphan = storage.location("1970-01-01T00".to_string(), 42, "cpu".to_string());
+        put_orphan(&db, &orphan).await;
+
+        let orphaned = db
+            .find_orphaned_files(Duration::from_secs(0))
+            .await
+            .unwrap();
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].display(), orphan.display());
+
+        // a dry run doesn't delete anything
+        assert_eq!(list_data(&db, &storage).await.len(), 2);
+
+        let deleted = db.collect_garbage(Duration::from_secs(0)).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].display(), orphan.display());
+
+        assert_eq!(list_data(&db, &storage).await, referenced);
+    }
+
+    #[tokio::test]
+    async fn recent_orphans_are_kept_during_grace_period() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+
+        let orphan = storage.location("1970-01-01T00".to_string(), 42, "cpu".to_string());
+        put_orphan(&db, &orphan).await;
+
+        assert!(db.find_orphaned_files(ONE_HOUR).await.unwrap().is_empty());
+        assert!(db.collect_garbage(ONE_HOUR).await.unwrap().is_empty());
+        assert_eq!(list_data(&db, &storage).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn files_of_dropped_chunks_are_deleted() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+
+        db.drop_chunk("1970-01-01T00", 0).await.unwrap();
+
+        let deleted = db.collect_garbage(Duration::from_secs(0)).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(list_data(&db, &storage).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn files_of_chunks_unloaded_from_memory_are_kept() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+
+        db.unload_chunk("1970-01-01T00", 0).unwrap();
+
+        assert!(db
+            .collect_garbage(Duration::from_secs(0))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(list_data(&db, &storage).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn collect_garbage_in_background() {
+        let root = TempDir::new().unwrap();
+        let (db, storage) = db_with_persisted_chunk(&root).await;
+
+        let orphan = storage.location("1970-01-01T00".to_string(), 42, "cpu".to_string());
+        put_orphan(&db, &orphan).await;
+
+        let task = db.collect_garbage_in_background(Duration::from_secs(0));
+        let t_start = std::time::Instant::now();
+        while !task.is_complete() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            assert!(
+                std::time::Instant::now() - t_start < std::time::Duration::from_secs(10),
+                "task deadline exceeded"
+            );
+        }
+
+        assert_eq!(list_data(&db, &storage).await.len(), 1);
+        assert_eq!(
+            task.metadata(),
+            &Job::CollectGarbage {
+                db_name: "garbage_test_db".to_string()
+            }
+        );
+    }
+}
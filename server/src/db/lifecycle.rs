@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::sync::Arc;
 
@@ -21,6 +22,7 @@ pub struct LifecycleManager {
     db_name: String,
     move_task: Option<TaskTracker<Job>>,
     write_task: Option<TaskTracker<Job>>,
+    compact_task: Option<TaskTracker<Job>>,
 }
 
 impl LifecycleManager {
@@ -32,6 +34,7 @@ impl LifecycleManager {
             db_name,
             move_task: None,
             write_task: None,
+            compact_task: None,
         }
     }
 
@@ -70,6 +73,9 @@ trait ChunkMover {
     /// Returns a boolean indicating if a write is in progress
     fn is_write_active(&self) -> bool;
 
+    /// Returns a boolean indicating if a compaction is in progress
+    fn is_compaction_active(&self) -> bool;
+
     /// Starts an operation to move a chunk to the read buffer
     fn move_to_read_buffer(&mut self, partition_key: String, chunk_id: u32);
 
@@ -79,6 +85,9 @@ trait ChunkMover {
     /// Drops a chunk from the database
     fn drop_chunk(&mut self, partition_key: String, chunk_id: u32);
 
+    /// Starts an operation to compact persisted chunks of a partition
+    fn compact_chunks(&mut self, partition_key: String, chunk_ids: Vec<u32>);
+
     /// The core policy logic
     fn check_for_work(&mut self, now: DateTime<Utc>) {
         let rules = self.rules();
@@ -142,6 +151,37 @@ trait ChunkMover {
             // TODO: Find and recover cancelled move jobs (#1099)
         }
 
+        if let Some(chunk_count) = rules.persisted_compaction_chunk_count {
+            if !self.is_compaction_active() {
+                // Group the persisted chunks small enough to be compacted
+                // by partition
+                let mut candidates: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+                for chunk in &chunks {
+                    let chunk_guard = chunk.read();
+                    let small_enough = rules
+                        .persisted_compaction_max_chunk_size
+                        .map(|max_size| Self::chunk_size(&*chunk_guard) <= max_size.get())
+                        .unwrap_or(true);
+
+                    if small_enough
+                        && matches!(chunk_guard.state(), ChunkState::WrittenToObjectStore(_, _))
+                    {
+                        candidates
+                            .entry(chunk_guard.key().to_string())
+                            .or_default()
+                            .push(chunk_guard.id());
+                    }
+                }
+
+                if let Some((partition_key, chunk_ids)) = candidates
+                    .into_iter()
+                    .find(|(_, chunk_ids)| chunk_ids.len() >= chunk_count.get() as usize)
+                {
+                    self.compact_chunks(partition_key, chunk_ids);
+                }
+            }
+        }
+
         if let Some(soft_limit) = rules.buffer_size_soft {
             let mut chunks = chunks.iter();
 
@@ -197,6 +237,13 @@ impl ChunkMover for LifecycleManager {
             .unwrap_or(false)
     }
 
+    fn is_compaction_active(&self) -> bool {
+        self.compact_task
+            .as_ref()
+            .map(|x| !x.is_complete())
+            .unwrap_or(false)
+    }
+
     fn move_to_read_buffer(&mut self, partition_key: String, chunk_id: u32) {
         info!(%partition_key, %chunk_id, "moving chunk to read buffer");
         self.move_task = Some(
@@ -217,10 +264,18 @@ impl ChunkMover for LifecycleManager {
         info!(%partition_key, %chunk_id, "dropping chunk");
         let _ = self
             .db
-            .drop_chunk(&partition_key, chunk_id)
+            .unload_chunk(&partition_key, chunk_id)
             .log_if_error("dropping chunk to free up memory");
     }
 
+    fn compact_chunks(&mut self, partition_key: String, chunk_ids: Vec<u32>) {
+        info!(%partition_key, ?chunk_ids, "compacting chunks");
+        self.compact_task = Some(
+            self.db
+                .compact_chunks_in_background(partition_key, chunk_ids),
+        )
+    }
+
     fn db_name(&self) -> &str {
         &self.db_name
     }
@@ -267,6 +322,7 @@ fn can_move(rules: &LifecycleRules, chunk: &Chunk, now: DateTime<Utc>) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use object_store::{memory::InMemory, ObjectStore};
     use std::num::{NonZeroU32, NonZeroUsize};
     use tracker::MemRegistry;
 
@@ -330,6 +386,7 @@ mod tests {
         parquet_file::chunk::Chunk::new(
             chunk.key().to_string(),
             chunk.id(),
+            Arc::new(ObjectStore::new_in_memory(InMemory::new())),
             &tracker::MemRegistry::new(),
         )
     }
@@ -339,6 +396,7 @@ mod tests {
         Move(u32),
         Write(u32),
         Drop(u32),
+        Compact(Vec<u32>),
     }
 
     /// A dummy mover that is used to test the policy
@@ -347,6 +405,7 @@ mod tests {
         rules: LifecycleRules,
         move_active: bool,
         write_active: bool,
+        compact_active: bool,
         chunks: Vec<Arc<RwLock<Chunk>>>,
         events: Vec<MoverEvents>,
     }
@@ -361,6 +420,7 @@ mod tests {
                     .collect(),
                 move_active: false,
                 write_active: false,
+                compact_active: false,
                 events: vec![],
             }
         }
@@ -388,6 +448,10 @@ mod tests {
             self.write_active
         }
 
+        fn is_compaction_active(&self) -> bool {
+            self.compact_active
+        }
+
         fn move_to_read_buffer(&mut self, _: String, chunk_id: u32) {
             let chunk = self
                 .chunks
@@ -417,6 +481,11 @@ mod tests {
             self.events.push(MoverEvents::Drop(chunk_id))
         }
 
+        fn compact_chunks(&mut self, _: String, chunk_ids: Vec<u32>) {
+            self.compact_active = true;
+            self.events.push(MoverEvents::Compact(chunk_ids))
+        }
+
         fn db_name(&self) -> &str {
             "my_awesome_db"
         }
@@ -701,4 +770,57 @@ mod tests {
 
         assert_eq!(mover.events, vec![MoverEvents::Write(1)]);
     }
+
+    #[test]
+    fn test_compact_persisted_chunks() {
+        let rules = LifecycleRules {
+            persisted_compaction_chunk_count: Some(NonZeroU32::new(2).unwrap()),
+            ..Default::default()
+        };
+
+        let rb = Arc::new(read_buffer::Chunk::new_with_memory_tracker(
+            22,
+            &tracker::MemRegistry::new(),
+        ));
+
+        // only a single persisted chunk => nothing to compact
+        let chunks = vec![
+            transition_to_moved(new_chunk(0, Some(0), Some(0)), &rb),
+            transition_to_written_to_object_store(new_chunk(1, Some(0), Some(0)), &rb),
+        ];
+
+        let mut mover = DummyMover::new(rules.clone(), chunks);
+        mover.check_for_work(from_secs(10));
+        assert_eq!(mover.events, vec![]);
+
+        let chunks = vec![
+            // not persisted => cannot be compacted
+            transition_to_moved(new_chunk(0, Some(0), Some(0)), &rb),
+            transition_to_written_to_object_store(new_chunk(1, Some(0), Some(0)), &rb),
+            transition_to_writing_to_object_store(new_chunk(2, Some(0), Some(0)), &rb),
+            transition_to_written_to_object_store(new_chunk(3, Some(0), Some(0)), &rb),
+        ];
+
+        let mut mover = DummyMover::new(rules.clone(), chunks);
+        mover.check_for_work(from_secs(10));
+        assert_eq!(mover.events, vec![MoverEvents::Compact(vec![1, 3])]);
+
+        // only one compaction at a time
+        mover.check_for_work(from_secs(20));
+        assert_eq!(mover.events, vec![MoverEvents::Compact(vec![1, 3])]);
+
+        // all chunks are 20 bytes, which is too large to be compacted
+        let rules = LifecycleRules {
+            persisted_compaction_max_chunk_size: Some(NonZeroUsize::new(10).unwrap()),
+            ..rules
+        };
+        let chunks = vec![
+            transition_to_written_to_object_store(new_chunk(0, Some(0), Some(0)), &rb),
+            transition_to_written_to_object_store(new_chunk(1, Some(0), Some(0)), &rb),
+        ];
+
+        let mut mover = DummyMover::new(rules, chunks);
+        mover.check_for_work(from_secs(10));
+        assert_eq!(mover.events, vec![]);
+    }
 }
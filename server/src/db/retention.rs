@@ -0,0 +1,316 @@
+//! Enforcement of the retention period of a `Db`.
+//!
+//! A chunk expires once all of its data is older than the retention period
+//! configured in the lifecycle rules. Expired chunks are dropped from memory
+//! and removed from the preserved catalog, and their files are deleted from
+//! object storage. Chunks that were persisted and then dropped from memory
+//! expire the same way, based on the metadata in the preserved catalog.
+use std::{collections::BTreeSet, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use object_store::{path::Path, ObjectStoreApi};
+use observability_deps::tracing::{debug, info};
+use snafu::ResultExt;
+
+use data_types::{
+    chunk::{ChunkStorage, ChunkSummary},
+    partition_metadata::{Statistics, TableSummary},
+};
+use internal_types::schema::TIME_COLUMN_NAME;
+use parquet_file::{catalog::Action, storage::Storage};
+
+use super::{
+    catalog::chunk::ChunkState, CommittingCatalogTransaction, Db, DeletingExpiredFile,
+    DroppingChunk, ReadingPreservedCatalog, Result,
+};
+
+impl Db {
+    /// Returns the timestamp, in nanoseconds since the epoch, before which
+    /// data is older than the retention period at `now`, or `None` if data
+    /// is retained forever
+    pub fn retention_cutoff(&self, now: DateTime<Utc>) -> Option<i64> {
+        let retention_period = self.rules.read().lifecycle_rules.retention_period_seconds?;
+        let cutoff = now - Duration::seconds(retention_period.get().into());
+        Some(cutoff.timestamp_nanos())
+    }
+
+    /// Evicts all chunks whose data is older than the retention period at
+    /// `now`, returning the number of evicted chunks.
+    ///
+    /// Chunks that are being moved to the read buffer or written to object
+    /// storage are evicted once that has finished. Evicted chunks are
+    /// listed in `system.chunks` with the time they were evicted at.
+    pub async fn enforce_retention(&self, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = match self.retention_cutoff(now) {
+            Some(cutoff) => cutoff,
+            None => return Ok(0),
+        };
+
+        let storage = Storage::new(
+            Arc::clone(&self.store),
+            self.server_id,
+            self.rules.read().name.to_string(),
+        );
+
+        // Drop the expired chunks from memory first, so that they are no
+        // longer queried
+        let mut evicted = Vec::new();
+        let mut in_memory = BTreeSet::new();
+        for partition in self.catalog.partitions() {
+            let mut partition = partition.write();
+            let partition_key = partition.key().to_string();
+
+            let mut expired = Vec::new();
+            for chunk in partition.chunks() {
+                let chunk = chunk.read();
+                let busy = matches!(
+                    chunk.state(),
+                    ChunkState::Moving(_) | ChunkState::WritingToObjectStore(_)
+                );
+
+                if !busy && is_expired(&chunk.table_summaries(), cutoff) {
+                    expired.push(chunk.id());
+                } else {
+                    in_memory.insert((partition_key.clone(), chunk.id()));
+                }
+            }
+
+            for chunk_id in expired {
+                let summary = partition
+                    .chunk(chunk_id)
+                    .context(DroppingChunk {
+                        partition_key: &partition_key,
+                        chunk_id,
+                    })?
+                    .read()
+                    .summary();
+
+                debug!(%partition_key, %chunk_id, "evicting expired chunk");
+                partition.drop_chunk(chunk_id).context(DroppingChunk {
+                    partition_key: &partition_key,
+                    chunk_id,
+                })?;
+                evicted.push(summary);
+            }
+        }
+
+        // Then remove all expired chunks that are no longer in memory from
+        // the preserved catalog, which includes the chunks evicted above
+        let mut actions = Vec::new();
+        let mut paths: Vec<Path> = Vec::new();
+        for chunk in self
+            .preserved_catalog
+            .chunks()
+            .await
+            .context(ReadingPreservedCatalog)?
+        {
+            let key = (chunk.partition_key.clone(), chunk.chunk_id);
+            let summaries: Vec<_> = chunk.tables.iter().map(|t| t.summary.clone()).collect();
+            if in_memory.contains(&key) || !is_expired(&summaries, cutoff) {
+                continue;
+            }
+
+            if !evicted
+                .iter()
+                .any(|s| s.partition_key.as_str() == key.0 && s.id == key.1)
+            {
+                debug!(partition_key=%key.0, chunk_id=%key.1, "evicting expired persisted chunk");
+                evicted.push(ChunkSummary::new_without_timestamps(
+                    Arc::new(key.0.clone()),
+                    key.1,
+                    ChunkStorage::ObjectStoreOnly,
+                    0,
+                ));
+            }
+
+            paths.extend(chunk.paths(&storage));
+            actions.push(Action::RemoveChunk {
+                partition_key: key.0,
+                chunk_id: key.1,
+            });
+        }
+
+        if !actions.is_empty() {
+            self.preserved_catalog
+                .commit(actions)
+                .await
+                .context(CommittingCatalogTransaction)?;
+        }
+
+        for summary in &evicted {
+            self.catalog.record_eviction(summary.clone(), now);
+        }
+
+        // Files that fail to be deleted are no longer referenced by the
+        // preserved catalog and are eventually garbage collected
+        for path in &paths {
+            debug!(path=%path.display(), "deleting expired file");
+            self.store.delete(path).await.context(DeletingExpiredFile {
+                path: path.display(),
+            })?;
+        }
+
+        if !evicted.is_empty() {
+            info!(evicted=evicted.len(), deleted_files=paths.len(), %cutoff, "evicted expired chunks");
+        }
+
+        Ok(evicted.len())
+    }
+}
+
+/// Returns true if the data described by `summaries` has timestamps and all
+/// of them are before `cutoff`
+fn is_expired(summaries: &[TableSummary], cutoff: i64) -> bool {
+    let max_time = summaries
+        .iter()
+        .filter_map(|summary| summary.column(TIME_COLUMN_NAME))
+        .filter_map(|column| match &column.stats {
+            Statistics::I64(stats) if stats.count > 0 => Some(stats.max),
+            _ => None,
+        })
+        .max();
+
+    matches!(max_time, Some(max_time) if max_time < cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            test_helpers::{try_write_lp, write_lp},
+            Error,
+        },
+        query_tests::utils::make_database,
+    };
+    use object_store::{disk::File, ObjectStore};
+    use std::num::NonZeroU32;
+    use tempfile::TempDir;
+
+    /// Returns the time `seconds` seconds after the epoch
+    fn from_secs(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_utc(chrono::NaiveDateTime::from_timestamp(seconds, 0), Utc)
+    }
+
+    fn make_db(root: &TempDir) -> Arc<Db> {
+        let object_store = Arc::new(ObjectStore::new_file(File::new(root.path())));
+        let server_id = NonZeroU32::new(10).unwrap();
+        Arc::new(make_database(server_id, object_store, "retention_test_db"))
+    }
+
+    fn set_retention_period(db: &Db, seconds: Option<u32>) {
+        db.rules.write().lifecycle_rules.retention_period_seconds =
+            seconds.and_then(NonZeroU32::new);
+    }
+
+    #[tokio::test]
+    async fn enforce_retention() {
+        let root = TempDir::new().unwrap();
+        let db = make_db(&root);
+
+        // Partitions are by hour
+        write_lp(&db, "cpu bar=1 10000000000");
+        write_lp(&db, "cpu bar=2 7200000000000");
+
+        let old_partition = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(old_partition).await.unwrap();
+        db.load_chunk_to_read_buffer(old_partition, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(old_partition, mb_chunk.id())
+            .await
+            .unwrap();
+        let paths = db
+            .preserved_catalog()
+            .chunks()
+            .await
+            .unwrap()
+            .remove(0)
+            .paths(&Storage::new(
+                Arc::clone(&db.store),
+                db.server_id,
+                db.rules.read().name.to_string(),
+            ));
+        assert_eq!(paths.len(), 1);
+        assert!(db.store.head(&paths[0]).await.is_ok());
+
+        // Nothing is expired yet
+        set_retention_period(&db, Some(60));
+        assert_eq!(db.enforce_retention(from_secs(60)).await.unwrap(), 0);
+
+        // Only the persisted chunk of the old partition is evicted, its
+        // new open chunk has no data
+        assert_eq!(db.enforce_retention(from_secs(100)).await.unwrap(), 1);
+        let chunks = db.partition_chunk_summaries(old_partition);
+        assert!(
+            chunks.iter().all(|chunk| chunk.id != mb_chunk.id()),
+            "{:#?}",
+            chunks
+        );
+        assert!(db.preserved_catalog().chunks().await.unwrap().is_empty());
+        assert!(db.store.head(&paths[0]).await.is_err());
+
+        let evicted = db.catalog.evicted_chunk_summaries();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.id, mb_chunk.id());
+        assert_eq!(evicted[0].0.storage, ChunkStorage::ReadBufferAndObjectStore);
+        assert_eq!(evicted[0].1, from_secs(100));
+
+        // The open chunk of the newer partition expires later
+        assert_eq!(db.enforce_retention(from_secs(7200)).await.unwrap(), 0);
+        assert_eq!(db.enforce_retention(from_secs(7300)).await.unwrap(), 1);
+        assert!(db.partition_chunk_summaries("1970-01-01T02").is_empty());
+    }
+
+    #[tokio::test]
+    async fn enforce_retention_dropped_chunk() {
+        let root = TempDir::new().unwrap();
+        let db = make_db(&root);
+
+        write_lp(&db, "cpu bar=1 10000000000");
+
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+
+        // Dropping the chunk from memory keeps it in the preserved catalog
+        db.unload_chunk(partition_key, mb_chunk.id()).unwrap();
+        assert_eq!(db.preserved_catalog().chunks().await.unwrap().len(), 1);
+
+        set_retention_period(&db, Some(60));
+        assert_eq!(db.enforce_retention(from_secs(100)).await.unwrap(), 1);
+        assert!(db.preserved_catalog().chunks().await.unwrap().is_empty());
+
+        let evicted = db.catalog.evicted_chunk_summaries();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.storage, ChunkStorage::ObjectStoreOnly);
+    }
+
+    #[tokio::test]
+    async fn write_outside_retention_period() {
+        let root = TempDir::new().unwrap();
+        let db = make_db(&root);
+        set_retention_period(&db, Some(60));
+
+        // Writes are checked against the current time, and rejected as a
+        // whole if any of their data is too old
+        let now = Utc::now().timestamp_nanos();
+        let err = try_write_lp(&db, &format!("mem bar=1 {}\ncpu bar=1 10", now)).unwrap_err();
+        assert!(
+            matches!(err, Error::WriteOutsideRetentionPeriod { .. }),
+            "{}",
+            err
+        );
+        assert!(db.catalog.partition_keys().is_empty());
+
+        write_lp(&db, &format!("cpu bar=1 {}", now));
+
+        set_retention_period(&db, None);
+        write_lp(&db, "cpu bar=1 10");
+    }
+}
@@ -0,0 +1,267 @@
+//! Sorting of table data before it is persisted.
+//!
+//! The rows of every table are written to parquet sorted on a sort key,
+//! which consists of the tag columns of the table followed by time. Tag
+//! columns are ordered from low to high cardinality, so that runs of equal
+//! values are as long as possible, which improves the compression of the
+//! parquet files as well as the pruning of row groups by their min/max
+//! statistics. The sort key is recorded in the metadata of the persisted
+//! chunk, so that queries can take advantage of the pre-sorted data.
+use std::{num::NonZeroU64, sync::Arc};
+
+use arrow_deps::arrow::{
+    array::{new_null_array, Array, ArrayRef, UInt32Array, UInt64Array},
+    compute::{cast, concat, lexsort_to_indices, take, SortColumn},
+    datatypes::DataType,
+    error::Result as ArrowResult,
+    record_batch::RecordBatch,
+};
+use data_types::partition_metadata::TableSummary;
+use internal_types::schema::{Schema, TIME_COLUMN_NAME};
+
+/// Returns the sort key of a table with the given `schema` and `summary`.
+///
+/// The sort key starts with the tag columns of `configured` that are part of
+/// the table, followed by the remaining tag columns in order of increasing
+/// cardinality (tag columns with an unknown cardinality come last, ordered
+/// by name) and finally the time column, if the table has one.
+pub(super) fn sort_key(
+    schema: &Schema,
+    summary: &TableSummary,
+    configured: &[String],
+) -> Vec<String> {
+    let tags: Vec<&str> = schema
+        .tags_iter()
+        .map(|field| field.name().as_str())
+        .collect();
+
+    let mut key: Vec<String> = Vec::with_capacity(tags.len() + 1);
+    for name in configured {
+        if tags.contains(&name.as_str()) && !key.contains(name) {
+            key.push(name.clone());
+        }
+    }
+
+    let mut remaining: Vec<(u64, &str)> = tags
+        .into_iter()
+        .filter(|&name| !key.iter().any(|k| k == name))
+        .map(|name| {
+            let cardinality = summary
+                .column(name)
+                .and_then(|column| column.distinct_count())
+                .map_or(u64::MAX, NonZeroU64::get);
+            (cardinality, name)
+        })
+        .collect();
+    remaining.sort_unstable();
+    key.extend(remaining.into_iter().map(|(_, name)| name.to_string()));
+
+    if schema.find_index_of(TIME_COLUMN_NAME).is_some() {
+        key.push(TIME_COLUMN_NAME.to_string());
+    }
+
+    key
+}
+
+/// Concatenate `batches` into a single batch with the given `schema`, whose
+/// rows are sorted on the columns of `sort_key`.
+///
+/// Columns of the sort key that are not part of the schema are ignored, and
+/// rows with equal sort key values keep their relative order.
+pub(super) fn sort_batches(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    sort_key: &[String],
+) -> ArrowResult<RecordBatch> {
+    let arrow_schema = schema.as_arrow();
+    if batches.is_empty() {
+        return Ok(RecordBatch::new_empty(arrow_schema));
+    }
+
+    let columns = concat_batches(schema, batches)?;
+    let num_rows = batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+
+    let key = sort_key
+        .iter()
+        .filter_map(|name| schema.find_index_of(name))
+        .map(|idx| sort_values(&columns[idx]))
+        .collect::<ArrowResult<Vec<_>>>()?;
+    let order = sort_order(&key, num_rows)?;
+
+    let indices = UInt32Array::from(order.into_iter().map(|row| row as u32).collect::<Vec<_>>());
+    let columns = columns
+        .iter()
+        .map(|column| take(column.as_ref(), &indices, None))
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(arrow_schema, columns)
+}
+
+/// Concatenate the columns of `batches` into a column for each field of
+/// `schema`, filling columns missing from a batch with nulls
+pub(super) fn concat_batches(
+    schema: &Schema,
+    batches: &[RecordBatch],
+) -> ArrowResult<Vec<ArrayRef>> {
+    schema
+        .as_arrow()
+        .fields()
+        .iter()
+        .map(|field| {
+            let arrays: Vec<ArrayRef> = batches
+                .iter()
+                .map(|batch| match batch.schema().index_of(field.name()) {
+                    Ok(idx) => Arc::clone(batch.column(idx)),
+                    Err(_) => new_null_array(field.data_type(), batch.num_rows()),
+                })
+                .collect();
+            let arrays: Vec<&dyn Array> = arrays.iter().map(|array| array.as_ref()).collect();
+            concat(&arrays)
+        })
+        .collect()
+}
+
+/// Returns `column` as an array that can be sorted on: dictionary encoded
+/// tags are decoded to strings and timestamps are converted to integers
+fn sort_values(column: &ArrayRef) -> ArrowResult<ArrayRef> {
+    match column.data_type() {
+        DataType::Dictionary(_, _) => cast(column, &DataType::Utf8),
+        DataType::Timestamp(_, _) => cast(column, &DataType::Int64),
+        _ => Ok(Arc::clone(column)),
+    }
+}
+
+/// Returns the positions of `num_rows` rows sorted on the `key` columns and
+/// finally their position, so that rows with equal keys keep their relative
+/// order
+pub(super) fn sort_order(key: &[ArrayRef], num_rows: usize) -> ArrowResult<Vec<usize>> {
+    let position: ArrayRef = Arc::new(UInt64Array::from((0..num_rows as u64).collect::<Vec<_>>()));
+    let sort_columns: Vec<_> = key
+        .iter()
+        .chain(std::iter::once(&position))
+        .map(|values| SortColumn {
+            values: Arc::clone(values),
+            options: None,
+        })
+        .collect();
+
+    let sorted = lexsort_to_indices(&sort_columns, None)?;
+    Ok((0..sorted.len())
+        .map(|i| sorted.value(i) as usize)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::{
+        arrow::array::{Float64Array, StringArray, TimestampNanosecondArray},
+        assert_table_eq,
+    };
+    use data_types::partition_metadata::{ColumnSummary, StatValues, Statistics};
+    use internal_types::schema::{builder::SchemaBuilder, InfluxFieldType};
+
+    fn make_schema() -> Schema {
+        SchemaBuilder::new()
+            .tag("host")
+            .tag("region")
+            .tag("zone")
+            .influx_field("usage", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap()
+    }
+
+    fn tag_summary(name: &str, distinct_count: Option<u64>) -> ColumnSummary {
+        ColumnSummary {
+            name: name.to_string(),
+            stats: Statistics::String(StatValues {
+                min: "a".to_string(),
+                max: "z".to_string(),
+                count: 10,
+                distinct_count: distinct_count.and_then(NonZeroU64::new),
+            }),
+        }
+    }
+
+    #[test]
+    fn sort_key_orders_tags_by_cardinality() {
+        let schema = make_schema();
+        let summary = TableSummary {
+            name: "cpu".to_string(),
+            columns: vec![
+                tag_summary("host", Some(10)),
+                tag_summary("region", Some(2)),
+                tag_summary("zone", None),
+            ],
+        };
+
+        assert_eq!(
+            sort_key(&schema, &summary, &[]),
+            vec!["region", "host", "zone", "time"]
+        );
+
+        // configured columns come first, unknown and duplicate columns are
+        // ignored
+        let configured = vec![
+            "zone".to_string(),
+            "usage".to_string(),
+            "missing".to_string(),
+            "zone".to_string(),
+        ];
+        assert_eq!(
+            sort_key(&schema, &summary, &configured),
+            vec!["zone", "region", "host", "time"]
+        );
+
+        let schema = SchemaBuilder::new().tag("host").build().unwrap();
+        assert_eq!(sort_key(&schema, &summary, &[]), vec!["host"]);
+    }
+
+    #[test]
+    fn sort_batches_sorts_on_key() {
+        let schema = make_schema();
+
+        let first = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![
+                Arc::new(StringArray::from(vec!["b", "a", "a"])),
+                Arc::new(StringArray::from(vec!["west", "west", "east"])),
+                Arc::new(StringArray::from(vec!["1", "1", "1"])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+                Arc::new(TimestampNanosecondArray::from_vec(vec![10, 20, 30], None)),
+            ],
+        )
+        .unwrap();
+        let second = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(StringArray::from(vec!["west", "east"])),
+                Arc::new(StringArray::from(vec!["1", "1"])),
+                Arc::new(Float64Array::from(vec![4.0, 5.0])),
+                Arc::new(TimestampNanosecondArray::from_vec(vec![10, 10], None)),
+            ],
+        )
+        .unwrap();
+
+        let sort_key = vec!["region".to_string(), "host".to_string(), "time".to_string()];
+        let sorted = sort_batches(&schema, &[first, second], &sort_key).unwrap();
+
+        let expected = vec![
+            "+------+--------+------+-------+-------------------------------+",
+            "| host | region | zone | usage | time                          |",
+            "+------+--------+------+-------+-------------------------------+",
+            "| a    | east   | 1    | 3     | 1970-01-01 00:00:00.000000030 |",
+            "| b    | east   | 1    | 5     | 1970-01-01 00:00:00.000000010 |",
+            "| a    | west   | 1    | 4     | 1970-01-01 00:00:00.000000010 |",
+            "| a    | west   | 1    | 2     | 1970-01-01 00:00:00.000000020 |",
+            "| b    | west   | 1    | 1     | 1970-01-01 00:00:00.000000010 |",
+            "+------+--------+------+-------+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &[sorted]);
+
+        let sorted = sort_batches(&schema, &[], &sort_key).unwrap();
+        assert_eq!(sorted.num_rows(), 0);
+    }
+}
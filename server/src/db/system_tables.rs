@@ -49,9 +49,24 @@ impl SchemaProvider for SystemSchemaProvider {
     fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
         // TODO: Use of a MemTable potentially results in materializing redundant data
         let batch = match name {
-            CHUNKS => from_chunk_summaries(self.catalog.chunk_summaries())
-                .log_if_error("chunks table")
-                .ok()?,
+            CHUNKS => {
+                // Evicted chunks are listed after the chunks of the catalog
+                let chunks = self
+                    .catalog
+                    .chunk_summaries()
+                    .into_iter()
+                    .map(|chunk| (chunk, None))
+                    .chain(
+                        self.catalog
+                            .evicted_chunk_summaries()
+                            .into_iter()
+                            .map(|(chunk, time_evicted)| (chunk, Some(time_evicted))),
+                    )
+                    .collect();
+                from_chunk_summaries(chunks)
+                    .log_if_error("chunks table")
+                    .ok()?
+            }
             COLUMNS => from_partition_summaries(self.catalog.partition_summaries())
                 .log_if_error("chunks table")
                 .ok()?,
@@ -78,7 +93,9 @@ fn append_time(
 
 // TODO: Use a custom proc macro or serde to reduce the boilerplate
 
-fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
+/// Builds the chunks table from the summaries of chunks, with the time each
+/// chunk was evicted at, if it was
+fn from_chunk_summaries(chunks: Vec<(ChunkSummary, Option<DateTime<Utc>>)>) -> Result<RecordBatch> {
     let mut id = UInt32Builder::new(chunks.len());
     let mut partition_key = StringBuilder::new(chunks.len());
     let mut storage = StringBuilder::new(chunks.len());
@@ -86,8 +103,9 @@ fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
     let mut time_of_first_write = TimestampNanosecondBuilder::new(chunks.len());
     let mut time_of_last_write = TimestampNanosecondBuilder::new(chunks.len());
     let mut time_closing = TimestampNanosecondBuilder::new(chunks.len());
+    let mut time_evicted = TimestampNanosecondBuilder::new(chunks.len());
 
-    for chunk in chunks {
+    for (chunk, evicted) in chunks {
         id.append_value(chunk.id)?;
         partition_key.append_value(chunk.partition_key.as_ref())?;
         storage.append_value(chunk.storage.as_str())?;
@@ -96,6 +114,7 @@ fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
         append_time(&mut time_of_first_write, chunk.time_of_first_write)?;
         append_time(&mut time_of_last_write, chunk.time_of_last_write)?;
         append_time(&mut time_closing, chunk.time_closing)?;
+        append_time(&mut time_evicted, evicted)?;
     }
 
     let id = id.finish();
@@ -105,6 +124,7 @@ fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
     let time_of_first_write = time_of_first_write.finish();
     let time_of_last_write = time_of_last_write.finish();
     let time_closing = time_closing.finish();
+    let time_evicted = time_evicted.finish();
 
     let schema = Schema::new(vec![
         Field::new("id", id.data_type().clone(), false),
@@ -122,6 +142,7 @@ fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
             true,
         ),
         Field::new("time_closing", time_closing.data_type().clone(), true),
+        Field::new("time_evicted", time_evicted.data_type().clone(), true),
     ]);
 
     RecordBatch::try_new(
@@ -134,6 +155,7 @@ fn from_chunk_summaries(chunks: Vec<ChunkSummary>) -> Result<RecordBatch> {
             Arc::new(time_of_first_write),
             Arc::new(time_of_last_write),
             Arc::new(time_closing),
+            Arc::new(time_evicted),
         ],
     )
 }
@@ -195,39 +217,61 @@ mod tests {
     #[test]
     fn test_from_chunk_summaries() {
         let chunks = vec![
-            ChunkSummary {
-                partition_key: Arc::new("".to_string()),
-                id: 0,
-                storage: ChunkStorage::OpenMutableBuffer,
-                estimated_bytes: 23754,
-                time_of_first_write: Some(DateTime::from_utc(
-                    NaiveDateTime::from_timestamp(10, 0),
+            (
+                ChunkSummary {
+                    partition_key: Arc::new("".to_string()),
+                    id: 0,
+                    storage: ChunkStorage::OpenMutableBuffer,
+                    estimated_bytes: 23754,
+                    time_of_first_write: Some(DateTime::from_utc(
+                        NaiveDateTime::from_timestamp(10, 0),
+                        Utc,
+                    )),
+                    time_of_last_write: None,
+                    time_closing: None,
+                },
+                None,
+            ),
+            (
+                ChunkSummary {
+                    partition_key: Arc::new("".to_string()),
+                    id: 0,
+                    storage: ChunkStorage::OpenMutableBuffer,
+                    estimated_bytes: 23454,
+                    time_of_first_write: None,
+                    time_of_last_write: Some(DateTime::from_utc(
+                        NaiveDateTime::from_timestamp(80, 0),
+                        Utc,
+                    )),
+                    time_closing: None,
+                },
+                None,
+            ),
+            (
+                ChunkSummary {
+                    partition_key: Arc::new("p1".to_string()),
+                    id: 1,
+                    storage: ChunkStorage::ObjectStoreOnly,
+                    estimated_bytes: 0,
+                    time_of_first_write: None,
+                    time_of_last_write: None,
+                    time_closing: None,
+                },
+                Some(DateTime::from_utc(
+                    NaiveDateTime::from_timestamp(90, 0),
                     Utc,
                 )),
-                time_of_last_write: None,
-                time_closing: None,
-            },
-            ChunkSummary {
-                partition_key: Arc::new("".to_string()),
-                id: 0,
-                storage: ChunkStorage::OpenMutableBuffer,
-                estimated_bytes: 23454,
-                time_of_first_write: None,
-                time_of_last_write: Some(DateTime::from_utc(
-                    NaiveDateTime::from_timestamp(80, 0),
-                    Utc,
-                )),
-                time_closing: None,
-            },
+            ),
         ];
 
         let expected = vec![
-            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+",
-            "| id | partition_key | storage           | estimated_bytes | time_of_first_write | time_of_last_write  | time_closing |",
-            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+",
-            "| 0  |               | OpenMutableBuffer | 23754           | 1970-01-01 00:00:10 |                     |              |",
-            "| 0  |               | OpenMutableBuffer | 23454           |                     | 1970-01-01 00:01:20 |              |",
-            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+",
+            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+---------------------+",
+            "| id | partition_key | storage           | estimated_bytes | time_of_first_write | time_of_last_write  | time_closing | time_evicted        |",
+            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+---------------------+",
+            "| 0  |               | OpenMutableBuffer | 23754           | 1970-01-01 00:00:10 |                     |              |                     |",
+            "| 0  |               | OpenMutableBuffer | 23454           |                     | 1970-01-01 00:01:20 |              |                     |",
+            "| 1  | p1            | ObjectStoreOnly   | 0               |                     |                     |              | 1970-01-01 00:01:30 |",
+            "+----+---------------+-------------------+-----------------+---------------------+---------------------+--------------+---------------------+",
         ];
 
         let batch = from_chunk_summaries(chunks).unwrap();
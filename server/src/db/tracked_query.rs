@@ -0,0 +1,162 @@
+//! Tracking of the queries of a database.
+//!
+//! Each query admitted by [`Db::start_query`] is registered as a job, like
+//! the other long running tasks of the server, so that it can be listed and
+//! cancelled through the operations API. A query is also cancelled when it
+//! runs for longer than the query timeout of its database, and when the
+//! future running it is dropped, e.g. because the client disconnected.
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+
+use data_types::job::Job;
+use query::{AdmittedQuery, QueryError};
+use tracker::{TaskRegistration, TaskTracker, TrackedFutureExt};
+
+use super::Db;
+use crate::limits::{self, QueryPermit};
+
+/// A query admitted to run against a database
+#[derive(Debug)]
+pub struct TrackedQuery {
+    tracker: TaskTracker<Job>,
+    registration: TaskRegistration,
+    timeout: Option<Duration>,
+
+    /// Counts the query against the concurrent queries of the database
+    /// until it has finished running
+    permit: QueryPermit,
+}
+
+impl TrackedQuery {
+    /// The tracker of the query, which cancels it when cancelled
+    pub fn tracker(&self) -> &TaskTracker<Job> {
+        &self.tracker
+    }
+}
+
+#[async_trait]
+impl AdmittedQuery for TrackedQuery {
+    async fn run<F>(self, query: F) -> Result<F::Output, QueryError>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        let Self {
+            registration,
+            timeout,
+            permit,
+            ..
+        } = self;
+
+        let query = query.track(registration);
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, query)
+                .await
+                .map_err(|_| QueryError::TimedOut { timeout })?,
+            None => query.await,
+        };
+        std::mem::drop(permit);
+
+        result.map_err(|_| QueryError::Cancelled)
+    }
+}
+
+impl Db {
+    /// Admits `query` (e.g. its SQL text), returning the tracked query to
+    /// run it with, or returns an error if the maximum number of concurrent
+    /// queries of this database are already running
+    pub fn start_query(&self, query: &str) -> Result<TrackedQuery, limits::Error> {
+        let rules = self.rules.read();
+        let permit = self.limiter.start_query(&rules.limits)?;
+        let timeout = rules
+            .limits
+            .query_timeout_seconds
+            .map(|seconds| Duration::from_secs(seconds.get().into()));
+
+        let (tracker, registration) = self.jobs.register(Job::Query {
+            db_name: rules.name.to_string(),
+            query: query.to_string(),
+        });
+
+        Ok(TrackedQuery {
+            tracker,
+            registration,
+            timeout,
+            permit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use super::*;
+    use crate::query_tests::utils::make_db;
+
+    #[tokio::test]
+    async fn run_query() {
+        let db = make_db();
+
+        let query = db.start_query("select * from cpu").unwrap();
+        assert_eq!(
+            query.tracker().metadata(),
+            &Job::Query {
+                db_name: "placeholder".to_string(),
+                query: "select * from cpu".to_string(),
+            }
+        );
+        assert_eq!(db.limiter.running_queries(), 1);
+
+        assert_eq!(query.run(async { 42 }).await.unwrap(), 42);
+        assert_eq!(db.limiter.running_queries(), 0);
+    }
+
+    #[tokio::test]
+    async fn cancel_query() {
+        let db = make_db();
+
+        let query = db.start_query("select * from cpu").unwrap();
+        let tracker = query.tracker().clone();
+        tracker.cancel();
+
+        let err = query
+            .run(futures::future::pending::<()>())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::Cancelled));
+        assert!(tracker.is_complete());
+        assert_eq!(db.limiter.running_queries(), 0);
+    }
+
+    #[tokio::test]
+    async fn query_timeout() {
+        let db = make_db();
+        db.rules.write().limits.query_timeout_seconds = NonZeroU32::new(1);
+
+        let query = db.start_query("select * from cpu").unwrap();
+        let tracker = query.tracker().clone();
+
+        let err = query
+            .run(futures::future::pending::<()>())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::TimedOut { timeout } if timeout.as_secs() == 1));
+        assert!(tracker.is_complete());
+    }
+
+    #[tokio::test]
+    async fn drop_query() {
+        let db = make_db();
+
+        let query = db.start_query("select * from cpu").unwrap();
+        let tracker = query.tracker().clone();
+        assert!(!tracker.is_complete());
+
+        // e.g. because the client disconnected
+        std::mem::drop(query.run(futures::future::pending::<()>()));
+        assert!(tracker.is_complete());
+        assert_eq!(db.limiter.running_queries(), 0);
+    }
+}
@@ -74,10 +74,11 @@ use async_trait::async_trait;
 use bytes::BytesMut;
 use futures::stream::TryStreamExt;
 use observability_deps::tracing::{error, info, warn};
-use parking_lot::Mutex;
-use snafu::{OptionExt, ResultExt, Snafu};
+use parking_lot::{Mutex, RwLock};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
 use data_types::{
+    auth::ApiToken,
     database_rules::{DatabaseRules, WriterId},
     job::Job,
     {DatabaseName, DatabaseNameError},
@@ -87,7 +88,10 @@ use internal_types::{
     entry::{self, lines_to_sharded_entries, Entry, ShardedEntry},
     once::OnceNonZeroU32,
 };
-use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
+use object_store::{
+    path::{ObjectStorePath, DELIMITER},
+    ObjectStore, ObjectStoreApi,
+};
 use query::{exec::Executor, DatabaseStore};
 use tracker::{TaskId, TaskRegistration, TaskRegistryWithHistory, TaskTracker, TrackedFutureExt};
 
@@ -96,17 +100,21 @@ use futures::{pin_mut, FutureExt};
 use crate::{
     config::{
         object_store_path_for_database_config, Config, GRPCConnectionString, DB_RULES_FILE_NAME,
+        SERVER_CONFIG_FILE_NAME,
     },
     db::Db,
 };
 use data_types::database_rules::{NodeGroup, ShardId};
+use data_types::server_config;
 use internal_types::entry::SequencedEntry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::num::NonZeroU32;
 
+pub mod auth;
 pub mod buffer;
 mod config;
 pub mod db;
+pub mod limits;
 mod query_tests;
 pub mod snapshot;
 
@@ -165,6 +173,37 @@ pub enum Error {
     ShardNotFound { shard_id: ShardId },
     #[snafu(display("hard buffer limit reached"))]
     HardLimitReached {},
+    #[snafu(display("write outside retention period: {}", source))]
+    WriteOutsideRetentionPeriod { source: db::Error },
+    #[snafu(display("token not found: {}", id))]
+    TokenNotFound { id: String },
+    #[snafu(display("error serializing tokens {}", source))]
+    ErrorSerializingTokens { source: data_types::auth::Error },
+    #[snafu(display("error deserializing tokens {}", source))]
+    ErrorDeserializingTokens { source: data_types::auth::Error },
+    #[snafu(display("error serializing server config {}", source))]
+    ErrorSerializingServerConfig {
+        source: data_types::server_config::Error,
+    },
+    #[snafu(display("error deserializing server config {}", source))]
+    ErrorDeserializingServerConfig {
+        source: data_types::server_config::Error,
+    },
+    #[snafu(display(
+        "server config was modified concurrently: expected version {}, found version {}",
+        expected,
+        actual
+    ))]
+    ServerConfigConflict { expected: u64, actual: u64 },
+    #[snafu(display("writer id {} does not match the stored writer id {}", id, stored))]
+    WriterIdMismatch { id: NonZeroU32, stored: NonZeroU32 },
+    #[snafu(display("error opening backup: {}", source))]
+    OpeningBackup { source: db::Error },
+    #[snafu(display("limit of database {} exceeded: {}", db_name, source))]
+    LimitExceeded {
+        db_name: String,
+        source: limits::Error,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -195,7 +234,20 @@ impl JobRegistry {
     }
 }
 
-const STORE_ERROR_PAUSE_SECONDS: u64 = 100;
+/// Lines of line protocol converted to entries by
+/// [`Server::convert_lines`], to be written by [`Server::write_batch`]
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    num_lines: usize,
+    entries: Vec<ShardedEntry>,
+}
+
+impl WriteBatch {
+    /// The number of lines converted into this batch
+    pub fn num_lines(&self) -> usize {
+        self.num_lines
+    }
+}
 
 /// Used to configure a server instance
 #[derive(Debug)]
@@ -206,6 +258,10 @@ pub struct ServerConfig {
 
     /// The `ObjectStore` instance to use for persistence
     object_store: Arc<ObjectStore>,
+
+    /// A token granting admin access to all databases. Requests must be
+    /// authenticated with an API token if set
+    admin_token: Option<String>,
 }
 
 impl ServerConfig {
@@ -214,6 +270,7 @@ impl ServerConfig {
         Self {
             num_worker_threads: None,
             object_store,
+            admin_token: None,
         }
     }
 
@@ -223,6 +280,13 @@ impl ServerConfig {
         self
     }
 
+    /// Require requests to be authenticated with an API token, using `token`
+    /// as the initial admin token
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
     /// return a reference to the object store in this configuration
     pub fn store(&self) -> Arc<ObjectStore> {
         Arc::clone(&self.object_store)
@@ -240,6 +304,12 @@ pub struct Server<M: ConnectionManager> {
     pub store: Arc<ObjectStore>,
     exec: Arc<Executor>,
     jobs: Arc<JobRegistry>,
+    /// The hash of the admin token, see [`auth::hash_token`]
+    admin_token: Option<String>,
+    tokens: RwLock<Vec<ApiToken>>,
+    /// The version of the server config last loaded from or stored in the
+    /// object store. Held while the server config is updated
+    config_version: tokio::sync::Mutex<u64>,
 }
 
 #[derive(Debug)]
@@ -261,6 +331,7 @@ impl<M: ConnectionManager> Server<M> {
         let ServerConfig {
             num_worker_threads,
             object_store,
+            admin_token,
         } = config;
         let num_worker_threads = num_worker_threads.unwrap_or_else(num_cpus::get);
 
@@ -271,14 +342,42 @@ impl<M: ConnectionManager> Server<M> {
             connection_manager: Arc::new(connection_manager),
             exec: Arc::new(Executor::new(num_worker_threads)),
             jobs,
+            admin_token: admin_token.as_deref().map(auth::hash_token),
+            tokens: Default::default(),
+            config_version: tokio::sync::Mutex::new(0),
         }
     }
 
     /// sets the id of the server, which is used for replication and the base
-    /// path in object storage.
+    /// path in object storage. The id is stored in the server config below
+    /// that path, so it is restored by `load_database_configs` on restart.
+    ///
+    /// If a server config has already been stored for `id`, e.g. by a
+    /// previous run of this server, its remotes are restored along with any
+    /// remotes added since this server started. The API tokens stored for
+    /// `id` are loaded before the id is set.
     ///
     /// A valid server ID Must be non-zero.
-    pub fn set_id(&self, id: NonZeroU32) -> Result<()> {
+    pub async fn set_id(&self, id: NonZeroU32) -> Result<()> {
+        let mut version = self.config_version.lock().await;
+        if let Some(id) = self.id.get() {
+            return IdAlreadySet { id }.fail();
+        }
+
+        let mut remotes = BTreeMap::new();
+        if let Some(stored) = self.stored_server_config(id).await? {
+            *version = stored.version;
+            remotes = stored.remotes;
+        }
+        remotes.extend(self.config.remotes());
+        let tokens = self.stored_tokens(id).await?;
+
+        self.persist_server_config(&mut version, id, remotes.clone())
+            .await?;
+        self.config.set_remotes(remotes);
+        if let Some(tokens) = tokens {
+            *self.tokens.write() = tokens;
+        }
         self.id.set(id).map_err(|id| Error::IdAlreadySet { id })
     }
 
@@ -330,13 +429,188 @@ impl<M: ConnectionManager> Server<M> {
         Ok(path)
     }
 
-    /// Loads the database configurations based on the databases in the
-    /// object store. Any databases in the config already won't be
-    /// replaced.
+    // location in object store of the server config of the writer `id`
+    fn server_config_path(&self, id: NonZeroU32) -> object_store::path::Path {
+        let mut path = self.store.new_path();
+        path.push_dir(format!("{}", id));
+        path.set_file_name(SERVER_CONFIG_FILE_NAME);
+        path
+    }
+
+    /// Reads the server config of the writer `id` from the object store,
+    /// returning `None` if no server config has been stored for it yet
+    async fn stored_server_config(
+        &self,
+        id: NonZeroU32,
+    ) -> Result<Option<server_config::ServerConfig>> {
+        let location = self.server_config_path(id);
+
+        let mut prefix = self.store.new_path();
+        prefix.push_dir(format!("{}", id));
+        let list_result = self
+            .store
+            .list_with_delimiter(&prefix)
+            .await
+            .context(StoreError)?;
+        if !list_result
+            .objects
+            .iter()
+            .any(|object| object.location == location)
+        {
+            return Ok(None);
+        }
+
+        let bytes = get_store_bytes(&location, &self.store).await?;
+        let config = server_config::ServerConfig::decode(bytes.freeze())
+            .context(ErrorDeserializingServerConfig)?;
+        Ok(Some(config))
+    }
+
+    /// Returns the writer ids that have a server config stored in the object
+    /// store
+    async fn stored_writer_ids(&self) -> Result<Vec<NonZeroU32>> {
+        let list_result = self
+            .store
+            .list_with_delimiter(&self.store.new_path())
+            .await
+            .context(StoreError)?;
+
+        let mut ids = vec![];
+        for prefix in list_result.common_prefixes {
+            let id = prefix
+                .display()
+                .trim_end_matches(DELIMITER)
+                .rsplit(DELIMITER)
+                .next()
+                .and_then(|name| name.parse().ok());
+            if let Some(id) = id {
+                if self.stored_server_config(id).await?.is_some() {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Stores the server config of the writer `writer_id` with the given
+    /// remotes as the version following `version`.
+    ///
+    /// Fails if the stored server config is no longer at `version`, i.e. if
+    /// another server with the same writer id changed it since this server
+    /// last loaded or stored it. As the object store cannot update objects
+    /// conditionally, concurrent changes are only detected on a best-effort
+    /// basis
+    async fn persist_server_config(
+        &self,
+        version: &mut u64,
+        writer_id: NonZeroU32,
+        remotes: BTreeMap<WriterId, GRPCConnectionString>,
+    ) -> Result<()> {
+        let actual = self
+            .stored_server_config(writer_id)
+            .await?
+            .map(|config| config.version)
+            .unwrap_or_default();
+        ensure!(
+            actual == *version,
+            ServerConfigConflict {
+                expected: *version,
+                actual
+            }
+        );
+
+        let config = server_config::ServerConfig {
+            version: *version + 1,
+            writer_id: Some(writer_id),
+            remotes,
+        };
+
+        let mut data = BytesMut::new();
+        config
+            .encode(&mut data)
+            .context(ErrorSerializingServerConfig)?;
+
+        let len = data.len();
+
+        let stream_data = std::io::Result::Ok(data.freeze());
+        self.store
+            .put(
+                &self.server_config_path(writer_id),
+                futures::stream::once(async move { stream_data }),
+                Some(len),
+            )
+            .await
+            .context(StoreError)?;
+
+        *version += 1;
+        Ok(())
+    }
+
+    /// Restores the remotes from the server config in the object store, if
+    /// one has been stored.
+    ///
+    /// If the writer id is not set yet, it is restored too, provided the
+    /// object store holds the server config of a single writer. Servers
+    /// sharing an object store must have their writer id set explicitly
+    async fn load_server_config(&self) -> Result<()> {
+        let mut version = self.config_version.lock().await;
+        let id = match self.id.get() {
+            Some(id) => id,
+            None => match self.stored_writer_ids().await?.as_slice() {
+                [id] => *id,
+                [] => return Ok(()),
+                ids => {
+                    warn!(
+                        ?ids,
+                        "found the server config of several writers, set the writer id"
+                    );
+                    return Ok(());
+                }
+            },
+        };
+        let stored = match self.stored_server_config(id).await? {
+            Some(stored) => stored,
+            None => return Ok(()),
+        };
+
+        // the config is stored below the writer id, so they only differ if it
+        // was copied from another writer
+        if let Some(stored_id) = stored.writer_id {
+            ensure!(
+                id == stored_id,
+                WriterIdMismatch {
+                    id,
+                    stored: stored_id
+                }
+            );
+        }
+        if self.id.get().is_none() {
+            if let Some(tokens) = self.stored_tokens(id).await? {
+                *self.tokens.write() = tokens;
+            }
+            self.id.set(id).map_err(|id| Error::IdAlreadySet { id })?;
+        }
+
+        self.config.set_remotes(stored.remotes);
+        *version = stored.version;
+
+        Ok(())
+    }
+
+    /// Loads the server config and the database configurations based on the
+    /// databases in the object store. Any databases in the config already
+    /// won't be replaced.
+    ///
+    /// Databases are only loaded if the writer id is known, either from the
+    /// server config or from an earlier call to `set_id`. Object store errors
+    /// fail the load once the store has given up retrying them.
     pub async fn load_database_configs(&self) -> Result<()> {
+        self.load_server_config().await?;
+        if self.id.get().is_none() {
+            return Ok(());
+        }
+
         // get the database names from the object store prefixes
-        // TODO: update object store to pull back all common prefixes by
-        //       following the next tokens.
         let list_result = self
             .store
             .list_with_delimiter(&self.root_path()?)
@@ -353,38 +627,54 @@ impl<M: ConnectionManager> Server<M> {
                 let config = Arc::clone(&self.config);
                 let exec = Arc::clone(&self.exec);
 
+                let prefix = path.clone();
                 path.set_file_name(DB_RULES_FILE_NAME);
 
                 tokio::task::spawn(async move {
-                    let mut res = get_store_bytes(&path, &store).await;
-                    while let Err(e) = &res {
-                        error!(
-                            "error getting database config {:?} from object store: {}",
-                            path, e
-                        );
-                        tokio::time::sleep(tokio::time::Duration::from_secs(
-                            STORE_ERROR_PAUSE_SECONDS,
-                        ))
-                        .await;
-                        res = get_store_bytes(&path, &store).await;
+                    // Prefixes without rules are not databases, e.g. the
+                    // remains of a database deleted by an older version
+                    let list_result = store
+                        .list_with_delimiter(&prefix)
+                        .await
+                        .context(StoreError)?;
+                    if !list_result.objects.iter().any(|o| o.location == path) {
+                        warn!(prefix=%prefix.display(), "no database rules found, skipping");
+                        return Ok(());
                     }
 
-                    let res = res.unwrap().freeze();
+                    // the store retries transient errors, so errors here fail
+                    // the load rather than leaving out the database
+                    let res = get_store_bytes(&path, &store).await?.freeze();
 
                     match DatabaseRules::decode(res) {
                         Err(e) => {
                             error!("error parsing database config {:?} from store: {}", path, e)
                         }
-                        Ok(rules) => match config.create_db(rules) {
-                            Err(e) => error!("error adding database to config: {}", e),
-                            Ok(handle) => handle.commit(server_id, store, exec),
-                        },
+                        Ok(rules) => {
+                            let name = rules.name.clone();
+                            match config.create_db(rules) {
+                                Err(e) => error!("error adding database to config: {}", e),
+                                Ok(handle) => handle.commit(server_id, store, exec),
+                            }
+
+                            // chunks persisted before the restart must not be
+                            // overwritten by new chunks with the same ids
+                            if let Some(db) = config.db(&name) {
+                                if let Err(e) = db.load_preserved_catalog().await {
+                                    error!(%name, "error loading preserved catalog: {}", e)
+                                }
+                            }
+                        }
                     }
+
+                    Ok::<_, Error>(())
                 })
             })
             .collect();
 
-        futures::future::join_all(handles).await;
+        for res in futures::future::join_all(handles).await {
+            res.expect("loading database config panicked")?;
+        }
 
         Ok(())
     }
@@ -394,6 +684,21 @@ impl<M: ConnectionManager> Server<M> {
     /// the ShardConfig or sent to the local database for buffering in the
     /// WriteBuffer and/or the MutableBuffer if configured.
     pub async fn write_lines(&self, db_name: &str, lines: &[ParsedLine<'_>]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.convert_lines(db_name, lines, &mut batch)?;
+        self.write_batch(db_name, batch).await
+    }
+
+    /// Converts `lines` to the entries of the database `db_name`, adding
+    /// them to `batch`. Lines can be converted as they are received and
+    /// written at once with `write_batch`, without keeping the line protocol
+    /// around.
+    pub fn convert_lines(
+        &self,
+        db_name: &str,
+        lines: &[ParsedLine<'_>],
+        batch: &mut WriteBatch,
+    ) -> Result<()> {
         self.require_id()?;
 
         let db_name = DatabaseName::new(db_name).context(InvalidDatabaseName)?;
@@ -402,33 +707,47 @@ impl<M: ConnectionManager> Server<M> {
             .db(&db_name)
             .context(DatabaseNotFound { db_name: &*db_name })?;
 
-        // Split lines into shards while holding a read lock on the sharding config.
-        // Once the lock is released we have a vector of entries, each associated with a
-        // shard id, and an Arc to the mapping between shard ids and node
-        // groups. This map is atomically replaced every time the sharding
-        // config is updated, hence it's safe to use after we release the shard config
-        // lock.
-        let (sharded_entries, shards) = {
-            let rules = db.rules.read();
-            let shard_config = &rules.shard_config;
-
-            let sharded_entries = lines_to_sharded_entries(lines, shard_config.as_ref(), &*rules)
-                .context(LineConversion)?;
-
-            let shards = shard_config
-                .as_ref()
-                .map(|cfg| Arc::clone(&cfg.shards))
-                .unwrap_or_default();
-
-            (sharded_entries, shards)
-        };
+        let rules = db.rules.read();
+        let sharded_entries = lines_to_sharded_entries(lines, rules.shard_config.as_ref(), &*rules)
+            .context(LineConversion)?;
+
+        batch.num_lines += lines.len();
+        batch.entries.extend(sharded_entries);
+        Ok(())
+    }
+
+    /// Writes the entries of `batch`, converted by `convert_lines`, to the
+    /// database `db_name`, either all of them or none if the write exceeds
+    /// the limits of the database.
+    pub async fn write_batch(&self, db_name: &str, batch: WriteBatch) -> Result<()> {
+        self.require_id()?;
+
+        let db_name = DatabaseName::new(db_name).context(InvalidDatabaseName)?;
+        let db = self
+            .config
+            .db(&db_name)
+            .context(DatabaseNotFound { db_name: &*db_name })?;
+
+        // The map from shard ids to node groups is atomically replaced every
+        // time the sharding config is updated, hence it's safe to use after
+        // we release the lock on the rules.
+        let shards = db
+            .rules
+            .read()
+            .shard_config
+            .as_ref()
+            .map(|cfg| Arc::clone(&cfg.shards))
+            .unwrap_or_default();
+
+        let bytes = batch.entries.iter().map(|e| e.entry.data().len()).sum();
+        db.admit_write(batch.num_lines, bytes)
+            .context(LimitExceeded { db_name: &*db_name })?;
 
         // Write to all shards in parallel; as soon as one fails return error
         // immediately to the client and abort all other outstanding requests.
-        // This can take some time, but we're no longer holding the lock to the shard
-        // config.
         futures_util::future::try_join_all(
-            sharded_entries
+            batch
+                .entries
                 .into_iter()
                 .map(|e| self.write_sharded_entry(&db_name, &db, Arc::clone(&shards), e)),
         )
@@ -477,13 +796,26 @@ impl<M: ConnectionManager> Server<M> {
             .db(&db_name)
             .context(DatabaseNotFound { db_name: &*db_name })?;
 
-        let entry = entry_bytes.try_into().context(DecodingEntry)?;
+        let entry: Entry = entry_bytes.try_into().context(DecodingEntry)?;
+        let rows = entry
+            .partition_writes()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|write| write.table_batches())
+            .map(|batch| batch.row_count())
+            .sum();
+        db.admit_write(rows, entry.data().len())
+            .context(LimitExceeded { db_name: &*db_name })?;
+
         self.write_entry_local(&db, entry).await
     }
 
     pub async fn write_entry_local(&self, db: &Db, entry: Entry) -> Result<()> {
         db.store_entry(entry).map_err(|e| match e {
             db::Error::HardLimitReached {} => Error::HardLimitReached {},
+            e @ db::Error::WriteOutsideRetentionPeriod { .. } => {
+                Error::WriteOutsideRetentionPeriod { source: e }
+            }
             _ => Error::UnknownDatabaseError {
                 source: Box::new(e),
             },
@@ -533,16 +865,146 @@ impl<M: ConnectionManager> Server<M> {
         Ok(rules)
     }
 
+    /// Deletes the database `db_name`.
+    ///
+    /// The background worker and any running jobs of the database are
+    /// stopped before its data and then its rules are deleted from object
+    /// storage. No database with the same name can be created until the
+    /// deletion is complete.
+    pub async fn delete_database(&self, db_name: &DatabaseName<'static>) -> Result<()> {
+        let root = self.root_path()?;
+
+        let mut handle = self.config.remove_db(db_name)?;
+        handle.join().await;
+
+        let jobs: Vec<_> = self
+            .jobs
+            .inner
+            .lock()
+            .running()
+            .into_iter()
+            .filter(|job| job.metadata().db_name() == Some(db_name.as_str()))
+            .collect();
+        for job in &jobs {
+            job.cancel();
+        }
+        for job in &jobs {
+            job.join().await;
+        }
+
+        // Delete the rules last, so that a partially deleted database is
+        // loaded again on restart and its deletion can be retried
+        let location = object_store_path_for_database_config(&root, db_name);
+        let mut prefix = root;
+        prefix.push_dir(db_name.to_string());
+        let prefix_str = prefix.display();
+        let paths: Vec<_> = self
+            .store
+            .list(Some(&prefix))
+            .await
+            .context(StoreError)?
+            .try_concat()
+            .await
+            .context(StoreError)?;
+        // The listing may include databases whose name starts with `db_name`
+        for path in paths
+            .iter()
+            .filter(|path| path.display().starts_with(&prefix_str) && **path != location)
+        {
+            self.store.delete(path).await.context(StoreError)?;
+        }
+        self.store.delete(&location).await.context(StoreError)?;
+
+        info!(%db_name, "deleted database");
+
+        Ok(())
+    }
+
+    /// Writes a backup of the persisted chunks of the database `db_name` to
+    /// the directory `path` on the local file system, as a background job.
+    /// The directory must be empty.
+    ///
+    /// Callers must make sure that whoever requests the backup may write to
+    /// the local file system of the server
+    pub fn backup_database(
+        &self,
+        db_name: &DatabaseName<'_>,
+        path: impl Into<String>,
+    ) -> Result<TaskTracker<Job>> {
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.to_string(),
+        })?;
+
+        let path = path.into();
+        let target = Arc::new(ObjectStore::new_file(object_store::disk::File::new(&path)));
+
+        Ok(db.backup_in_background(target, path))
+    }
+
+    /// Creates a database from the backup in the directory `path` on the
+    /// local file system and restores its chunks as a background job. The
+    /// database is named `db_name` if given, and like the backed up database
+    /// otherwise
+    pub async fn restore_database(
+        &self,
+        path: impl Into<String>,
+        db_name: Option<DatabaseName<'static>>,
+    ) -> Result<TaskTracker<Job>> {
+        let server_id = self.require_id()?;
+
+        let path = path.into();
+        let store = Arc::new(ObjectStore::new_file(object_store::disk::File::new(&path)));
+        let backup = db::Backup::open(store).await.context(OpeningBackup)?;
+
+        let mut rules = backup.rules().clone();
+        if let Some(db_name) = db_name {
+            rules.name = db_name;
+        }
+        let db_name = rules.name.clone();
+        self.create_database(rules, server_id).await?;
+
+        let db = self.config.db(&db_name).context(DatabaseNotFound {
+            db_name: db_name.to_string(),
+        })?;
+
+        Ok(db.restore_in_background(backup, path))
+    }
+
     pub fn remotes_sorted(&self) -> Vec<(WriterId, String)> {
         self.config.remotes_sorted()
     }
 
-    pub fn update_remote(&self, id: WriterId, addr: GRPCConnectionString) {
-        self.config.update_remote(id, addr)
+    /// Adds or updates the remote server `id`, storing the change in the
+    /// server config once the writer id of this server is set
+    pub async fn update_remote(&self, id: WriterId, addr: GRPCConnectionString) -> Result<()> {
+        let mut version = self.config_version.lock().await;
+        let mut remotes = self.config.remotes();
+        remotes.insert(id, addr);
+
+        if let Some(id) = self.id.get() {
+            self.persist_server_config(&mut version, id, remotes.clone())
+                .await?;
+        }
+        self.config.set_remotes(remotes);
+        Ok(())
     }
 
-    pub fn delete_remote(&self, id: WriterId) -> Option<GRPCConnectionString> {
-        self.config.delete_remote(id)
+    /// Removes the remote server `id`, storing the change in the server
+    /// config once the writer id of this server is set. Returns the connection
+    /// string of the removed remote, if any
+    pub async fn delete_remote(&self, id: WriterId) -> Result<Option<GRPCConnectionString>> {
+        let mut version = self.config_version.lock().await;
+        let mut remotes = self.config.remotes();
+        let removed = remotes.remove(&id);
+
+        if removed.is_some() {
+            if let Some(id) = self.id.get() {
+                self.persist_server_config(&mut version, id, remotes.clone())
+                    .await?;
+            }
+            self.config.set_remotes(remotes);
+        }
+        Ok(removed)
     }
 
     pub fn spawn_dummy_job(&self, nanos: Vec<u64>) -> TaskTracker<Job> {
@@ -644,6 +1106,7 @@ where
 {
     type Database = Db;
     type Error = Error;
+    type AdmittedQuery = db::TrackedQuery;
 
     fn db_names_sorted(&self) -> Vec<String> {
         self.config
@@ -682,6 +1145,16 @@ where
     fn executor(&self) -> Arc<Executor> {
         Arc::clone(&self.exec)
     }
+
+    fn start_query(&self, name: &str, query: &str) -> Result<Self::AdmittedQuery, Self::Error> {
+        let db_name = DatabaseName::new(name).context(InvalidDatabaseName)?;
+        let db = self
+            .db(&db_name)
+            .context(DatabaseNotFound { db_name: name })?;
+
+        db.start_query(query)
+            .context(LimitExceeded { db_name: name })
+    }
 }
 
 /// The `Server` will ask the `ConnectionManager` for connections to a specific
@@ -789,7 +1262,7 @@ mod tests {
     use data_types::database_rules::{PartitionTemplate, TemplatePart, NO_SHARD_CONFIG};
     use influxdb_line_protocol::parse_lines;
     use object_store::{memory::InMemory, path::ObjectStorePath};
-    use query::{frontend::sql::SQLQueryPlanner, Database};
+    use query::{frontend::sql::SQLQueryPlanner, Database, PartitionChunk};
 
     use super::*;
 
@@ -817,7 +1290,7 @@ mod tests {
         let config = config();
         let store = config.store();
         let server = Server::new(manager, config);
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let name = DatabaseName::new("bananas").unwrap();
 
@@ -829,6 +1302,7 @@ mod tests {
             wal_buffer_config: None,
             lifecycle_rules: Default::default(),
             shard_config: None,
+            limits: Default::default(),
         };
 
         // Create a database
@@ -870,20 +1344,175 @@ mod tests {
         let manager = TestConnectionManager::new();
         let config2 = ServerConfig::new(store).with_num_worker_threads(1);
         let server2 = Server::new(manager, config2);
-        server2.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        // The writer id is restored from the server config
         server2.load_database_configs().await.unwrap();
+        assert_eq!(server2.require_id().unwrap().get(), 1);
 
         let _ = server2.db(&db2).unwrap();
         let _ = server2.db(&name).unwrap();
     }
 
+    #[tokio::test]
+    async fn load_database_configs_across_list_pages() {
+        // Listing the databases takes several pages of results
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new_with_page_size(2)));
+
+        let manager = TestConnectionManager::new();
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server = Server::new(manager, config);
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+
+        let names = vec!["a", "b", "c", "d", "e"];
+        for name in &names {
+            let name = DatabaseName::new(name.to_string()).unwrap();
+            server
+                .create_database(DatabaseRules::new(name), server.require_id().unwrap())
+                .await
+                .expect("failed to create database");
+        }
+
+        let manager = TestConnectionManager::new();
+        let config2 = ServerConfig::new(store).with_num_worker_threads(1);
+        let server2 = Server::new(manager, config2);
+        server2.load_database_configs().await.unwrap();
+
+        assert_eq!(server2.db_names_sorted(), names);
+    }
+
+    #[tokio::test]
+    async fn server_config_is_persisted() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server = Server::new(TestConnectionManager::new(), config);
+
+        // Nothing to load yet
+        server.load_database_configs().await.unwrap();
+        assert!(matches!(server.require_id(), Err(Error::IdNotSet)));
+
+        server.set_id(NonZeroU32::new(3).unwrap()).await.unwrap();
+        server
+            .update_remote(1, "http://127.0.0.1:8082".to_string())
+            .await
+            .unwrap();
+        server
+            .update_remote(2, "http://127.0.0.1:8092".to_string())
+            .await
+            .unwrap();
+        let removed = server.delete_remote(1).await.unwrap();
+        assert_eq!(removed.as_deref(), Some("http://127.0.0.1:8082"));
+        assert_eq!(server.delete_remote(1).await.unwrap(), None);
+
+        let config2 = ServerConfig::new(store).with_num_worker_threads(1);
+        let server2 = Server::new(TestConnectionManager::new(), config2);
+        server2.load_database_configs().await.unwrap();
+
+        assert_eq!(server2.require_id().unwrap().get(), 3);
+        assert_eq!(
+            server2.remotes_sorted(),
+            vec![(2, "http://127.0.0.1:8092".to_string())]
+        );
+
+        // Changes continue from the stored version
+        server2
+            .update_remote(4, "http://127.0.0.1:8102".to_string())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_config_concurrent_modification() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server = Server::new(TestConnectionManager::new(), config);
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+
+        let config2 = ServerConfig::new(store).with_num_worker_threads(1);
+        let server2 = Server::new(TestConnectionManager::new(), config2);
+        server2.load_database_configs().await.unwrap();
+
+        server
+            .update_remote(2, "http://127.0.0.1:8092".to_string())
+            .await
+            .unwrap();
+
+        // server2 has not seen the change made by server
+        let err = server2
+            .update_remote(3, "http://127.0.0.1:8102".to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ServerConfigConflict {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        assert!(server2.remotes_sorted().is_empty());
+
+        // Once reloaded the change succeeds
+        server2.load_database_configs().await.unwrap();
+        server2
+            .update_remote(3, "http://127.0.0.1:8102".to_string())
+            .await
+            .unwrap();
+        assert_eq!(server2.remotes_sorted().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn server_config_shared_store() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        // Two servers with different writer ids share the store
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server = Server::new(TestConnectionManager::new(), config);
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+        server
+            .update_remote(2, "http://127.0.0.1:8092".to_string())
+            .await
+            .unwrap();
+
+        let config2 = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server2 = Server::new(TestConnectionManager::new(), config2);
+        server2.set_id(NonZeroU32::new(2).unwrap()).await.unwrap();
+        server2
+            .update_remote(1, "http://127.0.0.1:8082".to_string())
+            .await
+            .unwrap();
+
+        // Each server restores its own config when given its writer id
+        for (id, remote) in &[(1, 2), (2, 1)] {
+            let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+            let restarted = Server::new(TestConnectionManager::new(), config);
+            restarted
+                .set_id(NonZeroU32::new(*id).unwrap())
+                .await
+                .unwrap();
+            restarted.load_database_configs().await.unwrap();
+
+            let remotes: Vec<_> = restarted
+                .remotes_sorted()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            assert_eq!(remotes, vec![*remote]);
+        }
+
+        // The writer id can not be restored without knowing which to use
+        let config3 = ServerConfig::new(store).with_num_worker_threads(1);
+        let server3 = Server::new(TestConnectionManager::new(), config3);
+        server3.load_database_configs().await.unwrap();
+        assert!(matches!(server3.require_id(), Err(Error::IdNotSet)));
+    }
+
     #[tokio::test]
     async fn duplicate_database_name_rejected() {
         // Covers #643
 
         let manager = TestConnectionManager::new();
         let server = Server::new(manager, config());
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let name = DatabaseName::new("bananas").unwrap();
 
@@ -910,11 +1539,229 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn delete_database() {
+        let manager = TestConnectionManager::new();
+        let config = config();
+        let store = config.store();
+        let server = Server::new(manager, config);
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+
+        let name = DatabaseName::new("bananas").unwrap();
+        let other = DatabaseName::new("bananas_split").unwrap();
+        for name in &[&name, &other] {
+            server
+                .create_database(
+                    DatabaseRules::new((*name).clone()),
+                    server.require_id().unwrap(),
+                )
+                .await
+                .expect("failed to create database");
+
+            let mut path = store.new_path();
+            path.push_all_dirs(&["1", name.as_str(), "data"]);
+            path.set_file_name("file.parquet");
+            let data = bytes::Bytes::from("data");
+            let len = data.len();
+            store
+                .put(
+                    &path,
+                    futures::stream::once(async move { std::io::Result::Ok(data) }),
+                    Some(len),
+                )
+                .await
+                .unwrap();
+        }
+
+        server.delete_database(&name).await.unwrap();
+        assert!(server.db(&name).is_none());
+        assert_eq!(server.db_names_sorted(), vec![other.to_string()]);
+
+        // only the files of the deleted database are removed
+        let mut paths: Vec<_> = store
+            .list(None)
+            .await
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap()
+            .iter()
+            .map(|path| path.display())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "1/bananas_split/data/file.parquet",
+                "1/bananas_split/rules.pb",
+                "1/config.pb",
+            ]
+        );
+
+        // a partially deleted database has no rules and is skipped on restart
+        let mut path = store.new_path();
+        path.push_all_dirs(&["1", name.as_str(), "data"]);
+        path.set_file_name("leftover.parquet");
+        let data = bytes::Bytes::from("data");
+        let len = data.len();
+        store
+            .put(
+                &path,
+                futures::stream::once(async move { std::io::Result::Ok(data) }),
+                Some(len),
+            )
+            .await
+            .unwrap();
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let restarted = Server::new(TestConnectionManager::new(), config);
+        restarted.load_database_configs().await.unwrap();
+        assert_eq!(restarted.db_names_sorted(), vec![other.to_string()]);
+
+        let err = server.delete_database(&name).await.unwrap_err();
+        assert!(matches!(err, Error::DatabaseNotFound { .. }));
+
+        // the name can be reused
+        server
+            .create_database(
+                DatabaseRules::new(name.clone()),
+                server.require_id().unwrap(),
+            )
+            .await
+            .expect("failed to recreate database");
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore_database() {
+        let manager = TestConnectionManager::new();
+        let server = Server::new(manager, config());
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+
+        let name = DatabaseName::new("bananas").unwrap();
+        server
+            .create_database(
+                DatabaseRules::new(name.clone()),
+                server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+        server
+            .write_lines("bananas", &parsed_lines("cpu bar=1 10"))
+            .await
+            .unwrap();
+
+        let db = server.db(&name).unwrap();
+        let partition_key = "1970-01-01T00";
+        let chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, chunk.id())
+            .await
+            .unwrap();
+        db.write_chunk_to_object_store(partition_key, chunk.id())
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().to_str().unwrap().to_string();
+        server.backup_database(&name, &path).unwrap().join().await;
+
+        // The backed up database already exists
+        let err = server.restore_database(&path, None).await.unwrap_err();
+        assert!(matches!(err, Error::DatabaseAlreadyExists { .. }));
+
+        let restored = DatabaseName::new("apples").unwrap();
+        server
+            .restore_database(&path, Some(restored.clone()))
+            .await
+            .unwrap()
+            .join()
+            .await;
+
+        let db = server.db(&restored).unwrap();
+        let planner = SQLQueryPlanner::default();
+        let executor = server.executor();
+        let physical_plan = planner
+            .query(db, "select * from cpu", executor.as_ref())
+            .unwrap();
+
+        let batches = executor.collect(physical_plan).await.unwrap();
+        let expected = vec![
+            "+-----+-------------------------------+",
+            "| bar | time                          |",
+            "+-----+-------------------------------+",
+            "| 1   | 1970-01-01 00:00:00.000000010 |",
+            "+-----+-------------------------------+",
+        ];
+        assert_table_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn persisted_chunks_survive_restart() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let name = DatabaseName::new("bananas").unwrap();
+        let partition_key = "1970-01-01T00";
+
+        let config = ServerConfig::new(Arc::clone(&store)).with_num_worker_threads(1);
+        let server = Server::new(TestConnectionManager::new(), config);
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
+        server
+            .create_database(
+                DatabaseRules::new(name.clone()),
+                server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Persists the data written so far as a new chunk
+        async fn persist(server: &Server<TestConnectionManager>, lp: &str) -> u32 {
+            server
+                .write_lines("bananas", &parsed_lines(lp))
+                .await
+                .unwrap();
+            let db = server.db(&DatabaseName::new("bananas").unwrap()).unwrap();
+            let partition_key = "1970-01-01T00";
+            let chunk = db.rollover_partition(partition_key).await.unwrap();
+            db.load_chunk_to_read_buffer(partition_key, chunk.id())
+                .await
+                .unwrap();
+            db.write_chunk_to_object_store(partition_key, chunk.id())
+                .await
+                .unwrap();
+            chunk.id()
+        }
+
+        let old_id = persist(&server, "cpu bar=1 10").await;
+        let old_chunks = server
+            .db(&name)
+            .unwrap()
+            .preserved_catalog()
+            .chunks()
+            .await
+            .unwrap();
+
+        // After a restart, new chunks get ids that have not been used yet
+        let config = ServerConfig::new(store).with_num_worker_threads(1);
+        let server = Server::new(TestConnectionManager::new(), config);
+        server.load_database_configs().await.unwrap();
+        let new_id = persist(&server, "cpu bar=2 20").await;
+        assert!(new_id > old_id, "{} > {}", new_id, old_id);
+
+        let chunks = server
+            .db(&name)
+            .unwrap()
+            .preserved_catalog()
+            .chunks()
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], old_chunks[0]);
+        assert_eq!(chunks[1].partition_key, partition_key);
+        assert_eq!(chunks[1].chunk_id, new_id);
+    }
+
     #[tokio::test]
     async fn db_names_sorted() {
         let manager = TestConnectionManager::new();
         let server = Server::new(manager, config());
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let names = vec!["bar", "baz"];
 
@@ -934,7 +1781,7 @@ mod tests {
     async fn writes_local() {
         let manager = TestConnectionManager::new();
         let server = Server::new(manager, config());
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let name = DatabaseName::new("foo".to_string()).unwrap();
         server
@@ -970,7 +1817,7 @@ mod tests {
     async fn write_entry_local() {
         let manager = TestConnectionManager::new();
         let server = Server::new(manager, config());
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let name = DatabaseName::new("foo".to_string()).unwrap();
         server
@@ -1018,7 +1865,7 @@ mod tests {
         let cancel_token = CancellationToken::new();
         let background_handle = spawn_worker(Arc::clone(&server), cancel_token.clone());
 
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let db_name = DatabaseName::new("foo").unwrap();
         server
@@ -1159,7 +2006,7 @@ mod tests {
     async fn hard_buffer_limit() {
         let manager = TestConnectionManager::new();
         let server = Server::new(manager, config());
-        server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        server.set_id(NonZeroU32::new(1).unwrap()).await.unwrap();
 
         let name = DatabaseName::new("foo".to_string()).unwrap();
         server
@@ -0,0 +1,296 @@
+//! Limits on the writes and queries of a database.
+//!
+//! The limits are configured in the [`Limits`] of the database rules and
+//! enforced by the [`Limiter`] of each database, so that a single database
+//! can not starve the others of resources. Writes that are too large or
+//! exceed the write rate of the database and queries beyond its maximum
+//! number of concurrent queries are rejected. Rejections of requests that
+//! may succeed when retried later include how long to wait before retrying.
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use snafu::{ensure, Snafu};
+
+use data_types::database_rules::Limits;
+
+/// How long clients are asked to wait before retrying a query that was
+/// rejected because too many queries were running
+const QUERY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Errors admitting a request
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("write of {} rows exceeds the limit of {} rows per write", rows, max))]
+    TooManyRows { rows: usize, max: usize },
+
+    #[snafu(display(
+        "write of {} bytes exceeds the limit of {} bytes per write",
+        bytes,
+        max
+    ))]
+    TooManyBytes { bytes: usize, max: usize },
+
+    #[snafu(display(
+        "write exceeds the limit of {} bytes per second, retry after {:?}",
+        max,
+        retry_after
+    ))]
+    WriteRateExceeded { max: usize, retry_after: Duration },
+
+    #[snafu(display("limit of {} concurrent queries reached", max))]
+    TooManyQueries { max: u32 },
+}
+
+impl Error {
+    /// How long the client should wait before retrying the request, if
+    /// retrying it unchanged can succeed
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::TooManyRows { .. } | Self::TooManyBytes { .. } => None,
+            Self::WriteRateExceeded { retry_after, .. } => Some(*retry_after),
+            Self::TooManyQueries { .. } => Some(QUERY_RETRY_DELAY),
+        }
+    }
+
+    /// The name of the exceeded limit, as in [`Limits`]
+    pub fn limit(&self) -> &'static str {
+        match self {
+            Self::TooManyRows { .. } => "max_write_rows",
+            Self::TooManyBytes { .. } => "max_write_bytes",
+            Self::WriteRateExceeded { .. } => "write_bytes_per_second",
+            Self::TooManyQueries { .. } => "max_concurrent_queries",
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Tracks the running queries and the recent writes of a database to
+/// enforce its [`Limits`]
+#[derive(Debug, Default)]
+pub struct Limiter {
+    running_queries: Arc<AtomicU32>,
+
+    /// The number of bytes that can currently be written, if the write rate
+    /// is limited and there have been writes
+    write_budget: Mutex<Option<WriteBudget>>,
+}
+
+#[derive(Debug)]
+struct WriteBudget {
+    /// Negative after a write larger than the budget
+    bytes: f64,
+    updated_at: Instant,
+}
+
+impl Limiter {
+    /// Admits a write of `rows` rows and `bytes` bytes, or returns an error
+    /// if it exceeds `limits`
+    pub fn admit_write(&self, limits: &Limits, rows: usize, bytes: usize) -> Result<()> {
+        if let Some(max) = limits.max_write_rows {
+            ensure!(
+                rows <= max.get(),
+                TooManyRows {
+                    rows,
+                    max: max.get()
+                }
+            );
+        }
+        if let Some(max) = limits.max_write_bytes {
+            ensure!(
+                bytes <= max.get(),
+                TooManyBytes {
+                    bytes,
+                    max: max.get()
+                }
+            );
+        }
+
+        let max = match limits.write_bytes_per_second {
+            Some(max) if bytes > 0 => max.get(),
+            _ => return Ok(()),
+        };
+
+        // The budget refills at the configured rate up to the bytes of one
+        // second. Writes larger than that are admitted once the budget is
+        // full, leaving it negative, so that they are not rejected forever
+        // but still count against the rate
+        let rate = max as f64;
+        let now = Instant::now();
+        let mut budget = self.write_budget.lock();
+        let budget = budget.get_or_insert(WriteBudget {
+            bytes: rate,
+            updated_at: now,
+        });
+        let elapsed = now.duration_since(budget.updated_at).as_secs_f64();
+        budget.bytes = (budget.bytes + elapsed * rate).min(rate);
+        budget.updated_at = now;
+
+        let required = (bytes as f64).min(rate);
+        ensure!(
+            budget.bytes >= required,
+            WriteRateExceeded {
+                max,
+                retry_after: Duration::from_secs_f64((required - budget.bytes) / rate),
+            }
+        );
+        budget.bytes -= bytes as f64;
+
+        Ok(())
+    }
+
+    /// Admits a query, returning a permit that must be held while the query
+    /// runs, or returns an error if the maximum number of concurrent
+    /// queries of `limits` are already running
+    pub fn start_query(&self, limits: &Limits) -> Result<QueryPermit> {
+        let running = self.running_queries.fetch_add(1, Ordering::SeqCst) + 1;
+        // Dropping the permit if the query is rejected releases its slot
+        let permit = QueryPermit {
+            running_queries: Arc::clone(&self.running_queries),
+        };
+
+        if let Some(max) = limits.max_concurrent_queries {
+            ensure!(running <= max.get(), TooManyQueries { max: max.get() });
+        }
+
+        Ok(permit)
+    }
+
+    /// The number of queries currently running
+    pub fn running_queries(&self) -> u32 {
+        self.running_queries.load(Ordering::SeqCst)
+    }
+}
+
+/// Permission for a query to run, counting towards the concurrent queries
+/// of its database until dropped
+#[derive(Debug)]
+pub struct QueryPermit {
+    running_queries: Arc<AtomicU32>,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.running_queries.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::{NonZeroU32, NonZeroUsize};
+
+    #[test]
+    fn unlimited() {
+        let limiter = Limiter::default();
+        let limits = Limits::default();
+
+        limiter.admit_write(&limits, 1_000_000, 1_000_000).unwrap();
+        let _permits: Vec<_> = (0..100)
+            .map(|_| limiter.start_query(&limits).unwrap())
+            .collect();
+        assert_eq!(limiter.running_queries(), 100);
+    }
+
+    #[test]
+    fn write_size() {
+        let limiter = Limiter::default();
+        let limits = Limits {
+            max_write_rows: NonZeroUsize::new(10),
+            max_write_bytes: NonZeroUsize::new(100),
+            ..Default::default()
+        };
+
+        limiter.admit_write(&limits, 10, 100).unwrap();
+
+        let err = limiter.admit_write(&limits, 11, 100).unwrap_err();
+        assert!(matches!(err, Error::TooManyRows { rows: 11, max: 10 }));
+        assert_eq!(err.retry_after(), None);
+        assert_eq!(err.limit(), "max_write_rows");
+
+        let err = limiter.admit_write(&limits, 10, 101).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooManyBytes {
+                bytes: 101,
+                max: 100
+            }
+        ));
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn write_rate() {
+        let limiter = Limiter::default();
+        let limits = Limits {
+            write_bytes_per_second: NonZeroUsize::new(1000),
+            ..Default::default()
+        };
+
+        limiter.admit_write(&limits, 1, 600).unwrap();
+        limiter.admit_write(&limits, 1, 300).unwrap();
+
+        let err = limiter.admit_write(&limits, 1, 500).unwrap_err();
+        assert!(matches!(err, Error::WriteRateExceeded { max: 1000, .. }));
+        // 400 more bytes are available after at most 0.4 seconds
+        let retry_after = err.retry_after().unwrap();
+        assert!(
+            retry_after <= Duration::from_millis(400),
+            "{:?}",
+            retry_after
+        );
+        assert!(
+            retry_after > Duration::from_millis(300),
+            "{:?}",
+            retry_after
+        );
+        assert_eq!(err.limit(), "write_bytes_per_second");
+    }
+
+    #[test]
+    fn write_larger_than_rate() {
+        let limiter = Limiter::default();
+        let limits = Limits {
+            write_bytes_per_second: NonZeroUsize::new(1000),
+            ..Default::default()
+        };
+
+        // admitted with a full budget, which it then exceeds
+        limiter.admit_write(&limits, 1, 5000).unwrap();
+
+        let err = limiter.admit_write(&limits, 1, 1).unwrap_err();
+        let retry_after = err.retry_after().unwrap();
+        assert!(retry_after > Duration::from_secs(3), "{:?}", retry_after);
+
+        // empty writes are always admitted
+        limiter.admit_write(&limits, 0, 0).unwrap();
+    }
+
+    #[test]
+    fn concurrent_queries() {
+        let limiter = Limiter::default();
+        let limits = Limits {
+            max_concurrent_queries: NonZeroU32::new(2),
+            ..Default::default()
+        };
+
+        let first = limiter.start_query(&limits).unwrap();
+        let _second = limiter.start_query(&limits).unwrap();
+
+        let err = limiter.start_query(&limits).unwrap_err();
+        assert!(matches!(err, Error::TooManyQueries { max: 2 }));
+        assert_eq!(err.retry_after(), Some(QUERY_RETRY_DELAY));
+        // the rejected query does not count as running
+        assert_eq!(limiter.running_queries(), 2);
+
+        drop(first);
+        assert_eq!(limiter.running_queries(), 1);
+        let _third = limiter.start_query(&limits).unwrap();
+    }
+}
@@ -5,6 +5,7 @@
 //! Immutable Buffer, and (eventually) Parquet files, running queries
 //! against it and verifying the same answer is produced in all scenarios
 
+pub mod influxql;
 pub mod influxrpc;
 pub mod scenarios;
 pub mod sql;
@@ -0,0 +1,188 @@
+//! Tests of InfluxQL query planning and execution, covering the
+//! `SELECT` and `SHOW` statements the 1.x `/query` endpoint supports
+
+use arrow_deps::{arrow::record_batch::RecordBatch, assert_batches_sorted_eq};
+use query::{
+    exec::stringset::{IntoStringSet, StringSetRef},
+    frontend::influxql::{parser::parse_statements, InfluxQLPlan, InfluxQLQueryPlanner},
+};
+
+use crate::query_tests::scenarios::*;
+
+/// Runs the `SELECT` statement `$QUERY` against each scenario and
+/// compares the formatted output to `$EXPECTED_LINES`
+macro_rules! run_influxql_select_test_case {
+    ($DB_SETUP:expr, $QUERY:expr, $EXPECTED_LINES:expr) => {
+        test_helpers::maybe_start_logging();
+        let query = $QUERY;
+        for scenario in $DB_SETUP.make().await {
+            let DBScenario {
+                scenario_name, db, ..
+            } = scenario;
+            println!("Running scenario '{}'", scenario_name);
+            println!("InfluxQL: '{:#?}'", query);
+
+            let statements = parse_statements(query).expect("parsed query");
+            let plan = InfluxQLQueryPlanner::new()
+                .statement(&db, &statements[0])
+                .expect("built plan successfully");
+            let plan = match plan {
+                InfluxQLPlan::Select { plan, .. } => plan.expect("plan has rows"),
+                _ => panic!("expected select plan, got {:?}", plan),
+            };
+
+            let results: Vec<RecordBatch> = db
+                .executor()
+                .run_logical_plan(plan)
+                .await
+                .expect("Running plan");
+
+            assert_batches_sorted_eq!($EXPECTED_LINES, &results);
+        }
+    };
+}
+
+/// Runs the `SHOW` statement `$QUERY` against each scenario and
+/// compares the names it returns to `$EXPECTED_NAMES`
+macro_rules! run_influxql_show_test_case {
+    ($DB_SETUP:expr, $QUERY:expr, $EXPECTED_NAMES:expr) => {
+        test_helpers::maybe_start_logging();
+        let query = $QUERY;
+        let expected_names = $EXPECTED_NAMES;
+        for scenario in $DB_SETUP.make().await {
+            let DBScenario {
+                scenario_name, db, ..
+            } = scenario;
+            println!("Running scenario '{}'", scenario_name);
+            println!("InfluxQL: '{:#?}'", query);
+
+            let statements = parse_statements(query).expect("parsed query");
+            let plan = InfluxQLQueryPlanner::new()
+                .statement(&db, &statements[0])
+                .expect("built plan successfully");
+            let executor = db.executor();
+            let names = match plan {
+                InfluxQLPlan::ShowMeasurements(plan)
+                | InfluxQLPlan::ShowTagKeys(plan)
+                | InfluxQLPlan::ShowTagValues { plan, .. } => executor
+                    .to_string_set(plan)
+                    .await
+                    .expect("converted plan to strings successfully"),
+                InfluxQLPlan::ShowFieldKeys(plan) => {
+                    let fields = executor
+                        .to_field_list(plan)
+                        .await
+                        .expect("converted plan to fields successfully");
+                    let names: Vec<_> = fields.fields.iter().map(|f| f.name.as_str()).collect();
+                    to_stringset(&names)
+                }
+                InfluxQLPlan::Select { .. } => panic!("expected show plan, got {:?}", plan),
+            };
+
+            assert_eq!(
+                names,
+                to_stringset(&expected_names),
+                "Error in  scenario '{}'\n\nexpected:\n{:?}\nactual:\n{:?}",
+                scenario_name,
+                expected_names,
+                names
+            );
+        }
+    };
+}
+
+#[tokio::test]
+async fn influxql_select_group_by_time() {
+    let expected = vec![
+        "+-------------------------------+-------+",
+        "| time                          | count |",
+        "+-------------------------------+-------+",
+        "| 1970-01-01 00:00:00.000000200 | 2     |",
+        "| 1970-01-01 00:00:00.000000400 | 2     |",
+        "| 1970-01-01 00:00:00.000000600 | 1     |",
+        "+-------------------------------+-------+",
+    ];
+    run_influxql_select_test_case!(
+        TwoMeasurementsManyNulls {},
+        "SELECT count(temp) FROM o2 GROUP BY time(200ns)",
+        &expected
+    );
+}
+
+#[tokio::test]
+async fn influxql_select_group_by_time_fill() {
+    // the windows in which only other_temp was written have no temp
+    let expected = vec![
+        "+-------------------------------+------+",
+        "| time                          | max  |",
+        "+-------------------------------+------+",
+        "| 1970-01-01 00:00:00           | 70.4 |",
+        "| 1970-01-01 00:00:00.000000200 | 0    |",
+        "| 1970-01-01 00:00:00.000000300 | 0    |",
+        "| 1970-01-01 00:00:00.000100    | 70.4 |",
+        "+-------------------------------+------+",
+    ];
+    run_influxql_select_test_case!(
+        TwoMeasurementsManyFields {},
+        "SELECT max(temp) FROM h2o GROUP BY time(100ns) FILL(0)",
+        &expected
+    );
+}
+
+#[tokio::test]
+async fn influxql_select_selectors_group_by_tag() {
+    let expected = vec![
+        "+---------------------+-------+------+-------+",
+        "| time                | state | max  | first |",
+        "+---------------------+-------+------+-------+",
+        "| 1970-01-01 00:00:00 | CA    | 79   | 79    |",
+        "| 1970-01-01 00:00:00 | MA    | 50.4 | 50.4  |",
+        "| 1970-01-01 00:00:00 | NY    | 61   | 60.8  |",
+        "+---------------------+-------+------+-------+",
+    ];
+    run_influxql_select_test_case!(
+        TwoMeasurementsManyNulls {},
+        "SELECT max(temp), first(temp) FROM o2 GROUP BY state",
+        &expected
+    );
+}
+
+#[tokio::test]
+async fn influxql_show_measurements() {
+    run_influxql_show_test_case!(
+        TwoMeasurementsManyNulls {},
+        "SHOW MEASUREMENTS",
+        vec!["h2o", "o2"]
+    );
+}
+
+#[tokio::test]
+async fn influxql_show_tag_keys() {
+    run_influxql_show_test_case!(
+        TwoMeasurementsManyNulls {},
+        "SHOW TAG KEYS FROM o2",
+        vec!["borough", "city", "state"]
+    );
+}
+
+#[tokio::test]
+async fn influxql_show_tag_values() {
+    run_influxql_show_test_case!(
+        TwoMeasurementsManyNulls {},
+        r#"SHOW TAG VALUES FROM o2 WITH KEY = "city" WHERE state = 'NY'"#,
+        vec!["NYC"]
+    );
+}
+
+#[tokio::test]
+async fn influxql_show_field_keys() {
+    run_influxql_show_test_case!(
+        TwoMeasurementsManyFields {},
+        "SHOW FIELD KEYS FROM h2o",
+        vec!["moisture", "other_temp", "temp"]
+    );
+}
+
+fn to_stringset(v: &[&str]) -> StringSetRef {
+    v.into_stringset().unwrap()
+}
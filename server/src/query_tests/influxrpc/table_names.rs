@@ -73,6 +73,21 @@ async fn list_table_names_data_pred_250_300() {
     run_table_names_test_case!(TwoMeasurements {}, tsp(250, 300), vec![]);
 }
 
+#[tokio::test]
+async fn list_table_names_with_delete() {
+    run_table_names_test_case!(TwoMeasurementsWithDelete {}, EMPTY_PREDICATE, vec!["cpu"]);
+}
+
+#[tokio::test]
+async fn list_table_names_with_delete_pred_0_101() {
+    run_table_names_test_case!(TwoMeasurementsWithDelete {}, tsp(0, 101), vec!["cpu"]);
+}
+
+#[tokio::test]
+async fn list_table_names_with_delete_pred_101_300() {
+    run_table_names_test_case!(TwoMeasurementsWithDelete {}, tsp(101, 300), vec![]);
+}
+
 // make a single timestamp predicate between r1 and r2
 fn tsp(r1: i64, r2: i64) -> Predicate {
     PredicateBuilder::default().timestamp_range(r1, r2).build()
@@ -128,6 +128,13 @@ async fn list_tag_name_end_to_end() {
     run_tag_keys_test_case!(EndToEndTest {}, predicate, expected_tag_keys);
 }
 
+#[tokio::test]
+async fn list_tag_columns_with_delete() {
+    let predicate = PredicateBuilder::default().build();
+    let expected_tag_keys = vec!["region"];
+    run_tag_keys_test_case!(TwoMeasurementsWithDelete {}, predicate, expected_tag_keys);
+}
+
 fn to_stringset(v: &[&str]) -> StringSetRef {
     v.into_stringset().unwrap()
 }
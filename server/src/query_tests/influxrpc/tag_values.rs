@@ -244,6 +244,19 @@ async fn list_tag_values_field_col() {
     }
 }
 
+#[tokio::test]
+async fn list_tag_values_with_delete() {
+    let tag_name = "region";
+    let predicate = PredicateBuilder::default().build();
+    let expected_tag_keys = vec!["west"];
+    run_tag_values_test_case!(
+        TwoMeasurementsWithDelete {},
+        tag_name,
+        predicate,
+        expected_tag_keys
+    );
+}
+
 fn to_stringset(v: &[&str]) -> StringSetRef {
     v.into_stringset().unwrap()
 }
@@ -3,7 +3,13 @@
 #[allow(unused_imports, dead_code, unused_macros)]
 use query::PartitionChunk;
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use data_types::{
+    delete::{DeleteExpr, DeleteOp, DeletePredicate},
+    timestamp::TimestampRange,
+};
 
 use crate::db::{test_helpers::write_lp, Db};
 
@@ -64,7 +70,7 @@ impl DBSetup for NoData {
         assert_eq!(count_mutable_buffer_chunks(&db), 1);
         assert_eq!(count_read_buffer_chunks(&db), 1); // only open chunk
 
-        db.drop_chunk(partition_key, 0).unwrap();
+        db.drop_chunk(partition_key, 0).await.unwrap();
 
         assert_eq!(count_mutable_buffer_chunks(&db), 1);
         assert_eq!(count_read_buffer_chunks(&db), 0);
@@ -95,6 +101,51 @@ impl DBSetup for TwoMeasurements {
     }
 }
 
+/// Two measurements data in a single chunk, where all rows of one
+/// measurement and the only rows of one series of the other have been
+/// deleted
+#[derive(Debug)]
+pub struct TwoMeasurementsWithDelete {}
+#[async_trait]
+impl DBSetup for TwoMeasurementsWithDelete {
+    async fn make(&self) -> Vec<DBScenario> {
+        let partition_key = "1970-01-01T00";
+        let lp_lines = vec![
+            "cpu,region=west user=23.2 100",
+            "cpu,region=east,host=foo user=21.0 150",
+            "disk,region=east bytes=99i 200",
+        ];
+
+        let mut scenarios = make_one_chunk_scenarios(partition_key, &lp_lines.join("\n")).await;
+        for scenario in &mut scenarios {
+            scenario
+                .db
+                .delete(Arc::new(DeletePredicate {
+                    table_name: "cpu".to_string(),
+                    range: TimestampRange::new(0, 1000),
+                    exprs: vec![DeleteExpr {
+                        column: "host".to_string(),
+                        op: DeleteOp::Eq,
+                        value: "foo".to_string(),
+                    }],
+                }))
+                .await
+                .unwrap();
+            scenario
+                .db
+                .delete(Arc::new(DeletePredicate {
+                    table_name: "disk".to_string(),
+                    range: TimestampRange::new(0, 1000),
+                    exprs: vec![],
+                }))
+                .await
+                .unwrap();
+            scenario.scenario_name = format!("{} with deletes", scenario.scenario_name);
+        }
+        scenarios
+    }
+}
+
 #[derive(Debug)]
 pub struct TwoMeasurementsUnsignedType {}
 #[async_trait]
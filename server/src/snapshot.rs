@@ -149,9 +149,10 @@ where
             let mut location = self.data_path.clone();
             let file_name = format!("{}.parquet", table_name);
             location.set_file_name(&file_name);
-            let data = parquet_file::storage::Storage::parquet_stream_to_bytes(stream, schema)
-                .await
-                .context(ParquetStreamToByte)?;
+            let data =
+                parquet_file::storage::Storage::parquet_stream_to_bytes(stream, schema, None)
+                    .await
+                    .context(ParquetStreamToByte)?;
             self.write_to_object_store(data, &location).await?;
             self.mark_table_finished(pos);
 
@@ -355,6 +356,7 @@ mem,host=A,region=west used=45 1
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
         let chunk = Arc::new(DBChunk::MutableBuffer {
             chunk: ChunkWB::new(11, &registry).snapshot(),
+            delete_predicates: vec![],
         });
         let mut metadata_path = store.new_path();
         metadata_path.push_dir("meta");
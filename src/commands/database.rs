@@ -1,12 +1,15 @@
 //! This module implements the `database` CLI command
-use std::{fs::File, io::Read, path::PathBuf, str::FromStr};
+use std::{convert::TryInto, fs::File, io::Read, path::PathBuf, str::FromStr};
 
+use data_types::job::Operation;
+use generated_types::google::FieldViolation;
 use influxdb_iox_client::{
-    connection::Builder,
+    connection::Connection,
     flight,
     format::QueryOutputFormat,
     management::{
-        self, generated_types::*, CreateDatabaseError, GetDatabaseError, ListDatabaseError,
+        self, generated_types::*, BackupDatabaseError, CollectGarbageError, CreateDatabaseError,
+        DeleteDatabaseError, GetDatabaseError, ListDatabaseError, RestoreDatabaseError,
     },
     write::{self, WriteError},
 };
@@ -27,8 +30,8 @@ pub enum Error {
     #[error("Error listing databases: {0}")]
     ListDatabaseError(#[from] ListDatabaseError),
 
-    #[error("Error connecting to IOx: {0}")]
-    ConnectionError(#[from] influxdb_iox_client::connection::Error),
+    #[error("Error deleting database: {0}")]
+    DeleteDatabaseError(#[from] DeleteDatabaseError),
 
     #[error("Error reading file {:?}: {}", file_name, source)]
     ReadingFile {
@@ -51,6 +54,18 @@ pub enum Error {
     #[error("Error in partition subcommand: {0}")]
     Partition(#[from] partition::Error),
 
+    #[error("Error collecting garbage: {0}")]
+    CollectGarbageError(#[from] CollectGarbageError),
+
+    #[error("Error backing up database: {0}")]
+    BackupDatabaseError(#[from] BackupDatabaseError),
+
+    #[error("Error restoring database: {0}")]
+    RestoreDatabaseError(#[from] RestoreDatabaseError),
+
+    #[error("Received invalid response: {0}")]
+    InvalidResponse(#[from] FieldViolation),
+
     #[error("JSON Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
 }
@@ -113,6 +128,27 @@ struct Create {
     /// Do not allow writing new data to this database
     #[structopt(long)]
     immutable: bool,
+
+    /// Once a partition has at least this many persisted chunks that are no
+    /// larger than persisted_compaction_max_chunk_size, they are compacted
+    /// into a single chunk (0 disables compaction, 1 is invalid)
+    #[structopt(long, default_value = "0")]
+    persisted_compaction_chunk_count: u32,
+
+    /// Only persisted chunks up to this number of bytes are compacted (0
+    /// compacts chunks of any size)
+    #[structopt(long, default_value = "0")]
+    persisted_compaction_max_chunk_size: usize,
+
+    /// Comma separated tag columns to sort persisted data on, ahead of the
+    /// remaining tag columns (sorted on by increasing cardinality) and time
+    #[structopt(long, use_delimiter = true)]
+    persisted_sort_key: Vec<String>,
+
+    /// Drop data with timestamps older than this number of seconds (0
+    /// retains data forever)
+    #[structopt(long, default_value = "0")]
+    retention_period_seconds: u32,
 }
 
 /// Get list of databases
@@ -126,6 +162,13 @@ struct Get {
     name: String,
 }
 
+/// Delete a database, including its rules and data in object storage
+#[derive(Debug, StructOpt)]
+struct Delete {
+    /// The name of the database
+    name: String,
+}
+
 /// Write data into the specified database
 #[derive(Debug, StructOpt)]
 struct Write {
@@ -150,21 +193,65 @@ struct Query {
     format: String,
 }
 
+/// Delete files in object storage that are no longer referenced by the
+/// database
+#[derive(Debug, StructOpt)]
+struct CollectGarbage {
+    /// The name of the database
+    name: String,
+
+    /// Only delete files that were last modified at least this many seconds
+    /// ago
+    #[structopt(long, default_value = "3600")] // 1 hour
+    grace_period_seconds: u64,
+
+    /// Only list the files that would be deleted
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+/// Write a backup of the rules, catalog and persisted chunks of a database
+/// to a directory on the server's file system
+#[derive(Debug, StructOpt)]
+struct Backup {
+    /// The name of the database
+    name: String,
+
+    /// The directory on the server's file system to write the backup to.
+    /// It must be empty
+    path: String,
+}
+
+/// Create a database from a backup in a directory on the server's file
+/// system
+#[derive(Debug, StructOpt)]
+struct Restore {
+    /// The directory on the server's file system containing the backup
+    path: String,
+
+    /// The name of the restored database. Defaults to the name of the
+    /// backed up database
+    #[structopt(long)]
+    name: Option<String>,
+}
+
 /// All possible subcommands for database
 #[derive(Debug, StructOpt)]
 enum Command {
     Create(Create),
     List(List),
     Get(Get),
+    Delete(Delete),
     Write(Write),
     Query(Query),
     Chunk(chunk::Config),
     Partition(partition::Config),
+    CollectGarbage(CollectGarbage),
+    Backup(Backup),
+    Restore(Restore),
 }
 
-pub async fn command(url: String, config: Config) -> Result<()> {
-    let connection = Builder::default().build(url.clone()).await?;
-
+pub async fn command(connection: Connection, config: Config) -> Result<()> {
     match config.command {
         Command::Create(command) => {
             let mut client = management::Client::new(connection);
@@ -180,6 +267,11 @@ pub async fn command(url: String, config: Config) -> Result<()> {
                     drop_non_persisted: command.drop_non_persisted,
                     persist: command.persist,
                     immutable: command.immutable,
+                    persisted_compaction_chunk_count: command.persisted_compaction_chunk_count,
+                    persisted_compaction_max_chunk_size: command.persisted_compaction_max_chunk_size
+                        as _,
+                    persisted_sort_key: command.persisted_sort_key,
+                    retention_period_seconds: command.retention_period_seconds,
                 }),
 
                 // Default to hourly partitions
@@ -209,6 +301,11 @@ pub async fn command(url: String, config: Config) -> Result<()> {
             let database = client.get_database(get.name).await?;
             println!("{}", serde_json::to_string_pretty(&database)?);
         }
+        Command::Delete(delete) => {
+            let mut client = management::Client::new(connection);
+            client.delete_database(delete.name).await?;
+            println!("Ok");
+        }
         Command::Write(write) => {
             let mut client = write::Client::new(connection);
 
@@ -252,10 +349,50 @@ pub async fn command(url: String, config: Config) -> Result<()> {
             println!("{}", formatted_result);
         }
         Command::Chunk(config) => {
-            chunk::command(url, config).await?;
+            chunk::command(connection, config).await?;
         }
         Command::Partition(config) => {
-            partition::command(url, config).await?;
+            partition::command(connection, config).await?;
+        }
+        Command::CollectGarbage(collect_garbage) => {
+            let mut client = management::Client::new(connection);
+            let CollectGarbage {
+                name,
+                grace_period_seconds,
+                dry_run,
+            } = collect_garbage;
+
+            if dry_run {
+                let orphaned_files = client
+                    .find_orphaned_files(name, grace_period_seconds)
+                    .await?;
+                println!("{}", orphaned_files.join("\n"));
+            } else {
+                let operation: Operation = client
+                    .collect_garbage(name, grace_period_seconds)
+                    .await?
+                    .try_into()?;
+
+                serde_json::to_writer_pretty(std::io::stdout(), &operation)?;
+            }
+        }
+        Command::Backup(backup) => {
+            let mut client = management::Client::new(connection);
+            let operation: Operation = client
+                .backup_database(backup.name, backup.path)
+                .await?
+                .try_into()?;
+
+            serde_json::to_writer_pretty(std::io::stdout(), &operation)?;
+        }
+        Command::Restore(restore) => {
+            let mut client = management::Client::new(connection);
+            let operation: Operation = client
+                .restore_database(restore.path, restore.name)
+                .await?
+                .try_into()?;
+
+            serde_json::to_writer_pretty(std::io::stdout(), &operation)?;
         }
     }
 
@@ -1,11 +1,12 @@
 //! This module implements the `chunk` CLI command
 use data_types::chunk::ChunkSummary;
+use data_types::job::Operation;
 use generated_types::google::FieldViolation;
 use influxdb_iox_client::{
-    connection::Builder,
-    management::{self, ListChunksError},
+    connection::Connection,
+    management::{self, DropPartitionChunkError, ListChunksError, PersistPartitionChunkError},
 };
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
 use structopt::StructOpt;
 use thiserror::Error;
 
@@ -14,14 +15,17 @@ pub enum Error {
     #[error("Error listing chunks: {0}")]
     ListChunkError(#[from] ListChunksError),
 
+    #[error("Error dropping chunk: {0}")]
+    DropPartitionChunkError(#[from] DropPartitionChunkError),
+
+    #[error("Error persisting chunk: {0}")]
+    PersistPartitionChunkError(#[from] PersistPartitionChunkError),
+
     #[error("Error interpreting server response: {0}")]
     ConvertingResponse(#[from] FieldViolation),
 
     #[error("Error rendering response as JSON: {0}")]
     WritingJson(#[from] serde_json::Error),
-
-    #[error("Error connecting to IOx: {0}")]
-    ConnectionError(#[from] influxdb_iox_client::connection::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -40,21 +44,47 @@ struct List {
     db_name: String,
 }
 
+/// Drops a chunk from the catalog and all storage systems
+#[derive(Debug, StructOpt)]
+struct DropChunk {
+    /// The name of the database
+    db_name: String,
+
+    /// The partition key
+    partition_key: String,
+
+    /// The chunk id
+    chunk_id: u32,
+}
+
+/// Writes a chunk in the read buffer to object storage
+#[derive(Debug, StructOpt)]
+struct PersistChunk {
+    /// The name of the database
+    db_name: String,
+
+    /// The partition key
+    partition_key: String,
+
+    /// The chunk id
+    chunk_id: u32,
+}
+
 /// All possible subcommands for chunk
 #[derive(Debug, StructOpt)]
 enum Command {
     List(List),
+    Drop(DropChunk),
+    Persist(PersistChunk),
 }
 
-pub async fn command(url: String, config: Config) -> Result<()> {
-    let connection = Builder::default().build(url).await?;
+pub async fn command(connection: Connection, config: Config) -> Result<()> {
+    let mut client = management::Client::new(connection);
 
     match config.command {
         Command::List(get) => {
             let List { db_name } = get;
 
-            let mut client = management::Client::new(connection);
-
             let chunks = client.list_chunks(db_name).await?;
 
             let chunks = chunks
@@ -64,6 +94,34 @@ pub async fn command(url: String, config: Config) -> Result<()> {
 
             serde_json::to_writer_pretty(std::io::stdout(), &chunks)?;
         }
+        Command::Drop(drop_chunk) => {
+            let DropChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            } = drop_chunk;
+
+            let operation: Operation = client
+                .drop_partition_chunk(db_name, partition_key, chunk_id)
+                .await?
+                .try_into()?;
+
+            serde_json::to_writer_pretty(std::io::stdout(), &operation)?;
+        }
+        Command::Persist(persist_chunk) => {
+            let PersistChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            } = persist_chunk;
+
+            let operation: Operation = client
+                .persist_partition_chunk(db_name, partition_key, chunk_id)
+                .await?
+                .try_into()?;
+
+            serde_json::to_writer_pretty(std::io::stdout(), &operation)?;
+        }
     }
 
     Ok(())
@@ -1,6 +1,6 @@
 use super::run::Config;
 use observability_deps::{
-    opentelemetry::{self, metrics::Counter},
+    opentelemetry::{self, metrics::Counter, KeyValue},
     opentelemetry_prometheus,
     prometheus::{Encoder, TextEncoder},
     tracing::log::warn,
@@ -25,6 +25,10 @@ pub struct SystemMetrics {
     pub lp_lines_success: Counter<u64>,
     /// How many bytes of protocol line were parsed and successfully loaded
     pub lp_bytes_success: Counter<u64>,
+    /// How many writes were rejected for exceeding the limits of a database
+    pub writes_rejected: Counter<u64>,
+    /// How many queries were rejected for exceeding the limits of a database
+    pub queries_rejected: Counter<u64>,
 }
 
 /// crate wide metrics
@@ -60,6 +64,31 @@ impl SystemMetrics {
                 .u64_counter("ingest.lp.bytes.success")
                 .with_description("line protocol formatted bytes which were successfully loaded")
                 .init(),
+
+            writes_rejected: meter()
+                .u64_counter("ingest.writes.rejected")
+                .with_description("writes which were rejected for exceeding a database limit")
+                .init(),
+
+            queries_rejected: meter()
+                .u64_counter("query.rejected")
+                .with_description("queries which were rejected for exceeding a database limit")
+                .init(),
+        }
+    }
+
+    /// Records a request to `db_name` rejected with `error`
+    pub fn record_limit_exceeded(&self, db_name: &str, error: &server::limits::Error) {
+        let metric_kv = [
+            KeyValue::new("db_name", db_name.to_string()),
+            KeyValue::new("limit", error.limit()),
+        ];
+
+        match error {
+            server::limits::Error::TooManyQueries { .. } => {
+                self.queries_rejected.add(1, &metric_kv)
+            }
+            _ => self.writes_rejected.add(1, &metric_kv),
         }
     }
 }
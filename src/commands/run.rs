@@ -18,6 +18,10 @@ pub const DEFAULT_GRPC_BIND_ADDR: &str = "127.0.0.1:8082";
 /// specified.
 pub const FALLBACK_AWS_REGION: &str = "us-east-1";
 
+/// The default maximum size, in bytes, of the local object store cache
+/// (1 GiB).
+pub const DEFAULT_OBJECT_STORE_CACHE_SIZE: &str = "1073741824";
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Run: {0}")]
@@ -81,9 +85,22 @@ pub struct Config {
     /// replicated writes, WAL segments and Chunks. Must be unique in a group of
     /// connected or semi-connected IOx servers. Must be a number that can be
     /// represented by a 32-bit unsigned integer.
+    ///
+    /// Once set, the identifier is stored in the object store. If the object
+    /// store holds the data of this server only, the identifier is restored
+    /// on restart, so it only needs to be specified on the first start.
     #[structopt(long = "--writer-id", env = "INFLUXDB_IOX_ID")]
     pub writer_id: Option<NonZeroU32>,
 
+    /// A token granting admin access to all databases.
+    ///
+    /// If set, all requests except health checks must be authenticated
+    /// with an API token, sent in the `Authorization` header as
+    /// `Token <token>`. Further tokens with narrower permissions can be
+    /// created with `influxdb_iox server token create`.
+    #[structopt(long = "--admin-token", env = "INFLUXDB_IOX_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+
     /// The address on which IOx will serve HTTP API requests.
     #[structopt(
     long = "--api-bind",
@@ -102,6 +119,36 @@ pub struct Config {
     )]
     pub grpc_bind_address: SocketAddr,
 
+    /// Path to a PEM encoded certificate chain.
+    ///
+    /// If set, along with `--tls-key`, the HTTP and gRPC APIs are served
+    /// over TLS.
+    #[structopt(
+        long = "--tls-cert",
+        env = "INFLUXDB_IOX_TLS_CERT",
+        requires = "tls-key"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM encoded private key of `--tls-cert`.
+    #[structopt(
+        long = "--tls-key",
+        env = "INFLUXDB_IOX_TLS_KEY",
+        requires = "tls-cert"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    /// Path to a PEM encoded CA certificate.
+    ///
+    /// If set, clients must present a certificate signed by this CA
+    /// (mutual TLS). Requires `--tls-cert` and `--tls-key`.
+    #[structopt(
+        long = "--tls-client-ca",
+        env = "INFLUXDB_IOX_TLS_CLIENT_CA",
+        requires = "tls-cert"
+    )]
+    pub tls_client_ca: Option<PathBuf>,
+
     /// The location InfluxDB IOx will use to store files locally.
     #[structopt(long = "--data-dir", env = "INFLUXDB_IOX_DB_DIR")]
     pub database_directory: Option<PathBuf>,
@@ -215,6 +262,48 @@ Possible values (case insensitive):
     #[structopt(long = "--azure-storage-access-key", env = "AZURE_STORAGE_ACCESS_KEY")]
     pub azure_storage_access_key: Option<String>,
 
+    /// If set, objects read from the object store are cached in this local
+    /// directory, so repeated reads don't have to fetch them again. Mostly
+    /// useful with a cloud object store.
+    ///
+    /// Cached data is checked against the object store before being used,
+    /// and anything already in the directory on startup is discarded.
+    #[structopt(
+        long = "--object-store-cache-dir",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_DIR"
+    )]
+    pub object_store_cache_dir: Option<PathBuf>,
+
+    /// The maximum number of bytes to store in the local object store
+    /// cache, after which the least recently used data is evicted.
+    ///
+    /// Must also set `--object-store-cache-dir` to have any effect.
+    #[structopt(
+        long = "--object-store-cache-size",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_SIZE",
+        default_value = DEFAULT_OBJECT_STORE_CACHE_SIZE,
+    )]
+    pub object_store_cache_size: usize,
+
+    /// The maximum number of times to retry an object store operation that
+    /// fails with a transient error, such as a network problem, with an
+    /// exponentially increasing delay between attempts.
+    #[structopt(
+        long = "--object-store-max-retries",
+        env = "INFLUXDB_IOX_OBJECT_STORE_MAX_RETRIES",
+        default_value = "5"
+    )]
+    pub object_store_max_retries: usize,
+
+    /// The number of seconds after which an attempt at an object store
+    /// operation is abandoned, and retried if any retries remain.
+    #[structopt(
+        long = "--object-store-timeout-secs",
+        env = "INFLUXDB_IOX_OBJECT_STORE_TIMEOUT_SECS",
+        default_value = "60"
+    )]
+    pub object_store_timeout_secs: u64,
+
     /// If set, Jaeger traces are emitted to this host
     /// using the OpenTelemetry tracer.
     ///
@@ -347,4 +436,29 @@ mod tests {
             clap::ErrorKind::ValueValidation
         );
     }
+
+    #[test]
+    fn test_tls() {
+        let c = Config::from_iter_safe(
+            to_vec(&["server", "--tls-cert", "cert.pem", "--tls-key", "key.pem"]).into_iter(),
+        )
+        .unwrap();
+        assert_eq!(c.tls_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(c.tls_key, Some(PathBuf::from("key.pem")));
+        assert_eq!(c.tls_client_ca, None);
+
+        assert_eq!(
+            Config::from_iter_safe(to_vec(&["server", "--tls-cert", "cert.pem"]).into_iter())
+                .map_err(|e| e.kind)
+                .expect_err("must fail"),
+            clap::ErrorKind::MissingRequiredArgument
+        );
+
+        assert_eq!(
+            Config::from_iter_safe(to_vec(&["server", "--tls-client-ca", "ca.pem"]).into_iter())
+                .map_err(|e| e.kind)
+                .expect_err("must fail"),
+            clap::ErrorKind::MissingRequiredArgument
+        );
+    }
 }
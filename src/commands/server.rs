@@ -1,7 +1,8 @@
 //! Implementation of command line option for manipulating and showing server
 //! config
 
-use crate::commands::server_remote;
+use crate::commands::{server_remote, server_token};
+use influxdb_iox_client::connection::Connection;
 use structopt::StructOpt;
 use thiserror::Error;
 
@@ -9,6 +10,9 @@ use thiserror::Error;
 pub enum Error {
     #[error("Remote: {0}")]
     RemoteError(#[from] server_remote::Error),
+
+    #[error("Token: {0}")]
+    TokenError(#[from] server_token::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -17,10 +21,12 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 #[structopt(name = "server", about = "IOx server commands")]
 pub enum Config {
     Remote(crate::commands::server_remote::Config),
+    Token(crate::commands::server_token::Config),
 }
 
-pub async fn command(url: String, config: Config) -> Result<()> {
+pub async fn command(connection: Connection, config: Config) -> Result<()> {
     match config {
-        Config::Remote(config) => Ok(server_remote::command(url, config).await?),
+        Config::Remote(config) => Ok(server_remote::command(connection, config).await?),
+        Config::Token(config) => Ok(server_token::command(connection, config).await?),
     }
 }
@@ -0,0 +1,131 @@
+use influxdb_iox_client::{
+    connection::Connection,
+    management::{
+        self,
+        generated_types::{Permission, Scope},
+    },
+};
+use structopt::StructOpt;
+use thiserror::Error;
+
+use prettytable::{format, Cell, Row, Table};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Create token error: {0}")]
+    CreateError(#[from] management::CreateTokenError),
+
+    #[error("List tokens error: {0}")]
+    ListError(#[from] management::ListTokensError),
+
+    #[error("Revoke token error: {0}")]
+    RevokeError(#[from] management::RevokeTokenError),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "token", about = "Manage the API tokens of the IOx server")]
+pub enum Config {
+    /// Create an API token and print its secret.
+    Create {
+        /// A human readable description of the token
+        #[structopt(short, long, default_value = "")]
+        description: String,
+
+        /// The permissions granted by the token, as `<scope>[:<database>]`
+        /// where scope is one of `read`, `write` or `admin`. Permissions
+        /// without a database apply to all databases and to the server.
+        #[structopt(required = true, parse(try_from_str = parse_permission))]
+        permissions: Vec<Permission>,
+    },
+    /// Revoke an API token.
+    Revoke { id: String },
+    /// List the API tokens, without their secrets.
+    List,
+}
+
+pub async fn command(connection: Connection, config: Config) -> Result<()> {
+    match config {
+        Config::Create {
+            description,
+            permissions,
+        } => {
+            let mut client = management::Client::new(connection);
+            let token = client.create_token(description, permissions).await?;
+            println!("{}", token.token);
+        }
+        Config::Revoke { id } => {
+            let mut client = management::Client::new(connection);
+            client.revoke_token(id).await?;
+            println!("Ok");
+        }
+        Config::List => {
+            let mut client = management::Client::new(connection);
+
+            let tokens = client.list_tokens().await?;
+            if tokens.is_empty() {
+                println!("no tokens created");
+            } else {
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+                table.set_titles(Row::new(vec![
+                    Cell::new("ID"),
+                    Cell::new("Description"),
+                    Cell::new("Permissions"),
+                ]));
+
+                for token in tokens {
+                    let permissions: Vec<_> =
+                        token.permissions.iter().map(format_permission).collect();
+                    table.add_row(Row::new(vec![
+                        Cell::new(&token.id),
+                        Cell::new(&token.description),
+                        Cell::new(&permissions.join(", ")),
+                    ]));
+                }
+                print!("{}", table);
+            }
+        }
+    };
+
+    Ok(())
+}
+
+fn parse_permission(s: &str) -> Result<Permission, String> {
+    let (scope, db_name) = match s.find(':') {
+        Some(idx) => (&s[..idx], &s[idx + 1..]),
+        None => (s, ""),
+    };
+
+    let scope = match scope {
+        "read" => Scope::Read,
+        "write" => Scope::Write,
+        "admin" => Scope::Admin,
+        _ => {
+            return Err(format!(
+                "invalid scope '{}', expected one of read, write or admin",
+                scope
+            ))
+        }
+    };
+
+    Ok(Permission {
+        db_name: db_name.to_string(),
+        scope: scope.into(),
+    })
+}
+
+fn format_permission(permission: &Permission) -> String {
+    let scope = match Scope::from_i32(permission.scope) {
+        Some(Scope::Read) => "read",
+        Some(Scope::Write) => "write",
+        Some(Scope::Admin) => "admin",
+        Some(Scope::Unspecified) | None => "unknown",
+    };
+
+    match permission.db_name.as_str() {
+        "" => scope.to_string(),
+        db_name => format!("{}:{}", scope, db_name),
+    }
+}
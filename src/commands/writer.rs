@@ -1,4 +1,4 @@
-use influxdb_iox_client::{connection::Builder, management::*};
+use influxdb_iox_client::{connection::Connection, management::*};
 use std::num::NonZeroU32;
 use structopt::StructOpt;
 use thiserror::Error;
@@ -10,9 +10,6 @@ pub enum Error {
 
     #[error("Error updating writer ID: {0}")]
     UpdateWriterIdError(#[from] UpdateWriterIdError),
-
-    #[error("Error connecting to IOx: {0}")]
-    ConnectionError(#[from] influxdb_iox_client::connection::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -39,8 +36,7 @@ enum Command {
     Get,
 }
 
-pub async fn command(url: String, config: Config) -> Result<()> {
-    let connection = Builder::default().build(url).await?;
+pub async fn command(connection: Connection, config: Config) -> Result<()> {
     let mut client = Client::new(connection);
 
     match config.command {
@@ -6,7 +6,13 @@ use crate::commands::{
 use futures::{future::FusedFuture, pin_mut, FutureExt};
 use hyper::server::conn::AddrIncoming;
 use object_store::{
-    self, aws::AmazonS3, azure::MicrosoftAzure, gcp::GoogleCloudStorage, ObjectStore,
+    self,
+    aws::AmazonS3,
+    azure::MicrosoftAzure,
+    cache::LocalDiskCache,
+    gcp::GoogleCloudStorage,
+    retry::{RetryConfig, RetryingStore},
+    ObjectStore,
 };
 use observability_deps::tracing::{self, error, info, warn, Instrument};
 use panic_logging::SendPanicsToTracing;
@@ -15,11 +21,13 @@ use server::{
     ServerConfig as AppServerConfig,
 };
 use snafu::{ResultExt, Snafu};
-use std::{convert::TryFrom, fs, net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{convert::TryFrom, fs, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tls::TlsConfig;
 
 mod http;
 mod planner;
 mod rpc;
+mod tls;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -71,8 +79,20 @@ pub enum Error {
     #[snafu(display("Amazon S3 configuration was invalid: {}", source))]
     InvalidS3Config { source: object_store::aws::Error },
 
+    #[snafu(display("Unable to create object store cache in {:?}: {}", path, source))]
+    CreatingObjectStoreCache {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
     #[snafu(display("cannot load database config: {}", source))]
     LoadDatabaseConfig { source: server::Error },
+
+    #[snafu(display("cannot set writer id: {}", source))]
+    SetWriterId { source: server::Error },
+
+    #[snafu(display("cannot load TLS configuration: {}", source))]
+    LoadTls { source: tls::Error },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -130,6 +150,14 @@ pub async fn main(logging_level: LoggingLevel, config: Config) -> Result<()> {
     }
 
     let object_store = ObjectStore::try_from(&config)?;
+    let object_store = ObjectStore::new_retrying(RetryingStore::new(
+        object_store,
+        RetryConfig {
+            max_retries: config.object_store_max_retries,
+            timeout: Some(Duration::from_secs(config.object_store_timeout_secs)),
+            ..Default::default()
+        },
+    ));
     let object_storage = Arc::new(object_store);
     let server_config = AppServerConfig::new(object_storage);
 
@@ -143,18 +171,32 @@ pub async fn main(logging_level: LoggingLevel, config: Config) -> Result<()> {
         server_config
     };
 
+    let server_config = match config.admin_token {
+        Some(token) => {
+            info!("API token authentication enabled");
+            server_config.with_admin_token(token)
+        }
+        None => server_config,
+    };
+
     let connection_manager = ConnectionManager {};
     let app_server = Arc::new(AppServer::new(connection_manager, server_config));
 
+    if let Some(id) = config.writer_id {
+        app_server.set_id(id).await.context(SetWriterId)?;
+    }
+
+    // Restores the remotes stored by a previous run, and the databases if the
+    // writer id is known. If it was not specified, the writer id is restored
+    // too when the object store holds the data of a single writer
+    app_server
+        .load_database_configs()
+        .await
+        .context(LoadDatabaseConfig)?;
+
     // if this ID isn't set the server won't be usable until this is set via an API
     // call
-    if let Some(id) = config.writer_id {
-        app_server.set_id(id).expect("writer id already set");
-        app_server
-            .load_database_configs()
-            .await
-            .context(LoadDatabaseConfig)?;
-    } else {
+    if app_server.require_id().is_err() {
         warn!("server ID not set. ID must be set via the INFLUXDB_IOX_ID config or API before writing or querying data.");
     }
 
@@ -164,20 +206,43 @@ pub async fn main(logging_level: LoggingLevel, config: Config) -> Result<()> {
     // Construct a token to trigger shutdown of API services
     let frontend_shutdown = internal_shutdown.child_token();
 
+    // Both APIs are served over TLS if a certificate is configured
+    let tls = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => {
+            let tls =
+                TlsConfig::load(cert, key, config.tls_client_ca.as_deref()).context(LoadTls)?;
+            info!(client_ca=?config.tls_client_ca, "TLS enabled");
+            Some(tls)
+        }
+        _ => None,
+    };
+
     // Construct and start up gRPC server
     let grpc_bind_addr = config.grpc_bind_address;
     let socket = tokio::net::TcpListener::bind(grpc_bind_addr)
         .await
         .context(StartListeningGrpc { grpc_bind_addr })?;
 
-    let grpc_server = rpc::serve(socket, Arc::clone(&app_server), frontend_shutdown.clone()).fuse();
+    let grpc_server = rpc::serve(
+        socket,
+        Arc::clone(&app_server),
+        tls.as_ref().map(TlsConfig::grpc),
+        frontend_shutdown.clone(),
+    )
+    .fuse();
 
     info!(bind_address=?grpc_bind_addr, "gRPC server listening");
 
     let bind_addr = config.http_bind_address;
     let addr = AddrIncoming::bind(&bind_addr).context(StartListeningHttp { bind_addr })?;
 
-    let http_server = http::serve(addr, Arc::clone(&app_server), frontend_shutdown.clone()).fuse();
+    let http_server = http::serve(
+        addr,
+        Arc::clone(&app_server),
+        tls.as_ref().map(TlsConfig::http),
+        frontend_shutdown.clone(),
+    )
+    .fuse();
     info!(bind_address=?bind_addr, "HTTP server listening");
 
     let git_hash = option_env!("GIT_HASH").unwrap_or("UNKNOWN");
@@ -271,111 +336,126 @@ impl TryFrom<&Config> for ObjectStore {
     type Error = Error;
 
     fn try_from(config: &Config) -> Result<Self, Self::Error> {
-        match config.object_store {
-            Some(ObjStoreOpt::Memory) | None => {
-                Ok(Self::new_in_memory(object_store::memory::InMemory::new()))
+        let object_store = uncached_object_store(config)?;
+
+        match config.object_store_cache_dir.as_ref() {
+            Some(cache_dir) => {
+                let cache =
+                    LocalDiskCache::new(object_store, cache_dir, config.object_store_cache_size)
+                        .context(CreatingObjectStoreCache { path: cache_dir })?;
+                Ok(Self::new_cached(cache))
             }
+            None => Ok(object_store),
+        }
+    }
+}
 
-            Some(ObjStoreOpt::Google) => {
-                match (
-                    config.bucket.as_ref(),
-                    config.google_service_account.as_ref(),
-                ) {
-                    (Some(bucket), Some(service_account)) => Ok(Self::new_google_cloud_storage(
-                        GoogleCloudStorage::new(service_account, bucket),
-                    )),
-                    (bucket, service_account) => {
-                        let mut missing_args = vec![];
-
-                        if bucket.is_none() {
-                            missing_args.push("bucket");
-                        }
-                        if service_account.is_none() {
-                            missing_args.push("google-service-account");
-                        }
-                        MissingObjectStoreConfig {
-                            object_store: ObjStoreOpt::Google,
-                            missing: missing_args.join(", "),
-                        }
-                        .fail()
+/// Creates the object store selected by `config`, without any cache
+fn uncached_object_store(config: &Config) -> Result<ObjectStore> {
+    match config.object_store {
+        Some(ObjStoreOpt::Memory) | None => Ok(ObjectStore::new_in_memory(
+            object_store::memory::InMemory::new(),
+        )),
+
+        Some(ObjStoreOpt::Google) => {
+            match (
+                config.bucket.as_ref(),
+                config.google_service_account.as_ref(),
+            ) {
+                (Some(bucket), Some(service_account)) => Ok(ObjectStore::new_google_cloud_storage(
+                    GoogleCloudStorage::new(service_account, bucket),
+                )),
+                (bucket, service_account) => {
+                    let mut missing_args = vec![];
+
+                    if bucket.is_none() {
+                        missing_args.push("bucket");
                     }
-                }
-            }
-
-            Some(ObjStoreOpt::S3) => {
-                match (
-                    config.bucket.as_ref(),
-                    config.aws_access_key_id.as_ref(),
-                    config.aws_secret_access_key.as_ref(),
-                    config.aws_default_region.as_str(),
-                ) {
-                    (Some(bucket), key_id, secret_key, region) => Ok(Self::new_amazon_s3(
-                        AmazonS3::new(key_id, secret_key, region, bucket)
-                            .context(InvalidS3Config)?,
-                    )),
-                    (bucket, _, _, _) => {
-                        let mut missing_args = vec![];
-
-                        if bucket.is_none() {
-                            missing_args.push("bucket");
-                        }
-                        MissingObjectStoreConfig {
-                            object_store: ObjStoreOpt::S3,
-                            missing: missing_args.join(", "),
-                        }
-                        .fail()
+                    if service_account.is_none() {
+                        missing_args.push("google-service-account");
                     }
+                    MissingObjectStoreConfig {
+                        object_store: ObjStoreOpt::Google,
+                        missing: missing_args.join(", "),
+                    }
+                    .fail()
                 }
             }
+        }
 
-            Some(ObjStoreOpt::Azure) => {
-                match (
-                    config.bucket.as_ref(),
-                    config.azure_storage_account.as_ref(),
-                    config.azure_storage_access_key.as_ref(),
-                ) {
-                    (Some(bucket), Some(storage_account), Some(access_key)) => {
-                        Ok(Self::new_microsoft_azure(MicrosoftAzure::new(
-                            storage_account,
-                            access_key,
-                            bucket,
-                        )))
+        Some(ObjStoreOpt::S3) => {
+            match (
+                config.bucket.as_ref(),
+                config.aws_access_key_id.as_ref(),
+                config.aws_secret_access_key.as_ref(),
+                config.aws_default_region.as_str(),
+            ) {
+                (Some(bucket), key_id, secret_key, region) => Ok(ObjectStore::new_amazon_s3(
+                    AmazonS3::new(key_id, secret_key, region, bucket).context(InvalidS3Config)?,
+                )),
+                (bucket, _, _, _) => {
+                    let mut missing_args = vec![];
+
+                    if bucket.is_none() {
+                        missing_args.push("bucket");
                     }
-                    (bucket, storage_account, access_key) => {
-                        let mut missing_args = vec![];
-
-                        if bucket.is_none() {
-                            missing_args.push("bucket");
-                        }
-                        if storage_account.is_none() {
-                            missing_args.push("azure-storage-account");
-                        }
-                        if access_key.is_none() {
-                            missing_args.push("azure-storage-access-key");
-                        }
-
-                        MissingObjectStoreConfig {
-                            object_store: ObjStoreOpt::Azure,
-                            missing: missing_args.join(", "),
-                        }
-                        .fail()
+                    MissingObjectStoreConfig {
+                        object_store: ObjStoreOpt::S3,
+                        missing: missing_args.join(", "),
                     }
+                    .fail()
                 }
             }
+        }
 
-            Some(ObjStoreOpt::File) => match config.database_directory.as_ref() {
-                Some(db_dir) => {
-                    fs::create_dir_all(db_dir)
-                        .context(CreatingDatabaseDirectory { path: db_dir })?;
-                    Ok(Self::new_file(object_store::disk::File::new(&db_dir)))
+        Some(ObjStoreOpt::Azure) => {
+            match (
+                config.bucket.as_ref(),
+                config.azure_storage_account.as_ref(),
+                config.azure_storage_access_key.as_ref(),
+            ) {
+                (Some(bucket), Some(storage_account), Some(access_key)) => {
+                    Ok(ObjectStore::new_microsoft_azure(MicrosoftAzure::new(
+                        storage_account,
+                        access_key,
+                        bucket,
+                    )))
                 }
-                None => MissingObjectStoreConfig {
-                    object_store: ObjStoreOpt::File,
-                    missing: "data-dir",
+                (bucket, storage_account, access_key) => {
+                    let mut missing_args = vec![];
+
+                    if bucket.is_none() {
+                        missing_args.push("bucket");
+                    }
+                    if storage_account.is_none() {
+                        missing_args.push("azure-storage-account");
+                    }
+                    if access_key.is_none() {
+                        missing_args.push("azure-storage-access-key");
+                    }
+
+                    MissingObjectStoreConfig {
+                        object_store: ObjStoreOpt::Azure,
+                        missing: missing_args.join(", "),
+                    }
+                    .fail()
                 }
-                .fail(),
-            },
+            }
         }
+
+        Some(ObjStoreOpt::File) => match config.database_directory.as_ref() {
+            Some(db_dir) => {
+                fs::create_dir_all(db_dir).context(CreatingDatabaseDirectory { path: db_dir })?;
+                Ok(ObjectStore::new_file(object_store::disk::File::new(
+                    &db_dir,
+                )))
+            }
+            None => MissingObjectStoreConfig {
+                object_store: ObjStoreOpt::File,
+                missing: "data-dir",
+            }
+            .fail(),
+        },
     }
 }
 
@@ -548,4 +628,28 @@ mod tests {
             data-dir"
         );
     }
+
+    #[test]
+    fn valid_cache_config() {
+        let cache_dir = TempDir::new().unwrap();
+
+        let config = Config::from_iter_safe(&[
+            "server",
+            "--object-store-cache-dir",
+            cache_dir.path().to_str().unwrap(),
+            "--object-store-cache-size",
+            "1000",
+        ])
+        .unwrap();
+
+        let object_store = ObjectStore::try_from(&config).unwrap();
+
+        match object_store {
+            ObjectStore(ObjectStoreIntegration::Cached(cache)) => assert!(matches!(
+                cache.inner(),
+                ObjectStore(ObjectStoreIntegration::InMemory(_))
+            )),
+            _ => panic!("expected cached object store"),
+        }
+    }
 }
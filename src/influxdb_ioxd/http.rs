@@ -12,38 +12,60 @@
 
 // Influx crates
 use super::{super::commands::metrics, planner::Planner};
+use arrow_deps::arrow::{
+    array::{Array, TimestampNanosecondArray},
+    datatypes::DataType,
+    json::writer::record_batches_to_json_rows,
+    record_batch::RecordBatch,
+};
 use data_types::{
+    auth::Scope,
     http::WalMetadataQuery,
-    names::{org_and_bucket_to_database, OrgBucketMappingError},
+    names::{db_and_rp_to_database, org_and_bucket_to_database, OrgBucketMappingError},
     DatabaseName,
 };
 use influxdb_iox_client::format::QueryOutputFormat;
-use influxdb_line_protocol::parse_lines;
+use influxdb_line_protocol::{ParsedLine, RawLine, StreamingParser};
+use internal_types::schema::TIME_COLUMN_NAME;
 use metrics::IOXD_METRICS;
 use object_store::ObjectStoreApi;
-use query::{Database, PartitionChunk};
-use server::{ConnectionManager, Server as AppServer};
+use query::{
+    frontend::influxql::{parser::parse_statements, InfluxQLPlan},
+    AdmittedQuery, Database, PartitionChunk,
+};
+use server::{ConnectionManager, Server as AppServer, WriteBatch};
 
 // External crates
 use bytes::{Bytes, BytesMut};
-use futures::{self, StreamExt};
-use http::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use futures::{self, Stream, StreamExt};
+use http::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER};
 use hyper::{Body, Method, Request, Response, StatusCode};
 use observability_deps::{
     opentelemetry::KeyValue,
-    tracing::{self, debug, error},
+    tracing::{self, debug, error, warn},
 };
 use routerify::{prelude::*, Middleware, RequestInfo, Router, RouterError, RouterService};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, ResultExt, Snafu};
 
-use data_types::http::WalMetadataResponse;
-use hyper::server::conn::AddrIncoming;
+use data_types::http::{Precision, WalMetadataResponse};
+use hyper::{
+    server::{
+        accept::{self, Accept},
+        conn::{AddrIncoming, AddrStream},
+    },
+    service::{make_service_fn, Service},
+};
 use std::{
     fmt::Debug,
+    io::Write,
+    pin::Pin,
     str::{self, FromStr},
     sync::Arc,
+    time::Duration,
 };
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
 use tokio_util::sync::CancellationToken;
 
 /// Constants used in API error codes.
@@ -151,11 +173,29 @@ pub enum ApplicationError {
     #[snafu(display("Error reading request body as utf8: {}", source))]
     ReadingBodyAsUtf8 { source: std::str::Utf8Error },
 
-    #[snafu(display("Error parsing line protocol: {}", source))]
+    #[snafu(display(
+        "partial write has occurred, {} lines written, errors: {}",
+        lines_written,
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    ))]
     ParsingLineProtocol {
-        source: influxdb_line_protocol::Error,
+        lines_written: usize,
+        errors: Vec<LineError>,
     },
 
+    #[snafu(display("Invalid precision '{}', expected one of ns, us, ms or s", precision))]
+    InvalidPrecision { precision: String },
+
+    #[snafu(display(
+        "partial write: {} dropped={}",
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+        errors.len()
+    ))]
+    PartialWriteV1 { errors: Vec<LineError> },
+
+    #[snafu(display("database name required"))]
+    DatabaseNameRequired {},
+
     #[snafu(display("Error decompressing body as gzip: {}", source))]
     ReadingBodyAsGzip { source: std::io::Error },
 
@@ -208,6 +248,26 @@ pub enum ApplicationError {
 
     #[snafu(display("Error while planning query: {}", source))]
     Planning { source: super::planner::Error },
+
+    #[snafu(display("Error parsing InfluxQL query: {}", source))]
+    ParsingInfluxQL {
+        source: query::frontend::influxql::parser::Error,
+    },
+
+    #[snafu(display("{}", source))]
+    Unauthorized { source: server::auth::Error },
+
+    #[snafu(display("Limit of database {} exceeded: {}", db_name, source))]
+    LimitExceeded {
+        db_name: String,
+        source: server::limits::Error,
+    },
+
+    #[snafu(display("Query of database {} did not complete: {}", db_name, source))]
+    QueryNotCompleted {
+        db_name: String,
+        source: query::QueryError,
+    },
 }
 
 impl ApplicationError {
@@ -229,6 +289,9 @@ impl ApplicationError {
             Self::ReadingBody { .. } => self.bad_request(),
             Self::ReadingBodyAsUtf8 { .. } => self.bad_request(),
             Self::ParsingLineProtocol { .. } => self.bad_request(),
+            Self::InvalidPrecision { .. } => self.bad_request(),
+            Self::PartialWriteV1 { .. } => self.bad_request(),
+            Self::DatabaseNameRequired { .. } => self.bad_request(),
             Self::ReadingBodyAsGzip { .. } => self.bad_request(),
             Self::RouteNotFound { .. } => self.not_found(),
             Self::DatabaseError { .. } => self.internal_error(),
@@ -241,6 +304,26 @@ impl ApplicationError {
             Self::FormattingResult { .. } => self.internal_error(),
             Self::ParsingFormat { .. } => self.bad_request(),
             Self::Planning { .. } => self.bad_request(),
+            Self::ParsingInfluxQL { .. } => self.bad_request(),
+            Self::Unauthorized {
+                source: server::auth::Error::Unauthenticated,
+            } => self.unauthorized(),
+            Self::Unauthorized { .. } => self.forbidden(),
+            Self::LimitExceeded { source, .. } => self.too_many_requests(source.retry_after()),
+            Self::QueryNotCompleted {
+                source: query::QueryError::TimedOut { .. },
+                ..
+            } => self.gateway_timeout(),
+            Self::QueryNotCompleted { .. } => self.service_unavailable(),
+        }
+    }
+
+    /// Builds a limit exceeded error, recording the rejected request
+    fn limit_exceeded(db_name: &str, source: server::limits::Error) -> Self {
+        IOXD_METRICS.record_limit_exceeded(db_name, &source);
+        Self::LimitExceeded {
+            db_name: db_name.to_string(),
+            source,
         }
     }
 
@@ -258,6 +341,44 @@ impl ApplicationError {
             .unwrap()
     }
 
+    fn unauthorized(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn forbidden(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn service_unavailable(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn gateway_timeout(&self) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::GATEWAY_TIMEOUT)
+            .body(self.body())
+            .unwrap()
+    }
+
+    fn too_many_requests(&self, retry_after: Option<Duration>) -> Response<Body> {
+        let mut builder = Response::builder().status(StatusCode::TOO_MANY_REQUESTS);
+        if let Some(retry_after) = retry_after {
+            // Retry-After is in whole seconds, so round up
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            builder = builder.header(RETRY_AFTER, secs);
+        }
+        builder.body(self.body()).unwrap()
+    }
+
     fn not_found(&self) -> Response<Body> {
         Response::builder()
             .status(StatusCode::NOT_FOUND)
@@ -266,9 +387,21 @@ impl ApplicationError {
     }
 
     fn body(&self) -> Body {
-        let json =
-            serde_json::json!({"error": self.to_string(), "error_code": self.api_error_code()})
-                .to_string();
+        let json = match self {
+            // Mirror the InfluxDB v2 line protocol error format so that
+            // existing clients can report which lines were rejected
+            Self::ParsingLineProtocol { errors, .. } => serde_json::json!({
+                "code": "invalid",
+                "message": self.to_string(),
+                "line": errors.first().map(|e| e.line),
+                "errors": errors,
+                "error_code": self.api_error_code(),
+            }),
+            _ => {
+                serde_json::json!({"error": self.to_string(), "error_code": self.api_error_code()})
+            }
+        }
+        .to_string();
         Body::from(json)
     }
 
@@ -304,6 +437,8 @@ fn router<M>(server: Arc<AppServer<M>>) -> Router<Body, ApplicationError>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
+    let auth_server = Arc::clone(&server);
+
     // Create a router and specify the the handlers.
     Router::builder()
         .data(server)
@@ -311,11 +446,19 @@ where
             debug!(request = ?req, "Processing request");
             Ok(req)
         }))
+        .middleware(Middleware::pre(move |req| {
+            let server = Arc::clone(&auth_server);
+            async move { authenticate(&server, req) }
+        }))
         .middleware(Middleware::post(|res| async move {
             debug!(response = ?res, "Successfully processed request");
             Ok(res)
         })) // this endpoint is for API backward compatibility with InfluxDB 2.x
         .post("/api/v2/write", write::<M>)
+        // these endpoints are for API backward compatibility with InfluxDB 1.x
+        .post("/write", write_v1::<M>)
+        .get("/query", query_v1::<M>)
+        .post("/query", query_v1::<M>)
         .get("/health", health)
         .get("/metrics", handle_metrics)
         .get("/iox/api/v1/databases/:name/query", query::<M>)
@@ -354,31 +497,127 @@ async fn error_handler(err: RouterError<ApplicationError>, req: RequestInfo) ->
     }
 }
 
+/// Paths that can be requested without an API token
+const UNAUTHENTICATED_PATHS: &[&str] = &["/health"];
+
+/// Paths of the InfluxDB 1.x compatible API, whose handlers authenticate
+/// requests themselves as the token may be sent in the request body
+const V1_PATHS: &[&str] = &["/write", "/query"];
+
+/// Rejects requests without a valid API token, sent in the `Authorization`
+/// header as `Token <token>`. Handlers check that the token grants the scope
+/// they need on the database they access with [`authorize`]
+fn authenticate<M>(
+    server: &AppServer<M>,
+    req: Request<Body>,
+) -> Result<Request<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    let path = req.uri().path();
+    if !UNAUTHENTICATED_PATHS.contains(&path) && !V1_PATHS.contains(&path) {
+        server
+            .authenticate(request_token(&req))
+            .context(Unauthorized)?;
+    }
+    Ok(req)
+}
+
+/// Checks that the API token sent with `req` grants `scope` on the database
+/// `db_name`
+fn authorize<M>(
+    server: &AppServer<M>,
+    req: &Request<Body>,
+    db_name: &str,
+    scope: Scope,
+) -> Result<(), ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    server
+        .authorize(request_token(req), Some(db_name), scope)
+        .context(Unauthorized)
+}
+
+/// Returns the API token sent in the `Authorization` header of `req`, if any
+fn request_token(req: &Request<Body>) -> Option<&str> {
+    let value = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Token ")
+}
+
+/// Returns the API token sent with a request to the InfluxDB 1.x compatible
+/// API. Like InfluxDB 2.x, the token is accepted as a `Token` or as the
+/// password of `Basic` authentication in the `Authorization` header, or as
+/// the password in the `p` parameter, in which case the user name is ignored
+fn request_token_v1(req: &Request<Body>, password: Option<&str>) -> Option<String> {
+    if let Some(token) = request_token(req) {
+        return Some(token.to_string());
+    }
+
+    let basic = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|credentials| base64::decode(credentials).ok())
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+        .and_then(|credentials| {
+            credentials
+                .splitn(2, ':')
+                .nth(1)
+                .map(|password| password.to_string())
+        });
+
+    basic.or_else(|| password.map(ToString::to_string))
+}
+
 #[derive(Debug, Deserialize)]
 /// Body of the request to the /write endpoint
 struct WriteInfo {
     org: String,
     bucket: String,
+    precision: Option<String>,
 }
 
-/// Parse the request's body into raw bytes, applying size limits and
-/// content encoding as needed.
-async fn parse_body(req: hyper::Request<Body>) -> Result<Bytes, ApplicationError> {
+/// A line of a write request that could not be parsed
+#[derive(Debug, Serialize)]
+pub struct LineError {
+    /// 1-based line number within the request body
+    line: usize,
+    /// Description of the problem
+    error: String,
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+/// Returns true if the request's body is gzip encoded, and an error if
+/// it has any other content encoding.
+fn is_gzipped(req: &hyper::Request<Body>) -> Result<bool, ApplicationError> {
     // clippy says the const needs to be assigned to a local variable:
     // error: a `const` item with interior mutability should not be borrowed
     let header_name = CONTENT_ENCODING;
-    let ungzip = match req.headers().get(&header_name) {
-        None => false,
+    match req.headers().get(&header_name) {
+        None => Ok(false),
         Some(content_encoding) => {
             let content_encoding = content_encoding.to_str().context(ReadingHeaderAsUtf8 {
                 header_name: header_name.as_str(),
             })?;
             match content_encoding {
-                "gzip" => true,
-                _ => InvalidContentEncoding { content_encoding }.fail()?,
+                "gzip" => Ok(true),
+                _ => InvalidContentEncoding { content_encoding }.fail(),
             }
         }
-    };
+    }
+}
+
+/// Parse the request's body into raw bytes, applying size limits and
+/// content encoding as needed.
+async fn parse_body(req: hyper::Request<Body>) -> Result<Bytes, ApplicationError> {
+    let ungzip = is_gzipped(&req)?;
 
     let mut payload = req.into_body();
 
@@ -413,6 +652,216 @@ async fn parse_body(req: hyper::Request<Body>) -> Result<Bytes, ApplicationError
     }
 }
 
+/// Parses `lines` as line protocol with timestamps in `precision`,
+/// returning the lines that parsed successfully (with nanosecond
+/// timestamps) and the errors for those that did not.
+fn parse_line_protocol(
+    lines: &[RawLine],
+    precision: Precision,
+) -> (Vec<ParsedLine<'_>>, Vec<LineError>) {
+    let mut parsed = vec![];
+    let mut errors = vec![];
+    let results = lines
+        .iter()
+        .filter_map(|line| line.parse().map(|res| (line.line_number(), res)));
+
+    for (line_number, line) in results {
+        match line {
+            Ok(mut line) => {
+                if let Some(timestamp) = line.timestamp {
+                    match precision.to_nanoseconds(timestamp) {
+                        Some(timestamp) => line.timestamp = Some(timestamp),
+                        None => {
+                            errors.push(LineError {
+                                line: line_number,
+                                error: format!(
+                                    "timestamp overflows when converted from {:?} to nanoseconds",
+                                    precision
+                                ),
+                            });
+                            continue;
+                        }
+                    }
+                }
+                parsed.push(line);
+            }
+            Err(e) => errors.push(LineError {
+                line: line_number,
+                error: e.to_string(),
+            }),
+        }
+    }
+    (parsed, errors)
+}
+
+/// The outcome of a successful [`write_body`]
+#[derive(Debug, Default)]
+struct BodyWritten {
+    /// Number of lines written to the database
+    lines: usize,
+    /// Number of bytes of (decompressed) line protocol received
+    bytes: usize,
+    /// The lines that could not be parsed, and so were not written
+    errors: Vec<LineError>,
+    /// The lines converted so far, which are written at the end
+    batch: WriteBatch,
+}
+
+/// Reads line protocol with timestamps in `precision` from the body of
+/// `req` and writes it to the database `db_name`.
+///
+/// Rather than copying the entire body into one buffer, the complete lines
+/// of each chunk are parsed and converted as the body is received, and only
+/// an incomplete last line is carried over to the next chunk. The converted
+/// lines are written once the whole body has been received, in a single
+/// write, so that the body is either written or rejected as a whole. Like
+/// InfluxDB, lines that can not be parsed are reported in the result rather
+/// than failing the write, while errors from the database (converted by
+/// `write_error`) fail it.
+async fn write_body<M, F>(
+    server: &AppServer<M>,
+    db_name: &str,
+    req: Request<Body>,
+    precision: Precision,
+    metric_kv: &[KeyValue],
+    write_error: F,
+) -> Result<BodyWritten, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+    F: Fn(server::Error) -> ApplicationError,
+{
+    let mut decoder = if is_gzipped(&req)? {
+        Some(flate2::write::GzDecoder::new(Vec::new()))
+    } else {
+        None
+    };
+
+    let mut payload = req.into_body();
+    let mut parser = StreamingParser::new();
+    let mut received = 0;
+    let mut written = BodyWritten::default();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.context(ReadingBody)?;
+
+        received += chunk.len();
+        if received > MAX_SIZE {
+            return RequestSizeExceeded {
+                max_body_size: MAX_SIZE,
+            }
+            .fail();
+        }
+
+        let chunk = match decoder.as_mut() {
+            Some(decoder) => {
+                decoder.write_all(&chunk).context(ReadingBodyAsGzip)?;
+                Bytes::from(std::mem::take(decoder.get_mut()))
+            }
+            None => chunk,
+        };
+
+        // Limit the decompressed size too, to prevent a decompression
+        // bomb based DoS.
+        written.bytes += chunk.len();
+        if written.bytes > MAX_SIZE {
+            return RequestSizeExceeded {
+                max_body_size: MAX_SIZE,
+            }
+            .fail();
+        }
+
+        let lines = parser.push(chunk);
+        written.convert(server, db_name, &lines, precision, metric_kv, &write_error)?;
+    }
+
+    let mut lines = vec![];
+    if let Some(decoder) = decoder {
+        let rest = decoder.finish().context(ReadingBodyAsGzip)?;
+        written.bytes += rest.len();
+        lines.extend(parser.push(rest.into()));
+    }
+    lines.extend(parser.finish());
+    written.convert(server, db_name, &lines, precision, metric_kv, &write_error)?;
+
+    // always write, even if there are no lines, so that writes to a
+    // missing database are reported as such
+    written
+        .write(server, db_name, metric_kv, &write_error)
+        .await?;
+
+    IOXD_METRICS
+        .lp_bytes_success
+        .add(written.bytes as u64, metric_kv);
+
+    Ok(written)
+}
+
+impl BodyWritten {
+    /// Parses `lines` and converts those that are valid, to be written by
+    /// `write`
+    fn convert<M, F>(
+        &mut self,
+        server: &AppServer<M>,
+        db_name: &str,
+        lines: &[RawLine],
+        precision: Precision,
+        metric_kv: &[KeyValue],
+        write_error: &F,
+    ) -> Result<(), ApplicationError>
+    where
+        M: ConnectionManager + Send + Sync + Debug + 'static,
+        F: Fn(server::Error) -> ApplicationError,
+    {
+        let (lines, errors) = parse_line_protocol(lines, precision);
+
+        debug!(num_lines=lines.len(), num_errors=errors.len(), %db_name, ?precision, "converting lines");
+
+        IOXD_METRICS
+            .lp_lines_errors
+            .add(errors.len() as u64, metric_kv);
+        self.errors.extend(errors);
+
+        let batch = &mut self.batch;
+        server.convert_lines(db_name, &lines, batch).map_err(|e| {
+            IOXD_METRICS
+                .lp_lines_errors
+                .add((batch.num_lines() + lines.len()) as u64, metric_kv);
+            write_error(e)
+        })
+    }
+
+    /// Writes the lines converted by `convert`
+    async fn write<M, F>(
+        &mut self,
+        server: &AppServer<M>,
+        db_name: &str,
+        metric_kv: &[KeyValue],
+        write_error: &F,
+    ) -> Result<(), ApplicationError>
+    where
+        M: ConnectionManager + Send + Sync + Debug + 'static,
+        F: Fn(server::Error) -> ApplicationError,
+    {
+        let batch = std::mem::take(&mut self.batch);
+        let num_lines = batch.num_lines();
+        debug!(num_lines, %db_name, "inserting lines into database");
+
+        server.write_batch(db_name, batch).await.map_err(|e| {
+            IOXD_METRICS
+                .lp_lines_errors
+                .add(num_lines as u64, metric_kv);
+            write_error(e)
+        })?;
+
+        IOXD_METRICS
+            .lp_lines_success
+            .add(num_lines as u64, metric_kv);
+        self.lines += num_lines;
+
+        Ok(())
+    }
+}
+
 #[observability_deps::instrument(level = "debug")]
 async fn write<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
 where
@@ -426,18 +875,14 @@ where
         query_string: String::from(query),
     })?;
 
+    let precision = match write_info.precision.as_deref() {
+        Some(precision) => Precision::from_v2(precision).context(InvalidPrecision { precision })?,
+        None => Precision::default(),
+    };
+
     let db_name = org_and_bucket_to_database(&write_info.org, &write_info.bucket)
         .context(BucketMappingError)?;
-
-    let body = parse_body(req).await?;
-
-    let body = str::from_utf8(&body).context(ReadingBodyAsUtf8)?;
-
-    let lines = parse_lines(body)
-        .collect::<Result<Vec<_>, influxdb_line_protocol::Error>>()
-        .context(ParsingLineProtocol)?;
-
-    debug!(num_lines=lines.len(), %db_name, org=%write_info.org, bucket=%write_info.bucket, "inserting lines into database");
+    authorize(&server, &req, &db_name, Scope::Write)?;
 
     let metric_kv = [
         KeyValue::new("db_name", db_name.to_string()),
@@ -445,29 +890,95 @@ where
         KeyValue::new("bucket", write_info.bucket.to_string()),
     ];
 
-    server.write_lines(&db_name, &lines).await.map_err(|e| {
-        IOXD_METRICS
-            .lp_lines_errors
-            .add(lines.len() as u64, &metric_kv);
-
-        match e {
-            server::Error::DatabaseNotFound { .. } => ApplicationError::DatabaseNotFound {
-                name: db_name.to_string(),
-            },
-            _ => ApplicationError::WritingPoints {
-                org: write_info.org.clone(),
-                bucket_name: write_info.bucket.clone(),
-                source: Box::new(e),
-            },
+    // Like InfluxDB 2.x, write all lines that could be parsed and report
+    // the rest back to the client
+    let written = write_body(&server, &db_name, req, precision, &metric_kv, |e| match e {
+        server::Error::DatabaseNotFound { .. } => ApplicationError::DatabaseNotFound {
+            name: db_name.to_string(),
+        },
+        server::Error::LimitExceeded { source, .. } => {
+            ApplicationError::limit_exceeded(&db_name, source)
         }
-    })?;
+        _ => ApplicationError::WritingPoints {
+            org: write_info.org.clone(),
+            bucket_name: write_info.bucket.clone(),
+            source: Box::new(e),
+        },
+    })
+    .await?;
+
+    if !written.errors.is_empty() {
+        return ParsingLineProtocol {
+            lines_written: written.lines,
+            errors: written.errors,
+        }
+        .fail();
+    }
 
-    IOXD_METRICS
-        .lp_lines_success
-        .add(lines.len() as u64, &metric_kv);
-    IOXD_METRICS
-        .lp_bytes_success
-        .add(body.len() as u64, &metric_kv);
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[derive(Debug, Deserialize)]
+/// Query string of the request to the InfluxDB 1.x compatible /write endpoint
+struct WriteInfoV1 {
+    db: Option<String>,
+    rp: Option<String>,
+    precision: Option<String>,
+    p: Option<String>,
+}
+
+#[observability_deps::instrument(level = "debug")]
+async fn write_v1<M>(req: Request<Body>) -> Result<Response<Body>, ApplicationError>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    let server = Arc::clone(&req.data::<Arc<AppServer<M>>>().expect("server state"));
+
+    let query = req.uri().query().unwrap_or_default();
+
+    let write_info: WriteInfoV1 =
+        serde_urlencoded::from_str(query).context(InvalidQueryString {
+            query_string: String::from(query),
+        })?;
+
+    let precision = match write_info.precision.as_deref() {
+        Some(precision) => Precision::from_v1(precision).context(InvalidPrecision { precision })?,
+        None => Precision::default(),
+    };
+
+    let db = write_info.db.as_deref().context(DatabaseNameRequired)?;
+    let db_name =
+        db_and_rp_to_database(db, write_info.rp.as_deref()).context(BucketMappingError)?;
+    let token = request_token_v1(&req, write_info.p.as_deref());
+    server
+        .authorize(token.as_deref(), Some(db_name.as_str()), Scope::Write)
+        .context(Unauthorized)?;
+
+    let metric_kv = [KeyValue::new("db_name", db_name.to_string())];
+
+    let written = write_body(&server, &db_name, req, precision, &metric_kv, |e| match e {
+        server::Error::DatabaseNotFound { .. } => ApplicationError::DatabaseNotFound {
+            name: db_name.to_string(),
+        },
+        server::Error::LimitExceeded { source, .. } => {
+            ApplicationError::limit_exceeded(&db_name, source)
+        }
+        _ => ApplicationError::DatabaseError {
+            database: db_name.to_string(),
+            source: Box::new(e),
+        },
+    })
+    .await?;
+
+    if !written.errors.is_empty() {
+        return PartialWriteV1 {
+            errors: written.errors,
+        }
+        .fail();
+    }
 
     Ok(Response::builder()
         .status(StatusCode::NO_CONTENT)
@@ -475,6 +986,320 @@ where
         .unwrap())
 }
 
+#[derive(Deserialize, Debug, PartialEq)]
+/// Parameters of the request to the InfluxDB 1.x compatible /query
+/// endpoint, sent either in the URI or as a form encoded body
+struct QueryInfoV1 {
+    db: Option<String>,
+    rp: Option<String>,
+    q: String,
+    epoch: Option<String>,
+    p: Option<String>,
+}
+
+/// Runs the statements of an InfluxDB 1.x style query and returns the
+/// results in the 1.x JSON format:
+///
+/// ```text
+/// {"results":[{"statement_id":0,"series":[{"name":"cpu","columns":["time","usage"],"values":[["2021-04-01T14:10:24Z",0.5]]}]}]}
+/// ```
+///
+/// Each statement is currently run by the SQL planner, so only the
+/// subset of InfluxQL that is also valid SQL is supported.
+#[tracing::instrument(level = "debug")]
+async fn query_v1<M: ConnectionManager + Send + Sync + Debug + 'static>(
+    req: Request<Body>,
+) -> Result<Response<Body>, ApplicationError> {
+    let server = Arc::clone(&req.data::<Arc<AppServer<M>>>().expect("server state"));
+
+    // The database and password may be sent in the body, which consumes
+    // the request
+    let header_token = request_token_v1(&req, None);
+
+    let mut params = req.uri().query().unwrap_or_default().to_string();
+    if req.method() == Method::POST {
+        let body = parse_body(req).await?;
+        let body = str::from_utf8(&body).context(ReadingBodyAsUtf8)?;
+        if !params.is_empty() && !body.is_empty() {
+            params.push('&');
+        }
+        params.push_str(body);
+    }
+
+    let info: QueryInfoV1 = serde_urlencoded::from_str(&params).context(InvalidQueryString {
+        query_string: &params,
+    })?;
+
+    let epoch = match info.epoch.as_deref() {
+        Some(epoch) => {
+            Some(Precision::from_v1(epoch).context(InvalidPrecision { precision: epoch })?)
+        }
+        None => None,
+    };
+
+    let db = info.db.as_deref().context(DatabaseNameRequired)?;
+    let db_name = db_and_rp_to_database(db, info.rp.as_deref()).context(BucketMappingError)?;
+    let token = header_token.or_else(|| info.p.clone());
+    server
+        .authorize(token.as_deref(), Some(db_name.as_str()), Scope::Read)
+        .context(Unauthorized)?;
+    debug!(q = %info.q, ?epoch, %db_name, "running InfluxDB 1.x query");
+
+    let db = server.db(&db_name).context(DatabaseNotFound {
+        name: db_name.as_str(),
+    })?;
+    let query = db
+        .start_query(&info.q)
+        .map_err(|e| ApplicationError::limit_exceeded(&db_name, e))?;
+
+    let statements = parse_statements(&info.q).context(ParsingInfluxQL)?;
+
+    let executor = db.executor();
+    let planner = Planner::new(Arc::clone(&executor));
+
+    let results = query
+        .run(async {
+            let mut results = vec![];
+            for (statement_id, statement) in statements.into_iter().enumerate() {
+                let series = async {
+                    let plan = planner
+                        .influxql(Arc::clone(&db), statement)
+                        .await
+                        .context(Planning)?;
+
+                    let series = match plan {
+                        InfluxQLPlan::Select {
+                            measurement,
+                            tag_columns,
+                            plan,
+                        } => match plan {
+                            Some(plan) => {
+                                let batches = executor
+                                    .run_logical_plan(plan)
+                                    .await
+                                    .map_err(|e| Box::new(e) as _)
+                                    .context(Query {
+                                        db_name: db_name.as_str(),
+                                    })?;
+                                batches_to_series_v1(&measurement, &tag_columns, &batches, epoch)
+                            }
+                            None => vec![],
+                        },
+                        InfluxQLPlan::ShowMeasurements(plan) => {
+                            let names = executor
+                                .to_string_set(plan)
+                                .await
+                                .map_err(|e| Box::new(e) as _)
+                                .context(Query {
+                                    db_name: db_name.as_str(),
+                                })?;
+                            series_v1(
+                                "measurements",
+                                &["name"],
+                                names.iter().map(|name| vec![name.as_str().into()]),
+                            )
+                        }
+                        InfluxQLPlan::ShowTagKeys(plan) => {
+                            let keys = executor
+                                .to_string_set(plan)
+                                .await
+                                .map_err(|e| Box::new(e) as _)
+                                .context(Query {
+                                    db_name: db_name.as_str(),
+                                })?;
+                            series_v1(
+                                "tagKeys",
+                                &["tagKey"],
+                                keys.iter().map(|key| vec![key.as_str().into()]),
+                            )
+                        }
+                        InfluxQLPlan::ShowTagValues { key, plan } => {
+                            let values = executor
+                                .to_string_set(plan)
+                                .await
+                                .map_err(|e| Box::new(e) as _)
+                                .context(Query {
+                                    db_name: db_name.as_str(),
+                                })?;
+                            series_v1(
+                                "tagValues",
+                                &["key", "value"],
+                                values
+                                    .iter()
+                                    .map(|value| vec![key.as_str().into(), value.as_str().into()]),
+                            )
+                        }
+                        InfluxQLPlan::ShowFieldKeys(plan) => {
+                            let fields = executor
+                                .to_field_list(plan)
+                                .await
+                                .map_err(|e| Box::new(e) as _)
+                                .context(Query {
+                                    db_name: db_name.as_str(),
+                                })?;
+                            series_v1(
+                                "fieldKeys",
+                                &["fieldKey", "fieldType"],
+                                fields.fields.iter().map(|field| {
+                                    let field_type = match field.data_type {
+                                        DataType::Float64 => "float",
+                                        DataType::Int64 => "integer",
+                                        DataType::UInt64 => "unsigned",
+                                        DataType::Utf8 => "string",
+                                        DataType::Boolean => "boolean",
+                                        _ => "unknown",
+                                    };
+                                    vec![field.name.as_str().into(), field_type.into()]
+                                }),
+                            )
+                        }
+                    };
+
+                    Ok::<_, ApplicationError>(series)
+                }
+                .await;
+
+                // Like InfluxDB 1.x, errors are reported per statement
+                results.push(match series {
+                    Ok(series) if series.is_empty() => {
+                        serde_json::json!({ "statement_id": statement_id })
+                    }
+                    Ok(series) => {
+                        serde_json::json!({ "statement_id": statement_id, "series": series })
+                    }
+                    Err(e) => {
+                        serde_json::json!({ "statement_id": statement_id, "error": e.to_string() })
+                    }
+                });
+            }
+            results
+        })
+        .await
+        .context(QueryNotCompleted {
+            db_name: db_name.as_str(),
+        })?;
+
+    let body = serde_json::json!({ "results": results }).to_string();
+
+    let response = Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .context(CreatingResponse)?;
+
+    Ok(response)
+}
+
+/// Creates the `series` of an InfluxDB 1.x query response for a `SHOW`
+/// statement, or no series if there are no `values`
+fn series_v1(
+    name: &str,
+    columns: &[&str],
+    values: impl Iterator<Item = Vec<serde_json::Value>>,
+) -> Vec<serde_json::Value> {
+    let values: Vec<_> = values.collect();
+    if values.is_empty() {
+        return vec![];
+    }
+
+    vec![serde_json::json!({
+        "name": name,
+        "columns": columns,
+        "values": values,
+    })]
+}
+
+/// Converts the results of a `SELECT` statement into the `series` of an
+/// InfluxDB 1.x query response, with one series for each distinct
+/// combination of `tag_columns` values. `batches` must be sorted on the
+/// `tag_columns`. Times are formatted as RFC3339 strings, unless an
+/// `epoch` precision was requested.
+fn batches_to_series_v1(
+    name: &str,
+    tag_columns: &[String],
+    batches: &[RecordBatch],
+    epoch: Option<Precision>,
+) -> Vec<serde_json::Value> {
+    let schema = match batches.first() {
+        Some(batch) => batch.schema(),
+        None => return vec![],
+    };
+
+    let columns: Vec<_> = schema
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .filter(|name| !tag_columns.contains(name))
+        .collect();
+
+    // the arrow json writer does not format timestamps the way 1.x
+    // clients expect, so do that here
+    let times: Vec<Option<i64>> = match schema.index_of(TIME_COLUMN_NAME) {
+        Ok(time_idx) => batches
+            .iter()
+            .flat_map(|batch| {
+                let array = batch
+                    .column(time_idx)
+                    .as_any()
+                    .downcast_ref::<TimestampNanosecondArray>();
+                (0..batch.num_rows()).map(move |i| match array {
+                    Some(array) if !array.is_null(i) => Some(array.value(i)),
+                    _ => None,
+                })
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+
+    // (tags, values) of each series
+    let mut series: Vec<(
+        serde_json::Map<String, serde_json::Value>,
+        Vec<serde_json::Value>,
+    )> = vec![];
+
+    for (i, mut row) in record_batches_to_json_rows(batches).into_iter().enumerate() {
+        let tags: serde_json::Map<_, _> = tag_columns
+            .iter()
+            .map(|tag| (tag.clone(), row.remove(tag).unwrap_or_else(|| "".into())))
+            .collect();
+
+        let values: Vec<_> = columns
+            .iter()
+            .map(|c| match times.get(i) {
+                Some(Some(time)) if c == TIME_COLUMN_NAME => match epoch {
+                    Some(epoch) => (time / epoch.multiplier()).into(),
+                    None => Utc
+                        .timestamp_nanos(*time)
+                        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                        .into(),
+                },
+                _ => row.remove(c).unwrap_or(serde_json::Value::Null),
+            })
+            .collect();
+
+        match series.last_mut() {
+            Some((series_tags, series_values)) if *series_tags == tags => {
+                series_values.push(values.into())
+            }
+            _ => series.push((tags, vec![values.into()])),
+        }
+    }
+
+    series
+        .into_iter()
+        .map(|(tags, values)| {
+            let mut series = serde_json::json!({
+                "name": name,
+                "columns": columns,
+                "values": values,
+            });
+            if !tags.is_empty() {
+                series["tags"] = tags.into();
+            }
+            series
+        })
+        .collect()
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 /// Parsed URI Parameters of the request to the .../query endpoint
 struct QueryParams {
@@ -508,11 +1333,15 @@ async fn query<M: ConnectionManager + Send + Sync + Debug + 'static>(
         .clone();
 
     let db_name = DatabaseName::new(&db_name_str).context(DatabaseNameError)?;
+    authorize(&server, &req, &db_name, Scope::Read)?;
     debug!(uri = ?req.uri(), %q, ?format, %db_name, "running SQL query");
 
     let db = server
         .db(&db_name)
         .context(DatabaseNotFound { name: &db_name_str })?;
+    let query = db
+        .start_query(&q)
+        .map_err(|e| ApplicationError::limit_exceeded(&db_name, e))?;
 
     let executor = db.executor();
     let physical_plan = Planner::new(Arc::clone(&executor))
@@ -522,9 +1351,12 @@ async fn query<M: ConnectionManager + Send + Sync + Debug + 'static>(
 
     // TODO: stream read results out rather than rendering the
     // whole thing in mem
-    let batches = executor
-        .collect(physical_plan)
+    let batches = query
+        .run(executor.collect(physical_plan))
         .await
+        .context(QueryNotCompleted {
+            db_name: db_name.as_str(),
+        })?
         .map_err(|e| Box::new(e) as _)
         .context(Query { db_name })?;
 
@@ -565,6 +1397,7 @@ async fn get_wal_meta<M: ConnectionManager + Send + Sync + Debug + 'static>(
         .unwrap_or_default();
 
     let db_name = DatabaseName::new(&db_name_str).context(DatabaseNameError)?;
+    authorize(&server, &req, &db_name, Scope::Read)?;
 
     let db = server
         .db(&db_name)
@@ -629,6 +1462,7 @@ async fn list_partitions<M: ConnectionManager + Send + Sync + Debug + 'static>(
 
     let db_name =
         org_and_bucket_to_database(&info.org, &info.bucket).context(BucketMappingError)?;
+    authorize(&server, &req, &db_name, Scope::Read)?;
 
     let db = server.db(&db_name).context(BucketNotFound {
         org: &info.org,
@@ -671,6 +1505,7 @@ async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static
 
     let db_name =
         org_and_bucket_to_database(&snapshot.org, &snapshot.bucket).context(BucketMappingError)?;
+    authorize(&server, &req, &db_name, Scope::Admin)?;
 
     // TODO: refactor the rest of this out of the http route and into the server
     // crate.
@@ -705,21 +1540,86 @@ async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static
     Ok(Response::new(Body::from(ret)))
 }
 
+/// The maximum number of TLS handshakes performed concurrently
+const MAX_CONCURRENT_TLS_HANDSHAKES: usize = 64;
+
+/// How long a client may take to complete the TLS handshake before its
+/// connection is dropped
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the HTTP API on the connections accepted by `addr`, over TLS if
+/// `tls` is set. Resolves when the server has shutdown.
 pub async fn serve<M>(
     addr: AddrIncoming,
     server: Arc<AppServer<M>>,
+    tls: Option<TlsAcceptor>,
     shutdown: CancellationToken,
 ) -> Result<(), hyper::Error>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
     let router = router(server);
-    let service = RouterService::new(router).unwrap();
+    let mut service = RouterService::new(router).unwrap();
+
+    match tls {
+        Some(tls) => {
+            // The router service takes the remote address from the
+            // underlying TCP connection of each TLS stream
+            let make_service = make_service_fn(move |conn: &TlsStream<AddrStream>| {
+                let (conn, _) = conn.get_ref();
+                service.call(conn)
+            });
 
-    hyper::Server::builder(addr)
-        .serve(service)
-        .with_graceful_shutdown(shutdown.cancelled())
-        .await
+            hyper::Server::builder(accept::from_stream(tls_incoming(addr, tls)))
+                .serve(make_service)
+                .with_graceful_shutdown(shutdown.cancelled())
+                .await
+        }
+        None => {
+            hyper::Server::builder(addr)
+                .serve(service)
+                .with_graceful_shutdown(shutdown.cancelled())
+                .await
+        }
+    }
+}
+
+/// Performs the TLS handshake of the connections accepted by `addr`,
+/// dropping the connections that fail to be accepted or to complete the
+/// handshake within `TLS_HANDSHAKE_TIMEOUT`
+fn tls_incoming(
+    mut addr: AddrIncoming,
+    tls: TlsAcceptor,
+) -> impl Stream<Item = Result<TlsStream<AddrStream>, std::io::Error>> {
+    futures::stream::poll_fn(move |cx| Pin::new(&mut addr).poll_accept(cx))
+        .filter_map(|conn| async move {
+            match conn {
+                Ok(conn) => Some(conn),
+                Err(error) => {
+                    warn!(%error, "failed to accept connection");
+                    None
+                }
+            }
+        })
+        .map(move |conn| {
+            let remote_addr = conn.remote_addr();
+            let handshake = tls.accept(conn);
+            async move {
+                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, handshake).await {
+                    Ok(Ok(conn)) => Some(Ok(conn)),
+                    Ok(Err(error)) => {
+                        debug!(%remote_addr, %error, "TLS handshake failed");
+                        None
+                    }
+                    Err(_) => {
+                        debug!(%remote_addr, "TLS handshake timed out");
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_TLS_HANDSHAKES)
+        .filter_map(futures::future::ready)
 }
 
 #[cfg(test)]
@@ -734,7 +1634,7 @@ mod tests {
     use object_store::{memory::InMemory, ObjectStore};
     use serde::de::DeserializeOwned;
     use server::{db::Db, ConnectionManagerImpl, ServerConfig as AppServerConfig};
-    use std::num::NonZeroU32;
+    use std::num::{NonZeroU32, NonZeroUsize};
     use test_helpers::assert_contains;
 
     fn config() -> AppServerConfig {
@@ -757,7 +1657,10 @@ mod tests {
     #[tokio::test]
     async fn test_write() {
         let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
-        app_server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
         app_server
             .create_database(
                 DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
@@ -805,10 +1708,13 @@ mod tests {
     async fn test_write_metrics() {
         metrics::init_metrics_for_test();
         let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
-        app_server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
         app_server
-            .create_database(
-                DatabaseRules::new(DatabaseName::new("MetricsOrg_MetricsBucket").unwrap()),
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("MetricsOrg_MetricsBucket").unwrap()),
                 app_server.require_id().unwrap(),
             )
             .await
@@ -858,12 +1764,680 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_write_limited() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        let mut rules = DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap());
+        rules.limits.write_bytes_per_second = NonZeroUsize::new(10);
+        app_server
+            .create_database(rules, app_server.require_id().unwrap())
+            .await
+            .unwrap();
+        let server_url = test_server(Arc::clone(&app_server));
+
+        let client = Client::new();
+        let url = format!("{}/api/v2/write?bucket=MyBucket&org=MyOrg", server_url);
+        let lp_data =
+            "h2o_temperature,location=santa_monica surface_degrees=65.2 1617286224000000000";
+
+        // the first write exceeds the rate, so the next is rejected
+        let response = client.post(&url).body(lp_data).send().await;
+        check_response("write", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client.post(&url).body(lp_data).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1, "{}", retry_after);
+        assert_contains!(
+            response.text().await.unwrap(),
+            "Limit of database MyOrg_MyBucket exceeded"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_limited_streamed_body() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        let mut rules = DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap());
+        rules.limits.max_write_rows = NonZeroUsize::new(2);
+        app_server
+            .create_database(rules, app_server.require_id().unwrap())
+            .await
+            .unwrap();
+
+        // each chunk is within the limit, but the request is not
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for chunk in &[
+                "h2o temp=65.2 1\n",
+                "h2o temp=50.2 2\n",
+                "h2o temp=70.4 3\n",
+            ] {
+                sender.send_data(Bytes::from(*chunk)).await.unwrap();
+            }
+        });
+
+        let err = write_body(
+            &app_server,
+            "MyOrg_MyBucket",
+            Request::new(body),
+            Precision::Nanoseconds,
+            &[],
+            |e| match e {
+                server::Error::LimitExceeded { source, .. } => {
+                    ApplicationError::limit_exceeded("MyOrg_MyBucket", source)
+                }
+                _ => panic!("unexpected error writing: {}", e),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, ApplicationError::LimitExceeded { .. }),
+            "{}",
+            err
+        );
+
+        // nothing was written
+        let test_db = app_server
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .expect("Database exists");
+        assert!(query::Database::partition_keys(test_db.as_ref())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_precision() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+        let server_url = test_server(Arc::clone(&app_server));
+
+        let client = Client::new();
+
+        for (precision, lp_data) in &[
+            (
+                "s",
+                "h2o_temperature,location=s surface_degrees=65.2 1617286224",
+            ),
+            (
+                "ms",
+                "h2o_temperature,location=ms surface_degrees=65.2 1617286224000",
+            ),
+            (
+                "us",
+                "h2o_temperature,location=us surface_degrees=65.2 1617286224000000",
+            ),
+            (
+                "ns",
+                "h2o_temperature,location=ns surface_degrees=65.2 1617286224000000000",
+            ),
+        ] {
+            let response = client
+                .post(&format!(
+                    "{}/api/v2/write?bucket=MyBucket&org=MyOrg&precision={}",
+                    server_url, precision
+                ))
+                .body(*lp_data)
+                .send()
+                .await;
+
+            check_response("write", response, StatusCode::NO_CONTENT, "").await;
+        }
+
+        let test_db = app_server
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .expect("Database exists");
+
+        let batches = run_query(
+            test_db,
+            "select location, time from h2o_temperature order by location",
+        )
+        .await;
+        let expected = vec![
+            "+----------+---------------------+",
+            "| location | time                |",
+            "+----------+---------------------+",
+            "| ms       | 2021-04-01 14:10:24 |",
+            "| ns       | 2021-04-01 14:10:24 |",
+            "| s        | 2021-04-01 14:10:24 |",
+            "| us       | 2021-04-01 14:10:24 |",
+            "+----------+---------------------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        // unknown precisions are rejected
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg&precision=h",
+                server_url
+            ))
+            .body("h2o_temperature surface_degrees=65.2 1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_write_partial() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+        let server_url = test_server(Arc::clone(&app_server));
+
+        let client = Client::new();
+
+        let lp_data =
+            "h2o_temperature,location=santa_monica surface_degrees=65.2 1617286224000000000\n\
+                       h2o_temperature,location=boston surface_degrees= 1617286224000000000\n\
+                       h2o_temperature,location=boston surface_degrees=50.2 1617286224000000000\n\
+                       h2o_temperature,location=boston 1617286224000000000";
+
+        let response = client
+            .post(&format!(
+                "{}/api/v2/write?bucket=MyBucket&org=MyOrg",
+                server_url
+            ))
+            .body(lp_data)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "invalid");
+        assert_eq!(body["line"], 2);
+        let failed_lines: Vec<_> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["line"].as_u64().unwrap())
+            .collect();
+        assert_eq!(failed_lines, vec![2, 4]);
+        assert_contains!(
+            body["message"].as_str().unwrap(),
+            "partial write has occurred, 2 lines written"
+        );
+
+        // the valid lines were still written
+        let test_db = app_server
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .expect("Database exists");
+
+        let batches = run_query(
+            test_db,
+            "select location, surface_degrees from h2o_temperature order by location",
+        )
+        .await;
+        let expected = vec![
+            "+--------------+-----------------+",
+            "| location     | surface_degrees |",
+            "+--------------+-----------------+",
+            "| boston       | 50.2            |",
+            "| santa_monica | 65.2            |",
+            "+--------------+-----------------+",
+        ];
+        assert_table_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_write_streamed_body() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // send lines split across chunks, including within a quoted
+        // string field containing a newline
+        let (mut sender, body) = Body::channel();
+        let chunks = vec![
+            "h2o,location=santa_mon",
+            "ica temp=65.2 1617286224000000000\nh2o,location=boston note=\"a\n",
+            "b\",temp=50.2 1617286224000000000\nh2o temp= 1\n",
+            "h2o,location=paris temp=60.1 1617286224000000000",
+        ];
+        tokio::spawn(async move {
+            for chunk in chunks {
+                sender.send_data(Bytes::from(chunk)).await.unwrap();
+            }
+        });
+
+        let written = write_body(
+            &app_server,
+            "MyOrg_MyBucket",
+            Request::new(body),
+            Precision::Nanoseconds,
+            &[],
+            |e| panic!("unexpected error writing: {}", e),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(written.lines, 3);
+        assert_eq!(written.bytes, 177);
+        let failed_lines: Vec<_> = written.errors.iter().map(|e| e.line).collect();
+        assert_eq!(failed_lines, vec![4]);
+
+        let test_db = app_server
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .expect("Database exists");
+
+        let batches = run_query(test_db, "select location, temp from h2o order by location").await;
+        let expected = vec![
+            "+--------------+------+",
+            "| location     | temp |",
+            "+--------------+------+",
+            "| boston       | 50.2 |",
+            "| paris        | 60.1 |",
+            "| santa_monica | 65.2 |",
+            "+--------------+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_write_body_failing_mid_stream() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // the body fails after complete lines have been received
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            sender
+                .send_data(Bytes::from("h2o temp=65.2 1\nh2o temp=50.2 2\n"))
+                .await
+                .unwrap();
+            sender.abort();
+        });
+
+        let err = write_body(
+            &app_server,
+            "MyOrg_MyBucket",
+            Request::new(body),
+            Precision::Nanoseconds,
+            &[],
+            |e| panic!("unexpected error writing: {}", e),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, ApplicationError::ReadingBody { .. }),
+            "{}",
+            err
+        );
+
+        // nothing was written
+        let test_db = app_server
+            .db(&DatabaseName::new("MyOrg_MyBucket").unwrap())
+            .expect("Database exists");
+        assert!(query::Database::partition_keys(test_db.as_ref())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_v1() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        for db_name in &["telegraf", "telegraf_one%5Fweek"] {
+            app_server
+                .create_database(
+                    DatabaseRules::new(DatabaseName::new(*db_name).unwrap()),
+                    app_server.require_id().unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        let server_url = test_server(Arc::clone(&app_server));
+
+        let client = Client::new();
+
+        let response = client
+            .post(&format!("{}/write?db=telegraf&precision=s", server_url))
+            .body("h2o_temperature,location=santa_monica surface_degrees=65.2 1617286224")
+            .send()
+            .await;
+        check_response("write_v1", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .post(&format!("{}/write?db=telegraf&rp=one_week", server_url))
+            .body("h2o_temperature,location=boston surface_degrees=50.2 1617286224000000000")
+            .send()
+            .await;
+        check_response("write_v1", response, StatusCode::NO_CONTENT, "").await;
+
+        for (db_name, location) in &[
+            ("telegraf", "santa_monica"),
+            ("telegraf_one%5Fweek", "boston"),
+        ] {
+            let test_db = app_server
+                .db(&DatabaseName::new(*db_name).unwrap())
+                .expect("Database exists");
+
+            let batches = run_query(test_db, "select location, time from h2o_temperature").await;
+            let expected = vec![
+                "+--------------+---------------------+".to_string(),
+                "| location     | time                |".to_string(),
+                "+--------------+---------------------+".to_string(),
+                format!("| {:<12} | 2021-04-01 14:10:24 |", location),
+                "+--------------+---------------------+".to_string(),
+            ];
+            assert_table_eq!(expected, &batches);
+        }
+
+        // a database is required
+        let response = client
+            .post(&format!("{}/write", server_url))
+            .body("h2o_temperature surface_degrees=50.2 1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // partial writes report the dropped lines
+        let response = client
+            .post(&format!("{}/write?db=telegraf", server_url))
+            .body("h2o_temperature surface_degrees=50.2 1\nh2o_temperature surface_degrees=")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_contains!(body["error"].as_str().unwrap(), "partial write: line 2");
+        assert_contains!(body["error"].as_str().unwrap(), "dropped=1");
+    }
+
+    #[tokio::test]
+    async fn test_v1_credentials() {
+        let app_server = Arc::new(AppServer::new(
+            ConnectionManagerImpl {},
+            config().with_admin_token("admin"),
+        ));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("telegraf").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+        let server_url = test_server(Arc::clone(&app_server));
+        let write_url = format!("{}/write", server_url);
+        let query_url = format!("{}/query", server_url);
+        let lp = "h2o_temperature surface_degrees=50.2 1";
+
+        let client = Client::new();
+
+        let response = client
+            .post(&write_url)
+            .query(&[("db", "telegraf")])
+            .body(lp)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // the token is accepted as the password, whatever the user name
+        let response = client
+            .post(&write_url)
+            .query(&[("db", "telegraf"), ("u", "telegraf"), ("p", "admin")])
+            .body(lp)
+            .send()
+            .await;
+        check_response("write_v1", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .post(&write_url)
+            .query(&[("db", "telegraf")])
+            .basic_auth("telegraf", Some("admin"))
+            .body(lp)
+            .send()
+            .await;
+        check_response("write_v1", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .post(&write_url)
+            .query(&[("db", "telegraf")])
+            .basic_auth("telegraf", Some("invalid"))
+            .body(lp)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(&query_url)
+            .query(&[
+                ("db", "telegraf"),
+                ("q", "SHOW MEASUREMENTS"),
+                ("u", "telegraf"),
+                ("p", "admin"),
+            ])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // the password may be sent in the body of a query
+        let response = client
+            .post(&query_url)
+            .form(&[
+                ("db", "telegraf"),
+                ("q", "SHOW MEASUREMENTS"),
+                ("p", "admin"),
+            ])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = client
+            .get(&query_url)
+            .query(&[("db", "telegraf"), ("q", "SHOW MEASUREMENTS")])
+            .basic_auth("telegraf", Some("invalid"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // other endpoints still require the token in the Authorization header
+        let response = client
+            .get(&format!("{}/api/v1/partitions", server_url))
+            .query(&[("org", "telegraf"), ("bucket", "")])
+            .basic_auth("telegraf", Some("admin"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_query_v1() {
+        let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
+        app_server
+            .create_database(
+                DatabaseRules::new(DatabaseName::new("telegraf").unwrap()),
+                app_server.require_id().unwrap(),
+            )
+            .await
+            .unwrap();
+        let server_url = test_server(Arc::clone(&app_server));
+
+        let client = Client::new();
+
+        let response = client
+            .post(&format!("{}/write?db=telegraf", server_url))
+            .body("h2o_temperature,location=santa_monica surface_degrees=65.2 1617286224000000000")
+            .send()
+            .await;
+        check_response("write_v1", response, StatusCode::NO_CONTENT, "").await;
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "telegraf"),
+                (
+                    "q",
+                    "select location, surface_degrees, time from h2o_temperature; select * from missing",
+                ),
+            ])
+            .send()
+            .await;
+        assert_eq!(get_content_type(&response), "application/json");
+
+        let body: serde_json::Value = response.unwrap().json().await.unwrap();
+        assert_eq!(
+            body["results"][0],
+            serde_json::json!({
+                "statement_id": 0,
+                "series": [{
+                    "name": "h2o_temperature",
+                    "columns": ["time", "location", "surface_degrees"],
+                    "values": [["2021-04-01T14:10:24Z", "santa_monica", 65.2]],
+                }]
+            })
+        );
+        assert_eq!(body["results"][1], serde_json::json!({ "statement_id": 1 }));
+
+        // aggregates are split into a series for each tag value
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "telegraf"),
+                (
+                    "q",
+                    "select max(surface_degrees) from h2o_temperature group by location; show measurements",
+                ),
+            ])
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(
+            body["results"],
+            serde_json::json!([
+                {
+                    "statement_id": 0,
+                    "series": [{
+                        "name": "h2o_temperature",
+                        "tags": { "location": "santa_monica" },
+                        "columns": ["time", "max"],
+                        "values": [["1970-01-01T00:00:00Z", 65.2]],
+                    }]
+                },
+                {
+                    "statement_id": 1,
+                    "series": [{
+                        "name": "measurements",
+                        "columns": ["name"],
+                        "values": [["h2o_temperature"]],
+                    }]
+                },
+            ])
+        );
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[
+                ("db", "telegraf"),
+                ("q", "drop measurement h2o_temperature"),
+            ])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // parameters may also be sent as a form body, and epoch changes the
+        // time format
+        let response = client
+            .post(&format!("{}/query?db=telegraf", server_url))
+            .form(&[("q", "select time from h2o_temperature"), ("epoch", "s")])
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(
+            body["results"][0]["series"][0]["values"],
+            serde_json::json!([[1617286224]])
+        );
+
+        let response = client
+            .get(&format!("{}/query", server_url))
+            .query(&[("db", "unknown"), ("q", "select * from h2o_temperature")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     /// Sets up a test database with some data for testing the query endpoint
     /// returns a client for communicting with the server, and the server
     /// endpoint
     async fn setup_test_data() -> (Client, String) {
         let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
-        app_server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
         app_server
             .create_database(
                 DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
@@ -999,7 +2573,10 @@ mod tests {
     #[tokio::test]
     async fn test_gzip_write() {
         let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
-        app_server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
         app_server
             .create_database(
                 DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
@@ -1047,7 +2624,10 @@ mod tests {
     #[tokio::test]
     async fn write_to_invalid_database() {
         let app_server = Arc::new(AppServer::new(ConnectionManagerImpl {}, config()));
-        app_server.set_id(NonZeroU32::new(1).unwrap()).unwrap();
+        app_server
+            .set_id(NonZeroU32::new(1).unwrap())
+            .await
+            .unwrap();
         app_server
             .create_database(
                 DatabaseRules::new(DatabaseName::new("MyOrg_MyBucket").unwrap()),
@@ -1150,7 +2730,7 @@ mod tests {
         let addr = AddrIncoming::bind(&bind_addr).expect("failed to bind server");
         let server_url = format!("http://{}", addr.local_addr());
 
-        tokio::task::spawn(serve(addr, server, CancellationToken::new()));
+        tokio::task::spawn(serve(addr, server, None, CancellationToken::new()));
         println!("Started server at {}", server_url);
         server_url
     }
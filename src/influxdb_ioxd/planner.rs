@@ -4,7 +4,11 @@ use std::sync::Arc;
 use arrow_deps::datafusion::{catalog::catalog::CatalogProvider, physical_plan::ExecutionPlan};
 use query::{
     exec::Executor,
-    frontend::{influxrpc::InfluxRPCPlanner, sql::SQLQueryPlanner},
+    frontend::{
+        influxql::{parser::Statement, InfluxQLPlan, InfluxQLQueryPlanner},
+        influxrpc::InfluxRPCPlanner,
+        sql::SQLQueryPlanner,
+    },
     group_by::{Aggregate, WindowDuration},
     plan::{fieldlist::FieldListPlan, seriesset::SeriesSetPlans, stringset::StringSetPlan},
     predicate::Predicate,
@@ -20,6 +24,11 @@ pub enum Error {
         source: query::frontend::sql::Error,
     },
 
+    #[snafu(display("Error planning InfluxQL query {}", source))]
+    InfluxQL {
+        source: query::frontend::influxql::Error,
+    },
+
     #[snafu(display("Error planning InfluxRPC query {}", source))]
     InfluxRPC {
         source: query::frontend::influxrpc::Error,
@@ -70,6 +79,24 @@ impl Planner {
             .context(InternalExecutionWhilePlanning)?
     }
 
+    /// Plan a parsed InfluxQL statement against the data in
+    /// `database`, on a separate threadpool
+    pub async fn influxql<D>(&self, database: Arc<D>, statement: Statement) -> Result<InfluxQLPlan>
+    where
+        D: Database + 'static,
+    {
+        let planner = InfluxQLQueryPlanner::new();
+
+        self.exec
+            .run(async move {
+                planner
+                    .statement(database.as_ref(), &statement)
+                    .context(InfluxQL)
+            })
+            .await
+            .context(InternalExecutionWhilePlanning)?
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRPCPlanner::table_names`], on a separate threadpool
     pub async fn table_names<D>(
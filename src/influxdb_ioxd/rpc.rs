@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use tokio::net::TcpListener;
 use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::ServerTlsConfig;
 
 use server::{ConnectionManager, Server};
 use tokio_util::sync::CancellationToken;
 
+pub mod auth;
 pub mod error;
 mod flight;
 mod management;
@@ -17,11 +19,12 @@ mod write;
 
 /// Instantiate a server listening on the specified address
 /// implementing the IOx, Storage, and Flight gRPC interfaces, the
-/// underlying hyper server instance. Resolves when the server has
-/// shutdown.
+/// underlying hyper server instance. Serves over TLS if `tls` is set.
+/// Resolves when the server has shutdown.
 pub async fn serve<M>(
     socket: TcpListener,
     server: Arc<Server<M>>,
+    tls: Option<ServerTlsConfig>,
     shutdown: CancellationToken,
 ) -> Result<(), tonic::transport::Error>
 where
@@ -43,10 +46,19 @@ where
             .await;
     }
 
-    tonic::transport::Server::builder()
+    let builder = tonic::transport::Server::builder();
+    let mut builder = match tls {
+        Some(tls) => builder.tls_config(tls)?,
+        None => builder,
+    };
+
+    builder
         .add_service(health_service)
-        .add_service(testing::make_server())
-        .add_service(storage::make_server(Arc::clone(&server)))
+        .add_service(testing::make_server(Arc::clone(&server)))
+        .add_service(storage::make_server(
+            Arc::clone(&server),
+            Arc::clone(&server),
+        ))
         .add_service(flight::make_server(Arc::clone(&server)))
         .add_service(write::make_server(Arc::clone(&server)))
         .add_service(management::make_server(Arc::clone(&server)))
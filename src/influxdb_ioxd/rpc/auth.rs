@@ -0,0 +1,79 @@
+//! Authentication and authorization of gRPC requests with API tokens.
+//!
+//! Clients send their token in the `authorization` metadata as
+//! `Token <token>`. Every service rejects requests without a valid token
+//! using an interceptor, and the handlers then check that the token grants
+//! the scope they need on the database they access.
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use data_types::auth::Scope;
+use server::{ConnectionManager, Server};
+use tonic::{metadata::MetadataMap, Interceptor, Request, Status};
+
+use super::error::default_auth_error_handler;
+
+/// The prefix of the token in the `authorization` metadata
+const TOKEN_PREFIX: &str = "Token ";
+
+/// Checks the API tokens sent with requests
+pub trait Authorizer: Debug + Send + Sync + 'static {
+    /// Checks that `token` is a valid API token
+    fn authenticate(&self, token: Option<&str>) -> Result<(), server::auth::Error>;
+
+    /// Checks that `token` grants `scope` on the database `db_name`, or on
+    /// the server if `db_name` is `None`
+    fn authorize(
+        &self,
+        token: Option<&str>,
+        db_name: Option<&str>,
+        scope: Scope,
+    ) -> Result<(), server::auth::Error>;
+}
+
+impl<M> Authorizer for Server<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    fn authenticate(&self, token: Option<&str>) -> Result<(), server::auth::Error> {
+        Self::authenticate(self, token)
+    }
+
+    fn authorize(
+        &self,
+        token: Option<&str>,
+        db_name: Option<&str>,
+        scope: Scope,
+    ) -> Result<(), server::auth::Error> {
+        Self::authorize(self, token, db_name, scope)
+    }
+}
+
+/// Returns the API token sent in `metadata`, if any
+pub fn request_token(metadata: &MetadataMap) -> Option<&str> {
+    let value = metadata.get("authorization")?.to_str().ok()?;
+    value.strip_prefix(TOKEN_PREFIX)
+}
+
+/// Returns an interceptor rejecting all requests without a valid API token
+pub fn interceptor(authorizer: Arc<dyn Authorizer>) -> Interceptor {
+    Interceptor::new(move |request: Request<()>| {
+        authorizer
+            .authenticate(request_token(request.metadata()))
+            .map_err(default_auth_error_handler)?;
+        Ok(request)
+    })
+}
+
+/// Checks that the API token sent with `request` grants `scope` on the
+/// database `db_name`, or on the server if `db_name` is `None`
+pub fn authorize<T>(
+    authorizer: &dyn Authorizer,
+    request: &Request<T>,
+    db_name: Option<&str>,
+    scope: Scope,
+) -> Result<(), Status> {
+    authorizer
+        .authorize(request_token(request.metadata()), db_name, scope)
+        .map_err(default_auth_error_handler)
+}
@@ -3,6 +3,8 @@ use generated_types::google::{
 };
 use observability_deps::tracing::error;
 
+use crate::commands::metrics::IOXD_METRICS;
+
 /// map common `server::Error` errors  to the appropriate tonic Status
 pub fn default_server_error_handler(error: server::Error) -> tonic::Status {
     use server::Error;
@@ -33,8 +35,34 @@ pub fn default_server_error_handler(error: server::Error) -> tonic::Status {
         Error::HardLimitReached {} => QuotaFailure {
             subject: "influxdata.com/iox/buffer".to_string(),
             description: "hard buffer limit reached".to_string(),
+            ..Default::default()
+        }
+        .into(),
+        Error::WriteOutsideRetentionPeriod { source } => PreconditionViolation {
+            category: "retention period".to_string(),
+            subject: "influxdata.com/iox".to_string(),
+            description: source.to_string(),
+        }
+        .into(),
+        Error::TokenNotFound { id } => NotFound {
+            resource_type: "token".to_string(),
+            resource_name: id,
+            ..Default::default()
+        }
+        .into(),
+        e @ Error::ServerConfigConflict { .. } => PreconditionViolation {
+            category: "server config".to_string(),
+            subject: "influxdata.com/iox".to_string(),
+            description: e.to_string(),
+        }
+        .into(),
+        Error::OpeningBackup { source } => PreconditionViolation {
+            category: "backup".to_string(),
+            subject: "influxdata.com/iox".to_string(),
+            description: source.to_string(),
         }
         .into(),
+        Error::LimitExceeded { db_name, source } => limit_exceeded_status(&db_name, &source),
         error => {
             error!(?error, "Unexpected error");
             InternalError {}.into()
@@ -85,3 +113,33 @@ pub fn default_db_error_handler(error: server::db::Error) -> tonic::Status {
         }
     }
 }
+
+/// map a request to `db_name` rejected by its limits to a tonic Status that
+/// includes when to retry the request, recording the rejection
+pub fn limit_exceeded_status(db_name: &str, error: &server::limits::Error) -> tonic::Status {
+    IOXD_METRICS.record_limit_exceeded(db_name, error);
+
+    QuotaFailure {
+        subject: "influxdata.com/iox/limits".to_string(),
+        description: error.to_string(),
+        retry_delay: error.retry_after(),
+    }
+    .into()
+}
+
+/// map a query that did not complete to the appropriate tonic Status
+pub fn query_not_completed_status(error: &query::QueryError) -> tonic::Status {
+    match error {
+        query::QueryError::Cancelled => tonic::Status::cancelled(error.to_string()),
+        query::QueryError::TimedOut { .. } => tonic::Status::deadline_exceeded(error.to_string()),
+    }
+}
+
+/// map `server::auth::Error` errors to the appropriate tonic Status
+pub fn default_auth_error_handler(error: server::auth::Error) -> tonic::Status {
+    use server::auth::Error;
+    match error {
+        Error::Unauthenticated => tonic::Status::unauthenticated(error.to_string()),
+        Error::PermissionDenied { .. } => tonic::Status::permission_denied(error.to_string()),
+    }
+}
@@ -21,11 +21,14 @@ use arrow_deps::{
         HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
     },
 };
-use data_types::{DatabaseName, DatabaseNameError};
+use data_types::{auth::Scope, DatabaseName, DatabaseNameError};
+use query::AdmittedQuery;
 use server::{ConnectionManager, Server};
 use std::fmt::Debug;
 
 use super::super::planner::Planner;
+use super::auth::{interceptor, request_token};
+use super::error::{default_auth_error_handler, limit_exceeded_status, query_not_completed_status};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -63,6 +66,18 @@ pub enum Error {
     Planning {
         source: super::super::planner::Error,
     },
+
+    #[snafu(display("Limit of database {} exceeded: {}", database_name, source))]
+    LimitExceeded {
+        database_name: String,
+        source: server::limits::Error,
+    },
+
+    #[snafu(display("Query of database {} did not complete: {}", database_name, source))]
+    QueryNotCompleted {
+        database_name: String,
+        source: query::QueryError,
+    },
 }
 
 impl From<Error> for tonic::Status {
@@ -87,6 +102,11 @@ impl Error {
             Self::InvalidDatabaseName { .. } => Status::invalid_argument(self.to_string()),
             Self::InvalidRecordBatch { .. } => Status::internal(self.to_string()),
             Self::Planning { .. } => Status::invalid_argument(self.to_string()),
+            Self::LimitExceeded {
+                database_name,
+                source,
+            } => limit_exceeded_status(database_name, source),
+            Self::QueryNotCompleted { source, .. } => query_not_completed_status(source),
         }
     }
 }
@@ -111,7 +131,8 @@ pub fn make_server<M>(server: Arc<Server<M>>) -> FlightServer<impl Flight>
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    FlightServer::new(FlightService { server })
+    let interceptor = interceptor(Arc::clone(&server));
+    FlightServer::with_interceptor(FlightService { server }, interceptor)
 }
 
 #[tonic::async_trait]
@@ -140,6 +161,9 @@ where
         &self,
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
+        // The database is only known once the ticket is decoded
+        let token = request_token(request.metadata()).map(ToString::to_string);
+
         let ticket = request.into_inner();
         let json_str = String::from_utf8(ticket.ticket.to_vec()).context(InvalidTicket {
             ticket: ticket.ticket,
@@ -148,12 +172,26 @@ where
         let read_info: ReadInfo =
             serde_json::from_str(&json_str).context(InvalidQuery { query: &json_str })?;
 
+        self.server
+            .authorize(
+                token.as_deref(),
+                Some(read_info.database_name.as_str()),
+                Scope::Read,
+            )
+            .map_err(default_auth_error_handler)?;
+
         let database = DatabaseName::new(&read_info.database_name).context(InvalidDatabaseName)?;
 
         let db = self.server.db(&database).context(DatabaseNotFound {
             database_name: &read_info.database_name,
         })?;
 
+        let query = db
+            .start_query(&read_info.sql_query)
+            .context(LimitExceeded {
+                database_name: &read_info.database_name,
+            })?;
+
         let executor = db.executor();
 
         let physical_plan = Planner::new(Arc::clone(&executor))
@@ -162,10 +200,12 @@ where
             .context(Planning)?;
 
         // execute the query
-        let results = executor
-            .new_context()
-            .collect(Arc::clone(&physical_plan))
+        let results = query
+            .run(executor.new_context().collect(Arc::clone(&physical_plan)))
             .await
+            .context(QueryNotCompleted {
+                database_name: &read_info.database_name,
+            })?
             .map_err(|e| Box::new(e) as _)
             .context(Query {
                 database_name: &read_info.database_name,
@@ -2,12 +2,17 @@ use std::convert::TryInto;
 use std::fmt::Debug;
 use std::sync::Arc;
 
+use data_types::auth::{Permission, Scope};
 use data_types::database_rules::DatabaseRules;
-use data_types::{field_validation::FromFieldOpt, DatabaseName};
+use data_types::{
+    field_validation::{FromFieldOpt, FromFieldVec},
+    DatabaseName,
+};
 use generated_types::google::{
     AlreadyExists, FieldViolation, FieldViolationExt, InternalError, NotFound,
 };
 use generated_types::influxdata::iox::management::v1::*;
+use object_store::path::ObjectStorePath;
 use observability_deps::tracing::info;
 use query::{Database, DatabaseStore};
 use server::{ConnectionManager, Error, Server};
@@ -17,6 +22,7 @@ struct ManagementService<M: ConnectionManager> {
     server: Arc<Server<M>>,
 }
 
+use super::auth::{authorize, interceptor};
 use super::error::{default_db_error_handler, default_server_error_handler};
 use std::num::NonZeroU32;
 
@@ -66,10 +72,11 @@ where
         &self,
         request: Request<UpdateWriterIdRequest>,
     ) -> Result<Response<UpdateWriterIdResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
         let id =
             NonZeroU32::new(request.get_ref().id).ok_or_else(|| FieldViolation::required("id"))?;
 
-        match self.server.set_id(id) {
+        match self.server.set_id(id).await {
             Ok(_) => Ok(Response::new(UpdateWriterIdResponse {})),
             Err(Error::IdAlreadySet { id }) => {
                 return Err(FieldViolation {
@@ -84,8 +91,9 @@ where
 
     async fn list_databases(
         &self,
-        _: Request<ListDatabasesRequest>,
+        request: Request<ListDatabasesRequest>,
     ) -> Result<Response<ListDatabasesResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Read)?;
         let names = self.server.db_names_sorted();
         Ok(Response::new(ListDatabasesResponse { names }))
     }
@@ -94,6 +102,8 @@ where
         &self,
         request: Request<GetDatabaseRequest>,
     ) -> Result<Response<GetDatabaseResponse>, Status> {
+        let db_name = Some(request.get_ref().name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Read)?;
         let name = DatabaseName::new(request.into_inner().name).field("name")?;
 
         match self.server.db_rules(&name) {
@@ -115,6 +125,7 @@ where
         &self,
         request: Request<CreateDatabaseRequest>,
     ) -> Result<Response<CreateDatabaseResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
         let rules: DatabaseRules = request
             .into_inner()
             .rules
@@ -145,6 +156,13 @@ where
         &self,
         request: Request<UpdateDatabaseRequest>,
     ) -> Result<Response<UpdateDatabaseResponse>, Status> {
+        let db_name = request
+            .get_ref()
+            .rules
+            .as_ref()
+            .map(|rules| rules.name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
+
         let request = request.into_inner();
         let rules: DatabaseRules = request.rules.required("rules")?;
         let db_name = rules.name.clone();
@@ -159,10 +177,28 @@ where
         }))
     }
 
+    async fn delete_database(
+        &self,
+        request: Request<DeleteDatabaseRequest>,
+    ) -> Result<Response<DeleteDatabaseResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
+        let db_name = DatabaseName::new(request.into_inner().db_name).field("db_name")?;
+
+        self.server
+            .delete_database(&db_name)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(DeleteDatabaseResponse {}))
+    }
+
     async fn list_chunks(
         &self,
         request: Request<ListChunksRequest>,
     ) -> Result<Response<ListChunksResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Read)?;
         let db_name = DatabaseName::new(request.into_inner().db_name).field("db_name")?;
 
         let db = match self.server.db(&db_name) {
@@ -194,6 +230,7 @@ where
         &self,
         request: Request<CreateDummyJobRequest>,
     ) -> Result<Response<CreateDummyJobResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
         let request = request.into_inner();
         let tracker = self.server.spawn_dummy_job(request.nanos);
         let operation = Some(super::operations::encode_tracker(tracker)?);
@@ -202,8 +239,9 @@ where
 
     async fn list_remotes(
         &self,
-        _: Request<ListRemotesRequest>,
+        request: Request<ListRemotesRequest>,
     ) -> Result<Response<ListRemotesResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Read)?;
         let remotes = self
             .server
             .remotes_sorted()
@@ -220,6 +258,7 @@ where
         &self,
         request: Request<UpdateRemoteRequest>,
     ) -> Result<Response<UpdateRemoteResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
         let remote = request
             .into_inner()
             .remote
@@ -228,7 +267,9 @@ where
             return Err(FieldViolation::required("id").scope("remote").into());
         }
         self.server
-            .update_remote(remote.id, remote.connection_string);
+            .update_remote(remote.id, remote.connection_string)
+            .await
+            .map_err(default_server_error_handler)?;
         Ok(Response::new(UpdateRemoteResponse {}))
     }
 
@@ -236,12 +277,15 @@ where
         &self,
         request: Request<DeleteRemoteRequest>,
     ) -> Result<Response<DeleteRemoteResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
         let request = request.into_inner();
         if request.id == 0 {
             return Err(FieldViolation::required("id").into());
         }
         self.server
             .delete_remote(request.id)
+            .await
+            .map_err(default_server_error_handler)?
             .ok_or_else(NotFound::default)?;
 
         Ok(Response::new(DeleteRemoteResponse {}))
@@ -251,6 +295,8 @@ where
         &self,
         request: Request<ListPartitionsRequest>,
     ) -> Result<Response<ListPartitionsResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Read)?;
         let ListPartitionsRequest { db_name } = request.into_inner();
         let db_name = DatabaseName::new(db_name).field("db_name")?;
 
@@ -273,6 +319,8 @@ where
         &self,
         request: Request<GetPartitionRequest>,
     ) -> Result<Response<GetPartitionResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Read)?;
         let GetPartitionRequest {
             db_name,
             partition_key,
@@ -301,6 +349,8 @@ where
         &self,
         request: Request<ListPartitionChunksRequest>,
     ) -> Result<Response<ListPartitionChunksResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Read)?;
         let ListPartitionChunksRequest {
             db_name,
             partition_key,
@@ -326,6 +376,8 @@ where
         &self,
         request: Request<NewPartitionChunkRequest>,
     ) -> Result<Response<NewPartitionChunkResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
         let NewPartitionChunkRequest {
             db_name,
             partition_key,
@@ -349,6 +401,8 @@ where
         &self,
         request: Request<ClosePartitionChunkRequest>,
     ) -> Result<Response<ClosePartitionChunkResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
         let ClosePartitionChunkRequest {
             db_name,
             partition_key,
@@ -367,6 +421,238 @@ where
 
         Ok(Response::new(ClosePartitionChunkResponse { operation }))
     }
+
+    async fn drop_partition_chunk(
+        &self,
+        request: Request<DropPartitionChunkRequest>,
+    ) -> Result<Response<DropPartitionChunkResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
+        let DropPartitionChunkRequest {
+            db_name,
+            partition_key,
+            chunk_id,
+        } = request.into_inner();
+        let db_name = DatabaseName::new(db_name).field("db_name")?;
+
+        let db = self.server.db(&db_name).ok_or_else(|| NotFound {
+            resource_type: "database".to_string(),
+            resource_name: db_name.to_string(),
+            ..Default::default()
+        })?;
+
+        let tracker = db.drop_chunk_in_background(partition_key, chunk_id);
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(DropPartitionChunkResponse { operation }))
+    }
+
+    async fn persist_partition_chunk(
+        &self,
+        request: Request<PersistPartitionChunkRequest>,
+    ) -> Result<Response<PersistPartitionChunkResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
+        let PersistPartitionChunkRequest {
+            db_name,
+            partition_key,
+            chunk_id,
+        } = request.into_inner();
+        let db_name = DatabaseName::new(db_name).field("db_name")?;
+
+        let db = self.server.db(&db_name).ok_or_else(|| NotFound {
+            resource_type: "database".to_string(),
+            resource_name: db_name.to_string(),
+            ..Default::default()
+        })?;
+
+        let tracker = db.write_chunk_to_object_store_in_background(partition_key, chunk_id);
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(PersistPartitionChunkResponse { operation }))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Write)?;
+        let DeleteRequest { db_name, predicate } = request.into_inner();
+        let db_name = DatabaseName::new(db_name).field("db_name")?;
+        let predicate = predicate.required("predicate")?;
+
+        let db = self.server.db(&db_name).ok_or_else(|| NotFound {
+            resource_type: "database".to_string(),
+            resource_name: db_name.to_string(),
+            ..Default::default()
+        })?;
+
+        db.delete(Arc::new(predicate))
+            .await
+            .map_err(default_db_error_handler)?;
+
+        Ok(Response::new(DeleteResponse {}))
+    }
+
+    async fn collect_garbage(
+        &self,
+        request: Request<CollectGarbageRequest>,
+    ) -> Result<Response<CollectGarbageResponse>, Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Admin)?;
+        let CollectGarbageRequest {
+            db_name,
+            grace_period_seconds,
+            dry_run,
+        } = request.into_inner();
+        let db_name = DatabaseName::new(db_name).field("db_name")?;
+        let grace_period = std::time::Duration::from_secs(grace_period_seconds);
+
+        let db = self.server.db(&db_name).ok_or_else(|| NotFound {
+            resource_type: "database".to_string(),
+            resource_name: db_name.to_string(),
+            ..Default::default()
+        })?;
+
+        if dry_run {
+            let orphaned_files = db
+                .find_orphaned_files(grace_period)
+                .await
+                .map_err(default_db_error_handler)?
+                .iter()
+                .map(|path| path.display())
+                .collect();
+
+            return Ok(Response::new(CollectGarbageResponse {
+                operation: None,
+                orphaned_files,
+            }));
+        }
+
+        let tracker = db.collect_garbage_in_background(grace_period);
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(CollectGarbageResponse {
+            operation,
+            orphaned_files: vec![],
+        }))
+    }
+
+    async fn backup_database(
+        &self,
+        request: Request<BackupDatabaseRequest>,
+    ) -> Result<Response<BackupDatabaseResponse>, Status> {
+        // The backup is written to the server's file system, so like a
+        // restore it requires admin permission on the server rather than
+        // only on the database
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+        let BackupDatabaseRequest { db_name, path } = request.into_inner();
+        let db_name = DatabaseName::new(db_name).field("db_name")?;
+        if path.is_empty() {
+            return Err(FieldViolation::required("path").into());
+        }
+
+        let tracker = self
+            .server
+            .backup_database(&db_name, path)
+            .map_err(default_server_error_handler)?;
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(BackupDatabaseResponse { operation }))
+    }
+
+    async fn restore_database(
+        &self,
+        request: Request<RestoreDatabaseRequest>,
+    ) -> Result<Response<RestoreDatabaseResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+        let RestoreDatabaseRequest { path, db_name } = request.into_inner();
+        if path.is_empty() {
+            return Err(FieldViolation::required("path").into());
+        }
+        let db_name = match db_name.as_str() {
+            "" => None,
+            _ => Some(DatabaseName::new(db_name).field("db_name")?),
+        };
+
+        let tracker = match self.server.restore_database(path, db_name).await {
+            Ok(tracker) => tracker,
+            Err(Error::DatabaseAlreadyExists { db_name }) => {
+                return Err(AlreadyExists {
+                    resource_type: "database".to_string(),
+                    resource_name: db_name,
+                    ..Default::default()
+                }
+                .into())
+            }
+            Err(e) => return Err(default_server_error_handler(e)),
+        };
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(RestoreDatabaseResponse { operation }))
+    }
+
+    async fn create_token(
+        &self,
+        request: Request<CreateTokenRequest>,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+
+        let CreateTokenRequest {
+            description,
+            permissions,
+        } = request.into_inner();
+        let permissions: Vec<Permission> = permissions.vec_field("permissions")?;
+        if permissions.is_empty() {
+            return Err(FieldViolation::required("permissions").into());
+        }
+
+        let token = self
+            .server
+            .create_token(description, permissions)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(CreateTokenResponse {
+            token: Some(token.into()),
+        }))
+    }
+
+    async fn list_tokens(
+        &self,
+        request: Request<ListTokensRequest>,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+
+        let tokens = self
+            .server
+            .list_tokens()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(Response::new(ListTokensResponse { tokens }))
+    }
+
+    async fn revoke_token(
+        &self,
+        request: Request<RevokeTokenRequest>,
+    ) -> Result<Response<RevokeTokenResponse>, Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+
+        let RevokeTokenRequest { id } = request.into_inner();
+        if id.is_empty() {
+            return Err(FieldViolation::required("id").into());
+        }
+
+        self.server
+            .revoke_token(&id)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(RevokeTokenResponse {}))
+    }
 }
 
 pub fn make_server<M>(
@@ -377,5 +663,9 @@ pub fn make_server<M>(
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    management_service_server::ManagementServiceServer::new(ManagementService { server })
+    let interceptor = interceptor(Arc::clone(&server));
+    management_service_server::ManagementServiceServer::with_interceptor(
+        ManagementService { server },
+        interceptor,
+    )
 }
@@ -6,7 +6,7 @@ use observability_deps::tracing::debug;
 use prost::Message;
 use tonic::Response;
 
-use data_types::job::Job;
+use data_types::{auth::Scope, job::Job};
 use generated_types::google::FieldViolationExt;
 use generated_types::{
     google::{
@@ -23,6 +23,8 @@ use tracker::{TaskId, TaskStatus, TaskTracker};
 use server::{ConnectionManager, Server};
 use std::convert::TryInto;
 
+use super::auth::{authorize, interceptor};
+
 /// Implementation of the write service
 struct OperationsService<M: ConnectionManager> {
     server: Arc<Server<M>>,
@@ -133,8 +135,10 @@ where
 {
     async fn list_operations(
         &self,
-        _request: tonic::Request<ListOperationsRequest>,
+        request: tonic::Request<ListOperationsRequest>,
     ) -> Result<tonic::Response<ListOperationsResponse>, tonic::Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Read)?;
+
         // TODO: Support pagination
         let operations: Result<Vec<_>, _> = self
             .server
@@ -153,6 +157,8 @@ where
         &self,
         request: tonic::Request<GetOperationRequest>,
     ) -> Result<tonic::Response<Operation>, tonic::Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Read)?;
+
         let request = request.into_inner();
         let tracker = get_tracker(self.server.as_ref(), request.name)?;
 
@@ -172,6 +178,8 @@ where
         &self,
         request: tonic::Request<CancelOperationRequest>,
     ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        authorize(self.server.as_ref(), &request, None, Scope::Admin)?;
+
         let request = request.into_inner();
 
         let tracker = get_tracker(self.server.as_ref(), request.name)?;
@@ -188,6 +196,8 @@ where
         // Unfortunately these are currently stripped by tonic
         // - https://github.com/hyperium/tonic/issues/75
 
+        authorize(self.server.as_ref(), &request, None, Scope::Read)?;
+
         let request = request.into_inner();
 
         let tracker = get_tracker(self.server.as_ref(), request.name)?;
@@ -211,5 +221,6 @@ pub fn make_server<M>(
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    operations_server::OperationsServer::new(OperationsService { server })
+    let interceptor = interceptor(Arc::clone(&server));
+    operations_server::OperationsServer::with_interceptor(OperationsService { server }, interceptor)
 }
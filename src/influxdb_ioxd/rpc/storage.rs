@@ -18,12 +18,25 @@ use generated_types::storage_server::{Storage, StorageServer};
 use query::DatabaseStore;
 use std::sync::Arc;
 
+use super::auth::{interceptor, Authorizer};
+
 /// Concrete implementation of the gRPC InfluxDB Storage Service API
 #[derive(Debug)]
 struct StorageService<T: DatabaseStore> {
     pub db_store: Arc<T>,
+    pub authorizer: Arc<dyn Authorizer>,
 }
 
-pub fn make_server<T: DatabaseStore + 'static>(db_store: Arc<T>) -> StorageServer<impl Storage> {
-    StorageServer::new(StorageService { db_store })
+pub fn make_server<T: DatabaseStore + 'static>(
+    db_store: Arc<T>,
+    authorizer: Arc<dyn Authorizer>,
+) -> StorageServer<impl Storage> {
+    let interceptor = interceptor(Arc::clone(&authorizer));
+    StorageServer::with_interceptor(
+        StorageService {
+            db_store,
+            authorizer,
+        },
+        interceptor,
+    )
 }
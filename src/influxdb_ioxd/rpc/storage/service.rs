@@ -2,6 +2,10 @@
 //! implemented in terms of the `query::Database` and
 //! `query::DatabaseStore`
 
+use crate::influxdb_ioxd::rpc::{
+    auth::authorize,
+    error::{limit_exceeded_status, query_not_completed_status},
+};
 use crate::influxdb_ioxd::{
     planner::Planner,
     rpc::storage::{
@@ -14,7 +18,9 @@ use crate::influxdb_ioxd::{
         StorageService,
     },
 };
-use data_types::{error::ErrorLogger, names::org_and_bucket_to_database, DatabaseName};
+use data_types::{
+    auth::Scope, error::ErrorLogger, names::org_and_bucket_to_database, DatabaseName,
+};
 use generated_types::{
     google::protobuf::Empty, storage_server::Storage, CapabilitiesResponse, Capability,
     Int64ValuesResponse, MeasurementFieldsRequest, MeasurementFieldsResponse,
@@ -28,7 +34,7 @@ use query::{
     exec::fieldlist::FieldList,
     exec::seriesset::{Error as SeriesSetError, SeriesSetItem},
     predicate::PredicateBuilder,
-    DatabaseStore,
+    AdmittedQuery, DatabaseStore,
 };
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::{collections::HashMap, sync::Arc};
@@ -155,6 +161,18 @@ pub enum Error {
 
     #[snafu(display("Operation not yet implemented:  {}", operation))]
     NotYetImplemented { operation: String },
+
+    #[snafu(display("Query of database '{}' rejected: {}", db_name, source))]
+    QueryRejected {
+        db_name: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Query of database '{}' did not complete: {}", db_name, source))]
+    QueryNotCompleted {
+        db_name: String,
+        source: query::QueryError,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -200,6 +218,16 @@ impl Error {
             Self::SendingResults { .. } => Status::internal(self.to_string()),
             Self::InternalHintsFieldNotSupported { .. } => Status::internal(self.to_string()),
             Self::NotYetImplemented { .. } => Status::internal(self.to_string()),
+            Self::QueryRejected { db_name, source } => {
+                // rejected by the limits of a server database
+                match source.downcast_ref::<server::Error>() {
+                    Some(server::Error::LimitExceeded { source, .. }) => {
+                        limit_exceeded_status(db_name, source)
+                    }
+                    _ => Status::resource_exhausted(self.to_string()),
+                }
+            }
+            Self::QueryNotCompleted { source, .. } => query_not_completed_status(source),
         }
     }
 }
@@ -218,9 +246,10 @@ where
     ) -> Result<tonic::Response<Self::ReadFilterStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let read_filter_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&read_filter_request)?;
+        let read_filter_request = req.into_inner();
 
         let ReadFilterRequest {
             read_source: _read_source,
@@ -251,9 +280,10 @@ where
     ) -> Result<tonic::Response<Self::ReadGroupStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let read_group_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&read_group_request)?;
+        let read_group_request = req.into_inner();
 
         let ReadGroupRequest {
             read_source: _read_source,
@@ -305,9 +335,10 @@ where
     ) -> Result<tonic::Response<Self::ReadGroupStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let read_window_aggregate_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&read_window_aggregate_request)?;
+        let read_window_aggregate_request = req.into_inner();
 
         let ReadWindowAggregateRequest {
             read_source: _read_source,
@@ -351,9 +382,10 @@ where
     ) -> Result<tonic::Response<Self::TagKeysStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let tag_keys_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&tag_keys_request)?;
+        let tag_keys_request = req.into_inner();
 
         let TagKeysRequest {
             tags_source: _tag_source,
@@ -390,9 +422,10 @@ where
     ) -> Result<tonic::Response<Self::TagValuesStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let tag_values_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&tag_values_request)?;
+        let tag_values_request = req.into_inner();
 
         let TagValuesRequest {
             tags_source: _tag_source,
@@ -509,9 +542,10 @@ where
     ) -> Result<tonic::Response<Self::MeasurementNamesStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let measurement_names_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&measurement_names_request)?;
+        let measurement_names_request = req.into_inner();
 
         let MeasurementNamesRequest {
             source: _source,
@@ -551,9 +585,10 @@ where
     ) -> Result<tonic::Response<Self::MeasurementTagKeysStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let measurement_tag_keys_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&measurement_tag_keys_request)?;
+        let measurement_tag_keys_request = req.into_inner();
 
         let MeasurementTagKeysRequest {
             source: _source,
@@ -591,9 +626,10 @@ where
     ) -> Result<tonic::Response<Self::MeasurementTagValuesStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let measurement_tag_values_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&measurement_tag_values_request)?;
+        let measurement_tag_values_request = req.into_inner();
 
         let MeasurementTagValuesRequest {
             source: _source,
@@ -633,9 +669,10 @@ where
     ) -> Result<tonic::Response<Self::MeasurementFieldsStream>, Status> {
         let (tx, rx) = mpsc::channel(4);
 
-        let measurement_fields_request = req.into_inner();
+        let db_name = get_database_name(req.get_ref())?;
+        authorize(self.authorizer.as_ref(), &req, Some(&db_name), Scope::Read)?;
 
-        let db_name = get_database_name(&measurement_fields_request)?;
+        let measurement_fields_request = req.into_inner();
 
         let MeasurementFieldsRequest {
             source: _source,
@@ -711,6 +748,10 @@ where
     let db = db_store
         .db(&db_name)
         .context(DatabaseNotFound { db_name })?;
+    let query = db_store
+        .start_query(db_name, "measurement_names")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected { db_name })?;
     let executor = db_store.executor();
 
     let plan = Planner::new(Arc::clone(&executor))
@@ -719,9 +760,10 @@ where
         .map_err(|e| Box::new(e) as _)
         .context(ListingTables { db_name })?;
 
-    let table_names = executor
-        .to_string_set(plan)
+    let table_names = query
+        .run(executor.to_string_set(plan))
         .await
+        .context(QueryNotCompleted { db_name })?
         .map_err(|e| Box::new(e) as _)
         .context(ListingTables { db_name })?;
 
@@ -760,6 +802,12 @@ where
     let db = db_store.db(&db_name).context(DatabaseNotFound {
         db_name: db_name.as_str(),
     })?;
+    let query = db_store
+        .start_query(db_name.as_str(), "tag_keys")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected {
+            db_name: db_name.as_str(),
+        })?;
 
     let executor = db_store.executor();
 
@@ -771,9 +819,12 @@ where
             db_name: db_name.as_str(),
         })?;
 
-    let tag_keys = executor
-        .to_string_set(tag_key_plan)
+    let tag_keys = query
+        .run(executor.to_string_set(tag_key_plan))
         .await
+        .context(QueryNotCompleted {
+            db_name: db_name.as_str(),
+        })?
         .map_err(|e| Box::new(e) as _)
         .context(ListingColumns {
             db_name: db_name.as_str(),
@@ -816,6 +867,10 @@ where
     let tag_name = &tag_name;
 
     let db = db_store.db(db_name).context(DatabaseNotFound { db_name })?;
+    let query = db_store
+        .start_query(db_name, "tag_values")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected { db_name })?;
     let executor = db_store.executor();
 
     let tag_value_plan = Planner::new(Arc::clone(&executor))
@@ -824,9 +879,10 @@ where
         .map_err(|e| Box::new(e) as _)
         .context(ListingTagValues { db_name, tag_name })?;
 
-    let tag_values = executor
-        .to_string_set(tag_value_plan)
+    let tag_values = query
+        .run(executor.to_string_set(tag_value_plan))
         .await
+        .context(QueryNotCompleted { db_name })?
         .map_err(|e| Box::new(e) as _)
         .context(ListingTagValues { db_name, tag_name })?;
 
@@ -870,6 +926,10 @@ where
 
     let db_name = owned_db_name.as_str();
     let db = db_store.db(db_name).context(DatabaseNotFound { db_name })?;
+    let query = db_store
+        .start_query(db_name, "read_filter")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected { db_name })?;
     let executor = db_store.executor();
 
     let series_plan = Planner::new(Arc::clone(&executor))
@@ -882,6 +942,7 @@ where
     // and to run the actual plans (so we can return a result to the
     // client before we start sending result)
     let (tx_series, rx_series) = mpsc::channel(4);
+    let client_tx = tx.clone();
     tokio::spawn(async move {
         convert_series_set(rx_series, tx)
             .await
@@ -890,14 +951,22 @@ where
 
     // fire up the plans and start the pipeline flowing
     tokio::spawn(async move {
-        executor
-            .to_series_set(series_plan, tx_series)
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(FilteringSeries {
-                db_name: owned_db_name.as_str(),
-            })
-            .log_if_error("Running series set plan")
+        let series = query.run(executor.to_series_set(series_plan, tx_series));
+
+        let result = tokio::select! {
+            result = series => result
+                .context(QueryNotCompleted {
+                    db_name: owned_db_name.as_str(),
+                })
+                .and_then(|result| {
+                    result.map_err(|e| Box::new(e) as _).context(FilteringSeries {
+                        db_name: owned_db_name.as_str(),
+                    })
+                }),
+            // the client went away, so stop running the query
+            _ = client_tx.closed() => Ok(()),
+        };
+        result.log_if_error("Running series set plan")
     });
 
     Ok(())
@@ -955,6 +1024,10 @@ where
     let db = db_store
         .db(&db_name)
         .context(DatabaseNotFound { db_name })?;
+    let query = db_store
+        .start_query(db_name, "read_group")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected { db_name })?;
     let executor = db_store.executor();
 
     let planner = Planner::new(Arc::clone(&executor));
@@ -976,6 +1049,7 @@ where
     // and to run the actual plans (so we can return a result to the
     // client before we start sending result)
     let (tx_series, rx_series) = mpsc::channel(4);
+    let client_tx = tx.clone();
     tokio::spawn(async move {
         convert_series_set(rx_series, tx)
             .await
@@ -984,14 +1058,22 @@ where
 
     // fire up the plans and start the pipeline flowing
     tokio::spawn(async move {
-        executor
-            .to_series_set(grouped_series_set_plan, tx_series)
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(GroupingSeries {
-                db_name: owned_db_name.as_str(),
-            })
-            .log_if_error("Running Grouped SeriesSet Plan")
+        let series = query.run(executor.to_series_set(grouped_series_set_plan, tx_series));
+
+        let result = tokio::select! {
+            result = series => result
+                .context(QueryNotCompleted {
+                    db_name: owned_db_name.as_str(),
+                })
+                .and_then(|result| {
+                    result.map_err(|e| Box::new(e) as _).context(GroupingSeries {
+                        db_name: owned_db_name.as_str(),
+                    })
+                }),
+            // the client went away, so stop running the query
+            _ = client_tx.closed() => Ok(()),
+        };
+        result.log_if_error("Running Grouped SeriesSet Plan")
     });
 
     Ok(())
@@ -1022,6 +1104,10 @@ where
 
     let db_name = db_name.as_str();
     let db = db_store.db(db_name).context(DatabaseNotFound { db_name })?;
+    let query = db_store
+        .start_query(db_name, "measurement_fields")
+        .map_err(|e| Box::new(e) as _)
+        .context(QueryRejected { db_name })?;
     let executor = db_store.executor();
 
     let field_list_plan = Planner::new(Arc::clone(&executor))
@@ -1030,9 +1116,10 @@ where
         .map_err(|e| Box::new(e) as _)
         .context(ListingFields { db_name })?;
 
-    let field_list = executor
-        .to_field_list(field_list_plan)
+    let field_list = query
+        .run(executor.to_field_list(field_list_plan))
         .await
+        .context(QueryNotCompleted { db_name })?
         .map_err(|e| Box::new(e) as _)
         .context(ListingFields { db_name })?;
 
@@ -1044,6 +1131,7 @@ mod tests {
     use super::super::id::ID;
 
     use super::*;
+    use crate::influxdb_ioxd::rpc::auth::Authorizer;
     use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
     use panic_logging::SendPanicsToTracing;
     use query::{test::TestChunk, test::TestDatabaseStore};
@@ -2591,6 +2679,25 @@ mod tests {
         Tonic { source: tonic::transport::Error },
     }
 
+    /// Allows all requests
+    #[derive(Debug)]
+    struct AllowAll {}
+
+    impl Authorizer for AllowAll {
+        fn authenticate(&self, _token: Option<&str>) -> Result<(), server::auth::Error> {
+            Ok(())
+        }
+
+        fn authorize(
+            &self,
+            _token: Option<&str>,
+            _db_name: Option<&str>,
+            _scope: Scope,
+        ) -> Result<(), server::auth::Error> {
+            Ok(())
+        }
+    }
+
     // Wrapper around raw clients and test database
     struct Fixture {
         iox_client: IOxTestingClient,
@@ -2618,11 +2725,15 @@ mod tests {
                 bind_addr
             );
 
+            let authorizer = Arc::new(AllowAll {});
             let router = tonic::transport::Server::builder()
-                .add_service(crate::influxdb_ioxd::rpc::testing::make_server())
-                .add_service(crate::influxdb_ioxd::rpc::storage::make_server(Arc::clone(
-                    &test_storage,
-                )));
+                .add_service(crate::influxdb_ioxd::rpc::testing::make_server(Arc::clone(
+                    &authorizer,
+                )))
+                .add_service(crate::influxdb_ioxd::rpc::storage::make_server(
+                    Arc::clone(&test_storage),
+                    authorizer,
+                ));
 
             let server = async move {
                 let stream = TcpListenerStream::new(socket);
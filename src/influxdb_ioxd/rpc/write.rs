@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use data_types::auth::Scope;
 use generated_types::{google::FieldViolation, influxdata::iox::write::v1::*};
 use influxdb_line_protocol::parse_lines;
 use observability_deps::tracing::debug;
@@ -7,6 +8,7 @@ use server::{ConnectionManager, Server};
 use std::fmt::Debug;
 use tonic::Response;
 
+use super::auth::{authorize, interceptor};
 use super::error::default_server_error_handler;
 
 /// Implementation of the write service
@@ -23,6 +25,9 @@ where
         &self,
         request: tonic::Request<WriteRequest>,
     ) -> Result<tonic::Response<WriteResponse>, tonic::Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Write)?;
+
         let request = request.into_inner();
 
         let db_name = request.db_name;
@@ -52,6 +57,9 @@ where
         &self,
         request: tonic::Request<WriteEntryRequest>,
     ) -> Result<tonic::Response<WriteEntryResponse>, tonic::Status> {
+        let db_name = Some(request.get_ref().db_name.as_str());
+        authorize(self.server.as_ref(), &request, db_name, Scope::Write)?;
+
         let request = request.into_inner();
         if request.entry.is_empty() {
             return Err(FieldViolation::required("entry").into());
@@ -73,5 +81,6 @@ pub fn make_server<M>(
 where
     M: ConnectionManager + Send + Sync + Debug + 'static,
 {
-    write_service_server::WriteServiceServer::new(WriteService { server })
+    let interceptor = interceptor(Arc::clone(&server));
+    write_service_server::WriteServiceServer::with_interceptor(WriteService { server }, interceptor)
 }
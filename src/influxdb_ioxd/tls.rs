@@ -0,0 +1,153 @@
+//! TLS configuration of the HTTP and gRPC APIs
+use std::{
+    fmt,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile, AllowAnyAuthenticatedClient, NoClientAuth, PrivateKey, RootCertStore,
+        ServerConfig, TLSError,
+    },
+    TlsAcceptor,
+};
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to read {:?}: {}", path, source))]
+    ReadingFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("No PEM encoded certificates found in {:?}", path))]
+    NoCertificates { path: PathBuf },
+
+    #[snafu(display("No PEM encoded PKCS8 or RSA private key found in {:?}", path))]
+    NoPrivateKey { path: PathBuf },
+
+    #[snafu(display("Invalid TLS certificate or key: {}", source))]
+    InvalidCertificate { source: TLSError },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The certificate the APIs are served with, and the CA certificate client
+/// certificates are verified with, if any
+#[derive(Clone)]
+pub struct TlsConfig {
+    identity: Identity,
+    client_ca: Option<Certificate>,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsConfig {
+    /// Loads the PEM encoded certificate chain and private key, and the
+    /// optional CA certificate for verifying client certificates
+    pub fn load(cert_path: &Path, key_path: &Path, client_ca_path: Option<&Path>) -> Result<Self> {
+        let cert = read(cert_path)?;
+        let key = read(key_path)?;
+
+        let certs = pemfile::certs(&mut Cursor::new(&cert)).unwrap_or_default();
+        ensure!(!certs.is_empty(), NoCertificates { path: cert_path });
+        let private_key = private_key(&key).context(NoPrivateKey { path: key_path })?;
+
+        let (client_ca, client_auth) = match client_ca_path {
+            Some(path) => {
+                let pem = read(path)?;
+                let mut roots = RootCertStore::empty();
+                let (valid, _) = roots
+                    .add_pem_file(&mut Cursor::new(&pem))
+                    .unwrap_or_default();
+                ensure!(valid > 0, NoCertificates { path });
+
+                (
+                    Some(Certificate::from_pem(pem)),
+                    AllowAnyAuthenticatedClient::new(roots),
+                )
+            }
+            None => (None, NoClientAuth::new()),
+        };
+
+        let mut config = ServerConfig::new(client_auth);
+        config
+            .set_single_cert(certs, private_key)
+            .context(InvalidCertificate)?;
+        config.set_protocols(&[b"http/1.1".to_vec()]);
+
+        Ok(Self {
+            identity: Identity::from_pem(cert, key),
+            client_ca,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Returns the TLS configuration of the gRPC server
+    pub fn grpc(&self) -> ServerTlsConfig {
+        let config = ServerTlsConfig::new().identity(self.identity.clone());
+        match &self.client_ca {
+            Some(client_ca) => config.client_ca_root(client_ca.clone()),
+            None => config,
+        }
+    }
+
+    /// Returns the acceptor performing the TLS handshake of HTTP connections
+    pub fn http(&self) -> TlsAcceptor {
+        self.acceptor.clone()
+    }
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("client_ca", &self.client_ca.is_some())
+            .finish()
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).context(ReadingFile { path })
+}
+
+/// Parses the first PKCS8 or RSA private key of `pem`, the formats
+/// supported by the gRPC server
+fn private_key(pem: &[u8]) -> Option<PrivateKey> {
+    pemfile::pkcs8_private_keys(&mut Cursor::new(pem))
+        .ok()
+        .and_then(|keys| keys.into_iter().next())
+        .or_else(|| {
+            pemfile::rsa_private_keys(&mut Cursor::new(pem))
+                .ok()
+                .and_then(|keys| keys.into_iter().next())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn missing_files() {
+        let err = TlsConfig::load(
+            Path::new("/does/not/exist.pem"),
+            Path::new("/does/not/exist.key"),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::ReadingFile { .. }));
+    }
+
+    #[test]
+    fn invalid_pem() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"not a certificate").unwrap();
+
+        let err = TlsConfig::load(file.path(), file.path(), None).unwrap_err();
+        assert!(matches!(err, Error::NoCertificates { .. }));
+    }
+}
@@ -7,7 +7,10 @@
     clippy::clone_on_ref_ptr
 )]
 
-use std::str::FromStr;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use dotenv::dotenv;
 use observability_deps::tracing::{debug, warn};
@@ -15,6 +18,7 @@ use structopt::StructOpt;
 use tokio::runtime::Runtime;
 
 use commands::logging::LoggingLevel;
+use influxdb_iox_client::connection::Connection;
 use ingest::parquet::writer::CompressionLevel;
 
 use tikv_jemallocator::Jemalloc;
@@ -30,6 +34,7 @@ mod commands {
     pub mod run;
     pub mod server;
     pub mod server_remote;
+    pub mod server_token;
     pub mod stats;
     pub mod writer;
 }
@@ -94,6 +99,34 @@ struct Config {
     )]
     host: String, /* TODO: This must be on the root due to https://github.com/clap-rs/clap/pull/2253 */
 
+    /// API token to authenticate with the IOx server
+    #[structopt(long, global = true, env = "INFLUXDB_IOX_TOKEN")]
+    token: Option<String>,
+
+    /// PEM encoded CA certificate to verify the TLS certificate of the IOx
+    /// server with. Enables TLS
+    #[structopt(long, global = true, env = "INFLUXDB_IOX_TLS_CA_CERT")]
+    tls_ca_cert: Option<PathBuf>,
+
+    /// PEM encoded certificate to present to IOx servers that require
+    /// client certificates. Enables TLS
+    #[structopt(
+        long,
+        global = true,
+        env = "INFLUXDB_IOX_TLS_CLIENT_CERT",
+        requires = "tls-client-key"
+    )]
+    tls_client_cert: Option<PathBuf>,
+
+    /// PEM encoded private key of `--tls-client-cert`
+    #[structopt(
+        long,
+        global = true,
+        env = "INFLUXDB_IOX_TLS_CLIENT_KEY",
+        requires = "tls-client-cert"
+    )]
+    tls_client_key: Option<PathBuf>,
+
     #[structopt(long)]
     /// Set the maximum number of threads to use. Defaults to the number of
     /// cores on the system
@@ -149,7 +182,13 @@ fn main() -> Result<(), std::io::Error> {
 
     let tokio_runtime = get_runtime(config.num_threads)?;
     tokio_runtime.block_on(async move {
-        let host = config.host;
+        let connection_options = ConnectionOptions {
+            host: config.host,
+            token: config.token,
+            tls_ca_cert: config.tls_ca_cert,
+            tls_client_cert: config.tls_client_cert,
+            tls_client_key: config.tls_client_key,
+        };
         match config.command {
             Command::Convert {
                 input,
@@ -189,28 +228,32 @@ fn main() -> Result<(), std::io::Error> {
             }
             Command::Database(config) => {
                 logging_level.setup_basic_logging();
-                if let Err(e) = commands::database::command(host, config).await {
+                let connection = connect(connection_options).await;
+                if let Err(e) = commands::database::command(connection, config).await {
                     eprintln!("{}", e);
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
             Command::Writer(config) => {
                 logging_level.setup_basic_logging();
-                if let Err(e) = commands::writer::command(host, config).await {
+                let connection = connect(connection_options).await;
+                if let Err(e) = commands::writer::command(connection, config).await {
                     eprintln!("{}", e);
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
             Command::Operation(config) => {
                 logging_level.setup_basic_logging();
-                if let Err(e) = commands::operations::command(host, config).await {
+                let connection = connect(connection_options).await;
+                if let Err(e) = commands::operations::command(connection, config).await {
                     eprintln!("{}", e);
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
             Command::Server(config) => {
                 logging_level.setup_basic_logging();
-                if let Err(e) = commands::server::command(host, config).await {
+                let connection = connect(connection_options).await;
+                if let Err(e) = commands::server::command(connection, config).await {
                     eprintln!("Server command failed: {}", e);
                     std::process::exit(ReturnCode::Failure as _)
                 }
@@ -229,6 +272,46 @@ fn main() -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// How to connect to the IOx server for commands that use the gRPC API
+#[derive(Debug)]
+struct ConnectionOptions {
+    host: String,
+    token: Option<String>,
+    tls_ca_cert: Option<PathBuf>,
+    tls_client_cert: Option<PathBuf>,
+    tls_client_key: Option<PathBuf>,
+}
+
+/// Connects to the IOx server, exiting the process on failure
+async fn connect(options: ConnectionOptions) -> Connection {
+    let mut builder = influxdb_iox_client::connection::Builder::default();
+    if let Some(token) = options.token {
+        builder = builder.token(token);
+    }
+    if let Some(ca_cert) = &options.tls_ca_cert {
+        builder = builder.tls_ca_certificate(read_or_exit(ca_cert));
+    }
+    if let (Some(cert), Some(key)) = (&options.tls_client_cert, &options.tls_client_key) {
+        builder = builder.tls_identity(read_or_exit(cert), read_or_exit(key));
+    }
+
+    match builder.build(options.host.as_str()).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Error connecting to IOx at {}: {}", options.host, e);
+            std::process::exit(ReturnCode::Failure as _)
+        }
+    }
+}
+
+/// Reads the file at `path`, exiting the process on failure
+fn read_or_exit(path: &Path) -> Vec<u8> {
+    std::fs::read(path).unwrap_or_else(|e| {
+        eprintln!("Error reading {:?}: {}", path, e);
+        std::process::exit(ReturnCode::Failure as _)
+    })
+}
+
 /// Creates the tokio runtime for executing IOx
 ///
 /// if nthreads is none, uses the default scheduler
@@ -1 +1,2 @@
 pub mod server_fixture;
+pub mod tls;
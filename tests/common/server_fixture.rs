@@ -14,6 +14,8 @@ use futures::prelude::*;
 use std::time::Duration;
 use tempfile::TempDir;
 
+use super::tls::{TestCertificates, SERVER_NAME};
+
 type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -37,17 +39,19 @@ struct BindAddresses {
 }
 
 impl BindAddresses {
-    /// return a new port assignment suitable for this test's use
-    fn new() -> Self {
+    /// return a new port assignment suitable for this test's use, with
+    /// https URLs if `tls` is set
+    fn new(tls: bool) -> Self {
         let http_port = NEXT_PORT.fetch_add(1, SeqCst);
         let grpc_port = NEXT_PORT.fetch_add(1, SeqCst);
 
         let http_bind_addr = format!("127.0.0.1:{}", http_port);
         let grpc_bind_addr = format!("127.0.0.1:{}", grpc_port);
 
-        let http_base = format!("http://{}", http_bind_addr);
-        let iox_api_v1_base = format!("http://{}/iox/api/v1", http_bind_addr);
-        let grpc_base = format!("http://{}", grpc_bind_addr);
+        let scheme = if tls { "https" } else { "http" };
+        let http_base = format!("{}://{}", scheme, http_bind_addr);
+        let iox_api_v1_base = format!("{}://{}/iox/api/v1", scheme, http_bind_addr);
+        let grpc_base = format!("{}://{}", scheme, grpc_bind_addr);
 
         Self {
             http_port,
@@ -75,6 +79,15 @@ pub struct ServerFixture {
     grpc_channel: tonic::transport::Channel,
 }
 
+/// Serves the APIs of a test server over TLS
+#[derive(Debug)]
+pub struct TestTls {
+    pub certificates: TestCertificates,
+
+    /// Require clients to present a certificate issued by the test CA
+    pub require_client_cert: bool,
+}
+
 /// Specifieds should we configure a server initially
 enum InitialConfig {
     /// Set the writer id to something so it can accept writes
@@ -106,7 +119,7 @@ impl ServerFixture {
             Some(server) => server,
             None => {
                 // if not, create one
-                let server = TestServer::new().expect("Could start test server");
+                let server = TestServer::new(None).expect("Could start test server");
                 let server = Arc::new(server);
 
                 // ensure the server is ready
@@ -128,7 +141,20 @@ impl ServerFixture {
     /// waits.  The database is left unconfigured (no writer id) and
     /// is not shared with any other tests.
     pub async fn create_single_use() -> Self {
-        let server = TestServer::new().expect("Could start test server");
+        let server = TestServer::new(None).expect("Could start test server");
+        let server = Arc::new(server);
+
+        // ensure the server is ready
+        server.wait_until_ready(InitialConfig::None).await;
+        Self::create_common(server).await
+    }
+
+    /// Create a new server fixture serving its APIs over TLS and wait for
+    /// it to be ready. The clients returned by the fixture connect over
+    /// TLS. Like [`create_single_use`][Self::create_single_use], the
+    /// database is left unconfigured and is not shared with other tests.
+    pub async fn create_single_use_with_tls(tls: TestTls) -> Self {
+        let server = TestServer::new(Some(tls)).expect("Could start test server");
         let server = Arc::new(server);
 
         // ensure the server is ready
@@ -169,6 +195,18 @@ impl ServerFixture {
         &self.server.addrs().iox_api_v1_base
     }
 
+    /// Return the TLS configuration of the server, if it serves its APIs
+    /// over TLS
+    pub fn tls(&self) -> Option<&TestTls> {
+        self.server.tls.as_ref()
+    }
+
+    /// Return a reqwest client suitable for sending requests to the HTTP
+    /// API of this server
+    pub fn http_client(&self) -> reqwest::Client {
+        self.server.http_client()
+    }
+
     /// Return an a http client suitable suitable for communicating with this
     /// server
     pub fn influxdb2_client(&self) -> influxdb2_client::Client {
@@ -218,14 +256,17 @@ struct TestServer {
     /// Which ports this server should use
     addrs: BindAddresses,
 
+    /// How the server serves its APIs over TLS, if at all
+    tls: Option<TestTls>,
+
     // The temporary directory **must** be last so that it is
     // dropped after the database closes.
     dir: TempDir,
 }
 
 impl TestServer {
-    fn new() -> Result<Self> {
-        let addrs = BindAddresses::new();
+    fn new(tls: Option<TestTls>) -> Result<Self> {
+        let addrs = BindAddresses::new(tls.is_some());
         let ready = Mutex::new(ServerState::Started);
 
         let dir = test_helpers::tmp_dir().unwrap();
@@ -247,8 +288,8 @@ impl TestServer {
             .expect("cloning file handle for stdout");
         let stderr_log_file = log_file;
 
-        let server_process = Command::cargo_bin("influxdb_iox")
-            .unwrap()
+        let mut command = Command::cargo_bin("influxdb_iox").unwrap();
+        command
             .arg("run")
             // Can enable for debugging
             //.arg("-vv")
@@ -256,14 +297,25 @@ impl TestServer {
             .env("INFLUXDB_IOX_GRPC_BIND_ADDR", &addrs.grpc_bind_addr)
             // redirect output to log file
             .stdout(stdout_log_file)
-            .stderr(stderr_log_file)
-            .spawn()
-            .unwrap();
+            .stderr(stderr_log_file);
+
+        if let Some(tls) = &tls {
+            let certificates = &tls.certificates;
+            command
+                .env("INFLUXDB_IOX_TLS_CERT", certificates.server_cert_path())
+                .env("INFLUXDB_IOX_TLS_KEY", certificates.server_key_path());
+            if tls.require_client_cert {
+                command.env("INFLUXDB_IOX_TLS_CLIENT_CA", certificates.ca_cert_path());
+            }
+        }
+
+        let server_process = command.spawn().unwrap();
 
         Ok(Self {
             ready,
             server_process,
             addrs,
+            tls,
             dir,
         })
     }
@@ -326,7 +378,7 @@ impl TestServer {
         };
 
         let try_http_connect = async {
-            let client = reqwest::Client::new();
+            let client = self.http_client();
             let url = format!("{}/health", self.addrs().http_base);
             let mut interval = tokio::time::interval(Duration::from_millis(500));
             loop {
@@ -390,9 +442,38 @@ impl TestServer {
     async fn grpc_channel(
         &self,
     ) -> influxdb_iox_client::connection::Result<tonic::transport::Channel> {
-        influxdb_iox_client::connection::Builder::default()
+        let mut builder = influxdb_iox_client::connection::Builder::default();
+        if let Some(tls) = &self.tls {
+            let certificates = &tls.certificates;
+            builder = builder
+                .tls_ca_certificate(&certificates.ca_cert)
+                .tls_domain_name(SERVER_NAME);
+            if tls.require_client_cert {
+                builder = builder.tls_identity(&certificates.client.cert, &certificates.client.key);
+            }
+        }
+
+        builder
             .build(&self.addrs().grpc_base)
             .await
+            .map(influxdb_iox_client::connection::Connection::into_channel)
+    }
+
+    /// Create a client for the HTTP endpoint
+    fn http_client(&self) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder();
+        if let Some(tls) = &self.tls {
+            let certificates = &tls.certificates;
+            builder = builder.add_root_certificate(
+                reqwest::Certificate::from_pem(&certificates.ca_cert).unwrap(),
+            );
+            if tls.require_client_cert {
+                builder = builder.identity(
+                    reqwest::Identity::from_pkcs12_der(&certificates.client.pkcs12, "").unwrap(),
+                );
+            }
+        }
+        builder.build().expect("Could not create HTTP client")
     }
 
     fn addrs(&self) -> &BindAddresses {
@@ -0,0 +1,203 @@
+//! Self-signed certificates for testing the TLS listeners of the server
+use std::path::PathBuf;
+
+use openssl::{
+    asn1::Asn1Time,
+    bn::BigNum,
+    hash::MessageDigest,
+    pkcs12::Pkcs12,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    x509::{
+        extension::{
+            AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage,
+            SubjectAlternativeName, SubjectKeyIdentifier,
+        },
+        X509NameBuilder, X509,
+    },
+};
+use tempfile::TempDir;
+
+/// The host name the server certificate is issued for
+pub const SERVER_NAME: &str = "localhost";
+
+/// A certificate and its private key, both PEM encoded
+#[derive(Debug, Clone)]
+pub struct CertifiedKey {
+    pub cert: Vec<u8>,
+    pub key: Vec<u8>,
+    /// The certificate and key as a PKCS #12 archive with an empty password
+    pub pkcs12: Vec<u8>,
+}
+
+/// A CA and the server and client certificates it issued, written to a
+/// temporary directory so they can be passed to the server
+#[derive(Debug)]
+pub struct TestCertificates {
+    pub ca_cert: Vec<u8>,
+    pub server: CertifiedKey,
+    pub client: CertifiedKey,
+    dir: TempDir,
+}
+
+impl TestCertificates {
+    /// Generates a new CA, and server and client certificates issued by it
+    pub fn generate() -> Self {
+        let (ca_cert, ca_key) = generate_ca();
+        let server = generate_leaf(
+            &ca_cert,
+            &ca_key,
+            "server",
+            ExtendedKeyUsage::new().server_auth(),
+        );
+        let client = generate_leaf(
+            &ca_cert,
+            &ca_key,
+            "client",
+            ExtendedKeyUsage::new().client_auth(),
+        );
+
+        let certificates = Self {
+            ca_cert: ca_cert.to_pem().unwrap(),
+            server,
+            client,
+            dir: test_helpers::tmp_dir().unwrap(),
+        };
+
+        std::fs::write(certificates.ca_cert_path(), &certificates.ca_cert).unwrap();
+        std::fs::write(certificates.server_cert_path(), &certificates.server.cert).unwrap();
+        std::fs::write(certificates.server_key_path(), &certificates.server.key).unwrap();
+
+        certificates
+    }
+
+    pub fn ca_cert_path(&self) -> PathBuf {
+        self.path("ca.pem")
+    }
+
+    pub fn server_cert_path(&self) -> PathBuf {
+        self.path("server.pem")
+    }
+
+    pub fn server_key_path(&self) -> PathBuf {
+        self.path("server.key")
+    }
+
+    fn path(&self, file_name: &str) -> PathBuf {
+        self.dir.path().join(file_name)
+    }
+}
+
+fn generate_key() -> PKey<Private> {
+    PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+}
+
+fn generate_ca() -> (X509, PKey<Private>) {
+    let key = generate_key();
+
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "IOx test CA").unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    builder
+        .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+        .unwrap();
+    builder
+        .append_extension(
+            KeyUsage::new()
+                .critical()
+                .key_cert_sign()
+                .crl_sign()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let subject_key_identifier = SubjectKeyIdentifier::new()
+        .build(&builder.x509v3_context(None, None))
+        .unwrap();
+    builder.append_extension(subject_key_identifier).unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+    (builder.build(), key)
+}
+
+fn generate_leaf(
+    ca_cert: &X509,
+    ca_key: &PKey<Private>,
+    common_name: &str,
+    extended_key_usage: &mut ExtendedKeyUsage,
+) -> CertifiedKey {
+    let key = generate_key();
+
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&BigNum::from_u32(2).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(ca_cert.subject_name()).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    builder
+        .append_extension(BasicConstraints::new().build().unwrap())
+        .unwrap();
+    builder
+        .append_extension(
+            KeyUsage::new()
+                .critical()
+                .digital_signature()
+                .key_encipherment()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    builder
+        .append_extension(extended_key_usage.build().unwrap())
+        .unwrap();
+    let subject_alternative_name = SubjectAlternativeName::new()
+        .dns(SERVER_NAME)
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(Some(ca_cert), None))
+        .unwrap();
+    builder.append_extension(subject_alternative_name).unwrap();
+    let authority_key_identifier = AuthorityKeyIdentifier::new()
+        .keyid(true)
+        .build(&builder.x509v3_context(Some(ca_cert), None))
+        .unwrap();
+    builder.append_extension(authority_key_identifier).unwrap();
+    builder.sign(ca_key, MessageDigest::sha256()).unwrap();
+    let cert = builder.build();
+
+    let pkcs12 = Pkcs12::builder()
+        .build("", common_name, &key, &cert)
+        .unwrap();
+
+    CertifiedKey {
+        cert: cert.to_pem().unwrap(),
+        key: key.private_key_to_pem_pkcs8().unwrap(),
+        pkcs12: pkcs12.to_der().unwrap(),
+    }
+}
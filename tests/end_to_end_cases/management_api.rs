@@ -1,10 +1,18 @@
 use std::num::NonZeroU32;
 
+use arrow_deps::assert_batches_sorted_eq;
+
 use generated_types::{
     google::protobuf::{Duration, Empty},
     influxdata::iox::management::v1::*,
 };
-use influxdb_iox_client::{management::CreateDatabaseError, operations};
+use influxdb_iox_client::{
+    management::{
+        CreateDatabaseError, DeleteDatabaseError, DeleteError, GetDatabaseError,
+        RestoreDatabaseError,
+    },
+    operations,
+};
 
 use test_helpers::assert_contains;
 
@@ -191,6 +199,12 @@ async fn test_create_get_update_database() {
             ..Default::default()
         }),
         shard_config: None,
+        limits: Some(Limits {
+            max_concurrent_queries: 4,
+            write_bytes_per_second: 1024,
+            query_timeout_seconds: 30,
+            ..Default::default()
+        }),
     };
 
     client
@@ -231,6 +245,56 @@ async fn test_create_get_update_database() {
     );
 }
 
+#[tokio::test]
+async fn test_delete_database() {
+    let server_fixture = ServerFixture::create_shared().await;
+    let mut client = server_fixture.management_client();
+    let mut write_client = server_fixture.write_client();
+
+    let db_name = rand_name();
+    let rules = DatabaseRules {
+        name: db_name.clone(),
+        ..Default::default()
+    };
+    client
+        .create_database(rules.clone())
+        .await
+        .expect("create database failed");
+
+    write_client
+        .write(&db_name, "cpu,region=west user=23.2 100")
+        .await
+        .expect("write succeded");
+
+    client
+        .delete_database(&db_name)
+        .await
+        .expect("delete database failed");
+
+    let err = client.get_database(&db_name).await.unwrap_err();
+    assert!(matches!(dbg!(err), GetDatabaseError::DatabaseNotFound));
+
+    let names = client
+        .list_databases()
+        .await
+        .expect("list databases failed");
+    assert!(!names.contains(&db_name));
+
+    let err = client.delete_database(&db_name).await.unwrap_err();
+    assert!(matches!(dbg!(err), DeleteDatabaseError::DatabaseNotFound));
+
+    let err = client.delete_database("bananas!").await.unwrap_err();
+    assert!(matches!(dbg!(err), DeleteDatabaseError::InvalidArgument(_)));
+
+    // the name can be reused and the new database starts out empty
+    client
+        .create_database(rules)
+        .await
+        .expect("create database failed");
+    let chunks = client.list_chunks(&db_name).await.expect("listing chunks");
+    assert!(chunks.is_empty());
+}
+
 #[tokio::test]
 async fn test_chunk_get() {
     use generated_types::influxdata::iox::management::v1::{Chunk, ChunkStorage};
@@ -647,6 +711,409 @@ async fn test_close_partition_chunk_error() {
     assert_contains!(err.to_string(), "Database not found");
 }
 
+/// Writes a line to `db_name` and moves the resulting chunk 0 of partition
+/// "cpu" to the read buffer
+async fn load_read_buffer_chunk(fixture: &ServerFixture, db_name: &str) {
+    let mut management_client = fixture.management_client();
+    let mut write_client = fixture.write_client();
+    let mut operations_client = fixture.operations_client();
+
+    write_client
+        .write(db_name, "cpu,region=west user=23.2 100")
+        .await
+        .expect("write succeded");
+
+    let operation = management_client
+        .close_partition_chunk(db_name, "cpu", 0)
+        .await
+        .expect("close partition chunk");
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+        .await
+        .expect("failed to wait operation");
+}
+
+#[tokio::test]
+async fn test_drop_partition_chunk() {
+    use influxdb_iox_client::management::generated_types::operation_metadata::Job;
+
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut operations_client = fixture.operations_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+    load_read_buffer_chunk(&fixture, &db_name).await;
+
+    let operation = management_client
+        .drop_partition_chunk(&db_name, "cpu", 0)
+        .await
+        .expect("drop partition chunk");
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    let meta = operations::ClientOperation::try_new(operation)
+        .unwrap()
+        .metadata();
+
+    if let Some(Job::DropChunk(drop_chunk)) = meta.job {
+        assert_eq!(drop_chunk.db_name, db_name);
+        assert_eq!(drop_chunk.partition_key, "cpu");
+        assert_eq!(drop_chunk.chunk_id, 0);
+    } else {
+        panic!("unexpected job returned")
+    };
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+        .await
+        .expect("failed to wait operation");
+
+    let chunks = management_client
+        .list_chunks(&db_name)
+        .await
+        .expect("listing chunks");
+    assert!(chunks.is_empty(), "Chunks: {:#?}", chunks);
+}
+
+#[tokio::test]
+async fn test_drop_partition_chunk_error() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+
+    let err = management_client
+        .drop_partition_chunk("this database does not exist", "nor_does_this_partition", 0)
+        .await
+        .expect_err("expected error");
+
+    assert_contains!(err.to_string(), "Database not found");
+}
+
+#[tokio::test]
+async fn test_persist_partition_chunk() {
+    use influxdb_iox_client::management::generated_types::operation_metadata::Job;
+    use influxdb_iox_client::management::generated_types::ChunkStorage;
+
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut operations_client = fixture.operations_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+    load_read_buffer_chunk(&fixture, &db_name).await;
+
+    let operation = management_client
+        .persist_partition_chunk(&db_name, "cpu", 0)
+        .await
+        .expect("persist partition chunk");
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    let meta = operations::ClientOperation::try_new(operation)
+        .unwrap()
+        .metadata();
+
+    if let Some(Job::WriteChunk(write_chunk)) = meta.job {
+        assert_eq!(write_chunk.db_name, db_name);
+        assert_eq!(write_chunk.partition_key, "cpu");
+        assert_eq!(write_chunk.chunk_id, 0);
+    } else {
+        panic!("unexpected job returned")
+    };
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(5)))
+        .await
+        .expect("failed to wait operation");
+
+    let chunks = management_client
+        .list_chunks(&db_name)
+        .await
+        .expect("listing chunks");
+
+    assert_eq!(chunks.len(), 1, "Chunks: {:#?}", chunks);
+    assert_eq!(chunks[0].id, 0);
+    assert_eq!(
+        chunks[0].storage,
+        ChunkStorage::ReadBufferAndObjectStore as i32
+    );
+}
+
+#[tokio::test]
+async fn test_persist_partition_chunk_error() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+
+    let err = management_client
+        .persist_partition_chunk("this database does not exist", "nor_does_this_partition", 0)
+        .await
+        .expect_err("expected error");
+
+    assert_contains!(err.to_string(), "Database not found");
+}
+
+#[tokio::test]
+async fn test_delete() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut write_client = fixture.write_client();
+    let mut flight_client = fixture.flight_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+
+    write_client
+        .write(
+            &db_name,
+            "cpu,region=west usage=23.2 100\n\
+             cpu,region=east usage=21.0 100\n\
+             cpu,region=west usage=25.1 200",
+        )
+        .await
+        .expect("write succeded");
+
+    management_client
+        .delete(
+            &db_name,
+            DeletePredicate {
+                table_name: "cpu".to_string(),
+                start: 0,
+                stop: 150,
+                exprs: vec![DeleteExpr {
+                    column: "region".to_string(),
+                    op: DeleteOp::Eq.into(),
+                    value: "west".to_string(),
+                }],
+            },
+        )
+        .await
+        .expect("delete");
+
+    // rows written after the delete are not deleted
+    write_client
+        .write(&db_name, "cpu,region=west usage=27.5 100")
+        .await
+        .expect("write succeded");
+
+    let mut query_results = flight_client
+        .perform_query(&db_name, "select region, usage, time from cpu")
+        .await
+        .unwrap();
+
+    let mut batches = vec![];
+    while let Some(data) = query_results.next().await.unwrap() {
+        batches.push(data);
+    }
+
+    let expected = vec![
+        "+--------+-------+-------------------------------+",
+        "| region | usage | time                          |",
+        "+--------+-------+-------------------------------+",
+        "| east   | 21    | 1970-01-01 00:00:00.000000100 |",
+        "| west   | 25.1  | 1970-01-01 00:00:00.000000200 |",
+        "| west   | 27.5  | 1970-01-01 00:00:00.000000100 |",
+        "+--------+-------+-------------------------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &batches);
+}
+
+#[tokio::test]
+async fn test_delete_error() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+
+    let predicate = DeletePredicate {
+        table_name: "cpu".to_string(),
+        start: 0,
+        stop: 100,
+        exprs: vec![],
+    };
+
+    let err = management_client
+        .delete("this database does not exist", predicate.clone())
+        .await
+        .expect_err("expected error");
+    assert!(matches!(dbg!(err), DeleteError::DatabaseNotFound));
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+
+    let err = management_client
+        .delete(
+            &db_name,
+            DeletePredicate {
+                start: 100,
+                stop: 0,
+                ..predicate
+            },
+        )
+        .await
+        .expect_err("expected error");
+    assert!(matches!(dbg!(err), DeleteError::InvalidArgument(_)));
+}
+
+#[tokio::test]
+async fn test_collect_garbage() {
+    use influxdb_iox_client::management::generated_types::operation_metadata::Job;
+
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut operations_client = fixture.operations_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+
+    // nothing has been persisted, so there is nothing to collect
+    let orphaned_files = management_client
+        .find_orphaned_files(&db_name, 0)
+        .await
+        .expect("finding orphaned files");
+    assert!(orphaned_files.is_empty(), "{:?}", orphaned_files);
+
+    let operation = management_client
+        .collect_garbage(&db_name, 0)
+        .await
+        .expect("collecting garbage");
+
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    let meta = operations::ClientOperation::try_new(operation)
+        .unwrap()
+        .metadata();
+
+    if let Some(Job::CollectGarbage(collect_garbage)) = meta.job {
+        assert_eq!(collect_garbage.db_name, db_name);
+    } else {
+        panic!("unexpected job returned")
+    };
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+        .await
+        .expect("failed to wait operation");
+}
+
+#[tokio::test]
+async fn test_collect_garbage_error() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+
+    let err = management_client
+        .find_orphaned_files("this database does not exist", 0)
+        .await
+        .expect_err("expected error");
+    assert_contains!(err.to_string(), "Database not found");
+
+    let err = management_client
+        .collect_garbage("this database does not exist", 0)
+        .await
+        .expect_err("expected error");
+    assert_contains!(err.to_string(), "Database not found");
+}
+
+#[tokio::test]
+async fn test_backup_and_restore_database() {
+    use influxdb_iox_client::management::generated_types::operation_metadata::Job;
+
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut operations_client = fixture.operations_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().to_str().unwrap().to_string();
+
+    let operation = management_client
+        .backup_database(&db_name, &path)
+        .await
+        .expect("backing up database");
+
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    let meta = operations::ClientOperation::try_new(operation)
+        .unwrap()
+        .metadata();
+
+    if let Some(Job::BackupDatabase(backup_database)) = meta.job {
+        assert_eq!(backup_database.db_name, db_name);
+        assert_eq!(backup_database.path, path);
+    } else {
+        panic!("unexpected job returned")
+    };
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+        .await
+        .expect("failed to wait operation");
+
+    let restored_name = rand_name();
+    let operation = management_client
+        .restore_database(&path, Some(restored_name.clone()))
+        .await
+        .expect("restoring database");
+
+    let operation_id = operation.name.parse().expect("not an integer");
+
+    let meta = operations::ClientOperation::try_new(operation)
+        .unwrap()
+        .metadata();
+
+    if let Some(Job::RestoreDatabase(restore_database)) = meta.job {
+        assert_eq!(restore_database.db_name, restored_name);
+        assert_eq!(restore_database.path, path);
+    } else {
+        panic!("unexpected job returned")
+    };
+
+    operations_client
+        .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+        .await
+        .expect("failed to wait operation");
+
+    let rules = management_client
+        .get_database(&restored_name)
+        .await
+        .expect("get database failed");
+    assert_eq!(rules.name, restored_name);
+
+    // the backed up database still exists
+    let err = management_client
+        .restore_database(&path, None)
+        .await
+        .expect_err("expected error");
+    assert!(matches!(
+        dbg!(err),
+        RestoreDatabaseError::DatabaseAlreadyExists
+    ));
+}
+
+#[tokio::test]
+async fn test_backup_and_restore_database_error() {
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().to_str().unwrap().to_string();
+
+    let err = management_client
+        .backup_database("this database does not exist", &path)
+        .await
+        .expect_err("expected error");
+    assert_contains!(err.to_string(), "Database not found");
+
+    // the directory does not contain a backup
+    let err = management_client
+        .restore_database(&path, None)
+        .await
+        .expect_err("expected error");
+    assert!(matches!(
+        dbg!(err),
+        RestoreDatabaseError::FailedPrecondition(_)
+    ));
+}
+
 #[tokio::test]
 async fn test_chunk_lifecycle() {
     use influxdb_iox_client::management::generated_types::ChunkStorage;
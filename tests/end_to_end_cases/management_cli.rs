@@ -104,6 +104,58 @@ async fn test_create_database() {
         );
 }
 
+#[tokio::test]
+async fn test_delete_database() {
+    let server_fixture = ServerFixture::create_shared().await;
+    let addr = server_fixture.grpc_base();
+    let db_name = rand_name();
+    let db = &db_name;
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("delete")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Database not found"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("create")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ok"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("delete")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ok"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("get")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Database not found"));
+}
+
 #[tokio::test]
 async fn test_create_database_size() {
     let server_fixture = ServerFixture::create_shared().await;
@@ -576,6 +628,114 @@ async fn test_close_partition_chunk_error() {
         .stderr(predicate::str::contains("Database not found"));
 }
 
+#[tokio::test]
+async fn test_drop_chunk() {
+    let server_fixture = ServerFixture::create_shared().await;
+    let addr = server_fixture.grpc_base();
+    let db_name = rand_name();
+
+    create_readable_database(&db_name, server_fixture.grpc_channel()).await;
+
+    let lp_data = vec!["cpu,region=west user=23.2 100"];
+    load_lp(addr, &db_name, lp_data);
+
+    let stdout: Operation = serde_json::from_slice(
+        &Command::cargo_bin("influxdb_iox")
+            .unwrap()
+            .arg("database")
+            .arg("chunk")
+            .arg("drop")
+            .arg(&db_name)
+            .arg("cpu")
+            .arg("0")
+            .arg("--host")
+            .arg(addr)
+            .assert()
+            .success()
+            .get_output()
+            .stdout,
+    )
+    .expect("Expected JSON output");
+
+    let expected_job = Job::DropChunk {
+        db_name,
+        partition_key: "cpu".into(),
+        chunk_id: 0,
+    };
+
+    assert_eq!(
+        Some(expected_job),
+        stdout.job,
+        "operation was {:#?}",
+        stdout
+    );
+}
+
+#[tokio::test]
+async fn test_persist_chunk() {
+    let server_fixture = ServerFixture::create_shared().await;
+    let addr = server_fixture.grpc_base();
+    let db_name = rand_name();
+
+    create_readable_database(&db_name, server_fixture.grpc_channel()).await;
+
+    let lp_data = vec!["cpu,region=west user=23.2 100"];
+    load_lp(addr, &db_name, lp_data);
+
+    let stdout: Operation = serde_json::from_slice(
+        &Command::cargo_bin("influxdb_iox")
+            .unwrap()
+            .arg("database")
+            .arg("chunk")
+            .arg("persist")
+            .arg(&db_name)
+            .arg("cpu")
+            .arg("0")
+            .arg("--host")
+            .arg(addr)
+            .assert()
+            .success()
+            .get_output()
+            .stdout,
+    )
+    .expect("Expected JSON output");
+
+    let expected_job = Job::WriteChunk {
+        db_name,
+        partition_key: "cpu".into(),
+        chunk_id: 0,
+    };
+
+    assert_eq!(
+        Some(expected_job),
+        stdout.job,
+        "operation was {:#?}",
+        stdout
+    );
+}
+
+#[tokio::test]
+async fn test_drop_and_persist_chunk_error() {
+    let server_fixture = ServerFixture::create_shared().await;
+    let addr = server_fixture.grpc_base();
+
+    for subcommand in &["drop", "persist"] {
+        Command::cargo_bin("influxdb_iox")
+            .unwrap()
+            .arg("database")
+            .arg("chunk")
+            .arg(subcommand)
+            .arg("non_existent_database")
+            .arg("non_existent_partition")
+            .arg("0")
+            .arg("--host")
+            .arg(addr)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Database not found"));
+    }
+}
+
 /// Loads the specified lines into the named database
 fn load_lp(addr: &str, db_name: &str, lp_data: Vec<&str>) {
     let lp_data_file = make_temp_file(lp_data.join("\n"));
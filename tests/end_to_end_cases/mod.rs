@@ -8,5 +8,6 @@ pub mod read_api;
 pub mod read_cli;
 pub mod scenario;
 pub mod storage_api;
+pub mod tls;
 pub mod write_api;
 pub mod write_cli;
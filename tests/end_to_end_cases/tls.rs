@@ -0,0 +1,131 @@
+use std::{num::NonZeroU32, time::Duration};
+
+use influxdb_iox_client::{connection::Builder, management};
+use reqwest::StatusCode;
+use tokio::net::TcpStream;
+
+use crate::common::{
+    server_fixture::{ServerFixture, TestTls},
+    tls::{TestCertificates, SERVER_NAME},
+};
+
+#[tokio::test]
+async fn test_tls() {
+    let server_fixture = ServerFixture::create_single_use_with_tls(TestTls {
+        certificates: TestCertificates::generate(),
+        require_client_cert: false,
+    })
+    .await;
+    let certificates = &server_fixture.tls().unwrap().certificates;
+
+    // The fixture clients trust the test CA
+    let mut client = server_fixture.management_client();
+    let id = NonZeroU32::new(42).unwrap();
+    client.update_writer_id(id).await.expect("set ID failed");
+    assert_eq!(client.get_writer_id().await.expect("get ID failed"), id);
+
+    let response = server_fixture
+        .http_client()
+        .get(&format!("{}/health", server_fixture.http_base()))
+        .send()
+        .await
+        .expect("health check failed");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Clients that do not trust the CA are rejected
+    let builder = Builder::default().tls_domain_name(SERVER_NAME);
+    assert_rejected(builder, server_fixture.grpc_base()).await;
+
+    // The server certificate is only valid for its name
+    let builder = Builder::default()
+        .tls_ca_certificate(&certificates.ca_cert)
+        .tls_domain_name("example.com");
+    assert_rejected(builder, server_fixture.grpc_base()).await;
+
+    let response = reqwest::get(&format!("{}/health", server_fixture.http_base())).await;
+    assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn test_stalled_tls_handshakes() {
+    let server_fixture = ServerFixture::create_single_use_with_tls(TestTls {
+        certificates: TestCertificates::generate(),
+        require_client_cert: false,
+    })
+    .await;
+    let addr = server_fixture
+        .http_base()
+        .trim_start_matches("https://")
+        .to_string();
+
+    // Clients that connect but never complete the handshake, more than the
+    // server performs concurrently
+    let mut stalled = Vec::new();
+    for _ in 0..100 {
+        stalled.push(TcpStream::connect(&addr).await.unwrap());
+    }
+
+    // Do not block other clients, as their handshakes time out
+    let response = tokio::time::timeout(
+        Duration::from_secs(30),
+        server_fixture
+            .http_client()
+            .get(&format!("{}/health", server_fixture.http_base()))
+            .send(),
+    )
+    .await
+    .expect("health check blocked by stalled handshakes")
+    .expect("health check failed");
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_mutual_tls() {
+    let server_fixture = ServerFixture::create_single_use_with_tls(TestTls {
+        certificates: TestCertificates::generate(),
+        require_client_cert: true,
+    })
+    .await;
+    let certificates = &server_fixture.tls().unwrap().certificates;
+
+    // The fixture clients present the client certificate
+    let mut client = server_fixture.management_client();
+    client
+        .list_databases()
+        .await
+        .expect("list databases failed");
+
+    let response = server_fixture
+        .http_client()
+        .get(&format!("{}/health", server_fixture.http_base()))
+        .send()
+        .await
+        .expect("health check failed");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Clients without a certificate are rejected
+    let builder = Builder::default()
+        .tls_ca_certificate(&certificates.ca_cert)
+        .tls_domain_name(SERVER_NAME);
+    assert_rejected(builder, server_fixture.grpc_base()).await;
+
+    // As are clients with a certificate not issued by the CA
+    let other = TestCertificates::generate();
+    let builder = Builder::default()
+        .tls_ca_certificate(&certificates.ca_cert)
+        .tls_domain_name(SERVER_NAME)
+        .tls_identity(&other.client.cert, &other.client.key);
+    assert_rejected(builder, server_fixture.grpc_base()).await;
+}
+
+/// Asserts that a client built by `builder` cannot use the server at `url`.
+/// Depending on the TLS version, the server rejects the client either
+/// while connecting or on its first request
+async fn assert_rejected(builder: Builder, url: &str) {
+    if let Ok(connection) = builder.build(url).await {
+        management::Client::new(connection)
+            .list_databases()
+            .await
+            .expect_err("request should have been rejected");
+    }
+}
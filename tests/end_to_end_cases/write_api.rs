@@ -71,3 +71,40 @@ async fn test_write() {
     // IMPORTANT: At this point, the database is flooded and pretty much
     // useless. Don't append any tests after the "hard limit" test!
 }
+
+#[tokio::test]
+async fn test_write_outside_retention_period() {
+    use generated_types::influxdata::iox::management::v1::{DatabaseRules, LifecycleRules};
+
+    let fixture = ServerFixture::create_shared().await;
+    let mut management_client = fixture.management_client();
+    let mut write_client = fixture.write_client();
+
+    let db_name = rand_name();
+    management_client
+        .create_database(DatabaseRules {
+            name: db_name.clone(),
+            lifecycle_rules: Some(LifecycleRules {
+                retention_period_seconds: 3600,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await
+        .expect("create database failed");
+
+    let err = write_client
+        .write(&db_name, "cpu,region=west user=23.2 100")
+        .await
+        .expect_err("expected write to fail");
+
+    assert_contains!(err.to_string(), "older than the retention period");
+    let WriteError::ServerError(status) = dbg!(err);
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    let now = chrono::Utc::now().timestamp_nanos();
+    write_client
+        .write(&db_name, format!("cpu,region=west user=23.2 {}", now))
+        .await
+        .expect("write succeded");
+}